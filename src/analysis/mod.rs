@@ -0,0 +1,446 @@
+/*
+Author: Jedda Boyle
+Contains: Analysis
+Resolves every identifier of a parsed program to the binding it refers to,
+for the tools which reason about source code such as the linter and the
+language server.
+Bindings are resolved the way the evaluator does: functions and catch
+blocks open a new scope while the blocks of if and try share the scope
+they are in. A let can be read by functions written before it, so the lets
+of a scope are known before the scope is walked.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::children;
+use super::ast::start_span;
+use super::ast::BlockStatement;
+use super::ast::CallExpression;
+use super::ast::ExportStatement;
+use super::ast::FunctionExpression;
+use super::ast::IdentifierExpression;
+use super::ast::ImportStatement;
+use super::ast::LetStatement;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::ast::TryExpression;
+use super::builtins;
+use super::interpreter;
+use super::lexer::token::Span;
+use super::lexer::Lexer;
+use super::parser::Parser;
+use super::parser::Program;
+
+// ================================================================================
+// Symbols.
+// ================================================================================
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SymbolKind {
+    Let,
+    Parameter,
+    Import,
+    Catch,
+    Prelude, // A function of the prelude.
+}
+
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: Span,            // Where the name is bound, 0:0 for the prelude.
+    pub scope: usize,          // The scope the symbol is bound in.
+    pub arity: Option<usize>,  // The number of parameters if it is bound to a function.
+    pub detail: String,        // A line of source describing the binding.
+    pub exported: bool,        // Whether the let is exported.
+    pub shadows: bool,         // Whether a parameter hides a binding of an outer scope.
+    pub references: Vec<Span>, // Where the symbol is read.
+}
+
+// The region of source a scope covers.
+pub struct Scope {
+    pub parent: Option<usize>,
+    pub start: Span,
+    pub end: Span,
+}
+
+// A call whose callee is a name.
+pub struct Call {
+    pub span: Span,
+    pub symbol: usize,
+    pub arguments: usize,
+}
+
+pub struct Analysis {
+    pub symbols: Vec<Symbol>,
+    pub scopes: Vec<Scope>, // The prelude is scope 0 and the program scope 1.
+    pub calls: Vec<Call>,
+}
+
+// Whether span is before or at other.
+fn is_before(span: Span, other: Span) -> bool {
+    return (span.line, span.column) <= (other.line, other.column);
+}
+
+// Whether the name starting at span covers position. The position just after
+// the name counts, as that is where a cursor usually is after typing it.
+fn covers(span: Span, name: &str, position: Span) -> bool {
+    return span.line == position.line
+        && span.column <= position.column
+        && position.column <= span.column + name.chars().count();
+}
+
+impl Analysis {
+    // Return the symbol whose binding or one of whose references is at position.
+    pub fn symbol_at(&self, position: Span) -> Option<usize> {
+        return self.symbols.iter().position(|symbol| {
+            symbol.kind != SymbolKind::Prelude && covers(symbol.span, &symbol.name, position)
+                || symbol
+                    .references
+                    .iter()
+                    .any(|reference| covers(*reference, &symbol.name, position))
+        });
+    }
+
+    // Return the symbols that can be named at position, innermost first.
+    pub fn visible_at(&self, position: Span) -> Vec<&Symbol> {
+        let mut innermost = 1;
+        for (i, scope) in self.scopes.iter().enumerate().skip(1) {
+            if is_before(scope.start, position)
+                && is_before(position, scope.end)
+                && is_before(self.scopes[innermost].start, scope.start)
+            {
+                innermost = i;
+            }
+        }
+        let mut to_return = Vec::new();
+        let mut scope = Some(innermost);
+        while let Some(index) = scope {
+            for symbol in self.symbols.iter().filter(|symbol| symbol.scope == index) {
+                if !to_return
+                    .iter()
+                    .any(|other: &&Symbol| other.name == symbol.name)
+                {
+                    to_return.push(symbol);
+                }
+            }
+            scope = self.scopes[index].parent;
+        }
+        return to_return;
+    }
+}
+
+// Resolve the identifiers of a program.
+pub fn analyse(program: &Program) -> Analysis {
+    let end = Span {
+        line: usize::MAX,
+        column: usize::MAX,
+    };
+    let mut walker = Walker {
+        analysis: Analysis {
+            symbols: Vec::new(),
+            scopes: Vec::new(),
+            calls: Vec::new(),
+        },
+        declared: Vec::new(),
+        stack: Vec::new(),
+    };
+    walker.push_scope(Span::default(), end);
+    walker.prelude();
+    walker.push_scope(Span { line: 1, column: 1 }, end);
+    walker.collect_lets(&program.statements);
+    for statement in program.statements.iter() {
+        walker.node(statement);
+    }
+    return walker.analysis;
+}
+
+#[allow(clippy::borrowed_box)]
+fn arity(value: &Box<dyn Node>) -> Option<usize> {
+    return value
+        .downcast_ref::<FunctionExpression>()
+        .map(|function| function.parameters.len());
+}
+
+// The parameter list of a function, such as fn(a, b).
+fn signature(function: &FunctionExpression) -> String {
+    let parameters: Vec<String> = function
+        .parameters
+        .iter()
+        .map(|parameter| parameter.to_source())
+        .collect();
+    return format!("fn({})", parameters.join(", "));
+}
+
+// A line of source describing a let. Functions are shown by their parameters
+// and long values are shortened.
+fn let_detail(statement: &LetStatement) -> String {
+    let value = match statement.value.downcast_ref::<FunctionExpression>() {
+        Some(function) => signature(function),
+        None => {
+            let source = statement.value.to_source();
+            if source.chars().count() > 60 {
+                format!("{}...", source.chars().take(57).collect::<String>())
+            } else {
+                source
+            }
+        }
+    };
+    return format!("let {} = {}", statement.identifier.token.literal, value);
+}
+
+// ================================================================================
+// Walking the tree.
+// ================================================================================
+
+struct Walker {
+    analysis: Analysis,
+    declared: Vec<bool>, // Whether the walk has reached each symbol yet.
+    stack: Vec<usize>,   // The scopes the walk is in.
+}
+
+#[allow(clippy::borrowed_box)]
+impl Walker {
+    fn push_scope(&mut self, start: Span, end: Span) {
+        self.analysis.scopes.push(Scope {
+            parent: self.stack.last().copied(),
+            start,
+            end,
+        });
+        self.stack.push(self.analysis.scopes.len() - 1);
+    }
+
+    fn add(&mut self, name: &str, kind: SymbolKind, span: Span, detail: String) -> usize {
+        self.analysis.symbols.push(Symbol {
+            name: name.to_string(),
+            kind,
+            span,
+            scope: *self.stack.last().unwrap(),
+            arity: None,
+            detail,
+            exported: false,
+            shadows: false,
+            references: Vec::new(),
+        });
+        self.declared.push(kind != SymbolKind::Let);
+        return self.analysis.symbols.len() - 1;
+    }
+
+    // The functions of the prelude so that calls to them are known.
+    fn prelude(&mut self) {
+        for function in builtins::library() {
+            let detail = format!(
+                "let {} = fn({}) (prelude)",
+                function.name,
+                function.parameters.join(", ")
+            );
+            let symbol = self.add(&function.name, SymbolKind::Prelude, Span::default(), detail);
+            self.analysis.symbols[symbol].arity = Some(function.parameters.len());
+        }
+        let mut parser = Parser::new(Lexer::new(interpreter::PRELUDE));
+        let program = parser.parse_program().unwrap();
+        for statement in program.statements.iter() {
+            if let Some(statement) = statement.downcast_ref::<LetStatement>() {
+                let detail = format!("{} (prelude)", let_detail(statement));
+                let name = &statement.identifier.token.literal;
+                let symbol = self.add(name, SymbolKind::Prelude, Span::default(), detail);
+                self.analysis.symbols[symbol].arity = arity(&statement.value);
+            }
+        }
+    }
+
+    // Add a symbol for each let in statements which is in the current scope,
+    // that is not inside a function or catch block.
+    fn collect_lets(&mut self, statements: &Vec<Box<dyn Node>>) {
+        for statement in statements.iter() {
+            self.collect_node_lets(statement);
+        }
+    }
+
+    fn collect_let(&mut self, statement: &LetStatement, exported: bool) {
+        let identifier = &statement.identifier.token;
+        let detail = let_detail(statement);
+        let symbol = self.add(
+            &identifier.literal,
+            SymbolKind::Let,
+            identifier.span,
+            detail,
+        );
+        self.analysis.symbols[symbol].arity = arity(&statement.value);
+        self.analysis.symbols[symbol].exported = exported;
+        self.collect_node_lets(&statement.value);
+    }
+
+    fn collect_node_lets(&mut self, node: &Box<dyn Node>) {
+        match node.get_type() {
+            NodeType::LetStatement => {
+                self.collect_let(node.downcast_ref::<LetStatement>().unwrap(), false)
+            }
+            NodeType::ExportStatement => {
+                let export = node.downcast_ref::<ExportStatement>().unwrap();
+                self.collect_let(&export.statement, true);
+            }
+            NodeType::ImportStatement => {
+                let import = node.downcast_ref::<ImportStatement>().unwrap();
+                let alias = &import.alias.token;
+                let detail = format!("import \"{}\" as {}", import.path, alias.literal);
+                self.add(&alias.literal, SymbolKind::Import, alias.span, detail);
+            }
+            NodeType::TryExpression => {
+                let expression = node.downcast_ref::<TryExpression>().unwrap();
+                self.collect_node_lets(&expression.body);
+                if let Some(finally_body) = expression.finally_body.as_ref() {
+                    self.collect_node_lets(finally_body);
+                }
+            }
+            // Functions have their own scope.
+            NodeType::FunctionExpression => {}
+            _ => {
+                for child in children(node) {
+                    self.collect_node_lets(child);
+                }
+            }
+        }
+    }
+
+    // Find the symbol a name refers to. The latest symbol the walk has reached
+    // is used, or the first one in the scope if the name is used before it is
+    // bound, as functions may do.
+    fn lookup(&self, name: &String) -> Option<usize> {
+        let symbols = &self.analysis.symbols;
+        for scope in self.stack.iter().rev() {
+            let in_scope = |i: &usize| symbols[*i].scope == *scope && symbols[*i].name == *name;
+            let declared = (0..symbols.len())
+                .rev()
+                .find(|i| in_scope(i) && self.declared[*i]);
+            let index = declared.or_else(|| (0..symbols.len()).find(|i| in_scope(i)));
+            if index.is_some() {
+                return index;
+            }
+        }
+        return None;
+    }
+
+    // Record that the name at span is read.
+    fn resolve(&mut self, name: &String, span: Span) -> Option<usize> {
+        let symbol = self.lookup(name);
+        if let Some(symbol) = symbol {
+            self.analysis.symbols[symbol].references.push(span);
+        }
+        return symbol;
+    }
+
+    // Mark the let at span as reached.
+    fn declare(&mut self, span: Span) {
+        let scope = *self.stack.last().unwrap();
+        for (i, symbol) in self.analysis.symbols.iter().enumerate() {
+            if symbol.scope == scope && symbol.kind == SymbolKind::Let && symbol.span == span {
+                self.declared[i] = true;
+            }
+        }
+    }
+
+    fn let_statement(&mut self, statement: &LetStatement) {
+        self.node(&statement.value);
+        self.declare(statement.identifier.token.span);
+    }
+
+    fn node(&mut self, node: &Box<dyn Node>) {
+        match node.get_type() {
+            NodeType::LetStatement => {
+                self.let_statement(node.downcast_ref::<LetStatement>().unwrap());
+            }
+            NodeType::ExportStatement => {
+                let export = node.downcast_ref::<ExportStatement>().unwrap();
+                self.let_statement(&export.statement);
+            }
+            NodeType::IdentifierExpression => {
+                let token = &node.downcast_ref::<IdentifierExpression>().unwrap().token;
+                self.resolve(&token.literal, token.span);
+            }
+            NodeType::FunctionExpression => {
+                self.function(node.downcast_ref::<FunctionExpression>().unwrap());
+            }
+            NodeType::CallExpression => {
+                let call = node.downcast_ref::<CallExpression>().unwrap();
+                self.call(call, start_span(node));
+            }
+            NodeType::TryExpression => {
+                let expression = node.downcast_ref::<TryExpression>().unwrap();
+                self.node(&expression.body);
+                if let Some(catch_body) = expression.catch_body.as_ref() {
+                    // The caught value is bound in a scope of its own.
+                    let block = catch_body.downcast_ref::<BlockStatement>().unwrap();
+                    self.push_scope(block.token.span, block.end);
+                    let identifier = &expression.catch_identifier.as_ref().unwrap().token;
+                    let detail = format!("catch ({})", identifier.literal);
+                    self.add(
+                        &identifier.literal,
+                        SymbolKind::Catch,
+                        identifier.span,
+                        detail,
+                    );
+                    self.collect_lets(&block.statements);
+                    self.node(catch_body);
+                    self.stack.pop();
+                }
+                if let Some(finally_body) = expression.finally_body.as_ref() {
+                    self.node(finally_body);
+                }
+            }
+            _ => {
+                for child in children(node) {
+                    self.node(child);
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, function: &FunctionExpression) {
+        let body = function.body.downcast_ref::<BlockStatement>().unwrap();
+        self.push_scope(function.token.span, body.end);
+        let detail = signature(function);
+        for parameter in function.parameters.iter() {
+            let token = &parameter
+                .downcast_ref::<IdentifierExpression>()
+                .unwrap()
+                .token;
+            // Only the scopes between the prelude and this function count
+            // as outer scopes.
+            let outer = self
+                .lookup(&token.literal)
+                .is_some_and(|symbol| self.analysis.symbols[symbol].scope != 0);
+            let parameter_detail = format!("{}: parameter of {}", token.literal, detail);
+            let symbol = self.add(
+                &token.literal,
+                SymbolKind::Parameter,
+                token.span,
+                parameter_detail,
+            );
+            self.analysis.symbols[symbol].shadows = outer;
+        }
+        self.collect_lets(&body.statements);
+        self.node(&function.body);
+        self.stack.pop();
+    }
+
+    fn call(&mut self, call: &CallExpression, span: Span) {
+        match call.func_identifier.downcast_ref::<IdentifierExpression>() {
+            Some(callee) => {
+                let symbol = self.resolve(&callee.token.literal, callee.token.span);
+                if let Some(symbol) = symbol {
+                    self.analysis.calls.push(Call {
+                        span,
+                        symbol,
+                        arguments: call.arguments.len(),
+                    });
+                }
+            }
+            None => self.node(&call.func_identifier),
+        }
+        for argument in call.arguments.iter() {
+            self.node(argument);
+        }
+    }
+}
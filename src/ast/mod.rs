@@ -9,11 +9,18 @@ It represents the program in a way that can be evaluated.
 // Imports
 // ================================================================================
 
+use super::builtins;
 use super::enviroment::Enviroment;
+use super::hook;
+use super::interpreter;
+use super::interpreter::ExecutionState;
 
+use super::lexer::token::Precedence;
+use super::lexer::token::Span;
 use super::lexer::token::TokenType;
 use super::lexer::token::Token;
 
+use super::object::ErrorKind;
 use super::object::ObjectType;
 use super::object::Object;
 use super::object;
@@ -22,41 +29,332 @@ use super::object;
 use downcast_rs::Downcast;
 
 use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::rc::Rc;
 
 // ================================================================================
 // Traits for the type of nodes that make up the AST.
 // ================================================================================
 
-#[derive(PartialEq)]
+// The name of a variant, such as LetStatement, is printed by the tracer.
+#[derive(PartialEq, Debug)]
 pub enum NodeType {
     LetStatement,
     ReturnStatement,
     ExpressionStatement,
     BlockStatement,
+    ThrowStatement,
+    ImportStatement,
+    ExportStatement,
 
     IdentifierExpression,
     IntegralExpression,
     BoolExpression,
+    StringExpression,
+    ArrayExpression,
     PrefixExpression,
     InfixExpression,
     IfElseExpression,
     FunctionExpression,
     CallExpression,
+    IndexExpression,
+    MemberExpression,
+    TryExpression,
+    PostfixExpression,
 }
 
+// Every NodeType, so that names given on the command line can be checked.
+pub const NODE_TYPES: [NodeType; 21] = [
+    NodeType::LetStatement,
+    NodeType::ReturnStatement,
+    NodeType::ExpressionStatement,
+    NodeType::BlockStatement,
+    NodeType::ThrowStatement,
+    NodeType::ImportStatement,
+    NodeType::ExportStatement,
+    NodeType::IdentifierExpression,
+    NodeType::IntegralExpression,
+    NodeType::BoolExpression,
+    NodeType::StringExpression,
+    NodeType::ArrayExpression,
+    NodeType::PrefixExpression,
+    NodeType::InfixExpression,
+    NodeType::IfElseExpression,
+    NodeType::FunctionExpression,
+    NodeType::CallExpression,
+    NodeType::IndexExpression,
+    NodeType::MemberExpression,
+    NodeType::TryExpression,
+    NodeType::PostfixExpression,
+];
+
 pub trait Node: Downcast {
     fn get_type(&self) -> NodeType;
 
+    // A debugging form which shows the structure of the tree.
     fn to_string(&self) -> String;
 
-    // The eval function evaluautes the current node and then passes the result (which is of type
+    // Source code which parses back into the same tree. Only the parentheses
+    // needed to keep the precedence of the tree are printed.
+    fn to_source(&self) -> String;
+
+    // The eval_node function evaluautes the current node and then passes the result (which is of type
     // Object) up the AST to its parent node. Eventually the result of the program is at the root node.
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object>;
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object>;
 }
 
 impl_downcast!(Node);
 
+impl dyn Node {
+    // Every node is evaluated through eval which tells the hooks about it and
+    // accounts for the work done against the execution limits.
+    pub fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let state = env.borrow().state.clone();
+        let hooks = state.borrow().hooks.clone();
+        hook::before_node(&hooks, self, &env);
+        let result = self.eval_counted(env.clone(), &state);
+        hook::after_node(&hooks, self, &env, &result);
+        return result;
+    }
+
+    fn eval_counted(
+        &self,
+        env: Rc<RefCell<Enviroment>>,
+        state: &Rc<RefCell<ExecutionState>>,
+    ) -> Rc<dyn Object> {
+        let exceeded = state.borrow_mut().step();
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+
+        let result = self.eval_node(env);
+
+        // Once a limit has been exceeded its error replaces whatever the node
+        // produced so that it reaches the top level. The error is kept if it was
+        // passed up directly since it may have gained stack frames on the way.
+        let exceeded = state.borrow().exceeded();
+        if let Some(exceeded) = exceeded {
+            if is_stopping_error(&result) {
+                return result;
+            }
+            return exceeded;
+        }
+
+        // A result that is not shared with anything else was allocated by this node.
+        if Rc::strong_count(&result) == 1 {
+            let exceeded = state.borrow_mut().allocate(1);
+            if let Some(exceeded) = exceeded {
+                return exceeded;
+            }
+        }
+        return result;
+    }
+}
+
+// Errors and return values are passed straight up the AST by every node
+// until they are caught or reach the function call.
+fn is_unwinding(result: &Rc<dyn Object>) -> bool {
+    return result.get_type() == ObjectType::Error || result.get_type() == ObjectType::ReturnValue;
+}
+
+// The precedence a node is parsed with, used to decide where parentheses
+// are needed when printing it as source. Literals and expressions which start
+// with a keyword never need them.
+#[allow(clippy::borrowed_box)]
+pub fn precedence(node: &Box<dyn Node>) -> u8 {
+    return match node.get_type() {
+        NodeType::InfixExpression => {
+            let infix = node.downcast_ref::<InfixExpression>().unwrap();
+            infix.token.get_precedence() as u8
+        }
+        NodeType::PrefixExpression => Precedence::Prefix as u8,
+        NodeType::PostfixExpression => Precedence::Postfix as u8,
+        NodeType::CallExpression | NodeType::IndexExpression | NodeType::MemberExpression => {
+            Precedence::Call as u8
+        }
+        _ => Precedence::Call as u8 + 1,
+    };
+}
+
+// Where a node starts in the source. Nodes made outside of the parser
+// start at 0:0.
+#[allow(clippy::borrowed_box)]
+pub fn start_span(node: &Box<dyn Node>) -> Span {
+    return node_span(&**node);
+}
+
+// The start_span of a node that is not boxed.
+pub fn node_span(node: &dyn Node) -> Span {
+    return match node.get_type() {
+        NodeType::LetStatement => node.downcast_ref::<LetStatement>().unwrap().token.span,
+        NodeType::ReturnStatement => node.downcast_ref::<ReturnStatement>().unwrap().token.span,
+        NodeType::ExpressionStatement => {
+            node.downcast_ref::<ExpressionStatement>()
+                .unwrap()
+                .token
+                .span
+        }
+        NodeType::BlockStatement => node.downcast_ref::<BlockStatement>().unwrap().token.span,
+        NodeType::ThrowStatement => node.downcast_ref::<ThrowStatement>().unwrap().token.span,
+        NodeType::ImportStatement => node.downcast_ref::<ImportStatement>().unwrap().token.span,
+        NodeType::ExportStatement => node.downcast_ref::<ExportStatement>().unwrap().token.span,
+        NodeType::IdentifierExpression => {
+            node.downcast_ref::<IdentifierExpression>()
+                .unwrap()
+                .token
+                .span
+        }
+        NodeType::IntegralExpression => {
+            node.downcast_ref::<IntegralExpression>()
+                .unwrap()
+                .token
+                .span
+        }
+        NodeType::BoolExpression => node.downcast_ref::<BoolExpression>().unwrap().token.span,
+        NodeType::StringExpression => node.downcast_ref::<StringExpression>().unwrap().token.span,
+        NodeType::ArrayExpression => node.downcast_ref::<ArrayExpression>().unwrap().token.span,
+        NodeType::PrefixExpression => node.downcast_ref::<PrefixExpression>().unwrap().token.span,
+        NodeType::InfixExpression => {
+            start_span(&node.downcast_ref::<InfixExpression>().unwrap().left)
+        }
+        NodeType::IfElseExpression => node.downcast_ref::<IfElseExpression>().unwrap().token.span,
+        NodeType::FunctionExpression => {
+            node.downcast_ref::<FunctionExpression>()
+                .unwrap()
+                .token
+                .span
+        }
+        NodeType::CallExpression => start_span(
+            &node
+                .downcast_ref::<CallExpression>()
+                .unwrap()
+                .func_identifier,
+        ),
+        NodeType::IndexExpression => {
+            start_span(&node.downcast_ref::<IndexExpression>().unwrap().left)
+        }
+        NodeType::MemberExpression => {
+            start_span(&node.downcast_ref::<MemberExpression>().unwrap().object)
+        }
+        NodeType::TryExpression => node.downcast_ref::<TryExpression>().unwrap().token.span,
+        NodeType::PostfixExpression => {
+            start_span(&node.downcast_ref::<PostfixExpression>().unwrap().left)
+        }
+    };
+}
+
+// The nodes directly inside a node in the order they appear in the source.
+// The identifiers of lets, imports, members and catches are not nodes and
+// are not included.
+#[allow(clippy::borrowed_box)]
+pub fn children(node: &Box<dyn Node>) -> Vec<&Box<dyn Node>> {
+    return match node.get_type() {
+        NodeType::LetStatement => vec![&node.downcast_ref::<LetStatement>().unwrap().value],
+        NodeType::ReturnStatement => vec![&node.downcast_ref::<ReturnStatement>().unwrap().value],
+        NodeType::ExpressionStatement => {
+            vec![&node.downcast_ref::<ExpressionStatement>().unwrap().value]
+        }
+        NodeType::BlockStatement => node
+            .downcast_ref::<BlockStatement>()
+            .unwrap()
+            .statements
+            .iter()
+            .collect(),
+        NodeType::ThrowStatement => vec![&node.downcast_ref::<ThrowStatement>().unwrap().value],
+        NodeType::ExportStatement => {
+            vec![
+                &node
+                    .downcast_ref::<ExportStatement>()
+                    .unwrap()
+                    .statement
+                    .value,
+            ]
+        }
+        NodeType::ArrayExpression => node
+            .downcast_ref::<ArrayExpression>()
+            .unwrap()
+            .elements
+            .iter()
+            .collect(),
+        NodeType::PrefixExpression => vec![&node.downcast_ref::<PrefixExpression>().unwrap().right],
+        NodeType::InfixExpression => {
+            let infix = node.downcast_ref::<InfixExpression>().unwrap();
+            vec![&infix.left, &infix.right]
+        }
+        NodeType::IfElseExpression => {
+            let ifelse = node.downcast_ref::<IfElseExpression>().unwrap();
+            let mut to_return = vec![&ifelse.condition, &ifelse.consequence];
+            to_return.extend(ifelse.alternative.iter());
+            to_return
+        }
+        NodeType::FunctionExpression => {
+            let function = node.downcast_ref::<FunctionExpression>().unwrap();
+            let mut to_return: Vec<&Box<dyn Node>> = function.parameters.iter().collect();
+            to_return.push(&function.body);
+            to_return
+        }
+        NodeType::CallExpression => {
+            let call = node.downcast_ref::<CallExpression>().unwrap();
+            let mut to_return = vec![&call.func_identifier];
+            to_return.extend(call.arguments.iter());
+            to_return
+        }
+        NodeType::IndexExpression => {
+            let index = node.downcast_ref::<IndexExpression>().unwrap();
+            vec![&index.left, &index.index]
+        }
+        NodeType::MemberExpression => {
+            vec![&node.downcast_ref::<MemberExpression>().unwrap().object]
+        }
+        NodeType::PostfixExpression => {
+            vec![&node.downcast_ref::<PostfixExpression>().unwrap().left]
+        }
+        NodeType::TryExpression => {
+            let expression = node.downcast_ref::<TryExpression>().unwrap();
+            let mut to_return = vec![&expression.body];
+            to_return.extend(expression.catch_body.iter());
+            to_return.extend(expression.finally_body.iter());
+            to_return
+        }
+        NodeType::ImportStatement
+        | NodeType::IdentifierExpression
+        | NodeType::IntegralExpression
+        | NodeType::BoolExpression
+        | NodeType::StringExpression => Vec::new(),
+    };
+}
+
+// Print an operand that is parsed with min_precedence as source, adding
+// parentheses if it binds less tightly.
+#[allow(clippy::borrowed_box)]
+fn operand_source(node: &Box<dyn Node>, min_precedence: u8) -> String {
+    if precedence(node) < min_precedence {
+        return format!("({})", node.to_source());
+    }
+    return node.to_source();
+}
+
+// Escape a string value so that it can be written between quotes.
+pub fn escape_string(value: &str) -> String {
+    let mut to_return = String::new();
+    for c in value.chars() {
+        match c {
+            '\\' => to_return.push_str("\\\\"),
+            '"' => to_return.push_str("\\\""),
+            '\n' => to_return.push_str("\\n"),
+            '\t' => to_return.push_str("\\t"),
+            _ => to_return.push(c),
+        }
+    }
+    return to_return;
+}
+
+fn is_stopping_error(result: &Rc<dyn Object>) -> bool {
+    return result
+        .downcast_ref::<object::Error>()
+        .is_some_and(|error| error.kind.stops_execution());
+}
+
 // ================================================================================
 // Nodes.
 // ================================================================================
@@ -65,23 +363,40 @@ impl_downcast!(Node);
 
 pub struct LetStatement {
     pub token: Token,
-    pub value: Box<Node>,
+    pub value: Box<dyn Node>,
     pub identifier: IdentifierExpression,
 }
 
 pub struct ReturnStatement {
     pub token: Token,
-    pub value: Box<Node>,
+    pub value: Box<dyn Node>,
 }
 
 pub struct ExpressionStatement {
     pub token: Token,
-    pub value: Box<Node>,
+    pub value: Box<dyn Node>,
 }
 
 pub struct BlockStatement {
+    pub token: Token, // The opening brace.
+    pub statements: Vec<Box<dyn Node>>,
+    pub end: Span, // Location of the closing brace.
+}
+
+pub struct ThrowStatement {
+    pub token: Token,
+    pub value: Box<dyn Node>,
+}
+
+pub struct ImportStatement {
     pub token: Token,
-    pub statements: Vec<Box<Node>>,
+    pub path: String, // Path of the module with the quotes removed.
+    pub alias: IdentifierExpression,
+}
+
+pub struct ExportStatement {
+    pub token: Token,
+    pub statement: Box<LetStatement>,
 }
 
 // Expression nodes.
@@ -100,34 +415,69 @@ pub struct BoolExpression {
     pub value: bool,
 }
 
+pub struct StringExpression {
+    pub token: Token,
+    pub value: String, // Literal with the quotes removed and escapes converted.
+}
+
+pub struct ArrayExpression {
+    pub token: Token,
+    pub elements: Vec<Box<dyn Node>>,
+}
+
 pub struct PrefixExpression {
     pub token: Token,
-    pub right: Box<Node>,
+    pub right: Box<dyn Node>,
 }
 
 pub struct InfixExpression {
     pub token: Token,
-    pub left: Box<Node>,
-    pub right: Box<Node>,
+    pub left: Box<dyn Node>,
+    pub right: Box<dyn Node>,
 }
 
 pub struct IfElseExpression {
     pub token: Token,
-    pub condition: Box<Node>,
-    pub consequence: Box<Node>,
-    pub alternative: Option<Box<Node>>,
+    pub condition: Box<dyn Node>,
+    pub consequence: Box<dyn Node>,
+    pub alternative: Option<Box<dyn Node>>,
 }
 
 pub struct FunctionExpression {
     pub token: Token,
-    pub parameters: Rc<Vec<Box<Node>>>,
-    pub body: Rc<Box<Node>>,
+    pub parameters: Rc<Vec<Box<dyn Node>>>,
+    pub body: Rc<Box<dyn Node>>,
 }
 
 pub struct CallExpression {
     pub token: Token,
-    pub arguments: Vec<Box<Node>>,
-    pub func_identifier: Box<Node>, // ast::IdentifierExpression
+    pub arguments: Vec<Box<dyn Node>>,
+    pub func_identifier: Box<dyn Node>, // ast::IdentifierExpression
+}
+
+pub struct IndexExpression {
+    pub token: Token,
+    pub left: Box<dyn Node>,
+    pub index: Box<dyn Node>,
+}
+
+pub struct MemberExpression {
+    pub token: Token,
+    pub object: Box<dyn Node>,
+    pub member: IdentifierExpression,
+}
+
+pub struct PostfixExpression {
+    pub token: Token,
+    pub left: Box<dyn Node>,
+}
+
+pub struct TryExpression {
+    pub token: Token,
+    pub body: Box<dyn Node>,
+    pub catch_identifier: Option<IdentifierExpression>,
+    pub catch_body: Option<Box<dyn Node>>,
+    pub finally_body: Option<Box<dyn Node>>,
 }
 
 // ================================================================================
@@ -141,13 +491,29 @@ impl Node for LetStatement {
         return NodeType::LetStatement;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         // value is the expression to the right of the equals sign.
         let value = self.value.eval(env.clone());
-        if value.get_type() == ObjectType::Error {
+        if is_unwinding(&value) {
             return value;
         }
 
+        // Functions take the name they are first bound to for use in stack traces.
+        let function = value
+            .downcast_ref::<object::Function>()
+            .filter(|function| function.name.is_none());
+        let value = if let Some(function) = function {
+            Rc::new(object::Function {
+                name: Some(self.identifier.token.literal.clone()),
+                span: function.span,
+                env: function.env.clone(),
+                body: function.body.clone(),
+                parameters: function.parameters.clone(),
+            })
+        } else {
+            value
+        };
+
         // Add value to the enviroment.
         env.borrow_mut()
             .insert(self.identifier.token.literal.clone(), value);
@@ -163,6 +529,14 @@ impl Node for LetStatement {
             self.value.to_string()
         );
     }
+
+    fn to_source(&self) -> String {
+        return format!(
+            "let {} = {};",
+            self.identifier.token.literal,
+            self.value.to_source()
+        );
+    }
 }
 
 impl Node for ReturnStatement {
@@ -170,13 +544,21 @@ impl Node for ReturnStatement {
         return NodeType::ReturnStatement;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
-        return self.value.eval(env);
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let value = self.value.eval(env);
+        if is_unwinding(&value) {
+            return value;
+        }
+        return Rc::new(object::ReturnValue { value });
     }
 
     fn to_string(&self) -> String {
         return format!("[{} {}]", self.token, self.value.to_string());
     }
+
+    fn to_source(&self) -> String {
+        return format!("return {};", self.value.to_source());
+    }
 }
 
 impl Node for ExpressionStatement {
@@ -184,13 +566,17 @@ impl Node for ExpressionStatement {
         return NodeType::ExpressionStatement;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         return self.value.eval(env);
     }
 
     fn to_string(&self) -> String {
         return format!("[{}]", self.value.to_string());
     }
+
+    fn to_source(&self) -> String {
+        return format!("{};", self.value.to_source());
+    }
 }
 
 impl Node for BlockStatement {
@@ -200,11 +586,11 @@ impl Node for BlockStatement {
 
     // Each line the the block statement is evaluated as its own AST
     // but with the same env.
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
-        let mut result: Rc<Object> = Rc::new(object::Null {});
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let mut result: Rc<dyn Object> = Rc::new(object::Null {});
         for statement in self.statements.iter().by_ref() {
             result = statement.eval(env.clone());
-            if statement.get_type() == NodeType::ReturnStatement {
+            if is_unwinding(&result) {
                 return result;
             }
         }
@@ -219,6 +605,98 @@ impl Node for BlockStatement {
         }
         return to_return;
     }
+
+    fn to_source(&self) -> String {
+        if self.statements.is_empty() {
+            return "{}".to_string();
+        }
+        let statements: Vec<String> = self.statements.iter().map(|x| x.to_source()).collect();
+        return format!("{{ {} }}", statements.join(" "));
+    }
+}
+
+impl Node for ThrowStatement {
+    fn get_type(&self) -> NodeType {
+        return NodeType::ThrowStatement;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let value = self.value.eval(env);
+        if is_unwinding(&value) {
+            return value;
+        }
+
+        // Throwing a caught error raises the original error again.
+        let caught = value.downcast_ref::<object::ErrorValue>();
+        if let Some(caught) = caught {
+            return caught.error.clone();
+        }
+        return Rc::new(object::Error::thrown(value));
+    }
+
+    fn to_string(&self) -> String {
+        return format!("[{} {}]", self.token, self.value.to_string());
+    }
+
+    fn to_source(&self) -> String {
+        return format!("throw {};", self.value.to_source());
+    }
+}
+
+impl Node for ImportStatement {
+    fn get_type(&self) -> NodeType {
+        return NodeType::ImportStatement;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let module = interpreter::import_module(&self.path, &env);
+        if is_unwinding(&module) {
+            return module;
+        }
+        env.borrow_mut()
+            .insert(self.alias.token.literal.clone(), module);
+        return Rc::new(object::Null {});
+    }
+
+    fn to_string(&self) -> String {
+        return format!(
+            "[{} {:?} as {}]",
+            self.token,
+            self.path,
+            self.alias.to_string()
+        );
+    }
+
+    fn to_source(&self) -> String {
+        return format!("import \"{}\" as {};", self.path, self.alias.token.literal);
+    }
+}
+
+impl Node for ExportStatement {
+    fn get_type(&self) -> NodeType {
+        return NodeType::ExportStatement;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let statement: &dyn Node = &*self.statement;
+        let result = statement.eval(env.clone());
+        if is_unwinding(&result) {
+            return result;
+        }
+        let name = self.statement.identifier.token.literal.clone();
+        if !env.borrow().exports.contains(&name) {
+            env.borrow_mut().exports.push(name);
+        }
+        return result;
+    }
+
+    fn to_string(&self) -> String {
+        return format!("[{} {}]", self.token, self.statement.to_string());
+    }
+
+    fn to_source(&self) -> String {
+        return format!("export {}", self.statement.to_source());
+    }
 }
 
 // Implement Node for each expression.
@@ -228,13 +706,17 @@ impl Node for IntegralExpression {
         return NodeType::IntegralExpression;
     }
 
-    fn eval(&self, _env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         return Rc::new(object::Integer { value: self.value });
     }
 
     fn to_string(&self) -> String {
         return format!("{}", self.token);
     }
+
+    fn to_source(&self) -> String {
+        return self.value.to_string();
+    }
 }
 
 impl Node for BoolExpression {
@@ -242,13 +724,65 @@ impl Node for BoolExpression {
         return NodeType::BoolExpression;
     }
 
-    fn eval(&self, _env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         return Rc::new(object::Boolean { value: self.value });
     }
 
     fn to_string(&self) -> String {
         return format!("{}", self.token);
     }
+
+    fn to_source(&self) -> String {
+        return self.value.to_string();
+    }
+}
+
+impl Node for StringExpression {
+    fn get_type(&self) -> NodeType {
+        return NodeType::StringExpression;
+    }
+
+    fn eval_node(&self, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        return Rc::new(object::Str {
+            value: self.value.clone(),
+        });
+    }
+
+    fn to_string(&self) -> String {
+        return format!("{}", self.token);
+    }
+
+    fn to_source(&self) -> String {
+        return format!("\"{}\"", escape_string(&self.value));
+    }
+}
+
+impl Node for ArrayExpression {
+    fn get_type(&self) -> NodeType {
+        return NodeType::ArrayExpression;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let mut elements: Vec<Rc<dyn Object>> = Vec::new();
+        for element in self.elements.iter() {
+            let value = element.eval(env.clone());
+            if is_unwinding(&value) {
+                return value;
+            }
+            elements.push(value);
+        }
+        return Rc::new(object::Array { elements });
+    }
+
+    fn to_string(&self) -> String {
+        let elements: Vec<String> = self.elements.iter().map(|x| x.to_string()).collect();
+        return format!("[{}]", elements.join(", "));
+    }
+
+    fn to_source(&self) -> String {
+        let elements: Vec<String> = self.elements.iter().map(|x| x.to_source()).collect();
+        return format!("[{}]", elements.join(", "));
+    }
 }
 
 impl Node for IdentifierExpression {
@@ -256,92 +790,68 @@ impl Node for IdentifierExpression {
         return NodeType::IdentifierExpression;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         // Fetch value of iderntifier from enviroment.
-        let value = env.borrow().get(&self.token.literal);
+        let mut value = env.borrow().get(&self.token.literal);
         // let value = borrowed_env.get(&self.token.literal);
 
+        // Fall back on the builtin functions.
         if value.is_none() {
-            return Rc::new(object::Error {
-                message: format!("Variable {} is not in scope.", self.token.literal).to_string(),
-            });
+            value = builtins::lookup(&self.token.literal);
         }
 
-        // Convert the value to the correct object type.
-        if value.as_ref().unwrap().get_type() == ObjectType::Integer {
-            let int_value = value
-                .as_ref()
-                .unwrap()
-                .downcast_ref::<object::Integer>()
-                .unwrap();
-            return Rc::new(object::Integer {
-                value: int_value.value,
-            });
-        }
-        if value.as_ref().unwrap().get_type() == ObjectType::Boolean {
-            let bool_value = value
-                .as_ref()
-                .unwrap()
-                .downcast_ref::<object::Boolean>()
-                .unwrap();
-            return Rc::new(object::Boolean {
-                value: bool_value.value,
-            });
-        }
-        if value.as_ref().unwrap().get_type() == ObjectType::Function {
-            let func_value = value
-                .as_ref()
-                .unwrap()
-                .downcast_ref::<object::Function>()
-                .unwrap();
-            return Rc::new(object::Function {
-                env: func_value.env.clone(),
-                body: func_value.body.clone(),
-                parameters: func_value.parameters.clone(),
-            });
+        if value.is_none() {
+            return Rc::new(object::Error::new(
+                ErrorKind::Name,
+                format!("Variable {} is not in scope.", self.token.literal).to_string(),
+            ));
         }
 
-        return Rc::new(object::Error {
-            message: format!(
-                "Variable {} in scope but there was an error extracting it from the enviroment.",
-                self.token.literal
-            )
-            .to_string(),
-        });
+        return value.unwrap();
     }
 
     fn to_string(&self) -> String {
         return format!("{}", self.token);
     }
+
+    fn to_source(&self) -> String {
+        return self.token.literal.clone();
+    }
 }
 
 impl PrefixExpression {
-    fn eval_minus_operator(&self, right: &Rc<Object>) -> Rc<Object> {
+    fn eval_minus_operator(&self, right: &Rc<dyn Object>) -> Rc<dyn Object> {
         let operand = right.downcast_ref::<object::Integer>();
-        if operand.is_some() {
-            let to_return = object::Integer {
-                value: -operand.unwrap().value,
+        if let Some(operand) = operand {
+            // The smallest integer has no positive counterpart.
+            return match operand.value.checked_neg() {
+                Some(value) => Rc::new(object::Integer { value }),
+                None => Rc::new(object::Error::new(
+                    ErrorKind::Runtime,
+                    "Integer overflow.".to_string(),
+                )),
             };
-            return Rc::new(to_return);
         }
         // The downcast was not successful so the operand is not an integer.
-        return Rc::new(object::Error {
-            message: "Error: Prefix operand is not an integer as expected".to_string(),
-        });
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            "Error: Prefix operand is not an integer as expected".to_string(),
+        ));
     }
 
-    fn eval_bang_operator(&self, right: &Rc<Object>) -> Rc<Object> {
+    fn eval_bang_operator(&self, right: &Rc<dyn Object>) -> Rc<dyn Object> {
         let operand = right.downcast_ref::<object::Boolean>();
-        if operand.is_some() {
+        if let Some(operand) = operand {
             let to_return = object::Boolean {
-                value: !operand.unwrap().value,
+                value: !operand.value,
             };
             return Rc::new(to_return);
         }
         // The downcast was not successful so the operand is not an bool.
-        return Rc::new(object::Error {
-            message: "Error: Prefix operand is not a boolean as expected".to_string(),
-        });
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            "Error: Prefix operand is not a boolean as expected".to_string(),
+        ));
     }
 }
 
@@ -350,9 +860,9 @@ impl Node for PrefixExpression {
         return NodeType::PrefixExpression;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         let right = self.right.eval(env.clone());
-        if right.get_type() == ObjectType::Error {
+        if is_unwinding(&right) {
             return right;
         }
 
@@ -364,9 +874,10 @@ impl Node for PrefixExpression {
                 return self.eval_minus_operator(&right);
             }
             _ => {
-                return Rc::new(object::Error {
-                    message: "Error: Not a valid prefix operator.".to_string(),
-                });
+                return Rc::new(object::Error::new(
+                    ErrorKind::Runtime,
+                    "Error: Not a valid prefix operator.".to_string(),
+                ));
             }
         }
     }
@@ -374,10 +885,22 @@ impl Node for PrefixExpression {
     fn to_string(&self) -> String {
         return format!("[{} {}]", self.token, self.right.to_string());
     }
+
+    fn to_source(&self) -> String {
+        return format!(
+            "{}{}",
+            self.token,
+            operand_source(&self.right, Precedence::Prefix as u8)
+        );
+    }
 }
 
 impl InfixExpression {
-    fn eval_boolean_infix_expression(&self, left: &Rc<Object>, right: &Rc<Object>) -> Rc<Object> {
+    fn eval_boolean_infix_expression(
+        &self,
+        left: &Rc<dyn Object>,
+        right: &Rc<dyn Object>,
+    ) -> Rc<dyn Object> {
         // The type of left and right has already been checked so unwrap can be done safely.
         let l_value = left.downcast_ref::<object::Boolean>().unwrap().value;
         let r_value = right.downcast_ref::<object::Boolean>().unwrap().value;
@@ -388,13 +911,65 @@ impl InfixExpression {
             TokenType::NotEqual => Some(l_value != r_value),
             _ => None,
         };
+        if value.is_none() {
+            return Rc::new(object::Error::new(
+                ErrorKind::Type,
+                format!("Operator {} is not supported for booleans.", self.token),
+            ));
+        }
 
         return Rc::new(object::Boolean {
             value: value.unwrap(),
         });
     }
 
-    fn eval_integer_infix_expression(&self, left: &Rc<Object>, right: &Rc<Object>) -> Rc<Object> {
+    fn eval_string_infix_expression(
+        &self,
+        left: &Rc<dyn Object>,
+        right: &Rc<dyn Object>,
+        env: &Rc<RefCell<Enviroment>>,
+    ) -> Rc<dyn Object> {
+        // The type of left and right has already been checked so unwrap can be done safely.
+        let l_value = &left.downcast_ref::<object::Str>().unwrap().value;
+        let r_value = &right.downcast_ref::<object::Str>().unwrap().value;
+
+        match self.token.token_type {
+            TokenType::Plus => {
+                // The string itself is counted by eval and its characters here.
+                let state = env.borrow().state.clone();
+                let length = l_value.chars().count() + r_value.chars().count();
+                let exceeded = state.borrow_mut().allocate(length as u64);
+                if let Some(exceeded) = exceeded {
+                    return exceeded;
+                }
+                return Rc::new(object::Str {
+                    value: format!("{}{}", l_value, r_value),
+                });
+            }
+            TokenType::Equal => {
+                return Rc::new(object::Boolean {
+                    value: l_value == r_value,
+                })
+            }
+            TokenType::NotEqual => {
+                return Rc::new(object::Boolean {
+                    value: l_value != r_value,
+                })
+            }
+            _ => {
+                return Rc::new(object::Error::new(
+                    ErrorKind::Type,
+                    format!("Operator {} is not supported for strings.", self.token),
+                ));
+            }
+        }
+    }
+
+    fn eval_integer_infix_expression(
+        &self,
+        left: &Rc<dyn Object>,
+        right: &Rc<dyn Object>,
+    ) -> Rc<dyn Object> {
         // The type of left and right has already been checked so unwrap can be done safely.
         let l_value = left.downcast_ref::<object::Integer>().unwrap().value;
         let r_value = right.downcast_ref::<object::Integer>().unwrap().value;
@@ -402,15 +977,29 @@ impl InfixExpression {
         // Two matches are required to deduce the operator because some operators
         // can result in integers or booleans.
 
+        if self.token.token_type == TokenType::Slash && r_value == 0 {
+            return Rc::new(object::Error::new(
+                ErrorKind::DivisionByZero,
+                "Division by zero.".to_string(),
+            ));
+        }
+
         // Check if operand is +, -, * or /
+        // The checked operations return None if the result overflows.
         let int_value = match self.token.token_type {
-            TokenType::Plus => Some(l_value + r_value),
-            TokenType::Minus => Some(l_value - r_value),
-            TokenType::Asterisk => Some(l_value * r_value),
-            TokenType::Slash => Some(l_value / r_value),
+            TokenType::Plus => Some(l_value.checked_add(r_value)),
+            TokenType::Minus => Some(l_value.checked_sub(r_value)),
+            TokenType::Asterisk => Some(l_value.checked_mul(r_value)),
+            TokenType::Slash => Some(l_value.checked_div(r_value)),
             _ => None,
         };
-        if int_value.is_some() {
+        if let Some(int_value) = int_value {
+            if int_value.is_none() {
+                return Rc::new(object::Error::new(
+                    ErrorKind::Runtime,
+                    "Integer overflow.".to_string(),
+                ));
+            }
             return Rc::new(object::Integer {
                 value: int_value.unwrap(),
             });
@@ -424,14 +1013,13 @@ impl InfixExpression {
             TokenType::NotEqual => Some(l_value != r_value),
             _ => None,
         };
-        if bool_value.is_some() {
-            return Rc::new(object::Boolean {
-                value: bool_value.unwrap(),
-            });
+        if let Some(bool_value) = bool_value {
+            return Rc::new(object::Boolean { value: bool_value });
         }
-        return Rc::new(object::Error {
-            message: "Integer infix being evaluated with invalid operand.".to_string(),
-        });
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            "Integer infix being evaluated with invalid operand.".to_string(),
+        ));
     }
 }
 
@@ -440,9 +1028,17 @@ impl Node for InfixExpression {
         return NodeType::InfixExpression;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        // The right operand is evaluated first and the left one is not
+        // evaluated at all if it fails.
         let r_operand = self.right.eval(env.clone());
+        if is_unwinding(&r_operand) {
+            return r_operand;
+        }
         let l_operand = self.left.eval(env.clone());
+        if is_unwinding(&l_operand) {
+            return l_operand;
+        }
 
         // Check if it is an integer operation.
         if r_operand.get_type() == ObjectType::Integer
@@ -456,11 +1052,17 @@ impl Node for InfixExpression {
         {
             return self.eval_boolean_infix_expression(&l_operand, &r_operand);
         }
+        // Check if it is a string operation.
+        else if r_operand.get_type() == ObjectType::Str && l_operand.get_type() == ObjectType::Str
+        {
+            return self.eval_string_infix_expression(&l_operand, &r_operand, &env);
+        }
         // Error
         else {
-            return Rc::new(object::Error {
-                message: "The operand types don't match.".to_string(),
-            });
+            return Rc::new(object::Error::new(
+                ErrorKind::Type,
+                "The operand types don't match.".to_string(),
+            ));
         }
     }
 
@@ -472,6 +1074,18 @@ impl Node for InfixExpression {
             self.right.to_string()
         );
     }
+
+    fn to_source(&self) -> String {
+        // Operators are left associative so the right operand needs
+        // parentheses when its precedence is equal.
+        let precedence = self.token.get_precedence() as u8;
+        return format!(
+            "{} {} {}",
+            operand_source(&self.left, precedence),
+            self.token,
+            operand_source(&self.right, precedence + 1)
+        );
+    }
 }
 
 impl Node for IfElseExpression {
@@ -479,22 +1093,23 @@ impl Node for IfElseExpression {
         return NodeType::IfElseExpression;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         // Evaluate the condition.
         let condition = self.condition.eval(env.clone());
         if condition.get_type() != ObjectType::Boolean {
-            if condition.get_type() == ObjectType::Error {
+            if is_unwinding(&condition) {
                 return condition;
             } else {
-                return Rc::new(object::Error {
-                    message: "Condition is not of boolean type.".to_string(),
-                });
+                return Rc::new(object::Error::new(
+                    ErrorKind::Type,
+                    "Condition is not of boolean type.".to_string(),
+                ));
             }
         }
 
         // Given condition evaluate the correct block statement.
         let bool_condition = condition.downcast_ref::<object::Boolean>().unwrap();
-        if bool_condition.value == true {
+        if bool_condition.value {
             return self.consequence.eval(env.clone());
         }
         // Evaluate alternative it is exists.
@@ -524,6 +1139,19 @@ impl Node for IfElseExpression {
             self.alternative.as_ref().unwrap().to_string()
         );
     }
+
+    fn to_source(&self) -> String {
+        let mut to_return = format!(
+            "if ({}) {}",
+            self.condition.to_source(),
+            self.consequence.to_source()
+        );
+        if let Some(alternative) = &self.alternative {
+            to_return.push_str(" else ");
+            to_return.push_str(&alternative.to_source());
+        }
+        return to_return;
+    }
 }
 
 impl Node for FunctionExpression {
@@ -531,9 +1159,11 @@ impl Node for FunctionExpression {
         return NodeType::FunctionExpression;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         // Return function object.
         let to_return = object::Function {
+            name: None,
+            span: self.token.span,
             body: self.body.clone(),
             parameters: self.parameters.clone(),
             // Create new enviroment which holds data within the function's scope.
@@ -549,22 +1179,98 @@ impl Node for FunctionExpression {
             to_return.push_str(&par.to_string());
             to_return.push(',')
         }
-        if self.parameters.len() != 0 {
+        if !self.parameters.is_empty() {
             to_return.pop(); // Remove trailing comma
         }
-        to_return.push_str(&")\n".to_string());
+        to_return.push_str(")\n");
         to_return.push_str(&self.body.to_string());
         return to_return;
     }
+
+    fn to_source(&self) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(|x| x.to_source()).collect();
+        return format!("fn({}) {}", parameters.join(", "), self.body.to_source());
+    }
+}
+
+// Call func, which must be a function written in rust-monkey, with args. span
+// is where it was called from and is used in the stack trace.
+pub fn call_function(
+    func: Rc<dyn Object>,
+    args: Vec<Rc<dyn Object>>,
+    span: Span,
+) -> Rc<dyn Object> {
+    let function_object = func.downcast_ref::<object::Function>();
+    if function_object.is_none() {
+        return Rc::new(object::Error::new(
+            ErrorKind::Runtime,
+            "Error in calling function".to_string().to_string(),
+        ));
+    }
+
+    // Create a new enviroment for the scope of the function being called.
+    // It is nested in the scope the function was defined in so that
+    // functions close over their variables and modules keep their own.
+    let outer_env = function_object.unwrap().env.clone();
+    let env = Rc::new(RefCell::new(Enviroment::new(Some(outer_env))));
+
+    // Keep the arguments for the stack trace in case the call fails.
+    let arguments = args.clone();
+
+    // Assign the value to the enviroment.
+    // parameters is a vector of identifier_expressions where token.literal is the name.
+    // par := the name of the parameter
+    // arg := the value being passed into the function.
+    for (par, arg) in function_object
+        .as_ref()
+        .unwrap()
+        .parameters
+        .iter()
+        .zip(args)
+    {
+        let parameter = par.downcast_ref::<IdentifierExpression>().unwrap();
+        env.borrow_mut()
+            .insert(parameter.token.literal.clone(), arg);
+    }
+
+    // Account for the call against the depth limit.
+    let state = env.borrow().state.clone();
+    let exceeded = state.borrow_mut().enter_call();
+    if let Some(exceeded) = exceeded {
+        state.borrow_mut().exit_call();
+        return exceeded;
+    }
+    let hooks = state.borrow().hooks.clone();
+    hook::enter_call(&hooks, function_object.unwrap(), span, &env);
+    let result = function_object.as_ref().unwrap().body.eval(env);
+    hook::exit_call(&hooks, function_object.unwrap(), &result);
+    state.borrow_mut().exit_call();
+
+    // Unwrap the value given to return.
+    let return_value = result.downcast_ref::<object::ReturnValue>();
+    if let Some(return_value) = return_value {
+        return return_value.value.clone();
+    }
+
+    // Pass the error on with this call added to its stack trace.
+    if result.get_type() == ObjectType::Error {
+        let error = result.downcast_ref::<object::Error>().unwrap();
+        return Rc::new(error.with_frame(object::Frame {
+            function: function_object.as_ref().unwrap().name.clone(),
+            span,
+            arguments,
+        }));
+    }
+    return result;
 }
 
 impl CallExpression {
-    fn eval_arguments(&self, env: Rc<RefCell<Enviroment>>) -> Vec<Rc<Object>> {
-        let mut to_return: Vec<Rc<Object>> = Vec::new();
-        let mut result: Rc<Object>;
+    fn eval_arguments(&self, env: Rc<RefCell<Enviroment>>) -> Vec<Rc<dyn Object>> {
+        let mut to_return: Vec<Rc<dyn Object>> = Vec::new();
+        let mut result: Rc<dyn Object>;
         for arg in self.arguments.iter().by_ref() {
             result = arg.eval(env.clone());
-            if result.get_type() == ObjectType::Error {
+            if is_unwinding(&result) {
                 to_return.clear();
                 to_return.push(result);
                 return to_return;
@@ -575,44 +1281,24 @@ impl CallExpression {
         return to_return;
     }
 
-    fn eval_func(
+    fn eval_builtin(
         &self,
-        func: Rc<Object>,
-        args: Vec<Rc<Object>>,
-        outer_env: Rc<RefCell<Enviroment>>,
-    ) -> Rc<Object> {
-        // Create a new enviroment for the scope of the function being called.
-        let env = Rc::new(RefCell::new(Enviroment::new(Some(outer_env.clone()))));
-
-        let function_object = func.downcast_ref::<object::Function>();
-        if function_object.is_none() {
-            return Rc::new(object::Error {
-                message: format!("Error in calling function").to_string(),
-            });
-        }
-
-        // Assign the value to the enviroment.
-        // parameters is a vector of identifier_expressions where token.literal is the name.
-        // par := the name of the parameter
-        // arg := the value being passed into the function.
-        for (par, arg) in function_object
-            .as_ref()
-            .unwrap()
-            .parameters
-            .iter()
-            .zip(args)
-        {
-            let parameter = par.downcast_ref::<IdentifierExpression>().unwrap();
-            env.borrow_mut()
-                .insert(parameter.token.literal.clone(), arg);
-        }
-
-        let result = function_object.as_ref().unwrap().body.eval(env);
-
+        func: Rc<dyn Object>,
+        args: Vec<Rc<dyn Object>>,
+        env: Rc<RefCell<Enviroment>>,
+    ) -> Rc<dyn Object> {
+        let builtin = func.downcast_ref::<object::Builtin>().unwrap();
+        let arguments = args.clone();
+        let result = (builtin.func)(args, env);
+
+        // Pass the error on with this call added to its stack trace.
         if result.get_type() == ObjectType::Error {
-            return Rc::new(object::Error {
-                message: format!("Error in calling function").to_string(),
-            });
+            let error = result.downcast_ref::<object::Error>().unwrap();
+            return Rc::new(error.with_frame(object::Frame {
+                function: Some(builtin.name.clone()),
+                span: self.token.span,
+                arguments,
+            }));
         }
         return result;
     }
@@ -623,26 +1309,31 @@ impl Node for CallExpression {
         return NodeType::CallExpression;
     }
 
-    fn eval(&self, env: Rc<RefCell<Enviroment>>) -> Rc<Object> {
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
         // func_identifier is type ast::IdentifierExpression so evaluating it
         // looks into env and tries to return the associated value.
         // Since the idenifier is looking for a function the result is of
         // type object::Function
         let func = self.func_identifier.eval(env.clone());
-        if func.get_type() != ObjectType::Function {
-            return Rc::new(object::Error {
-                message: "Called function is not in scope.".to_string(),
-            });
+        if is_unwinding(&func) {
+            return func;
+        }
+        if func.get_type() != ObjectType::Function && func.get_type() != ObjectType::Builtin {
+            return Rc::new(object::Error::new(
+                ErrorKind::Type,
+                format!("{} is not a function.", self.func_identifier.to_string()),
+            ));
         }
 
         let arguments = self.eval_arguments(env.clone());
-        if arguments.len() == 1 && arguments[0].get_type() == ObjectType::Error {
-            return Rc::new(object::Error {
-                message: "Couldn't evaluate function arguments.".to_string(),
-            });
+        if arguments.len() == 1 && is_unwinding(&arguments[0]) {
+            return arguments[0].clone();
         }
 
-        return self.eval_func(func, arguments, env.clone());
+        if func.get_type() == ObjectType::Builtin {
+            return self.eval_builtin(func, arguments, env.clone());
+        }
+        return call_function(func, arguments, self.token.span);
     }
 
     fn to_string(&self) -> String {
@@ -651,10 +1342,243 @@ impl Node for CallExpression {
             to_return.push_str(&arg.to_string());
             to_return.push(',')
         }
-        if self.arguments.len() != 0 {
+        if !self.arguments.is_empty() {
             to_return.pop(); // Remove trailing comma
         }
-        to_return.push_str(&")]".to_string());
+        to_return.push_str(")]");
+        return to_return;
+    }
+
+    fn to_source(&self) -> String {
+        let arguments: Vec<String> = self.arguments.iter().map(|x| x.to_source()).collect();
+        return format!(
+            "{}({})",
+            operand_source(&self.func_identifier, Precedence::Call as u8),
+            arguments.join(", ")
+        );
+    }
+}
+
+impl Node for IndexExpression {
+    fn get_type(&self) -> NodeType {
+        return NodeType::IndexExpression;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let left = self.left.eval(env.clone());
+        if is_unwinding(&left) {
+            return left;
+        }
+        let index = self.index.eval(env);
+        if is_unwinding(&index) {
+            return index;
+        }
+
+        let position = index.downcast_ref::<object::Integer>();
+        if position.is_none() {
+            return Rc::new(object::Error::new(
+                ErrorKind::Type,
+                format!("Index {} is not an integer.", index.to_string()),
+            ));
+        }
+        let position = position.unwrap().value;
+
+        let element = match left.downcast_ref::<object::Array>() {
+            Some(array) => usize::try_from(position)
+                .ok()
+                .and_then(|x| array.elements.get(x).cloned()),
+            None => {
+                return Rc::new(object::Error::new(
+                    ErrorKind::Type,
+                    format!("{} can not be indexed.", left.get_type()),
+                ))
+            }
+        };
+        if element.is_none() {
+            return Rc::new(object::Error::new(
+                ErrorKind::Runtime,
+                format!("Index {} is out of range.", position),
+            ));
+        }
+        return element.unwrap();
+    }
+
+    fn to_string(&self) -> String {
+        return format!("[{}[{}]]", self.left.to_string(), self.index.to_string());
+    }
+
+    fn to_source(&self) -> String {
+        return format!(
+            "{}[{}]",
+            operand_source(&self.left, Precedence::Call as u8),
+            self.index.to_source()
+        );
+    }
+}
+
+impl Node for MemberExpression {
+    fn get_type(&self) -> NodeType {
+        return NodeType::MemberExpression;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let object = self.object.eval(env);
+        if is_unwinding(&object) {
+            return object;
+        }
+
+        let name = &self.member.token.literal;
+        let member = if let Some(error) = object.downcast_ref::<object::ErrorValue>() {
+            error.get_member(name)
+        } else if let Some(module) = object.downcast_ref::<object::Module>() {
+            module.get_member(name)
+        } else {
+            None
+        };
+        if member.is_none() {
+            return Rc::new(object::Error::new(
+                ErrorKind::Name,
+                format!(
+                    "{} has no member {}.",
+                    self.object.to_string(),
+                    self.member.token.literal
+                ),
+            ));
+        }
+        return member.unwrap();
+    }
+
+    fn to_string(&self) -> String {
+        return format!("[{}.{}]", self.object.to_string(), self.member.to_string());
+    }
+
+    fn to_source(&self) -> String {
+        return format!(
+            "{}.{}",
+            operand_source(&self.object, Precedence::Call as u8),
+            self.member.token.literal
+        );
+    }
+}
+
+impl Node for TryExpression {
+    fn get_type(&self) -> NodeType {
+        return NodeType::TryExpression;
+    }
+
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let mut result = self.body.eval(env.clone());
+
+        // Errors that stop the program, such as exceeding a limit, are not caught
+        // and skip the finally block.
+        let error = result.downcast_ref::<object::Error>();
+        if error.is_some_and(|error| error.kind.stops_execution()) {
+            return result;
+        }
+
+        // Catch the error in a new scope which holds the caught value.
+        // A thrown value is caught as it is and a native error as an ErrorValue.
+        if let (Some(error), Some(catch_body)) = (error, &self.catch_body) {
+            let caught: Rc<dyn Object> = match error.value.as_ref() {
+                Some(value) => value.clone(),
+                None => Rc::new(object::ErrorValue {
+                    error: result.clone(),
+                }),
+            };
+            let catch_env = Rc::new(RefCell::new(Enviroment::new(Some(env.clone()))));
+            catch_env.borrow_mut().insert(
+                self.catch_identifier
+                    .as_ref()
+                    .unwrap()
+                    .token
+                    .literal
+                    .clone(),
+                caught,
+            );
+            result = catch_body.eval(catch_env);
+            if is_stopping_error(&result) {
+                return result;
+            }
+        }
+
+        // The finally block always runs but its value is discarded unless it fails.
+        if let Some(finally_body) = &self.finally_body {
+            let cleanup = finally_body.eval(env.clone());
+            if is_unwinding(&cleanup) {
+                return cleanup;
+            }
+        }
+        return result;
+    }
+
+    fn to_string(&self) -> String {
+        let mut to_return = format!("{} do \n{}", self.token, self.body.to_string());
+        if let Some(catch_body) = &self.catch_body {
+            to_return.push_str(&format!(
+                "catch {} do \n{}",
+                self.catch_identifier.as_ref().unwrap().to_string(),
+                catch_body.to_string()
+            ));
+        }
+        if let Some(finally_body) = &self.finally_body {
+            to_return.push_str(&format!("finally do \n{}", finally_body.to_string()));
+        }
+        return to_return;
+    }
+
+    fn to_source(&self) -> String {
+        let mut to_return = format!("try {}", self.body.to_source());
+        if let Some(catch_body) = &self.catch_body {
+            to_return.push_str(&format!(
+                " catch ({}) {}",
+                self.catch_identifier.as_ref().unwrap().token.literal,
+                catch_body.to_source()
+            ));
+        }
+        if let Some(finally_body) = &self.finally_body {
+            to_return.push_str(" finally ");
+            to_return.push_str(&finally_body.to_source());
+        }
         return to_return;
     }
 }
+
+impl Node for PostfixExpression {
+    fn get_type(&self) -> NodeType {
+        return NodeType::PostfixExpression;
+    }
+
+    // The ? operator unwraps an ok result and returns an err result from the
+    // enclosing function.
+    fn eval_node(&self, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+        let left = self.left.eval(env);
+        if is_unwinding(&left) {
+            return left;
+        }
+
+        let result = left.downcast_ref::<object::ResultValue>();
+        if result.is_none() {
+            return Rc::new(object::Error::new(
+                ErrorKind::Type,
+                format!(
+                    "Operator ? expects a result but was given {}.",
+                    left.to_string()
+                ),
+            ));
+        }
+        if result.unwrap().is_ok {
+            return result.unwrap().value.clone();
+        }
+        return Rc::new(object::ReturnValue {
+            value: left.clone(),
+        });
+    }
+
+    fn to_string(&self) -> String {
+        return format!("[{} {}]", self.left.to_string(), self.token);
+    }
+
+    fn to_source(&self) -> String {
+        return format!("{}?", operand_source(&self.left, Precedence::Postfix as u8));
+    }
+}
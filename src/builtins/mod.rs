@@ -0,0 +1,776 @@
+/*
+Author: Jedda Boyle
+Contains: Builtin functions.
+Builtins are functions implemented in rust which can be called from
+rust-monkey code. They are found after the enviroment chain when an
+identifier is looked up, so user bindings can shadow them.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::call_function;
+use super::enviroment::Enviroment;
+use super::lexer::token::Span;
+use super::object;
+use super::object::BuiltinFunction;
+use super::object::ErrorKind;
+use super::object::Object;
+use super::object::ObjectType;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// ================================================================================
+// Lookup.
+// ================================================================================
+
+const BUILTINS: [(&str, BuiltinFunction); 17] = [
+    ("ok", ok),
+    ("err", err),
+    ("is_ok", is_ok),
+    ("unwrap_or", unwrap_or),
+    ("len", len),
+    ("first", first),
+    ("last", last),
+    ("rest", rest),
+    ("push", push),
+    ("args", args),
+    ("env", env),
+    ("exit", exit),
+    ("puts", puts),
+    ("print", print),
+    ("assert", assert),
+    ("assert_eq", assert_eq),
+    ("assert_error", assert_error),
+];
+
+// Return the builtin called name if there is one.
+pub fn lookup(name: &String) -> Option<Rc<dyn Object>> {
+    for (builtin_name, func) in BUILTINS.iter() {
+        if builtin_name == name {
+            return Some(Rc::new(object::Builtin {
+                name: name.clone(),
+                func: *func,
+            }));
+        }
+    }
+    return None;
+}
+
+// Return the names of every builtin.
+pub fn names() -> Vec<String> {
+    return BUILTINS.iter().map(|(name, _)| name.to_string()).collect();
+}
+
+// The functions of the prelude which loop over arrays, with their parameters.
+// They are bound in the prelude along with the functions written in
+// rust-monkey rather than looked up like the builtins above, so that
+// --no-prelude leaves them out too.
+const LIBRARY: [(&str, &str, BuiltinFunction); 7] = [
+    ("range", "start, end", range),
+    ("map", "arr, f", map),
+    ("filter", "arr, f", filter),
+    ("reduce", "arr, initial, f", reduce),
+    ("zip", "a, b", zip),
+    ("any", "arr, f", any),
+    ("all", "arr, f", all),
+];
+
+pub struct LibraryFunction {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub value: Rc<dyn Object>,
+}
+
+// Return the functions of the prelude which are written in rust.
+pub fn library() -> Vec<LibraryFunction> {
+    return LIBRARY
+        .iter()
+        .map(|(name, parameters, func)| LibraryFunction {
+            name: name.to_string(),
+            parameters: parameters.split(", ").map(|x| x.to_string()).collect(),
+            value: Rc::new(object::Builtin {
+                name: name.to_string(),
+                func: *func,
+            }),
+        })
+        .collect();
+}
+
+// ================================================================================
+// Helpers.
+// ================================================================================
+
+fn check_arguments(name: &str, args: &[Rc<dyn Object>], expected: usize) -> Option<Rc<dyn Object>> {
+    if args.len() != expected {
+        return Some(Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "{} expects {} arguments but was given {}.",
+                name,
+                expected,
+                args.len()
+            ),
+        )));
+    }
+    return None;
+}
+
+fn expect_result(name: &str, arg: &Rc<dyn Object>) -> Result<bool, Rc<dyn Object>> {
+    let result = arg.downcast_ref::<object::ResultValue>();
+    if result.is_none() {
+        return Err(Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "{} expects a result but was given {}.",
+                name,
+                arg.to_string()
+            ),
+        )));
+    }
+    return Ok(result.unwrap().is_ok);
+}
+
+fn expect_array<'a>(
+    name: &str,
+    arg: &'a Rc<dyn Object>,
+) -> Result<&'a Vec<Rc<dyn Object>>, Rc<dyn Object>> {
+    let array = arg.downcast_ref::<object::Array>();
+    if array.is_none() {
+        return Err(Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "{} expects an array but was given {}.",
+                name,
+                arg.get_type()
+            ),
+        )));
+    }
+    return Ok(&array.unwrap().elements);
+}
+
+fn expect_integer(name: &str, arg: &Rc<dyn Object>) -> Result<i64, Rc<dyn Object>> {
+    let integer = arg.downcast_ref::<object::Integer>();
+    if integer.is_none() {
+        return Err(Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "{} expects an integer but was given {}.",
+                name,
+                arg.get_type()
+            ),
+        )));
+    }
+    return Ok(integer.unwrap().value);
+}
+
+fn expect_function(name: &str, arg: &Rc<dyn Object>) -> Option<Rc<dyn Object>> {
+    if arg.get_type() != ObjectType::Function && arg.get_type() != ObjectType::Builtin {
+        return Some(Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "{} expects a function but was given {}.",
+                name,
+                arg.get_type()
+            ),
+        )));
+    }
+    return None;
+}
+
+// The value of a function which is used as a condition, as an if uses it.
+fn expect_condition(result: &Rc<dyn Object>) -> Result<bool, Rc<dyn Object>> {
+    if result.get_type() == ObjectType::Error {
+        return Err(result.clone());
+    }
+    let condition = result.downcast_ref::<object::Boolean>();
+    if condition.is_none() {
+        return Err(Rc::new(object::Error::new(
+            ErrorKind::Type,
+            "Condition is not of boolean type.".to_string(),
+        )));
+    }
+    return Ok(condition.unwrap().value);
+}
+
+// Call a function or a builtin given to another builtin.
+fn call(
+    func: &Rc<dyn Object>,
+    args: Vec<Rc<dyn Object>>,
+    env: &Rc<RefCell<Enviroment>>,
+) -> Rc<dyn Object> {
+    let builtin = func.downcast_ref::<object::Builtin>();
+    if let Some(builtin) = builtin {
+        return (builtin.func)(args, env.clone());
+    }
+    return call_function(func.clone(), args, Span::default());
+}
+
+// Account for one element of a loop over an array against the step limit,
+// which is also where an interrupt stops a loop that calls no functions.
+fn step(env: &Rc<RefCell<Enviroment>>) -> Option<Rc<dyn Object>> {
+    let state = env.borrow().state.clone();
+    return state.borrow_mut().step();
+}
+
+// Account for count objects or elements created by a builtin against the
+// allocation limit. This is checked before they are created so that a huge
+// array fails at the limit rather than running out of memory.
+fn allocate(env: &Rc<RefCell<Enviroment>>, count: usize) -> Option<Rc<dyn Object>> {
+    let state = env.borrow().state.clone();
+    return state.borrow_mut().allocate(count as u64);
+}
+
+// ================================================================================
+// Results.
+// ================================================================================
+
+fn ok(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("ok", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    return Rc::new(object::ResultValue {
+        is_ok: true,
+        value: args[0].clone(),
+    });
+}
+
+fn err(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("err", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    return Rc::new(object::ResultValue {
+        is_ok: false,
+        value: args[0].clone(),
+    });
+}
+
+fn is_ok(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("is_ok", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    return match expect_result("is_ok", &args[0]) {
+        Ok(value) => Rc::new(object::Boolean { value }),
+        Err(error) => error,
+    };
+}
+
+fn unwrap_or(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("unwrap_or", &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    return match expect_result("unwrap_or", &args[0]) {
+        Ok(true) => args[0]
+            .downcast_ref::<object::ResultValue>()
+            .unwrap()
+            .value
+            .clone(),
+        Ok(false) => args[1].clone(),
+        Err(error) => error,
+    };
+}
+
+// ================================================================================
+// Arrays.
+// ================================================================================
+
+fn len(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("len", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    let length = match args[0].downcast_ref::<object::Str>() {
+        Some(string) => string.value.chars().count(),
+        None => match expect_array("len", &args[0]) {
+            Ok(elements) => elements.len(),
+            Err(error) => return error,
+        },
+    };
+    return Rc::new(object::Integer {
+        value: length as i64,
+    });
+}
+
+fn first(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("first", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    return match expect_array("first", &args[0]) {
+        Ok(elements) => elements
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Rc::new(object::Null {})),
+        Err(error) => error,
+    };
+}
+
+fn last(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("last", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    return match expect_array("last", &args[0]) {
+        Ok(elements) => elements
+            .last()
+            .cloned()
+            .unwrap_or_else(|| Rc::new(object::Null {})),
+        Err(error) => error,
+    };
+}
+
+// Return every element but the first.
+fn rest(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("rest", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    let elements = match expect_array("rest", &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let exceeded = allocate(&env, elements.len().saturating_sub(1));
+    if let Some(exceeded) = exceeded {
+        return exceeded;
+    }
+    return Rc::new(object::Array {
+        elements: elements.iter().skip(1).cloned().collect(),
+    });
+}
+
+// Return a new array with the value added to the end.
+fn push(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("push", &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    let elements = match expect_array("push", &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let exceeded = allocate(&env, elements.len() + 1);
+    if let Some(exceeded) = exceeded {
+        return exceeded;
+    }
+    let mut elements = elements.clone();
+    elements.push(args[1].clone());
+    return Rc::new(object::Array { elements });
+}
+
+// ================================================================================
+// Library.
+// ================================================================================
+
+// The functions of LIBRARY.
+
+// Return the integers from start up to but not including end.
+fn range(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("range", &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    let start = match expect_integer("range", &args[0]) {
+        Ok(start) => start,
+        Err(error) => return error,
+    };
+    let end = match expect_integer("range", &args[1]) {
+        Ok(end) => end,
+        Err(error) => return error,
+    };
+    let mut elements: Vec<Rc<dyn Object>> = Vec::new();
+    for i in start..end {
+        let exceeded = step(&env).or_else(|| allocate(&env, 1));
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        elements.push(Rc::new(object::Integer { value: i }));
+    }
+    return Rc::new(object::Array { elements });
+}
+
+// Return an array of the results of calling f on each element of arr.
+fn map(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("map", &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    let arr = match expect_array("map", &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let error = expect_function("map", &args[1]);
+    if let Some(error) = error {
+        return error;
+    }
+    let mut elements: Vec<Rc<dyn Object>> = Vec::new();
+    for element in arr.iter() {
+        let exceeded = step(&env);
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        let result = call(&args[1], vec![element.clone()], &env);
+        if result.get_type() == ObjectType::Error {
+            return result;
+        }
+        let exceeded = allocate(&env, 1);
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        elements.push(result);
+    }
+    return Rc::new(object::Array { elements });
+}
+
+// Return the elements of arr for which f returns true.
+fn filter(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("filter", &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    let arr = match expect_array("filter", &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let error = expect_function("filter", &args[1]);
+    if let Some(error) = error {
+        return error;
+    }
+    let mut elements: Vec<Rc<dyn Object>> = Vec::new();
+    for element in arr.iter() {
+        let exceeded = step(&env);
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        match expect_condition(&call(&args[1], vec![element.clone()], &env)) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(error) => return error,
+        }
+        let exceeded = allocate(&env, 1);
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        elements.push(element.clone());
+    }
+    return Rc::new(object::Array { elements });
+}
+
+// Combine the elements of arr from left to right, starting with initial,
+// by calling f with the result so far and the next element.
+fn reduce(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("reduce", &args, 3);
+    if let Some(error) = error {
+        return error;
+    }
+    let arr = match expect_array("reduce", &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let error = expect_function("reduce", &args[2]);
+    if let Some(error) = error {
+        return error;
+    }
+    let mut acc = args[1].clone();
+    for element in arr.iter() {
+        let exceeded = step(&env);
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        acc = call(&args[2], vec![acc, element.clone()], &env);
+        if acc.get_type() == ObjectType::Error {
+            return acc;
+        }
+    }
+    return acc;
+}
+
+// Pair up the elements of a and b. The result is as long as the shorter array.
+fn zip(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("zip", &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    let a = match expect_array("zip", &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let b = match expect_array("zip", &args[1]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let mut elements: Vec<Rc<dyn Object>> = Vec::new();
+    for (x, y) in a.iter().zip(b.iter()) {
+        // The pair and its two elements.
+        let exceeded = step(&env).or_else(|| allocate(&env, 3));
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        elements.push(Rc::new(object::Array {
+            elements: vec![x.clone(), y.clone()],
+        }));
+    }
+    return Rc::new(object::Array { elements });
+}
+
+// Return whether f returns expected for any element of arr, stopping at the
+// first one it does.
+fn find(
+    name: &str,
+    args: Vec<Rc<dyn Object>>,
+    env: Rc<RefCell<Enviroment>>,
+    expected: bool,
+) -> Rc<dyn Object> {
+    let error = check_arguments(name, &args, 2);
+    if let Some(error) = error {
+        return error;
+    }
+    let arr = match expect_array(name, &args[0]) {
+        Ok(elements) => elements,
+        Err(error) => return error,
+    };
+    let error = expect_function(name, &args[1]);
+    if let Some(error) = error {
+        return error;
+    }
+    for element in arr.iter() {
+        let exceeded = step(&env);
+        if let Some(exceeded) = exceeded {
+            return exceeded;
+        }
+        match expect_condition(&call(&args[1], vec![element.clone()], &env)) {
+            Ok(condition) if condition == expected => {
+                return Rc::new(object::Boolean { value: true });
+            }
+            Ok(_) => {}
+            Err(error) => return error,
+        }
+    }
+    return Rc::new(object::Boolean { value: false });
+}
+
+// Return true if f returns true for any element of arr.
+fn any(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    return find("any", args, env, true);
+}
+
+// Return true if f returns true for every element of arr.
+fn all(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let result = find("all", args, env, false);
+    let found = result.downcast_ref::<object::Boolean>();
+    if let Some(found) = found {
+        return Rc::new(object::Boolean {
+            value: !found.value,
+        });
+    }
+    return result;
+}
+
+// ================================================================================
+// Process.
+// ================================================================================
+
+// Return the command line arguments given to the script.
+fn args(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("args", &args, 0);
+    if let Some(error) = error {
+        return error;
+    }
+    let state = env.borrow().state.clone();
+    let count = state.borrow().args.len();
+    let exceeded = allocate(&env, count);
+    if let Some(exceeded) = exceeded {
+        return exceeded;
+    }
+    let elements = state
+        .borrow()
+        .args
+        .iter()
+        .map(|arg| Rc::new(object::Str { value: arg.clone() }) as Rc<dyn Object>)
+        .collect();
+    return Rc::new(object::Array { elements });
+}
+
+// Return the value of an environment variable or null if it is not set.
+fn env(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let error = check_arguments("env", &args, 1);
+    if let Some(error) = error {
+        return error;
+    }
+    let name = args[0].downcast_ref::<object::Str>();
+    if name.is_none() {
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!("env expects a string but was given {}.", args[0].get_type()),
+        ));
+    }
+    return match std::env::var(&name.unwrap().value) {
+        Ok(value) => Rc::new(object::Str { value }),
+        Err(_) => Rc::new(object::Null {}),
+    };
+}
+
+// End the program with an exit status. The error raised can not be caught so
+// it unwinds to the top level where the host reads the code from it.
+fn exit(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    // The code is optional and defaults to 0.
+    if args.len() > 1 {
+        return check_arguments("exit", &args, 1).unwrap();
+    }
+    let code: Rc<dyn Object> = match args.first() {
+        Some(code) => code.clone(),
+        None => Rc::new(object::Integer { value: 0 }),
+    };
+    if code.get_type() != ObjectType::Integer {
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!("exit expects an integer but was given {}.", code.get_type()),
+        ));
+    }
+    let mut error = object::Error::new(ErrorKind::Exit, format!("exit({})", code.to_string()));
+    error.value = Some(code);
+    return Rc::new(error);
+}
+
+// ================================================================================
+// Output.
+// ================================================================================
+
+// Write the arguments separated by spaces to the output of the interpreter.
+fn write_arguments(args: &Vec<Rc<dyn Object>>, env: &Rc<RefCell<Enviroment>>, end: &str) {
+    let values: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let state = env.borrow().state.clone();
+    let text = format!("{}{}", values.join(" "), end);
+    state.borrow_mut().output.write(&text);
+}
+
+// Write the arguments followed by a newline.
+fn puts(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    write_arguments(&args, &env, "\n");
+    return Rc::new(object::Null {});
+}
+
+// Write the arguments without a newline.
+fn print(args: Vec<Rc<dyn Object>>, env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    write_arguments(&args, &env, "");
+    return Rc::new(object::Null {});
+}
+
+// ================================================================================
+// Testing.
+// ================================================================================
+
+// A failed assertion raises an AssertionError. The call of the builtin is
+// added to its stack trace, which is how the test runner finds where the
+// assertion failed.
+
+// Fail unless the condition is true. An optional message describes the failure.
+fn assert(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    if args.len() != 1 && args.len() != 2 {
+        return check_arguments("assert", &args, 1).unwrap();
+    }
+    let condition = args[0].downcast_ref::<object::Boolean>();
+    if condition.is_none() {
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "assert expects a boolean but was given {}.",
+                args[0].get_type()
+            ),
+        ));
+    }
+    if !condition.unwrap().value {
+        let message = match args.get(1) {
+            Some(message) => message.to_string(),
+            None => "assertion failed.".to_string(),
+        };
+        return Rc::new(object::Error::new(ErrorKind::Assertion, message));
+    }
+    return Rc::new(object::Null {});
+}
+
+// Fail unless actual equals expected. An optional message describes the failure.
+fn assert_eq(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    if args.len() != 2 && args.len() != 3 {
+        return check_arguments("assert_eq", &args, 2).unwrap();
+    }
+    let (actual, expected) = (&args[0], &args[1]);
+    if object::equal(actual, expected) {
+        return Rc::new(object::Null {});
+    }
+    // The types are shown when they differ as 1 and "1" print the same.
+    let mut message = if actual.get_type() != expected.get_type() {
+        format!(
+            "expected {} ({}) but was {} ({}).",
+            expected.to_string(),
+            expected.get_type(),
+            actual.to_string(),
+            actual.get_type()
+        )
+    } else {
+        format!(
+            "expected {} but was {}.",
+            expected.to_string(),
+            actual.to_string()
+        )
+    };
+    if let Some(prefix) = args.get(2) {
+        message = format!("{}: {}", prefix.to_string(), message);
+    }
+    return Rc::new(object::Error::new(ErrorKind::Assertion, message));
+}
+
+// Call a function without arguments and fail unless it raises an error. If a
+// kind such as "TypeError" is given the error must be of that kind. The
+// error is returned as a catch block would receive it.
+fn assert_error(args: Vec<Rc<dyn Object>>, _env: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    if args.len() != 1 && args.len() != 2 {
+        return check_arguments("assert_error", &args, 1).unwrap();
+    }
+    if args[0].get_type() != ObjectType::Function {
+        return Rc::new(object::Error::new(
+            ErrorKind::Type,
+            format!(
+                "assert_error expects a function but was given {}.",
+                args[0].get_type()
+            ),
+        ));
+    }
+    let kind = args.get(1).map(|kind| kind.to_string());
+    let result = call_function(args[0].clone(), Vec::new(), Span::default());
+    let error = result.downcast_ref::<object::Error>();
+    if error.is_none() {
+        return Rc::new(object::Error::new(
+            ErrorKind::Assertion,
+            format!(
+                "expected an error but the function returned {}.",
+                result.to_string()
+            ),
+        ));
+    }
+    let error = error.unwrap();
+    if error.kind.stops_execution() {
+        return result;
+    }
+    let kind = kind.filter(|kind| *kind != error.kind.to_string());
+    if let Some(kind) = kind {
+        return Rc::new(object::Error::new(
+            ErrorKind::Assertion,
+            format!(
+                "expected {} but the function raised {}: {}",
+                kind, error.kind, error.message
+            ),
+        ));
+    }
+    return match error.value.as_ref() {
+        Some(value) => value.clone(),
+        None => Rc::new(object::ErrorValue {
+            error: result.clone(),
+        }),
+    };
+}
@@ -0,0 +1,1336 @@
+/*
+Author: Jedda Boyle
+Contains: Command line interface.
+Parses the command line into a subcommand and runs it. Every subcommand
+that takes a file reads stdin when given - and the exit status reports
+whether the input could be parsed and evaluated.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::compiler;
+use super::compiler::Target;
+use super::coverage::Coverage;
+use super::coverage::Report;
+use super::dap;
+use super::debug;
+use super::debugger::Debugger;
+use super::formatter;
+use super::interpreter::InterruptHandle;
+use super::interpreter::Interpreter;
+use super::json::Json;
+use super::lexer::token::TokenType;
+use super::lexer::Lexer;
+use super::linter;
+use super::lsp;
+use super::object;
+use super::object::Object;
+use super::parser;
+use super::parser::Program;
+use super::profiler::Profiler;
+use super::repl;
+use super::testing;
+use super::tracer;
+use super::tracer::Tracer;
+
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::io::prelude::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+// ================================================================================
+// Exit statuses.
+// ================================================================================
+
+// An uncaught error exits with 1, see Interpreter::exit_status.
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_NOT_FORMATTED: i32 = 1;
+pub const EXIT_LINT_ERROR: i32 = 1;
+pub const EXIT_PARSE_ERROR: i32 = 2;
+pub const EXIT_USAGE_ERROR: i32 = 3;
+
+// ================================================================================
+// Results.
+// ================================================================================
+
+pub const DEBUG: bool = false;
+
+// Write the value of each statement that is not null to the output of the
+// interpreter and return the results. An uncaught error is written as a
+// diagnostic.
+pub fn eval(program: &mut parser::Program, interpreter: &Interpreter) -> Vec<Rc<dyn Object>> {
+    let results = interpreter.eval_program(program);
+    print_results(&results, interpreter);
+    return results;
+}
+
+// Write the results of eval_program as eval does.
+pub fn print_results(results: &Vec<Rc<dyn Object>>, interpreter: &Interpreter) {
+    // The error raised by exit is not printed.
+    let mut printed = &results[..];
+    if interpreter.exit_code(results).is_some() {
+        printed = &results[..results.len() - 1];
+    }
+    for result in printed.iter() {
+        let text = format!("{}\n", result.to_string());
+        if result.get_type() == object::ObjectType::Error {
+            interpreter.write_error(&text);
+        } else if result.get_type() != object::ObjectType::Null || DEBUG {
+            interpreter.write(&text);
+        }
+    }
+}
+
+// ================================================================================
+// Interrupts.
+// ================================================================================
+
+// The interrupt handle of the REPL's interpreter so that the SIGINT handler
+// can cancel the running evaluation instead of killing the process.
+static INTERRUPT: OnceLock<InterruptHandle> = OnceLock::new();
+
+#[cfg(unix)]
+extern "C" fn handle_sigint(_signal: i32) {
+    if let Some(handle) = INTERRUPT.get() {
+        handle.interrupt();
+    }
+}
+
+#[cfg(unix)]
+fn install_interrupt_handler(handle: InterruptHandle) {
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+    const SIGINT: i32 = 2;
+
+    if INTERRUPT.set(handle).is_ok() {
+        unsafe {
+            signal(SIGINT, handle_sigint);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn install_interrupt_handler(_handle: InterruptHandle) {}
+
+// ================================================================================
+// Help.
+// ================================================================================
+
+const USAGE: &str = "Usage: rust-monkey [command] [options]
+
+Commands:
+  run <file> [-- args...]   Evaluate a file.
+  repl                      Start the read-eval-print loop.
+  debug <file> [-- args...] Step through a file in the debugger.
+  eval -e <source>          Evaluate source given on the command line.
+  check <file>              Parse a file and report any syntax errors.
+  tokens <file>             Print the tokens of a file.
+  ast <file>                Print the parse tree of a file.
+  fmt [--check] <files...>  Format files.
+  lint [--json] <files...>  Report likely mistakes in files.
+  coverage <reports...>     Print the source annotated with its coverage.
+  lsp                       Start the language server.
+  dap                       Start the debug adapter.
+  test [paths...]           Run the tests in *_test.rm files.
+  compile --target c|js <file>
+                            Compile a file to another language.
+  help [command]            Print help for a command.
+
+Options:
+  --no-prelude              Do not load the prelude of map, filter, reduce
+                            and the other library functions before running,
+                            eval or the REPL.
+  --profile                 Time the calls of every function while running
+                            or with eval and print a table of them to stderr.
+  --profile-folded <file>   Write the time in each stack of calls to file in
+                            the folded format read by flamegraph tools.
+  --coverage <file>         Count the statements run and the branches taken
+                            and add them to the LCOV report in file.
+  --trace                   Log every node evaluated to stderr while running,
+                            with eval or in the REPL. See help run.
+
+With no command the REPL is started and with only a file the file is run.
+Files given as - are read from stdin.
+
+Exit status:
+  0  Success.
+  1  Evaluation raised an uncaught error.
+     Scripts can also set the status with exit(code) or a top level return.
+     fmt --check found a file that is not formatted or lint found an error.
+  2  The input could not be parsed or compiled.
+  3  The command line was invalid or a file could not be read.";
+
+const RUN_USAGE: &str = "Usage: rust-monkey run [options] <file> [-- args...]
+
+Evaluate a file, printing the value of every statement that is not null.
+Reads the program from stdin if file is -.
+
+The arguments after the file are returned by args(). The exit status is the
+code given to exit(code) or the value of a return at the top level.
+
+Options:
+  --profile                Print the number of calls of every function and
+                           the time spent in it to stderr, sorted by the time
+                           spent in the function itself rather than in the
+                           functions it called.
+  --profile-folded <file>  Write the time spent in each stack of calls to file
+                           in the folded format read by flamegraph tools.
+  --coverage <file>        Count how often each statement of the program and
+                           the modules it imports is run and which way each
+                           if goes. The counts are added to those already in
+                           file, which is written in the LCOV format.
+  --trace                  Log every node to stderr once it is evaluated with
+                           its node type, line and column, the depth of its
+                           enviroment chain, the node as printed by the ast
+                           command and its value. Lines are indented by the
+                           number of calls running.
+  --trace-kind <types>     Only log nodes of these comma separated node types,
+                           such as CallExpression,LetStatement.
+  --trace-json             Log JSON objects, one per line, with kind, line,
+                           column, calls, env_depth, node, type and value.
+  --trace-output <file>    Write the log to file instead.";
+
+const REPL_USAGE: &str = "Usage: rust-monkey repl [--no-prelude] [--trace]
+
+Start the read-eval-print loop. Type :help at the prompt for its commands.";
+
+const DEBUG_USAGE: &str =
+    "Usage: rust-monkey debug [--no-prelude] [--break <line>]... <file> [-- args...]
+
+Run a file in the debugger. The program pauses before its first statement
+and then at every breakpoint. While it is paused the current call can be
+inspected and stepped through with these commands:
+
+{commands}
+
+Options:
+  --break <line>  Set a breakpoint before starting. Can be given many times.";
+
+const EVAL_USAGE: &str = "Usage: rust-monkey eval [--no-prelude] [--profile] -e <source>
+       rust-monkey eval [--no-prelude] [--profile] -
+
+Evaluate source given on the command line, or read from stdin with -,
+and print the value of every statement that is not null. The options are
+those of run.";
+
+const CHECK_USAGE: &str = "Usage: rust-monkey check <file>
+
+Parse a file without evaluating it and report any syntax errors.";
+
+const TOKENS_USAGE: &str = "Usage: rust-monkey tokens <file>
+
+Print the line, column, type and literal of every token in a file.";
+
+const AST_USAGE: &str = "Usage: rust-monkey ast <file>
+
+Print the parse tree of every statement in a file.";
+
+const FMT_USAGE: &str = "Usage: rust-monkey fmt [--check] <files...>
+
+Rewrite files in the canonical style: four space indentation, spaces around
+operators, only the parentheses that are needed and long calls and arrays
+wrapped one element per line. Comments and single blank lines are kept.
+The source of - is read from stdin and written formatted to stdout.
+
+Options:
+  --check  Do not write anything. List the files that are not formatted and
+           exit with 1 if there are any.";
+
+const LINT_USAGE: &str = "Usage: rust-monkey lint [--json] [--config <file>] <files...>
+
+Report code that parses but is probably wrong. Each problem is printed as
+file:line:column: severity[rule]: message.
+
+Rules:
+{rules}
+
+The severity of each rule is off, warning or error and defaults to warning.
+It is set in the file .rust-monkey-lint in the directory of the file being
+linted or the nearest directory above it, with lines such as:
+  unused-variable = off
+  argument-count = error
+
+Options:
+  --json           Print the problems as a JSON array of objects with file,
+                   line, column, rule, severity and message members.
+  --config <file>  Read the severities from file instead.
+
+The exit status is 1 if a rule set to error was broken.";
+
+const LSP_USAGE: &str = "Usage: rust-monkey lsp
+
+Start a language server which speaks the Language Server Protocol over stdin
+and stdout. It reports parse errors and lint problems as diagnostics and
+provides hover, go to definition, find references, document symbols and
+completion. Editors start it themselves; it is not meant to be run by hand.
+
+The exit status is 0 if the client sent shutdown before exit and 1 otherwise.";
+
+const DAP_USAGE: &str = "Usage: rust-monkey dap
+
+Start a debug adapter which speaks the Debug Adapter Protocol over stdin and
+stdout. Editors start it themselves; it is not meant to be run by hand.
+
+The launch request takes these arguments:
+  program      The path of the file to debug.
+  args         The arguments returned by args().
+  stopOnEntry  Pause before the first statement.
+  noPrelude    Do not load the prelude.
+
+The output of the program is sent to the editor as output events.";
+
+const COVERAGE_USAGE: &str = "Usage: rust-monkey coverage [--lcov <file>] <reports...>
+
+Merge the LCOV reports written by --coverage and print the source of every
+file in them. Each line is preceded by the number of times it was run,
+##### if it was never run or - if it has no statement, and followed by the
+number of times each branch of its ifs was taken. A summary of the lines
+and branches covered is printed last.
+
+Options:
+  --lcov <file>  Also write the merged report to file in the LCOV format.";
+
+const TEST_USAGE: &str =
+    "Usage: rust-monkey test [--no-prelude] [--coverage <file>] [--filter <text>] [paths...]
+
+Run the tests in the files whose names end in _test.rm. Directories are
+searched for them recursively and the current directory is searched if no
+path is given, and a path of - reads a file of tests from stdin. A test is
+a function bound by a let at the top level of a file whose name starts with
+test_, for example:
+
+  let test_add = fn() {
+      assert_eq(1 + 2, 3);
+  };
+
+Each file is evaluated in an interpreter of its own and then each of its
+tests is called with no arguments. A test fails if it raises an error, such
+as the AssertionError raised by these builtins:
+
+  assert(condition[, message])         Fail unless condition is true.
+  assert_eq(actual, expected[, message])
+                                       Fail unless actual equals expected.
+  assert_error(function[, kind])       Fail unless calling function raises
+                                       an error, of kind if it is given,
+                                       such as \"TypeError\". Returns the
+                                       error as a catch block receives it.
+
+Options:
+  --filter <text>    Only run the tests whose names contain text.
+  --coverage <file>  Add the coverage of the tests to the LCOV report in file.
+
+The exit status is 1 if a test failed or no test was run.";
+
+const COMPILE_USAGE: &str =
+    "Usage: rust-monkey compile [--no-prelude] --target <target> [-o <file>] <file>
+
+Compile a file, the prelude and the modules it imports into one program in
+another language, which is written to stdout. Running the program prints
+what run prints and exits with the same status.
+
+Targets:
+  c   C99 with a small runtime, which builds with cc -std=c99 -O2. Values
+      are tagged and the heap is collected by a mark and sweep collector.
+  js  ES2015 which runs in node or a browser. Integers are JavaScript
+      numbers, so a result beyond 2^53 raises the Integer overflow error
+      that the interpreter raises beyond 2^63.
+
+Options:
+  --target <target>  The language to compile to.
+  -o <file>          Write the program to file instead.";
+
+// The help of a command, with the lists that are built at run time filled in.
+fn usage(command: &str) -> Option<String> {
+    let text = match command {
+        "run" => RUN_USAGE,
+        "repl" => REPL_USAGE,
+        "debug" => return Some(debug_usage()),
+        "eval" => EVAL_USAGE,
+        "check" => CHECK_USAGE,
+        "tokens" => TOKENS_USAGE,
+        "ast" => AST_USAGE,
+        "fmt" => FMT_USAGE,
+        "lint" => return Some(lint_usage()),
+        "coverage" => COVERAGE_USAGE,
+        "lsp" => LSP_USAGE,
+        "dap" => DAP_USAGE,
+        "test" => TEST_USAGE,
+        "compile" => COMPILE_USAGE,
+        _ => return None,
+    };
+    return Some(text.to_string());
+}
+
+fn is_help(arg: &String) -> bool {
+    return arg == "--help" || arg == "-h";
+}
+
+// Report a command line error and return the usage exit status.
+fn usage_error(command: &str, message: String) -> i32 {
+    eprintln!("rust-monkey {}: {}", command, message);
+    eprintln!("Try 'rust-monkey {} --help' for more information.", command);
+    return EXIT_USAGE_ERROR;
+}
+
+// ================================================================================
+// Input.
+// ================================================================================
+
+// Read the contents of path or stdin if path is -.
+fn read_input(path: &String) -> Result<String, String> {
+    if path == "-" {
+        let mut input = String::new();
+        return match io::stdin().read_to_string(&mut input) {
+            Ok(_) => Ok(input),
+            Err(error) => Err(format!("Could not read stdin: {}", error)),
+        };
+    }
+    return fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path, error));
+}
+
+// Commands that take exactly one file.
+fn file_argument(command: &str, args: &[String]) -> Result<String, i32> {
+    if args.iter().any(is_help) {
+        println!("{}", usage(command).unwrap());
+        return Err(EXIT_SUCCESS);
+    }
+    if args.len() != 1 {
+        return Err(usage_error(
+            command,
+            format!("expected one file but was given {}.", args.len()),
+        ));
+    }
+    return read_input(&args[0]).map_err(|error| {
+        eprintln!("{}", error);
+        EXIT_USAGE_ERROR
+    });
+}
+
+fn parse(input: &str, interpreter: &Interpreter) -> Result<Program, i32> {
+    return interpreter
+        .parse(input, DEBUG)
+        .map_err(|_| EXIT_PARSE_ERROR);
+}
+
+// ================================================================================
+// Commands.
+// ================================================================================
+
+// Options shared by the commands that evaluate code.
+struct Options {
+    prelude: bool,
+    profile: bool,                   // Print a table of the time in each function.
+    profile_folded: Option<PathBuf>, // Write the folded stacks here.
+    coverage: Option<PathBuf>,       // Merge the coverage into this LCOV file.
+    trace: bool,                     // Log every node evaluated.
+    trace_json: bool,                // Log as JSON lines rather than text.
+    trace_kinds: Vec<String>,        // Only log these NodeTypes.
+    trace_output: Option<PathBuf>,   // Log here rather than to stderr.
+}
+
+impl Options {
+    fn interpreter(&self) -> Interpreter {
+        if self.prelude {
+            return Interpreter::new();
+        }
+        return Interpreter::without_prelude();
+    }
+
+    // Start the profiler if the program is to be profiled.
+    fn profiler(&self, interpreter: &Interpreter) -> Option<Rc<RefCell<Profiler>>> {
+        if !self.profile && self.profile_folded.is_none() {
+            return None;
+        }
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        interpreter.add_hook(profiler.clone());
+        return Some(profiler);
+    }
+
+    // Start recording coverage if it is to be written.
+    fn coverage(&self, interpreter: &Interpreter) -> Option<Rc<RefCell<Coverage>>> {
+        self.coverage.as_ref()?;
+        let coverage = Rc::new(RefCell::new(Coverage::new()));
+        interpreter.add_hook(coverage.clone());
+        return Some(coverage);
+    }
+
+    // Start the tracer if evaluation is to be traced.
+    fn tracer(&self, interpreter: &Interpreter) -> Result<(), i32> {
+        if !self.trace {
+            return Ok(());
+        }
+        let output: Box<dyn io::Write> = match &self.trace_output {
+            Some(path) => match fs::File::create(path) {
+                Ok(file) => Box::new(io::BufWriter::new(file)),
+                Err(error) => {
+                    eprintln!("Could not write {}: {}", path.display(), error);
+                    return Err(EXIT_USAGE_ERROR);
+                }
+            },
+            None => Box::new(io::stderr()),
+        };
+        let tracer = Tracer::new(output, self.trace_kinds.clone(), self.trace_json);
+        interpreter.add_hook(Rc::new(RefCell::new(tracer)));
+        return Ok(());
+    }
+}
+
+// Remove the options at the start of args.
+fn take_options<'a>(args: &'a [String], options: &mut Options) -> Result<&'a [String], i32> {
+    let mut args = args;
+    loop {
+        match args.first().map(|arg| arg.as_str()) {
+            Some("--no-prelude") => options.prelude = false,
+            Some("--profile") => options.profile = true,
+            Some("--trace") => options.trace = true,
+            Some("--trace-json") => {
+                options.trace = true;
+                options.trace_json = true;
+            }
+            Some("--trace-kind") => {
+                let kinds: Vec<String> = match args.get(1) {
+                    Some(kinds) => kinds
+                        .split(',')
+                        .map(|kind| kind.trim().to_string())
+                        .collect(),
+                    None => {
+                        return Err(usage_error(
+                            "run",
+                            "--trace-kind expects a list of node types.".to_string(),
+                        ))
+                    }
+                };
+                if let Err(kind) = tracer::check_kinds(&kinds) {
+                    return Err(usage_error(
+                        "run",
+                        format!(
+                            "unknown node type {}. The node types are {}.",
+                            kind,
+                            tracer::kind_names().join(", ")
+                        ),
+                    ));
+                }
+                options.trace = true;
+                options.trace_kinds.extend(kinds);
+                args = &args[1..];
+            }
+            Some(option @ "--profile-folded")
+            | Some(option @ "--coverage")
+            | Some(option @ "--trace-output") => {
+                let path = match args.get(1) {
+                    Some(path) => PathBuf::from(path),
+                    None => return Err(usage_error("run", format!("{} expects a file.", option))),
+                };
+                match option {
+                    "--coverage" => options.coverage = Some(path),
+                    "--profile-folded" => options.profile_folded = Some(path),
+                    _ => {
+                        options.trace = true;
+                        options.trace_output = Some(path);
+                    }
+                }
+                args = &args[1..];
+            }
+            _ => return Ok(args),
+        }
+        args = &args[1..];
+    }
+}
+
+// Run the command given by the command line arguments, including the program
+// name, and return the exit status.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut options = Options {
+        prelude: true,
+        profile: false,
+        profile_folded: None,
+        coverage: None,
+        trace: false,
+        trace_json: false,
+        trace_kinds: Vec::new(),
+        trace_output: None,
+    };
+    let args = match take_options(&args[1..], &mut options) {
+        Ok(args) => args,
+        Err(status) => return status,
+    };
+    if args.is_empty() {
+        return run_repl(&[], &options);
+    }
+    let command = args[0].as_str();
+    let rest = match take_options(&args[1..], &mut options) {
+        Ok(rest) => rest,
+        Err(status) => return status,
+    };
+    return match command {
+        "run" => run_file(rest, &options),
+        "repl" => run_repl(rest, &options),
+        "debug" => run_debug(rest, &options),
+        "eval" => run_eval(rest, &options),
+        "check" => run_check(rest),
+        "tokens" => run_tokens(rest),
+        "ast" => run_ast(rest),
+        "fmt" => run_fmt(rest),
+        "lint" => run_lint(rest),
+        "coverage" => run_coverage(rest),
+        "lsp" => run_lsp(rest),
+        "dap" => run_dap(rest),
+        "test" => run_test(rest, &options),
+        "compile" => run_compile(rest, &options),
+        "help" => run_help(rest),
+        "--help" | "-h" => run_help(&[]),
+        _ if command.starts_with('-') && command != "-" => {
+            eprintln!("rust-monkey: unknown option {}.", command);
+            eprintln!("Try 'rust-monkey --help' for more information.");
+            EXIT_USAGE_ERROR
+        }
+        // rust-monkey file.rm is short for rust-monkey run file.rm.
+        _ => run_file(args, &options),
+    };
+}
+
+fn run_help(args: &[String]) -> i32 {
+    if args.is_empty() {
+        println!("{}", USAGE);
+        return EXIT_SUCCESS;
+    }
+    return match usage(&args[0]) {
+        Some(text) => {
+            println!("{}", text);
+            EXIT_SUCCESS
+        }
+        None => usage_error("help", format!("unknown command {}.", args[0])),
+    };
+}
+
+fn run_file(args: &[String], options: &Options) -> i32 {
+    if !args.is_empty() && is_help(&args[0]) {
+        println!("{}", RUN_USAGE);
+        return EXIT_SUCCESS;
+    }
+    if args.is_empty() {
+        return usage_error("run", "expected a file.".to_string());
+    }
+    let input = match read_input(&args[0]) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("{}", error);
+            return EXIT_USAGE_ERROR;
+        }
+    };
+
+    // The arguments after the file, or after --, are given to the script.
+    let mut script_args = &args[1..];
+    if script_args.first().is_some_and(|arg| arg == "--") {
+        script_args = &script_args[1..];
+    }
+    let mut path: Option<PathBuf> = None;
+    if args[0] != "-" {
+        path = Some(PathBuf::from(&args[0]));
+    }
+    return evaluate(&input, options, path, script_args.to_vec());
+}
+
+fn run_repl(args: &[String], options: &Options) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", REPL_USAGE);
+        return EXIT_SUCCESS;
+    }
+    if !args.is_empty() {
+        return usage_error("repl", format!("unexpected argument {}.", args[0]));
+    }
+    let interpreter = options.interpreter();
+    if let Err(status) = options.tracer(&interpreter) {
+        return status;
+    }
+    // Ctrl-C stops the current evaluation and returns to the prompt.
+    install_interrupt_handler(interpreter.interrupt_handle());
+    return repl::run(&interpreter);
+}
+
+fn debug_usage() -> String {
+    return DEBUG_USAGE.replace("{commands}", debug::HELP);
+}
+
+fn run_debug(args: &[String], options: &Options) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", debug_usage());
+        return EXIT_SUCCESS;
+    }
+    let mut breakpoints = Vec::new();
+    let mut args = args;
+    while args.first().is_some_and(|arg| arg == "--break") {
+        match args.get(1).and_then(|line| line.parse::<usize>().ok()) {
+            Some(line) => breakpoints.push(line),
+            None => return usage_error("debug", "--break expects a line number.".to_string()),
+        }
+        args = &args[2..];
+    }
+    if args.is_empty() || args[0] == "-" {
+        return usage_error("debug", "expected a file.".to_string());
+    }
+    let input = match read_input(&args[0]) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("{}", error);
+            return EXIT_USAGE_ERROR;
+        }
+    };
+    let mut script_args = &args[1..];
+    if script_args.first().is_some_and(|arg| arg == "--") {
+        script_args = &script_args[1..];
+    }
+
+    let interpreter = options.interpreter();
+    interpreter.set_args(script_args.to_vec());
+    interpreter.set_path(PathBuf::from(&args[0]));
+    let program = match parse(&input, &interpreter) {
+        Ok(program) => program,
+        Err(status) => return status,
+    };
+    let frontend = debug::Terminal::new(&input);
+    let mut debugger = Debugger::new(
+        &program,
+        interpreter.enviroment.clone(),
+        Box::new(frontend),
+        true,
+    );
+    debugger.breakpoints.extend(breakpoints);
+    let debugger = Rc::new(RefCell::new(debugger));
+    interpreter.add_hook(debugger.clone());
+    let results = interpreter.eval_program(&program);
+    // Quitting interrupts the program, which is not worth a traceback.
+    if debugger.borrow().stopped() {
+        println!("The program was stopped.");
+        return EXIT_FAILURE;
+    }
+    print_results(&results, &interpreter);
+    let status = interpreter.exit_status(&results);
+    println!("The program exited with status {}.", status);
+    return status;
+}
+
+fn run_eval(args: &[String], options: &Options) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", EVAL_USAGE);
+        return EXIT_SUCCESS;
+    }
+    let input = match args {
+        [flag, source] if flag == "-e" => source.clone(),
+        [path] if path == "-" => match read_input(path) {
+            Ok(input) => input,
+            Err(error) => {
+                eprintln!("{}", error);
+                return EXIT_USAGE_ERROR;
+            }
+        },
+        _ => return usage_error("eval", "expected -e <source> or -.".to_string()),
+    };
+    return evaluate(&input, options, None, Vec::new());
+}
+
+fn run_check(args: &[String]) -> i32 {
+    let input = match file_argument("check", args) {
+        Ok(input) => input,
+        Err(status) => return status,
+    };
+    return match parse(&input, &Interpreter::without_prelude()) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(status) => status,
+    };
+}
+
+fn run_tokens(args: &[String]) -> i32 {
+    let input = match file_argument("tokens", args) {
+        Ok(input) => input,
+        Err(status) => return status,
+    };
+    print_tokens(&input, &Interpreter::without_prelude());
+    return EXIT_SUCCESS;
+}
+
+fn run_ast(args: &[String]) -> i32 {
+    let input = match file_argument("ast", args) {
+        Ok(input) => input,
+        Err(status) => return status,
+    };
+    let interpreter = Interpreter::without_prelude();
+    return match parse(&input, &interpreter) {
+        Ok(program) => {
+            print_program(&program, &interpreter);
+            EXIT_SUCCESS
+        }
+        Err(status) => status,
+    };
+}
+
+fn run_fmt(args: &[String]) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", FMT_USAGE);
+        return EXIT_SUCCESS;
+    }
+    let mut check = false;
+    let mut paths: Vec<&String> = Vec::new();
+    for arg in args.iter() {
+        if arg == "--check" {
+            check = true;
+        } else if arg.starts_with('-') && arg != "-" {
+            return usage_error("fmt", format!("unknown option {}.", arg));
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.is_empty() {
+        return usage_error("fmt", "expected a file.".to_string());
+    }
+
+    // Every file is formatted even if one of them fails.
+    let mut status = EXIT_SUCCESS;
+    for path in paths {
+        let input = match read_input(path) {
+            Ok(input) => input,
+            Err(error) => {
+                eprintln!("{}", error);
+                status = EXIT_USAGE_ERROR;
+                continue;
+            }
+        };
+        let formatted = match formatter::format_source(&input) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                eprintln!("Could not parse {}:\n\t{}", path, errors.join("\n\t"));
+                status = status.max(EXIT_PARSE_ERROR);
+                continue;
+            }
+        };
+        if check {
+            if formatted != input {
+                println!("{}", path);
+                status = status.max(EXIT_NOT_FORMATTED);
+            }
+        } else if path == "-" {
+            print!("{}", formatted);
+        } else if formatted != input {
+            if let Err(error) = fs::write(path, formatted) {
+                eprintln!("Could not write {}: {}", path, error);
+                status = EXIT_USAGE_ERROR;
+            }
+        }
+    }
+    return status;
+}
+
+fn lint_usage() -> String {
+    let rules: Vec<String> = linter::RULES
+        .iter()
+        .map(|(rule, description)| format!("  {:<20}{}", rule, description))
+        .collect();
+    return LINT_USAGE.replace("{rules}", &rules.join("\n"));
+}
+
+// Read the lint configuration given on the command line or else the one that
+// applies to path.
+fn lint_config(config_path: &Option<PathBuf>, path: &String) -> Result<linter::Config, String> {
+    let mut config_path = config_path.clone();
+    if config_path.is_none() {
+        let file = PathBuf::from(path);
+        let directory = match file.parent() {
+            Some(parent) if path != "-" && parent != PathBuf::new() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        config_path = linter::find_config(&directory);
+    }
+    if config_path.is_none() {
+        return Ok(linter::Config::new());
+    }
+    let config_path = config_path.unwrap();
+    let text = fs::read_to_string(&config_path)
+        .map_err(|error| format!("Could not read {}: {}", config_path.display(), error))?;
+    return linter::Config::parse(&text)
+        .map_err(|error| format!("{}: {}", config_path.display(), error));
+}
+
+fn run_lint(args: &[String]) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", lint_usage());
+        return EXIT_SUCCESS;
+    }
+    let mut json = false;
+    let mut config_path: Option<PathBuf> = None;
+    let mut paths: Vec<&String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            json = true;
+        } else if arg == "--config" {
+            match args.next() {
+                Some(path) => config_path = Some(PathBuf::from(path)),
+                None => return usage_error("lint", "--config expects a file.".to_string()),
+            }
+        } else if arg.starts_with('-') && arg != "-" {
+            return usage_error("lint", format!("unknown option {}.", arg));
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.is_empty() {
+        return usage_error("lint", "expected a file.".to_string());
+    }
+
+    let mut status = EXIT_SUCCESS;
+    let mut problems: Vec<Json> = Vec::new();
+    for path in paths {
+        let config = lint_config(&config_path, path);
+        let input = read_input(path);
+        if config.is_err() || input.is_err() {
+            eprintln!("{}", config.err().or(input.err()).unwrap());
+            status = EXIT_USAGE_ERROR;
+            continue;
+        }
+        let diagnostics = match linter::lint_source(&input.unwrap(), &config.unwrap()) {
+            Ok(diagnostics) => diagnostics,
+            Err(errors) => {
+                eprintln!("Could not parse {}:\n\t{}", path, errors.join("\n\t"));
+                status = status.max(EXIT_PARSE_ERROR);
+                continue;
+            }
+        };
+        for diagnostic in diagnostics.iter() {
+            if diagnostic.severity == linter::Severity::Error {
+                status = status.max(EXIT_LINT_ERROR);
+            }
+            if json {
+                problems.push(diagnostic.to_json(path));
+            } else {
+                println!("{}:{}", path, diagnostic);
+            }
+        }
+    }
+    if json {
+        println!("{}", Json::Array(problems));
+    }
+    return status;
+}
+
+fn run_coverage(args: &[String]) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", COVERAGE_USAGE);
+        return EXIT_SUCCESS;
+    }
+    let mut lcov: Option<PathBuf> = None;
+    let mut paths: Vec<&String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--lcov" {
+            match args.next() {
+                Some(path) => lcov = Some(PathBuf::from(path)),
+                None => return usage_error("coverage", "--lcov expects a file.".to_string()),
+            }
+        } else if arg.starts_with('-') {
+            return usage_error("coverage", format!("unknown option {}.", arg));
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.is_empty() {
+        return usage_error("coverage", "expected a report.".to_string());
+    }
+
+    let mut report = Report::new();
+    for path in paths {
+        match read_coverage(&PathBuf::from(path)) {
+            Ok(other) => report.merge(&other),
+            Err(error) => {
+                eprintln!("{}", error);
+                return EXIT_USAGE_ERROR;
+            }
+        }
+    }
+    let mut status = EXIT_SUCCESS;
+    for path in report.files.keys() {
+        match fs::read_to_string(path) {
+            Ok(source) => println!("{}", report.annotate(path, &source)),
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path, error);
+                status = EXIT_USAGE_ERROR;
+            }
+        }
+    }
+    println!("{}", report.summary());
+    if let Some(path) = lcov {
+        if let Err(error) = fs::write(&path, report.to_lcov()) {
+            eprintln!("Could not write {}: {}", path.display(), error);
+            status = EXIT_USAGE_ERROR;
+        }
+    }
+    return status;
+}
+
+fn run_lsp(args: &[String]) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", LSP_USAGE);
+        return EXIT_SUCCESS;
+    }
+    if !args.is_empty() {
+        return usage_error("lsp", format!("unexpected argument {}.", args[0]));
+    }
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    return lsp::serve(&mut stdin.lock(), &mut stdout.lock());
+}
+
+fn run_dap(args: &[String]) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", DAP_USAGE);
+        return EXIT_SUCCESS;
+    }
+    if !args.is_empty() {
+        return usage_error("dap", format!("unexpected argument {}.", args[0]));
+    }
+    let stdin = io::BufReader::new(io::stdin());
+    return dap::serve(Box::new(stdin), Box::new(io::stdout()));
+}
+
+fn run_test(args: &[String], options: &Options) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", TEST_USAGE);
+        return EXIT_SUCCESS;
+    }
+    let mut filter = String::new();
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--filter" {
+            match args.next() {
+                Some(text) => filter = text.clone(),
+                None => return usage_error("test", "--filter expects some text.".to_string()),
+            }
+        } else if arg.starts_with('-') && arg != "-" {
+            return usage_error("test", format!("unknown option {}.", arg));
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+    let mut files = Vec::new();
+    for path in paths.iter() {
+        if path.as_os_str() == "-" {
+            files.push(path.clone());
+            continue;
+        }
+        match testing::discover(path) {
+            Ok(found) => files.extend(found),
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path.display(), error);
+                return EXIT_USAGE_ERROR;
+            }
+        }
+    }
+
+    let mut summary = TestSummary {
+        passed: 0,
+        failed: 0,
+        filtered: 0,
+    };
+    let mut report = Report::new();
+    for file in files.iter() {
+        println!("{}", file.display());
+        let coverage = test_file(file, &filter, options, &mut summary);
+        if let Some(coverage) = coverage {
+            report.merge(&coverage);
+        }
+    }
+
+    let mut status = EXIT_SUCCESS;
+    if let Some(path) = &options.coverage {
+        if let Err(error) = write_coverage(&report, path) {
+            eprintln!("{}", error);
+            status = EXIT_USAGE_ERROR;
+        }
+    }
+    if summary.passed + summary.failed == 0 {
+        println!("No tests were run.");
+        return status.max(EXIT_FAILURE);
+    }
+    print!("\n{} passed, {} failed", summary.passed, summary.failed);
+    if summary.filtered != 0 {
+        print!(", {} filtered out", summary.filtered);
+    }
+    println!(".");
+    if summary.failed != 0 {
+        status = status.max(EXIT_FAILURE);
+    }
+    return status;
+}
+
+fn run_compile(args: &[String], options: &Options) -> i32 {
+    if args.iter().any(is_help) {
+        println!("{}", COMPILE_USAGE);
+        return EXIT_SUCCESS;
+    }
+    let mut target: Option<Target> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut paths: Vec<&String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--target" {
+            let name = match args.next() {
+                Some(name) => name,
+                None => return usage_error("compile", "--target expects a target.".to_string()),
+            };
+            target = Target::from_name(name);
+            if target.is_none() {
+                return usage_error(
+                    "compile",
+                    format!(
+                        "unknown target {}. The targets are {}.",
+                        name,
+                        compiler::TARGETS.join(", ")
+                    ),
+                );
+            }
+        } else if arg == "-o" {
+            match args.next() {
+                Some(path) => output = Some(PathBuf::from(path)),
+                None => return usage_error("compile", "-o expects a file.".to_string()),
+            }
+        } else if arg.starts_with('-') && arg != "-" {
+            return usage_error("compile", format!("unknown option {}.", arg));
+        } else {
+            paths.push(arg);
+        }
+    }
+    if target.is_none() {
+        return usage_error("compile", "expected --target <target>.".to_string());
+    }
+    if paths.len() != 1 {
+        return usage_error(
+            "compile",
+            format!("expected one file but was given {}.", paths.len()),
+        );
+    }
+    let input = match read_input(paths[0]) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("{}", error);
+            return EXIT_USAGE_ERROR;
+        }
+    };
+    if let Err(status) = parse(&input, &Interpreter::without_prelude()) {
+        return status;
+    }
+
+    let mut path: Option<PathBuf> = None;
+    if paths[0] != "-" {
+        path = Some(PathBuf::from(paths[0]));
+    }
+    let program = match compiler::compile(target.unwrap(), &input, path, options.prelude) {
+        Ok(program) => program,
+        Err(errors) => {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+            return EXIT_PARSE_ERROR;
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(error) = fs::write(&path, program) {
+                eprintln!("Could not write {}: {}", path.display(), error);
+                return EXIT_USAGE_ERROR;
+            }
+        }
+        None => print!("{}", program),
+    }
+    return EXIT_SUCCESS;
+}
+
+struct TestSummary {
+    passed: usize,
+    failed: usize,
+    filtered: usize, // Tests not run because their names did not match.
+}
+
+fn print_failure(name: &str, failure: &testing::Failure) {
+    println!("  FAIL  {}", name);
+    if failure.span.line != 0 {
+        print!("        {}: ", failure.span);
+    } else {
+        print!("        ");
+    }
+    println!("{}: {}", failure.kind, failure.message);
+}
+
+// Evaluate a test file and run the tests in it whose names contain filter.
+// Returns the coverage of the file if it is being recorded.
+fn test_file(
+    file: &Path,
+    filter: &str,
+    options: &Options,
+    summary: &mut TestSummary,
+) -> Option<Report> {
+    // A file named - is read from stdin and imports it makes are resolved from
+    // the working directory.
+    let from_stdin = file.as_os_str() == "-";
+    let input = match read_input(&file.display().to_string()) {
+        Ok(input) => input,
+        Err(error) => {
+            println!("  FAIL  {}", error);
+            summary.failed += 1;
+            return None;
+        }
+    };
+    let interpreter = options.interpreter();
+    if !from_stdin {
+        interpreter.set_path(file.to_path_buf());
+    }
+    let program = match parse(&input, &interpreter) {
+        Ok(program) => program,
+        Err(_) => {
+            println!("  FAIL  the file could not be parsed");
+            summary.failed += 1;
+            return None;
+        }
+    };
+    let coverage = options.coverage(&interpreter);
+
+    // A file whose top level fails has no tests that can be run.
+    let results = interpreter.eval_program(&program);
+    let failure = results.last().and_then(testing::Failure::from_result);
+    if let Some(failure) = failure {
+        print_failure("<top level>", &failure);
+        summary.failed += 1;
+    } else {
+        for name in testing::test_names(&program) {
+            if !name.contains(filter) {
+                summary.filtered += 1;
+                continue;
+            }
+            match testing::run_test(&interpreter, &name) {
+                None => {
+                    println!("  ok    {}", name);
+                    summary.passed += 1;
+                }
+                Some(failure) => {
+                    print_failure(&name, &failure);
+                    summary.failed += 1;
+                }
+            }
+        }
+    }
+    return coverage.map(|coverage| coverage.borrow().report());
+}
+
+// Parse and evaluate input in a new interpreter. Imports are resolved relative
+// to path, or the working directory if input was not read from a file.
+fn evaluate(
+    input: &str,
+    options: &Options,
+    path: Option<PathBuf>,
+    script_args: Vec<String>,
+) -> i32 {
+    let interpreter = options.interpreter();
+    interpreter.set_args(script_args);
+    if let Some(path) = path {
+        interpreter.set_path(path);
+    }
+    let mut program = match parse(input, &interpreter) {
+        Ok(program) => program,
+        Err(status) => return status,
+    };
+    if let Err(status) = options.tracer(&interpreter) {
+        return status;
+    }
+    let profiler = options.profiler(&interpreter);
+    let coverage = options.coverage(&interpreter);
+    let results = eval(&mut program, &interpreter);
+    let mut status = interpreter.exit_status(&results);
+    if let Some(profiler) = profiler {
+        status = report_profile(&mut profiler.borrow_mut(), options, status);
+    }
+    if let Some(coverage) = coverage {
+        let path = options.coverage.as_ref().unwrap();
+        if let Err(error) = write_coverage(&coverage.borrow().report(), path) {
+            eprintln!("{}", error);
+            status = EXIT_USAGE_ERROR;
+        }
+    }
+    return status;
+}
+
+// Read the LCOV report at path.
+fn read_coverage(path: &PathBuf) -> Result<Report, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+    return Report::parse_lcov(&text).map_err(|error| format!("{}: {}", path.display(), error));
+}
+
+// Write report to path in the LCOV format, adding the counts already in it
+// so that the coverage of several runs is collected in one file.
+fn write_coverage(report: &Report, path: &PathBuf) -> Result<(), String> {
+    let mut merged = Report::new();
+    if path.exists() {
+        merged = read_coverage(path)?;
+    }
+    merged.merge(report);
+    return fs::write(path, merged.to_lcov())
+        .map_err(|error| format!("Could not write {}: {}", path.display(), error));
+}
+
+// Print or write the profile of a program that exited with status.
+fn report_profile(profiler: &mut Profiler, options: &Options, status: i32) -> i32 {
+    profiler.finish();
+    if options.profile {
+        eprint!("{}", profiler.table());
+    }
+    if let Some(path) = &options.profile_folded {
+        if let Err(error) = fs::write(path, profiler.folded()) {
+            eprintln!("Could not write {}: {}", path.display(), error);
+            return EXIT_USAGE_ERROR;
+        }
+    }
+    return status;
+}
+
+// ================================================================================
+// Dumps.
+// ================================================================================
+
+// The dumps are written to the output of the interpreter, which ignores write
+// errors such as a closed pipe.
+pub fn print_tokens(input: &str, interpreter: &Interpreter) {
+    if input.is_empty() {
+        return;
+    }
+    let mut text = String::new();
+    let mut lexer = Lexer::new(input);
+    loop {
+        let token = lexer.next_token().unwrap();
+        if token.token_type == TokenType::Eof {
+            break;
+        }
+        text.push_str(&format!(
+            "{}:{}\t{:?}\t{}\n",
+            token.span.line, token.span.column, token.token_type, token.literal
+        ));
+    }
+    interpreter.write(&text);
+}
+
+pub fn print_program(program: &Program, interpreter: &Interpreter) {
+    let mut text = String::new();
+    for statement in program.statements.iter() {
+        text.push_str(&format!("{}\n", statement.to_string()));
+    }
+    interpreter.write(&text);
+}
@@ -0,0 +1,1419 @@
+/*
+Author: Jedda Boyle
+Contains: C backend
+Compiles a program to a single C99 file which builds with the system cc. The
+output is the runtime in runtime.c followed by the prelude, the modules the
+program imports and the program:
+    - Each function is a C function which keeps its parameters, variables
+      and temporaries in a frame on the runtime's value stack. The variables
+      which functions inside it use are kept in an environment on the heap
+      instead, which closures created in the call hold.
+    - The variables at the top level of each file are globals.
+    - Expressions are broken into statements which keep every value that is
+      allocated in the frame before anything else is allocated, so that the
+      collector finds every value which is still in use.
+    - A try is a setjmp, to which errors raised inside it jump. A return
+      inside a try removes its handler and runs its finally block first.
+Operands are evaluated in the order the interpreter evaluates them, which
+is the right operand of an infix expression first.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::bindings;
+use super::Unit;
+use super::Units;
+use crate::ast::children;
+use crate::ast::node_span;
+use crate::ast::ArrayExpression;
+use crate::ast::BlockStatement;
+use crate::ast::BoolExpression;
+use crate::ast::CallExpression;
+use crate::ast::ExportStatement;
+use crate::ast::ExpressionStatement;
+use crate::ast::FunctionExpression;
+use crate::ast::IdentifierExpression;
+use crate::ast::IfElseExpression;
+use crate::ast::ImportStatement;
+use crate::ast::IndexExpression;
+use crate::ast::InfixExpression;
+use crate::ast::IntegralExpression;
+use crate::ast::LetStatement;
+use crate::ast::MemberExpression;
+use crate::ast::Node;
+use crate::ast::NodeType;
+use crate::ast::PostfixExpression;
+use crate::ast::PrefixExpression;
+use crate::ast::ReturnStatement;
+use crate::ast::StringExpression;
+use crate::ast::ThrowStatement;
+use crate::ast::TryExpression;
+use crate::builtins;
+use crate::lexer::token::TokenType;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+// The types and functions used by compiled code.
+pub const RUNTIME: &str = include_str!("runtime.c");
+
+// ================================================================================
+// Compile.
+// ================================================================================
+
+// Compile units to a C program which runs on its own.
+pub fn compile(units: &Units) -> Result<String, Vec<String>> {
+    let mut compiler = Compiler::new(units);
+    if let Some(prelude) = &units.prelude {
+        compiler.unit(prelude, Kind::Prelude);
+    }
+    for (index, module) in units.modules.iter().enumerate() {
+        compiler.unit(module, Kind::Module(index));
+    }
+    compiler.unit(&units.main, Kind::Program);
+    return compiler.finish();
+}
+
+// ================================================================================
+// Compiler.
+// ================================================================================
+
+// Where the value of a statement goes.
+#[derive(Clone)]
+enum Sink {
+    Discard,
+    Print,          // Printed unless it is null, as at the top level of a program.
+    Return,         // Returned from the function, or the file, being compiled.
+    Assign(String), // Assigned to a variable or temporary.
+}
+
+// The C function being written: a rust-monkey function or the top level of a file.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Function,
+    Prelude,
+    Module(usize),
+    Program,
+}
+
+// Where a variable is kept.
+enum Location {
+    Slot(usize),           // In the frame of the function.
+    Env(usize),            // In the environment of the function.
+    Global(String, usize), // In the globals of a file.
+}
+
+// A compiled expression. A stable operand is a constant or a temporary,
+// whose value does not change and can not raise an error.
+struct Operand {
+    code: String,
+    stable: bool,
+}
+
+impl Operand {
+    fn stable(code: String) -> Operand {
+        return Operand { code, stable: true };
+    }
+
+    fn unstable(code: String) -> Operand {
+        return Operand {
+            code,
+            stable: false,
+        };
+    }
+}
+
+// A try whose body is being compiled, which a return has to leave.
+#[derive(Clone)]
+#[allow(clippy::borrowed_box)]
+struct Try<'a> {
+    handler: String,
+    finally: Option<&'a Box<dyn Node>>,
+}
+
+// The state of the C function being written.
+struct Context<'a> {
+    kind: Kind,
+    scopes: Vec<HashMap<String, Location>>,
+    env: bool,       // Whether the function has an environment.
+    env_size: usize, // The variables in the environment.
+    temps: usize,    // The first slot of the frame which is not in use.
+    size: usize,     // The slots of the frame.
+    handlers: usize,
+    tries: Vec<Try<'a>>,
+}
+
+impl<'a> Context<'a> {
+    fn new(kind: Kind, env: bool) -> Self {
+        return Context {
+            kind,
+            scopes: Vec::new(),
+            env,
+            env_size: 0,
+            temps: 0,
+            size: 0,
+            handlers: 0,
+            tries: Vec::new(),
+        };
+    }
+}
+
+struct Compiler<'a> {
+    units: &'a Units,
+    unit: &'a Unit,
+    prelude: HashMap<String, usize>, // The global of each binding of the prelude.
+    builtins: Vec<String>,
+    strings: Vec<String>, // The string constants as C literals.
+    string_indexes: HashMap<String, usize>,
+    globals: Vec<String>,    // The declarations of the globals of each file.
+    imports: Vec<String>,    // The definitions of the modules.
+    prototypes: Vec<String>, // The declarations of the functions.
+    definitions: String,
+    functions: usize,
+    contexts: Vec<Context<'a>>,
+    output: String,
+    indent: usize,
+    errors: Vec<String>,
+}
+
+#[allow(clippy::borrowed_box)]
+impl<'a> Compiler<'a> {
+    fn new(units: &'a Units) -> Self {
+        return Compiler {
+            units,
+            unit: &units.main,
+            prelude: HashMap::new(),
+            builtins: builtin_names(units),
+            strings: Vec::new(),
+            string_indexes: HashMap::new(),
+            globals: Vec::new(),
+            imports: Vec::new(),
+            prototypes: Vec::new(),
+            definitions: String::new(),
+            functions: 0,
+            contexts: Vec::new(),
+            output: String::new(),
+            indent: 0,
+            errors: Vec::new(),
+        };
+    }
+
+    fn finish(self) -> Result<String, Vec<String>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        let mut to_return = format!(
+            "// Compiled from {} by rust-monkey.\n\n{}\n",
+            self.units.main.name, RUNTIME
+        );
+        to_return.push_str(&banner("Program."));
+        to_return.push_str(&format!(
+            "static Value strings[{}];\n",
+            self.strings.len().max(1)
+        ));
+        for global in self.globals.iter() {
+            to_return.push_str(global);
+            to_return.push('\n');
+        }
+        to_return.push('\n');
+        for prototype in self.prototypes.iter() {
+            to_return.push_str(prototype);
+            to_return.push('\n');
+        }
+        for import in self.imports.iter() {
+            to_return.push('\n');
+            to_return.push_str(import);
+        }
+        to_return.push_str(&self.definitions);
+
+        // The constants and globals are kept from the collector before
+        // anything is allocated.
+        to_return.push_str(&banner("Main."));
+        to_return.push_str("static void initialize(void) {\n");
+        to_return.push_str(&format!(
+            "    rm_root(strings, {});\n",
+            self.strings.len().max(1)
+        ));
+        if self.units.prelude.is_some() {
+            to_return.push_str(&format!(
+                "    rm_root(prelude, {});\n",
+                self.prelude.len().max(1)
+            ));
+        }
+        for (index, module) in self.units.modules.iter().enumerate() {
+            to_return.push_str(&format!(
+                "    rm_root({}, {});\n",
+                globals_name(Kind::Module(index)),
+                bindings(&module.program.statements).len().max(1)
+            ));
+            to_return.push_str(&format!(
+                "    rm_root(&{}.module, 1);\n",
+                import_name(index)
+            ));
+        }
+        to_return.push_str(&format!(
+            "    rm_root(globals, {});\n",
+            bindings(&self.units.main.program.statements).len().max(1)
+        ));
+        for (index, string) in self.strings.iter().enumerate() {
+            to_return.push_str(&format!("    strings[{}] = {};\n", index, string));
+        }
+        if self.units.prelude.is_some() {
+            to_return.push_str("    prelude_body();\n");
+        }
+        to_return.push_str("}\n\n");
+        to_return.push_str("int main(int argc, char **argv) {\n");
+        to_return.push_str("    return rm_main(argc, argv, initialize, program);\n");
+        to_return.push_str("}\n");
+        return Ok(to_return);
+    }
+
+    fn error(&mut self, node: &dyn Node, message: String) {
+        let span = node_span(node);
+        self.errors.push(format!(
+            "{}: line {}, column {}: {}",
+            self.unit.name, span.line, span.column, message
+        ));
+    }
+
+    // ============================================================================
+    // Output.
+    // ============================================================================
+
+    fn line(&mut self, text: &str) {
+        let padding = "    ".repeat(self.indent);
+        self.output.push_str(&padding);
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn context(&self) -> &Context<'a> {
+        return self.contexts.last().unwrap();
+    }
+
+    fn context_mut(&mut self) -> &mut Context<'a> {
+        return self.contexts.last_mut().unwrap();
+    }
+
+    // A slot of the frame for a temporary, which is in use until the
+    // statement it is allocated in ends.
+    fn temp(&mut self) -> usize {
+        let context = self.context_mut();
+        let to_return = context.temps;
+        context.temps += 1;
+        context.size = context.size.max(context.temps);
+        return to_return;
+    }
+
+    // Keep the value of code in a temporary.
+    fn store(&mut self, code: String) -> Operand {
+        let slot = self.temp();
+        self.line(&format!("t[{}] = {};", slot, code));
+        return Operand::stable(format!("t[{}]", slot));
+    }
+
+    // Assign value, which was evaluated since the frame had mark slots in
+    // use, to target. A value which was only just stored in a temporary is
+    // stored in target instead.
+    fn assign(&mut self, target: &str, value: Operand, mark: usize) {
+        if temp_slot(&value.code).is_some_and(|slot| slot >= mark) {
+            let start = self.output[..self.output.len() - 1]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            let padding = "    ".repeat(self.indent);
+            let prefix = format!("{}{} = ", padding, value.code);
+            if self.output[start..].starts_with(&prefix) {
+                let assignment = format!("{}{} = ", padding, target);
+                self.output
+                    .replace_range(start..start + prefix.len(), &assignment);
+                return;
+            }
+        }
+        self.line(&format!("{} = {};", target, value.code));
+    }
+
+    // The constant holding value.
+    fn string(&mut self, value: &String) -> String {
+        if let Some(index) = self.string_indexes.get(value) {
+            return format!("strings[{}]", index);
+        }
+        let index = self.strings.len();
+        self.strings
+            .push(format!("rm_string({}, {})", c_string(value), value.len()));
+        self.string_indexes.insert(value.clone(), index);
+        return format!("strings[{}]", index);
+    }
+
+    // ============================================================================
+    // Units.
+    // ============================================================================
+
+    // The top level of a file is a C function whose variables are globals.
+    fn unit(&mut self, unit: &'a Unit, kind: Kind) {
+        self.unit = unit;
+        let statements = &unit.program.statements;
+        let array = globals_name(kind);
+        let names: Vec<String> = bindings(statements)
+            .into_iter()
+            .map(|binding| binding.name)
+            .collect();
+        self.globals
+            .push(format!("static Value {}[{}];", array, names.len().max(1)));
+        let mut scope = HashMap::new();
+        for (index, name) in names.iter().enumerate() {
+            scope.insert(name.clone(), Location::Global(array.clone(), index));
+        }
+        if kind == Kind::Prelude {
+            self.prelude = names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| (name.clone(), index))
+                .collect();
+        }
+
+        let mut context = Context::new(kind, statements.iter().any(catch_captures));
+        if context.env {
+            context.temps = 1;
+            context.size = 1;
+        }
+        context.scopes.push(scope);
+        self.contexts.push(context);
+        self.definitions.push_str(&banner(&match kind {
+            Kind::Prelude => "Prelude.".to_string(),
+            Kind::Module(_) => format!("Module {}.", unit.name),
+            _ => format!("{}.", unit.name),
+        }));
+
+        self.indent = 1;
+        if kind == Kind::Program {
+            // The program prints the value of every statement, as run does.
+            for statement in statements.iter() {
+                let mark = self.context().temps;
+                self.statement(statement, &Sink::Print);
+                self.context_mut().temps = mark;
+            }
+            self.line("return rm_pop(t, rm_unbound());");
+        } else {
+            self.statements(statements, &Sink::Discard);
+            self.line("rm_pop(t, rm_null());");
+        }
+        let body = std::mem::take(&mut self.output);
+        let context = self.contexts.pop().unwrap();
+
+        let signature = match kind {
+            Kind::Prelude => "static void prelude_body(void)".to_string(),
+            Kind::Module(index) => format!("static void {}_body(void)", import_name(index)),
+            _ => "static Value program(void)".to_string(),
+        };
+        self.prototypes.push(format!("{};", signature));
+        self.definitions.push_str(&format!("{} {{\n", signature));
+        self.definitions
+            .push_str(&format!("    Value *t = rm_frame({});\n", context.size));
+        if context.env {
+            self.definitions.push_str(&format!(
+                "    Env *e = rm_env(&t[0], NULL, {});\n",
+                context.env_size
+            ));
+        }
+        self.definitions.push_str(&body);
+        self.definitions.push_str("}\n\n");
+
+        if let Kind::Module(index) = kind {
+            self.import(index, unit, &names);
+        }
+    }
+
+    // The definition of a module, which exports the globals its export
+    // statements bind.
+    fn import(&mut self, index: usize, unit: &Unit, names: &[String]) {
+        let mut exports: Vec<String> = Vec::new();
+        for statement in unit.program.statements.iter() {
+            if let Some(statement) = statement.downcast_ref::<ExportStatement>() {
+                let name = &statement.statement.identifier.token.literal;
+                if !exports.contains(name) {
+                    exports.push(name.clone());
+                }
+            }
+        }
+        let name = import_name(index);
+        let quoted: Vec<String> = exports.iter().map(|export| c_string(export)).collect();
+        let slots: Vec<String> = exports
+            .iter()
+            .map(|export| {
+                names
+                    .iter()
+                    .position(|name| name == export)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        let mut to_return = String::new();
+        to_return.push_str(&format!(
+            "static const char *const {}_names[] = {{{}}};\n",
+            name,
+            if !quoted.is_empty() {
+                quoted.join(", ")
+            } else {
+                "NULL".to_string()
+            }
+        ));
+        to_return.push_str(&format!(
+            "static const int {}_slots[] = {{{}}};\n",
+            name,
+            if !slots.is_empty() {
+                slots.join(", ")
+            } else {
+                "0".to_string()
+            }
+        ));
+        to_return.push_str(&format!(
+            "static Import {} = {{\n    {},\n    {}_body,\n    {},\n    {},\n    {}_names,\n    {}_slots,\n    0,\n    {{RM_UNBOUND, {{0}}}},\n}};\n",
+            name,
+            c_string(&unit.name),
+            name,
+            globals_name(Kind::Module(index)),
+            exports.len(),
+            name,
+            name
+        ));
+        self.imports.push(to_return);
+    }
+
+    // ============================================================================
+    // Variables.
+    // ============================================================================
+
+    // The C lvalue of the variable called name, if it is bound anywhere.
+    fn variable(&self, name: &String) -> Option<String> {
+        let current = self.contexts.len() - 1;
+        for index in (0..=current).rev() {
+            for scope in self.contexts[index].scopes.iter().rev() {
+                let location = match scope.get(name) {
+                    Some(location) => location,
+                    None => continue,
+                };
+                return Some(match location {
+                    Location::Slot(slot) => format!("t[{}]", slot),
+                    Location::Env(slot) if index == current => format!("e->slots[{}]", slot),
+                    Location::Env(slot) => format!("{}->slots[{}]", self.env_path(index), slot),
+                    Location::Global(array, slot) => format!("{}[{}]", array, slot),
+                });
+            }
+        }
+        if self.context().kind != Kind::Prelude {
+            if let Some(slot) = self.prelude.get(name) {
+                return Some(format!("prelude[{}]", slot));
+            }
+        }
+        return None;
+    }
+
+    // The environment of the function at index from inside the current
+    // function. Each function between them with an environment is a link
+    // of the chain.
+    fn env_path(&self, index: usize) -> String {
+        let mut to_return = "self->env".to_string();
+        let current = self.contexts.len() - 1;
+        for context in self.contexts[index + 1..current].iter() {
+            if context.env {
+                to_return.push_str("->parent");
+            }
+        }
+        return to_return;
+    }
+
+    // The variable a let binds name to, which is in the innermost scope.
+    fn binding(&self, name: &String) -> String {
+        return self.variable(name).unwrap();
+    }
+
+    // The environment closures created in the current function hold.
+    fn closure_env(&self) -> &'static str {
+        let context = self.context();
+        if context.env {
+            return "e";
+        }
+        if context.kind == Kind::Function {
+            return "self->env";
+        }
+        return "NULL";
+    }
+
+    // ============================================================================
+    // Statements.
+    // ============================================================================
+
+    // Write statements, giving the value of the last one to sink and
+    // discarding the values of the others.
+    fn statements(&mut self, statements: &'a Vec<Box<dyn Node>>, sink: &Sink) {
+        if statements.is_empty() {
+            self.sink_null(sink);
+            return;
+        }
+        for (i, statement) in statements.iter().enumerate() {
+            let mark = self.context().temps;
+            if i + 1 == statements.len() {
+                self.statement(statement, sink);
+            } else {
+                self.statement(statement, &Sink::Discard);
+            }
+            self.context_mut().temps = mark;
+        }
+    }
+
+    fn block(&mut self, block: &'a Box<dyn Node>, sink: &Sink) {
+        let block = block.downcast_ref::<BlockStatement>().unwrap();
+        self.statements(&block.statements, sink);
+    }
+
+    fn sink(&mut self, sink: &Sink, value: Operand) {
+        match sink {
+            Sink::Discard => {
+                // The value is still evaluated for the errors it raises.
+                if !value.stable {
+                    self.line(&format!("{};", value.code));
+                }
+            }
+            Sink::Print => self.line(&format!("rm_print({});", value.code)),
+            Sink::Return => self.return_value(value),
+            Sink::Assign(target) => self.line(&format!("{} = {};", target, value.code)),
+        }
+    }
+
+    // Give sink the null value of a statement which is not an expression.
+    fn sink_null(&mut self, sink: &Sink) {
+        match sink {
+            Sink::Discard | Sink::Print => {}
+            _ => self.sink(sink, Operand::stable("rm_null()".to_string())),
+        }
+    }
+
+    // Return value from the function, leaving the tries the return is in
+    // and running their finally blocks on the way.
+    fn return_value(&mut self, value: Operand) {
+        let tries = self.context().tries.clone();
+        let value = if !tries.is_empty() && !value.stable {
+            self.store(value.code)
+        } else {
+            value
+        };
+        for (i, entry) in tries.iter().enumerate().rev() {
+            self.line(&format!("rm_pop_handler(&{});", entry.handler));
+            if let Some(finally) = entry.finally {
+                self.context_mut().tries.truncate(i);
+                self.block(finally, &Sink::Discard);
+            }
+        }
+        self.context_mut().tries = tries;
+        match self.context().kind {
+            Kind::Function => self.line(&format!("return rm_leave(t, {});", value.code)),
+            Kind::Program => self.line(&format!("return rm_pop(t, {});", value.code)),
+            _ => {
+                self.line(&format!("rm_pop(t, {});", value.code));
+                self.line("return;");
+            }
+        }
+    }
+
+    fn statement(&mut self, node: &'a Box<dyn Node>, sink: &Sink) {
+        match node.get_type() {
+            NodeType::LetStatement => {
+                self.let_statement(node.downcast_ref::<LetStatement>().unwrap());
+                self.sink_null(sink);
+            }
+            NodeType::ExportStatement => {
+                let statement = &node.downcast_ref::<ExportStatement>().unwrap().statement;
+                self.let_statement(statement);
+                self.sink_null(sink);
+            }
+            NodeType::ImportStatement => {
+                let statement = node.downcast_ref::<ImportStatement>().unwrap();
+                let target = self.binding(&statement.alias.token.literal);
+                let module = import_name(self.unit.imports[&statement.path]);
+                self.line(&format!("{} = rm_import(&{});", target, module));
+                self.sink_null(sink);
+            }
+            NodeType::ReturnStatement => {
+                let statement = node.downcast_ref::<ReturnStatement>().unwrap();
+                let value = self.expression(&statement.value);
+                self.return_value(value);
+            }
+            NodeType::ThrowStatement => {
+                let statement = node.downcast_ref::<ThrowStatement>().unwrap();
+                let value = self.expression(&statement.value);
+                self.line(&format!("rm_throw({});", value.code));
+            }
+            NodeType::ExpressionStatement => {
+                let value = &node.downcast_ref::<ExpressionStatement>().unwrap().value;
+                match value.get_type() {
+                    NodeType::IfElseExpression => self.if_statement(value, sink),
+                    NodeType::TryExpression => self.try_statement(value, sink),
+                    _ => {
+                        let value = self.expression(value);
+                        self.sink(sink, value);
+                    }
+                }
+            }
+            _ => {
+                let value = self.expression(node);
+                self.sink(sink, value);
+            }
+        }
+    }
+
+    fn let_statement(&mut self, statement: &'a LetStatement) {
+        let name = &statement.identifier.token.literal;
+        let target = self.binding(name);
+        match statement.value.get_type() {
+            NodeType::IfElseExpression => {
+                self.if_statement(&statement.value, &Sink::Assign(target));
+            }
+            NodeType::TryExpression => {
+                self.try_statement(&statement.value, &Sink::Assign(target));
+            }
+            NodeType::FunctionExpression => {
+                let function = statement
+                    .value
+                    .downcast_ref::<FunctionExpression>()
+                    .unwrap();
+                let closure = self.function(function, Some(name));
+                self.line(&format!("{} = {};", target, closure));
+            }
+            _ => {
+                let mark = self.context().temps;
+                let value = self.expression(&statement.value);
+                self.assign(&target, value, mark);
+            }
+        }
+    }
+
+    fn if_statement(&mut self, node: &'a Box<dyn Node>, sink: &Sink) {
+        let mut expression = node.downcast_ref::<IfElseExpression>().unwrap();
+        let mut keyword = "if";
+        loop {
+            let condition = self.expression(&expression.condition);
+            self.line(&format!("{} (rm_test({})) {{", keyword, condition.code));
+            self.indent += 1;
+            self.block(&expression.consequence, sink);
+            self.indent -= 1;
+
+            // An else whose block is only another if is written as else if,
+            // unless its condition has to be broken into statements.
+            let alternative = match &expression.alternative {
+                Some(alternative) => alternative,
+                None => break,
+            };
+            if let Some(inner) = else_if(alternative).filter(|inner| !emits(&inner.condition)) {
+                expression = inner;
+                keyword = "} else if";
+                continue;
+            }
+            self.line("} else {");
+            self.indent += 1;
+            self.block(alternative, sink);
+            self.indent -= 1;
+            self.line("}");
+            return;
+        }
+
+        // Without an else the value is null.
+        match sink {
+            Sink::Return => {
+                self.line("}");
+                self.sink_null(sink);
+            }
+            Sink::Assign(_) => {
+                self.line("} else {");
+                self.indent += 1;
+                self.sink_null(sink);
+                self.indent -= 1;
+                self.line("}");
+            }
+            _ => self.line("}"),
+        }
+    }
+
+    // The value of a try is kept in a temporary until its finally block has
+    // run, since the finally block can still raise an error.
+    fn try_statement(&mut self, node: &'a Box<dyn Node>, sink: &Sink) {
+        let expression = node.downcast_ref::<TryExpression>().unwrap();
+        let value = match sink {
+            Sink::Discard => None,
+            _ => Some(format!("t[{}]", self.temp())),
+        };
+        let inner = match &value {
+            Some(value) => Sink::Assign(value.clone()),
+            None => Sink::Discard,
+        };
+        let finally = expression.finally_body.as_ref();
+
+        let handler = self.handler();
+        self.line(&format!("if (setjmp({}.jump) == 0) {{", handler));
+        self.indent += 1;
+        self.guarded(&handler, finally, |compiler| {
+            compiler.block(&expression.body, &inner);
+        });
+        self.indent -= 1;
+        self.line("} else {");
+        self.indent += 1;
+        match &expression.catch_body {
+            Some(catch_body) => {
+                let name = &expression.catch_identifier.as_ref().unwrap().token.literal;
+                let statements = &catch_body
+                    .downcast_ref::<BlockStatement>()
+                    .unwrap()
+                    .statements;
+                self.catch_scope(name, statements);
+                let caught = self.binding(name);
+                self.line(&format!("{} = rm_catch();", caught));
+                if let Some(finally) = finally {
+                    // The finally block runs when the catch block fails too.
+                    let handler = self.handler();
+                    self.line(&format!("if (setjmp({}.jump) == 0) {{", handler));
+                    self.indent += 1;
+                    self.guarded(&handler, Some(finally), |compiler| {
+                        compiler.statements(statements, &inner);
+                    });
+                    self.indent -= 1;
+                    self.line("} else {");
+                    self.indent += 1;
+                    self.reraise(finally);
+                    self.indent -= 1;
+                    self.line("}");
+                } else {
+                    self.statements(statements, &inner);
+                }
+                self.context_mut().scopes.pop();
+            }
+            None => match finally {
+                Some(finally) => self.reraise(finally),
+                None => self.line("rm_raise(rm_caught());"),
+            },
+        }
+        self.indent -= 1;
+        self.line("}");
+        if let Some(finally) = finally {
+            self.block(finally, &Sink::Discard);
+        }
+        if let Some(value) = value {
+            self.sink(sink, Operand::stable(value));
+        }
+    }
+
+    // Declare a handler and add it to the runtime's handlers.
+    fn handler(&mut self) -> String {
+        self.context_mut().handlers += 1;
+        let to_return = format!("h{}", self.context().handlers);
+        self.line(&format!("Handler {};", to_return));
+        self.line(&format!("rm_push_handler(&{});", to_return));
+        return to_return;
+    }
+
+    // Write the code of write while handler is the innermost handler, and
+    // then remove it.
+    fn guarded<F: FnOnce(&mut Self)>(
+        &mut self,
+        handler: &String,
+        finally: Option<&'a Box<dyn Node>>,
+        write: F,
+    ) {
+        self.context_mut().tries.push(Try {
+            handler: handler.clone(),
+            finally,
+        });
+        write(self);
+        self.context_mut().tries.pop();
+        self.line(&format!("rm_pop_handler(&{});", handler));
+    }
+
+    // Run the finally block and raise the error that was caught again.
+    fn reraise(&mut self, finally: &'a Box<dyn Node>) {
+        let error = self.store("rm_caught()".to_string());
+        self.block(finally, &Sink::Discard);
+        self.line(&format!("rm_raise({});", error.code));
+    }
+
+    // Start the scope of a catch block, which binds the caught value to name.
+    fn catch_scope(&mut self, name: &String, statements: &'a Vec<Box<dyn Node>>) {
+        let mut names = vec![name.clone()];
+        for binding in bindings(statements) {
+            if !names.contains(&binding.name) {
+                names.push(binding.name);
+            }
+        }
+        let mut used = HashSet::new();
+        for statement in statements.iter() {
+            closure_references(statement, &mut used);
+        }
+        let mut scope = HashMap::new();
+        for variable in names.iter() {
+            let location = if used.contains(variable) {
+                let context = self.context_mut();
+                context.env_size += 1;
+                Location::Env(context.env_size - 1)
+            } else {
+                // The slot may hold a value from an earlier statement.
+                let slot = self.temp();
+                if variable != name {
+                    self.line(&format!("t[{}] = rm_unbound();", slot));
+                }
+                Location::Slot(slot)
+            };
+            scope.insert(variable.clone(), location);
+        }
+        self.context_mut().scopes.push(scope);
+    }
+
+    // ============================================================================
+    // Expressions.
+    // ============================================================================
+
+    fn expression(&mut self, node: &'a Box<dyn Node>) -> Operand {
+        match node.get_type() {
+            NodeType::IdentifierExpression => {
+                let name = &node
+                    .downcast_ref::<IdentifierExpression>()
+                    .unwrap()
+                    .token
+                    .literal;
+                if let Some(variable) = self.variable(name) {
+                    return Operand::unstable(format!("rm_var({}, {})", variable, c_string(name)));
+                }
+                if let Some(index) = self.builtins.iter().position(|builtin| builtin == name) {
+                    return Operand::stable(format!("rm_builtin({})", index));
+                }
+                return Operand::unstable(format!("rm_undefined({})", c_string(name)));
+            }
+            NodeType::IntegralExpression => {
+                let value = node.downcast_ref::<IntegralExpression>().unwrap().value;
+                return Operand::stable(integer(value));
+            }
+            NodeType::BoolExpression => {
+                let value = node.downcast_ref::<BoolExpression>().unwrap().value;
+                return Operand::stable(format!("rm_boolean({})", value as i32));
+            }
+            NodeType::StringExpression => {
+                let value = &node.downcast_ref::<StringExpression>().unwrap().value;
+                return Operand::stable(self.string(value));
+            }
+            NodeType::ArrayExpression => {
+                let elements = &node.downcast_ref::<ArrayExpression>().unwrap().elements;
+                let items = self.values(elements);
+                return self.store(format!("rm_array({}, {})", elements.len(), items));
+            }
+            NodeType::PrefixExpression => {
+                let expression = node.downcast_ref::<PrefixExpression>().unwrap();
+                // A negative integer is written as it is.
+                if expression.token.token_type == TokenType::Minus {
+                    if let Some(right) = expression.right.downcast_ref::<IntegralExpression>() {
+                        return Operand::stable(integer(-right.value));
+                    }
+                }
+                let right = self.expression(&expression.right);
+                return Operand::unstable(match expression.token.token_type {
+                    TokenType::Bang => format!("rm_not({})", right.code),
+                    _ => format!("rm_neg({})", right.code),
+                });
+            }
+            NodeType::InfixExpression => {
+                let expression = node.downcast_ref::<InfixExpression>().unwrap();
+                let operands = self.operands(&[&expression.right, &expression.left]);
+                let (right, left) = (&operands[0].code, &operands[1].code);
+                let function = match expression.token.token_type {
+                    TokenType::Plus => {
+                        // Adding strings allocates the result.
+                        return self.store(format!("rm_add({}, {})", left, right));
+                    }
+                    TokenType::Minus => "rm_sub",
+                    TokenType::Asterisk => "rm_mul",
+                    TokenType::Slash => "rm_div",
+                    TokenType::Lt => "rm_lt",
+                    TokenType::Gt => "rm_gt",
+                    TokenType::Equal => "rm_eq",
+                    _ => "rm_ne",
+                };
+                return Operand::unstable(format!("{}({}, {})", function, left, right));
+            }
+            NodeType::IfElseExpression => {
+                let value = format!("t[{}]", self.temp());
+                self.if_statement(node, &Sink::Assign(value.clone()));
+                return Operand::stable(value);
+            }
+            NodeType::TryExpression => {
+                let value = format!("t[{}]", self.temp());
+                self.try_statement(node, &Sink::Assign(value.clone()));
+                return Operand::stable(value);
+            }
+            NodeType::FunctionExpression => {
+                let function = node.downcast_ref::<FunctionExpression>().unwrap();
+                let closure = self.function(function, None);
+                return self.store(closure);
+            }
+            NodeType::CallExpression => {
+                let expression = node.downcast_ref::<CallExpression>().unwrap();
+                let source = c_string(&expression.func_identifier.to_string());
+                // The callee is checked before the arguments are evaluated.
+                let mut callee = self.expression(&expression.func_identifier);
+                if !callee.stable && expression.arguments.iter().any(emits) {
+                    callee = self.store(format!("rm_function({}, {})", callee.code, source));
+                }
+                let arguments = self.values(&expression.arguments);
+                return self.store(format!(
+                    "rm_call({}, {}, {}, {})",
+                    callee.code,
+                    expression.arguments.len(),
+                    arguments,
+                    source
+                ));
+            }
+            NodeType::IndexExpression => {
+                let expression = node.downcast_ref::<IndexExpression>().unwrap();
+                let operands = self.operands(&[&expression.left, &expression.index]);
+                return Operand::unstable(format!(
+                    "rm_index({}, {})",
+                    operands[0].code, operands[1].code
+                ));
+            }
+            NodeType::MemberExpression => {
+                let expression = node.downcast_ref::<MemberExpression>().unwrap();
+                let object = self.expression(&expression.object);
+                return Operand::unstable(format!(
+                    "rm_member({}, {}, {})",
+                    object.code,
+                    c_string(&expression.member.token.literal),
+                    c_string(&expression.object.to_string())
+                ));
+            }
+            NodeType::PostfixExpression => {
+                // An err result is returned from the function.
+                let expression = node.downcast_ref::<PostfixExpression>().unwrap();
+                let mut result = self.expression(&expression.left);
+                if !result.stable {
+                    result = self.store(result.code);
+                }
+                let value = self.temp();
+                self.line(&format!(
+                    "if (!rm_question({}, &t[{}])) {{",
+                    result.code, value
+                ));
+                self.indent += 1;
+                self.return_value(result);
+                self.indent -= 1;
+                self.line("}");
+                return Operand::stable(format!("t[{}]", value));
+            }
+            _ => {
+                self.error(
+                    &**node,
+                    "Statements can not be compiled as expressions.".to_string(),
+                );
+                return Operand::stable("rm_null()".to_string());
+            }
+        }
+    }
+
+    // Compile nodes, which are evaluated in order. The value of each is kept
+    // in a temporary if a later one is broken into statements, which would
+    // otherwise run before it.
+    fn operands(&mut self, nodes: &[&'a Box<dyn Node>]) -> Vec<Operand> {
+        let mut to_return = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let mut operand = self.expression(node);
+            if !operand.stable && nodes[i + 1..].iter().any(|node| emits(node)) {
+                operand = self.store(operand.code);
+            }
+            to_return.push(operand);
+        }
+        return to_return;
+    }
+
+    // Evaluate nodes into consecutive slots of the frame, as the elements of
+    // an array or the arguments of a call, and return a pointer to the first.
+    fn values(&mut self, nodes: &'a Vec<Box<dyn Node>>) -> String {
+        if nodes.is_empty() {
+            return "NULL".to_string();
+        }
+        let first = self.temp();
+        for _ in 1..nodes.len() {
+            self.temp();
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            let mark = self.context().temps;
+            let value = self.expression(node);
+            self.assign(&format!("t[{}]", first + i), value, mark);
+            self.context_mut().temps = mark;
+        }
+        return format!("&t[{}]", first);
+    }
+
+    // A closure of a function, which is compiled to a C function of its own.
+    fn function(&mut self, expression: &'a FunctionExpression, name: Option<&String>) -> String {
+        self.functions += 1;
+        let c_name = match name.filter(|name| is_c_identifier(name)) {
+            Some(name) => format!("{}_{}", name, self.functions),
+            None => format!("function_{}", self.functions),
+        };
+        let parameters: Vec<String> = expression
+            .parameters
+            .iter()
+            .map(|parameter| {
+                parameter
+                    .downcast_ref::<IdentifierExpression>()
+                    .unwrap()
+                    .token
+                    .literal
+                    .clone()
+            })
+            .collect();
+        let statements = &expression
+            .body
+            .downcast_ref::<BlockStatement>()
+            .unwrap()
+            .statements;
+
+        // The variables used by the closures inside the function are kept in
+        // its environment and the rest in its frame, after the closure and
+        // the arguments.
+        let mut used = HashSet::new();
+        for statement in statements.iter() {
+            closure_references(statement, &mut used);
+        }
+        let names: Vec<String> = bindings(statements)
+            .into_iter()
+            .map(|binding| binding.name)
+            .collect();
+        let env = parameters
+            .iter()
+            .chain(names.iter())
+            .any(|name| used.contains(name))
+            || statements.iter().any(catch_captures);
+        let mut context = Context::new(Kind::Function, env);
+        let mut next = 1 + parameters.len();
+        if env {
+            next += 1;
+        }
+        let mut scope = HashMap::new();
+        let mut copies = Vec::new();
+        for (i, parameter) in parameters.iter().enumerate() {
+            if used.contains(parameter) {
+                copies.push(format!("e->slots[{}] = t[{}];", context.env_size, i + 1));
+                scope.insert(parameter.clone(), Location::Env(context.env_size));
+                context.env_size += 1;
+            } else {
+                scope.insert(parameter.clone(), Location::Slot(i + 1));
+            }
+        }
+        for name in names.iter() {
+            if scope.contains_key(name) {
+                continue;
+            }
+            if used.contains(name) {
+                scope.insert(name.clone(), Location::Env(context.env_size));
+                context.env_size += 1;
+            } else {
+                scope.insert(name.clone(), Location::Slot(next));
+                next += 1;
+            }
+        }
+        context.temps = next;
+        context.size = next;
+        context.scopes.push(scope);
+
+        let closure = format!(
+            "rm_closure({}, {}, {}_text)",
+            c_name,
+            self.closure_env(),
+            c_name
+        );
+        self.contexts.push(context);
+        let output = std::mem::take(&mut self.output);
+        let indent = std::mem::replace(&mut self.indent, 1);
+        self.statements(statements, &Sink::Return);
+        self.indent = indent;
+        let body = std::mem::replace(&mut self.output, output);
+        let context = self.contexts.pop().unwrap();
+
+        // The function prints as it does in the interpreter.
+        let list: Vec<String> = expression
+            .parameters
+            .iter()
+            .map(|parameter| parameter.to_string())
+            .collect();
+        let text = format!("Func ({})\n{}", list.join(","), expression.body.to_string());
+        let signature = format!(
+            "static Value {}(Closure *self, int argc, Value *argv)",
+            c_name
+        );
+        self.prototypes.push(format!("{};", signature));
+        let mut definition = format!(
+            "static const char {}_text[] = {};\n\n{} {{\n",
+            c_name,
+            c_string(&text),
+            signature
+        );
+        definition.push_str(&format!(
+            "    Value *t = rm_enter(self, {}, {}, argc, argv);\n",
+            context.size,
+            parameters.len()
+        ));
+        if env {
+            definition.push_str(&format!(
+                "    Env *e = rm_env(&t[{}], self->env, {});\n",
+                parameters.len() + 1,
+                context.env_size
+            ));
+        }
+        for copy in copies.iter() {
+            definition.push_str(&format!("    {}\n", copy));
+        }
+        definition.push_str(&body);
+        definition.push_str("}\n\n");
+        self.definitions.push_str(&definition);
+        return closure;
+    }
+}
+
+// ================================================================================
+// Analysis.
+// ================================================================================
+
+// Add the names node uses which it does not bind itself.
+#[allow(clippy::borrowed_box)]
+fn references(node: &Box<dyn Node>, names: &mut HashSet<String>) {
+    match node.get_type() {
+        NodeType::IdentifierExpression => {
+            let name = &node
+                .downcast_ref::<IdentifierExpression>()
+                .unwrap()
+                .token
+                .literal;
+            names.insert(name.clone());
+        }
+        NodeType::FunctionExpression => {
+            names.extend(free_variables(
+                node.downcast_ref::<FunctionExpression>().unwrap(),
+            ));
+        }
+        NodeType::TryExpression => {
+            let expression = node.downcast_ref::<TryExpression>().unwrap();
+            references(&expression.body, names);
+            if let Some(finally_body) = &expression.finally_body {
+                references(finally_body, names);
+            }
+            if let Some(catch_body) = &expression.catch_body {
+                let mut inner = HashSet::new();
+                references(catch_body, &mut inner);
+                let bound = catch_names(expression);
+                names.extend(inner.into_iter().filter(|name| !bound.contains(name)));
+            }
+        }
+        _ => {
+            for child in children(node) {
+                references(child, names);
+            }
+        }
+    }
+}
+
+// The names a function uses from the scopes around it.
+fn free_variables(function: &FunctionExpression) -> HashSet<String> {
+    let statements = &function
+        .body
+        .downcast_ref::<BlockStatement>()
+        .unwrap()
+        .statements;
+    let mut to_return = HashSet::new();
+    for statement in statements.iter() {
+        references(statement, &mut to_return);
+    }
+    for parameter in function.parameters.iter() {
+        to_return.remove(
+            &parameter
+                .downcast_ref::<IdentifierExpression>()
+                .unwrap()
+                .token
+                .literal,
+        );
+    }
+    for binding in bindings(statements) {
+        to_return.remove(&binding.name);
+    }
+    return to_return;
+}
+
+// Add the names the functions in node use from the scopes around them.
+#[allow(clippy::borrowed_box)]
+fn closure_references(node: &Box<dyn Node>, names: &mut HashSet<String>) {
+    if let Some(function) = node.downcast_ref::<FunctionExpression>() {
+        names.extend(free_variables(function));
+        return;
+    }
+    for child in children(node) {
+        closure_references(child, names);
+    }
+}
+
+// The names bound in the scope of the catch block of a try.
+fn catch_names(expression: &TryExpression) -> HashSet<String> {
+    let mut to_return = HashSet::new();
+    if let Some(identifier) = &expression.catch_identifier {
+        to_return.insert(identifier.token.literal.clone());
+    }
+    if let Some(catch_body) = &expression.catch_body {
+        let statements = &catch_body
+            .downcast_ref::<BlockStatement>()
+            .unwrap()
+            .statements;
+        for binding in bindings(statements) {
+            to_return.insert(binding.name);
+        }
+    }
+    return to_return;
+}
+
+// Whether a catch block in node, outside of any function, binds a name which
+// a closure uses, so that the function node is in needs an environment.
+#[allow(clippy::borrowed_box)]
+fn catch_captures(node: &Box<dyn Node>) -> bool {
+    match node.get_type() {
+        NodeType::FunctionExpression => return false,
+        NodeType::TryExpression => {
+            let expression = node.downcast_ref::<TryExpression>().unwrap();
+            if let Some(catch_body) = &expression.catch_body {
+                let mut used = HashSet::new();
+                closure_references(catch_body, &mut used);
+                if catch_names(expression)
+                    .iter()
+                    .any(|name| used.contains(name))
+                {
+                    return true;
+                }
+            }
+        }
+        _ => {}
+    }
+    return children(node).iter().any(|child| catch_captures(child));
+}
+
+// Whether node is compiled to statements rather than only an expression.
+#[allow(clippy::borrowed_box)]
+fn emits(node: &Box<dyn Node>) -> bool {
+    return match node.get_type() {
+        NodeType::CallExpression
+        | NodeType::IfElseExpression
+        | NodeType::TryExpression
+        | NodeType::FunctionExpression
+        | NodeType::ArrayExpression
+        | NodeType::PostfixExpression => true,
+        NodeType::InfixExpression
+            if node
+                .downcast_ref::<InfixExpression>()
+                .unwrap()
+                .token
+                .token_type
+                == TokenType::Plus =>
+        {
+            true
+        }
+        _ => children(node).iter().any(|child| emits(child)),
+    };
+}
+
+// ================================================================================
+// Helpers.
+// ================================================================================
+
+fn banner(title: &str) -> String {
+    let rule = format!("// {}", "=".repeat(80));
+    return format!("{}\n// {}\n{}\n\n", rule, title, rule);
+}
+
+// The builtins compiled code refers to by index, in the order of
+// rm_builtin_names. The functions of the prelude written in the runtime are
+// only bound when the prelude is compiled in.
+fn builtin_names(units: &Units) -> Vec<String> {
+    let mut to_return = builtins::names();
+    if units.prelude.is_some() {
+        to_return.extend(
+            builtins::library()
+                .into_iter()
+                .map(|function| function.name),
+        );
+    }
+    return to_return;
+}
+
+fn import_name(index: usize) -> String {
+    return format!("import_{}", index + 1);
+}
+
+// The array holding the globals of a file.
+fn globals_name(kind: Kind) -> String {
+    return match kind {
+        Kind::Prelude => "prelude".to_string(),
+        Kind::Module(index) => format!("{}_globals", import_name(index)),
+        _ => "globals".to_string(),
+    };
+}
+
+fn integer(value: i64) -> String {
+    if value > i32::MAX as i64 || value < i32::MIN as i64 {
+        return format!("rm_integer(INT64_C({}))", value);
+    }
+    return format!("rm_integer({})", value);
+}
+
+// The slot of a temporary such as t[3].
+fn temp_slot(code: &str) -> Option<usize> {
+    return code.strip_prefix("t[")?.strip_suffix(']')?.parse().ok();
+}
+
+fn is_c_identifier(name: &str) -> bool {
+    return name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+}
+
+// A string as a C string literal. Bytes which are not printable ASCII are
+// written in octal, which unlike hex escapes can not run into the next
+// character, and ? is escaped so that it does not start a trigraph.
+fn c_string(value: &str) -> String {
+    let mut to_return = "\"".to_string();
+    for byte in value.bytes() {
+        match byte {
+            b'\\' => to_return.push_str("\\\\"),
+            b'"' => to_return.push_str("\\\""),
+            b'?' => to_return.push_str("\\?"),
+            b'\n' => to_return.push_str("\\n"),
+            b'\t' => to_return.push_str("\\t"),
+            0x20..=0x7e => to_return.push(byte as char),
+            _ => to_return.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    to_return.push('"');
+    return to_return;
+}
+
+// The expression of a block which is only an expression statement.
+#[allow(clippy::borrowed_box)]
+fn single_expression(block: &Box<dyn Node>) -> Option<&Box<dyn Node>> {
+    let block = block.downcast_ref::<BlockStatement>().unwrap();
+    if block.statements.len() != 1
+        || block.statements[0].get_type() != NodeType::ExpressionStatement
+    {
+        return None;
+    }
+    return Some(
+        &block.statements[0]
+            .downcast_ref::<ExpressionStatement>()
+            .unwrap()
+            .value,
+    );
+}
+
+// The if of an else block which is only an if.
+#[allow(clippy::borrowed_box)]
+fn else_if(block: &Box<dyn Node>) -> Option<&IfElseExpression> {
+    let value = single_expression(block)?;
+    return value.downcast_ref::<IfElseExpression>();
+}
@@ -0,0 +1,959 @@
+/*
+Author: Jedda Boyle
+Contains: JavaScript backend
+Compiles a program to ES2015 that runs in node or a browser. The output is
+the runtime in runtime.js, the prelude, the modules the program imports and
+then the program. Each is compiled to code that reads like its source:
+    - A let declares a JavaScript let, or assigns to one declared at the top
+      of the function when the name is first bound inside a block.
+    - A function is an arrow function, which closes over its scope as a
+      rust-monkey function does.
+    - An if or try whose value is returned, printed, bound or discarded is a
+      statement. Elsewhere an if is a ternary if each branch is an
+      expression and otherwise it is an IIFE, as a try always is.
+    - The operators call the runtime, which checks the types of the operands
+      and raises the errors the interpreter raises, such as DivisionByZero
+      and Integer overflow.
+A return inside an IIFE and the ? operator throw a $Return which the function
+that encloses them catches.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::bindings;
+use super::Unit;
+use super::Units;
+use crate::ast::children;
+use crate::ast::node_span;
+use crate::ast::ArrayExpression;
+use crate::ast::BlockStatement;
+use crate::ast::BoolExpression;
+use crate::ast::CallExpression;
+use crate::ast::ExportStatement;
+use crate::ast::ExpressionStatement;
+use crate::ast::FunctionExpression;
+use crate::ast::IdentifierExpression;
+use crate::ast::IfElseExpression;
+use crate::ast::ImportStatement;
+use crate::ast::IndexExpression;
+use crate::ast::InfixExpression;
+use crate::ast::IntegralExpression;
+use crate::ast::LetStatement;
+use crate::ast::MemberExpression;
+use crate::ast::Node;
+use crate::ast::NodeType;
+use crate::ast::PostfixExpression;
+use crate::ast::PrefixExpression;
+use crate::ast::ReturnStatement;
+use crate::ast::StringExpression;
+use crate::ast::ThrowStatement;
+use crate::ast::TryExpression;
+use crate::builtins;
+use crate::lexer::token::TokenType;
+
+use std::collections::HashSet;
+
+// The functions and classes used by compiled code.
+pub const RUNTIME: &str = include_str!("runtime.js");
+
+// The largest integer a JavaScript number holds exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+// Words which can not name a JavaScript variable. Names of rust-monkey
+// variables which are one of these are given a $ suffix.
+const RESERVED: [&str; 46] = [
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+];
+
+// ================================================================================
+// Compile.
+// ================================================================================
+
+// Compile units to a program which runs on its own.
+pub fn compile(units: &Units) -> Result<String, Vec<String>> {
+    let mut to_return = format!(
+        "// Compiled from {} by rust-monkey.\n\n{}",
+        units.main.name, RUNTIME
+    );
+    if let Some(prelude) = &units.prelude {
+        let mut compiler = Compiler::new(prelude);
+        compiler.banner("Prelude.");
+        compiler.prelude(prelude);
+        to_return.push('\n');
+        to_return.push_str(&compiler.finish()?);
+    }
+    to_return.push('\n');
+    to_return.push_str(&compile_program(units)?);
+    return Ok(to_return);
+}
+
+// Compile the modules and the program of units without the runtime or the
+// prelude they need to run.
+pub fn compile_program(units: &Units) -> Result<String, Vec<String>> {
+    let mut to_return = String::new();
+    let mut errors = Vec::new();
+    for (index, module) in units.modules.iter().enumerate() {
+        let mut compiler = Compiler::new(module);
+        compiler.banner(&format!("Module {}.", module.name));
+        compiler.module(index, module);
+        match compiler.finish() {
+            Ok(code) => {
+                to_return.push_str(&code);
+                to_return.push('\n');
+            }
+            Err(module_errors) => errors.extend(module_errors),
+        }
+    }
+    let mut compiler = Compiler::new(&units.main);
+    compiler.banner(&format!("{}.", units.main.name));
+    compiler.main(&units.main);
+    match compiler.finish() {
+        Ok(code) => to_return.push_str(&code),
+        Err(main_errors) => errors.extend(main_errors),
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    return Ok(to_return);
+}
+
+// ================================================================================
+// Compiler.
+// ================================================================================
+
+// Where the value of a statement goes.
+#[derive(Clone)]
+enum Sink {
+    Discard,
+    Print,          // Printed unless it is null, as at the top level of a program.
+    Return,         // Returned from the JavaScript function being written.
+    Assign(String), // Assigned to a variable.
+}
+
+// The state of the function being compiled.
+struct Function {
+    scopes: Vec<HashSet<String>>, // The names declared in each scope of the function.
+    iifes: usize,                 // The IIFEs the code being compiled is in.
+    escapes: bool,                // Whether the function is left by a $Return.
+    exports: bool,                // Whether export let adds to $exports.
+    temps: usize,                 // The temporaries $r1, $r2... the function uses.
+}
+
+struct Compiler<'a> {
+    unit: &'a Unit,
+    output: String,
+    indent: usize,
+    function: Function,
+    errors: Vec<String>,
+}
+
+#[allow(clippy::borrowed_box)]
+impl<'a> Compiler<'a> {
+    fn new(unit: &'a Unit) -> Self {
+        return Compiler {
+            unit,
+            output: String::new(),
+            indent: 0,
+            function: Function {
+                scopes: Vec::new(),
+                iifes: 0,
+                escapes: false,
+                exports: false,
+                temps: 0,
+            },
+            errors: Vec::new(),
+        };
+    }
+
+    fn finish(self) -> Result<String, Vec<String>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        return Ok(self.output);
+    }
+
+    fn error(&mut self, node: &dyn Node, message: String) {
+        let span = node_span(node);
+        self.errors.push(format!(
+            "{}: line {}, column {}: {}",
+            self.unit.name, span.line, span.column, message
+        ));
+    }
+
+    // ============================================================================
+    // Output.
+    // ============================================================================
+
+    fn padding(&self) -> String {
+        return "    ".repeat(self.indent);
+    }
+
+    // Write a line at the current indentation. Lines after the first in text
+    // are already indented.
+    fn line(&mut self, text: &str) {
+        let padding = self.padding();
+        self.output.push_str(&padding);
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn banner(&mut self, title: &str) {
+        let rule = format!("// {}", "=".repeat(80));
+        self.line(&rule);
+        self.line(&format!("// {}", title));
+        self.line(&rule);
+        self.output.push('\n');
+    }
+
+    // Write the lines of write one level further in and return them rather
+    // than adding them to the output.
+    fn nested<F: FnOnce(&mut Self)>(&mut self, write: F) -> String {
+        let output = std::mem::take(&mut self.output);
+        self.indent += 1;
+        write(self);
+        self.indent -= 1;
+        return std::mem::replace(&mut self.output, output);
+    }
+
+    // ============================================================================
+    // Units.
+    // ============================================================================
+
+    // The prelude is compiled at the top level of the file so that its
+    // bindings enclose the program and every module.
+    fn prelude(&mut self, unit: &Unit) {
+        self.function.iifes = 1;
+        for function in builtins::library() {
+            let name = identifier(&function.name);
+            self.line(&format!("let {} = ${};", name, function.name));
+        }
+        let start = self.output.len();
+        self.scope(&unit.program.statements, Vec::new());
+        self.statements(&unit.program.statements, &Sink::Discard);
+        let temporaries = self.temporaries(self.indent);
+        self.output.insert_str(start, &temporaries);
+    }
+
+    // A module is a function which is called the first time it is imported.
+    fn module(&mut self, index: usize, unit: &Unit) {
+        self.function.exports = true;
+        let body = self.nested(|compiler| {
+            compiler.scope(&unit.program.statements, Vec::new());
+            compiler.statements(&unit.program.statements, &Sink::Discard);
+        });
+        self.line(&format!(
+            "const {} = $module({}, ($exports) => {{",
+            module_name(index),
+            quote(&unit.name)
+        ));
+        let temporaries = self.temporaries(self.indent + 1);
+        self.output.push_str(&temporaries);
+        self.output.push_str(&body);
+        self.line("});");
+    }
+
+    // The program prints the value of every statement, as run does.
+    fn main(&mut self, unit: &Unit) {
+        let body = self.nested(|compiler| {
+            compiler.scope(&unit.program.statements, Vec::new());
+            for statement in unit.program.statements.iter() {
+                compiler.statement(statement, &Sink::Print);
+            }
+        });
+        self.line("$run(() => {");
+        let temporaries = self.temporaries(self.indent + 1);
+        self.output.push_str(&temporaries);
+        self.output.push_str(&body);
+        self.line("});");
+    }
+
+    // The declaration of the temporaries of the function being compiled,
+    // written at the top of its body at indent.
+    fn temporaries(&self, indent: usize) -> String {
+        if self.function.temps == 0 {
+            return String::new();
+        }
+        let names: Vec<String> = (1..=self.function.temps)
+            .map(|temp| format!("$r{}", temp))
+            .collect();
+        return format!("{}let {};\n", "    ".repeat(indent), names.join(", "));
+    }
+
+    // ============================================================================
+    // Scopes.
+    // ============================================================================
+
+    // Start the scope of statements, in which parameters are already declared.
+    // The names first bound inside a block are declared at the top of it.
+    fn scope(&mut self, statements: &Vec<Box<dyn Node>>, parameters: Vec<String>) {
+        let mut declared: HashSet<String> = parameters.into_iter().collect();
+        let hoisted: Vec<String> = bindings(statements)
+            .into_iter()
+            .filter(|binding| binding.nested && !declared.contains(&binding.name))
+            .map(|binding| binding.name)
+            .collect();
+        if !hoisted.is_empty() {
+            let names: Vec<String> = hoisted.iter().map(identifier).collect();
+            self.line(&format!("let {};", names.join(", ")));
+        }
+        declared.extend(hoisted);
+        self.function.scopes.push(declared);
+    }
+
+    // Declare name in the current scope, returning false if it already was.
+    fn declare(&mut self, name: &str) -> bool {
+        return self
+            .function
+            .scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string());
+    }
+
+    // ============================================================================
+    // Statements.
+    // ============================================================================
+
+    // Write statements, giving the value of the last one to sink and
+    // discarding the values of the others.
+    fn statements(&mut self, statements: &Vec<Box<dyn Node>>, sink: &Sink) {
+        if statements.is_empty() {
+            self.sink_null(sink);
+            return;
+        }
+        for (i, statement) in statements.iter().enumerate() {
+            if i + 1 == statements.len() {
+                self.statement(statement, sink);
+            } else {
+                self.statement(statement, &Sink::Discard);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Box<dyn Node>, sink: &Sink) {
+        let block = block.downcast_ref::<BlockStatement>().unwrap();
+        self.statements(&block.statements, sink);
+    }
+
+    fn sink(&mut self, sink: &Sink, value: String) {
+        match sink {
+            Sink::Discard => self.line(&format!("{};", value)),
+            Sink::Print => self.line(&format!("$print({});", value)),
+            Sink::Return => self.line(&format!("return {};", value)),
+            Sink::Assign(name) => self.line(&format!("{} = {};", identifier(name), value)),
+        }
+    }
+
+    // Give sink the null value of a statement which is not an expression.
+    fn sink_null(&mut self, sink: &Sink) {
+        match sink {
+            Sink::Discard | Sink::Print => {}
+            _ => self.sink(sink, "null".to_string()),
+        }
+    }
+
+    fn statement(&mut self, node: &Box<dyn Node>, sink: &Sink) {
+        match node.get_type() {
+            NodeType::LetStatement => {
+                self.let_statement(node.downcast_ref::<LetStatement>().unwrap());
+                self.sink_null(sink);
+            }
+            NodeType::ExportStatement => {
+                let statement = &node.downcast_ref::<ExportStatement>().unwrap().statement;
+                self.let_statement(statement);
+                if self.function.exports {
+                    let name = identifier(&statement.identifier.token.literal);
+                    self.line(&format!(
+                        "$exports.{} = {};",
+                        statement.identifier.token.literal, name
+                    ));
+                }
+                self.sink_null(sink);
+            }
+            NodeType::ImportStatement => {
+                let statement = node.downcast_ref::<ImportStatement>().unwrap();
+                let module = module_name(self.unit.imports[&statement.path]);
+                self.bind(&statement.alias.token.literal, format!("{}()", module));
+                self.sink_null(sink);
+            }
+            NodeType::ReturnStatement => {
+                let statement = node.downcast_ref::<ReturnStatement>().unwrap();
+                let value = self.expression(&statement.value);
+                // A return can only leave the JavaScript function it is
+                // written in, which is an IIFE inside an expression.
+                if self.function.iifes != 0 {
+                    self.function.escapes = true;
+                    self.line(&format!("throw new $Return({});", value));
+                } else {
+                    self.line(&format!("return {};", value));
+                }
+            }
+            NodeType::ThrowStatement => {
+                let statement = node.downcast_ref::<ThrowStatement>().unwrap();
+                let value = self.expression(&statement.value);
+                self.line(&format!("throw $throw({});", value));
+            }
+            NodeType::ExpressionStatement => {
+                let value = &node.downcast_ref::<ExpressionStatement>().unwrap().value;
+                match value.get_type() {
+                    NodeType::IfElseExpression => self.if_statement(value, sink),
+                    NodeType::TryExpression => self.try_statement(value, sink),
+                    _ => {
+                        let value = self.expression(value);
+                        self.sink(sink, value);
+                    }
+                }
+            }
+            _ => {
+                let value = self.expression(node);
+                self.sink(sink, value);
+            }
+        }
+    }
+
+    // Bind name to value with a let or, if it is already declared in this
+    // scope, an assignment.
+    fn bind(&mut self, name: &String, value: String) {
+        if self.declare(name) {
+            self.line(&format!("let {} = {};", identifier(name), value));
+        } else {
+            self.line(&format!("{} = {};", identifier(name), value));
+        }
+    }
+
+    fn let_statement(&mut self, statement: &LetStatement) {
+        let name = &statement.identifier.token.literal;
+        match statement.value.get_type() {
+            NodeType::IfElseExpression if ternary_branches(&statement.value).is_none() => {
+                if self.declare(name) {
+                    self.line(&format!("let {};", identifier(name)));
+                }
+                self.if_statement(&statement.value, &Sink::Assign(name.clone()));
+            }
+            NodeType::TryExpression => {
+                if self.declare(name) {
+                    self.line(&format!("let {};", identifier(name)));
+                }
+                self.try_statement(&statement.value, &Sink::Assign(name.clone()));
+            }
+            _ => {
+                let value = self.expression(&statement.value);
+                self.bind(name, value);
+            }
+        }
+    }
+
+    fn if_statement(&mut self, node: &Box<dyn Node>, sink: &Sink) {
+        let mut expression = node.downcast_ref::<IfElseExpression>().unwrap();
+        let mut keyword = "if";
+        loop {
+            let condition = self.condition(&expression.condition);
+            self.line(&format!("{} ({}) {{", keyword, condition));
+            self.indent += 1;
+            self.block(&expression.consequence, sink);
+            self.indent -= 1;
+
+            // An else whose block is only another if is written as else if.
+            let alternative = match &expression.alternative {
+                Some(alternative) => alternative,
+                None => break,
+            };
+            if let Some(inner) = else_if(alternative) {
+                expression = inner;
+                keyword = "} else if";
+                continue;
+            }
+            self.line("} else {");
+            self.indent += 1;
+            self.block(alternative, sink);
+            self.indent -= 1;
+            self.line("}");
+            return;
+        }
+
+        // Without an else the value is null.
+        match sink {
+            Sink::Return => {
+                self.line("}");
+                self.line("return null;");
+            }
+            Sink::Assign(_) => {
+                self.line("} else {");
+                self.indent += 1;
+                self.sink_null(sink);
+                self.indent -= 1;
+                self.line("}");
+            }
+            _ => self.line("}"),
+        }
+    }
+
+    fn try_statement(&mut self, node: &Box<dyn Node>, sink: &Sink) {
+        let expression = node.downcast_ref::<TryExpression>().unwrap();
+        self.line("try {");
+        self.indent += 1;
+        self.block(&expression.body, sink);
+        self.indent -= 1;
+        if let Some(catch_body) = &expression.catch_body {
+            // The catch block is a scope of its own which binds the error.
+            let name = &expression.catch_identifier.as_ref().unwrap().token.literal;
+            self.line("} catch ($e) {");
+            self.indent += 1;
+            self.line(&format!("let {} = $catch($e);", identifier(name)));
+            let statements = &catch_body
+                .downcast_ref::<BlockStatement>()
+                .unwrap()
+                .statements;
+            self.scope(statements, vec![name.clone()]);
+            self.statements(statements, sink);
+            self.function.scopes.pop();
+            self.indent -= 1;
+        }
+        if let Some(finally_body) = &expression.finally_body {
+            self.line("} finally {");
+            self.indent += 1;
+            self.block(finally_body, &Sink::Discard);
+            self.indent -= 1;
+        }
+        self.line("}");
+    }
+
+    // ============================================================================
+    // Expressions.
+    // ============================================================================
+
+    fn expression(&mut self, node: &Box<dyn Node>) -> String {
+        match node.get_type() {
+            NodeType::IdentifierExpression => {
+                let expression = node.downcast_ref::<IdentifierExpression>().unwrap();
+                return identifier(&expression.token.literal);
+            }
+            NodeType::IntegralExpression => {
+                let value = node.downcast_ref::<IntegralExpression>().unwrap().value;
+                if value > MAX_SAFE_INTEGER {
+                    self.error(
+                        &**node,
+                        format!("{} is too large for a JavaScript number.", value),
+                    );
+                }
+                return value.to_string();
+            }
+            NodeType::BoolExpression => {
+                return node
+                    .downcast_ref::<BoolExpression>()
+                    .unwrap()
+                    .value
+                    .to_string();
+            }
+            NodeType::StringExpression => {
+                return quote(&node.downcast_ref::<StringExpression>().unwrap().value);
+            }
+            NodeType::ArrayExpression => {
+                let expression = node.downcast_ref::<ArrayExpression>().unwrap();
+                let elements: Vec<String> = expression
+                    .elements
+                    .iter()
+                    .map(|element| self.expression(element))
+                    .collect();
+                return format!("[{}]", elements.join(", "));
+            }
+            NodeType::PrefixExpression => {
+                let expression = node.downcast_ref::<PrefixExpression>().unwrap();
+                // A negative integer is written as it is.
+                if expression.token.token_type == TokenType::Minus
+                    && expression.right.get_type() == NodeType::IntegralExpression
+                {
+                    return format!("-{}", self.expression(&expression.right));
+                }
+                let right = self.expression(&expression.right);
+                return match expression.token.token_type {
+                    TokenType::Bang => format!("$not({})", right),
+                    _ => format!("$neg({})", right),
+                };
+            }
+            NodeType::InfixExpression => {
+                let expression = node.downcast_ref::<InfixExpression>().unwrap();
+                let right = self.expression(&expression.right);
+                let left = self.expression(&expression.left);
+                let function = match expression.token.token_type {
+                    TokenType::Plus => "$add",
+                    TokenType::Minus => "$sub",
+                    TokenType::Asterisk => "$mul",
+                    TokenType::Slash => "$div",
+                    TokenType::Lt => "$lt",
+                    TokenType::Gt => "$gt",
+                    TokenType::Equal => "$eq",
+                    _ => "$ne",
+                };
+                // The interpreter evaluates the right operand first, so it is
+                // kept in a temporary unless one of the operands is a constant
+                // or a variable, whose evaluation has no effects.
+                if is_constant(&expression.left) || is_constant(&expression.right) {
+                    return format!("{}({}, {})", function, left, right);
+                }
+                self.function.temps += 1;
+                let temp = format!("$r{}", self.function.temps);
+                return format!("({} = {}, {}({}, {}))", temp, right, function, left, temp);
+            }
+            NodeType::IfElseExpression => {
+                if let Some(ternary) = self.ternary(node) {
+                    return ternary;
+                }
+                return self.iife(|compiler| compiler.if_statement(node, &Sink::Return));
+            }
+            NodeType::TryExpression => {
+                return self.iife(|compiler| compiler.try_statement(node, &Sink::Return));
+            }
+            NodeType::FunctionExpression => {
+                return self.function(node.downcast_ref::<FunctionExpression>().unwrap());
+            }
+            NodeType::CallExpression => {
+                let expression = node.downcast_ref::<CallExpression>().unwrap();
+                let mut callee = self.expression(&expression.func_identifier);
+                match expression.func_identifier.get_type() {
+                    NodeType::IdentifierExpression
+                    | NodeType::CallExpression
+                    | NodeType::IndexExpression
+                    | NodeType::MemberExpression
+                    | NodeType::PostfixExpression => {}
+                    _ => callee = format!("({})", callee),
+                }
+                let arguments: Vec<String> = expression
+                    .arguments
+                    .iter()
+                    .map(|argument| self.expression(argument))
+                    .collect();
+                return format!("{}({})", callee, arguments.join(", "));
+            }
+            NodeType::IndexExpression => {
+                let expression = node.downcast_ref::<IndexExpression>().unwrap();
+                let left = self.expression(&expression.left);
+                let index = self.expression(&expression.index);
+                return format!("$index({}, {})", left, index);
+            }
+            NodeType::MemberExpression => {
+                let expression = node.downcast_ref::<MemberExpression>().unwrap();
+                let object = self.expression(&expression.object);
+                return format!(
+                    "$member({}, {}, {})",
+                    object,
+                    quote(&expression.member.token.literal),
+                    quote(&expression.object.to_string())
+                );
+            }
+            NodeType::PostfixExpression => {
+                let expression = node.downcast_ref::<PostfixExpression>().unwrap();
+                self.function.escapes = true;
+                return format!("$unwrap({})", self.expression(&expression.left));
+            }
+            _ => {
+                self.error(
+                    &**node,
+                    "Statements can not be compiled as expressions.".to_string(),
+                );
+                return "null".to_string();
+            }
+        }
+    }
+
+    // The condition of an if, which is checked to be a boolean unless it
+    // can only be one.
+    fn condition(&mut self, node: &Box<dyn Node>) -> String {
+        let value = self.expression(node);
+        let boolean = match node.get_type() {
+            NodeType::BoolExpression => true,
+            NodeType::InfixExpression => {
+                let token_type = node
+                    .downcast_ref::<InfixExpression>()
+                    .unwrap()
+                    .token
+                    .token_type;
+                token_type == TokenType::Lt
+                    || token_type == TokenType::Gt
+                    || token_type == TokenType::Equal
+                    || token_type == TokenType::NotEqual
+            }
+            NodeType::PrefixExpression => {
+                node.downcast_ref::<PrefixExpression>()
+                    .unwrap()
+                    .token
+                    .token_type
+                    == TokenType::Bang
+            }
+            _ => false,
+        };
+        if boolean {
+            return value;
+        }
+        return format!("$test({})", value);
+    }
+
+    // An if whose branches are each a single expression as a ternary.
+    fn ternary(&mut self, node: &Box<dyn Node>) -> Option<String> {
+        let (consequence, alternative) = ternary_branches(node)?;
+        let expression = node.downcast_ref::<IfElseExpression>().unwrap();
+        let condition = self.condition(&expression.condition);
+        let consequence = self.expression(consequence);
+        let alternative = match alternative {
+            Some(alternative) => self.expression(alternative),
+            None => "null".to_string(),
+        };
+        return Some(format!("{} ? {} : {}", condition, consequence, alternative));
+    }
+
+    // Statements written by write in a function which is called at once, so
+    // that they can be used as an expression.
+    fn iife<F: FnOnce(&mut Self)>(&mut self, write: F) -> String {
+        self.function.iifes += 1;
+        let body = self.nested(write);
+        self.function.iifes -= 1;
+        return format!("(() => {{\n{}{}}})()", body, self.padding());
+    }
+
+    fn function(&mut self, expression: &FunctionExpression) -> String {
+        let parameters: Vec<String> = expression
+            .parameters
+            .iter()
+            .map(|parameter| {
+                parameter
+                    .downcast_ref::<IdentifierExpression>()
+                    .unwrap()
+                    .token
+                    .literal
+                    .clone()
+            })
+            .collect();
+        let list: Vec<String> = parameters.iter().map(identifier).collect();
+        let list = list.join(", ");
+        let statements = &expression
+            .body
+            .downcast_ref::<BlockStatement>()
+            .unwrap()
+            .statements;
+
+        let outer = std::mem::replace(
+            &mut self.function,
+            Function {
+                scopes: Vec::new(),
+                iifes: 0,
+                escapes: false,
+                exports: false,
+                temps: 0,
+            },
+        );
+
+        // A body which is only an expression is written as the expression
+        // body of the arrow function, unless it needs temporaries.
+        let concise = single_expression(&expression.body).filter(|value| {
+            value.get_type() != NodeType::IfElseExpression
+                && value.get_type() != NodeType::TryExpression
+                && bindings(statements).is_empty()
+                && !escapes(value)
+        });
+        let errors = self.errors.len();
+        let mut to_return = None;
+        if let Some(value) = concise {
+            self.function
+                .scopes
+                .push(parameters.iter().cloned().collect());
+            let value = self.expression(value);
+            if self.function.temps == 0 {
+                to_return = Some(format!("({}) => {}", list, value));
+            } else {
+                self.function.scopes.clear();
+                self.function.temps = 0;
+                self.errors.truncate(errors);
+            }
+        }
+        let to_return = if let Some(to_return) = to_return {
+            to_return
+        } else {
+            let body = self.nested(|compiler| {
+                compiler.scope(statements, parameters);
+                compiler.statements(statements, &Sink::Return);
+            });
+            let body = format!("{}{}", self.temporaries(self.indent + 1), body);
+            // A function left by a $Return catches it and returns its value.
+            let body = if self.function.escapes {
+                let padding = self.padding();
+                format!(
+                    "{}    try {{\n{}{}    }} catch ($e) {{\n{}        return $return($e);\n{}    }}\n",
+                    padding,
+                    indent(&body),
+                    padding,
+                    padding,
+                    padding
+                )
+            } else {
+                body
+            };
+            format!("({}) => {{\n{}{}}}", list, body, self.padding())
+        };
+        self.function = outer;
+        return to_return;
+    }
+}
+
+// ================================================================================
+// Helpers.
+// ================================================================================
+
+// The JavaScript name of a rust-monkey variable.
+fn identifier(name: &String) -> String {
+    if RESERVED.contains(&name.as_str()) {
+        return format!("{}$", name);
+    }
+    return name.clone();
+}
+
+fn module_name(index: usize) -> String {
+    return format!("$module_{}", index + 1);
+}
+
+// A string as a JavaScript string literal.
+fn quote(value: &str) -> String {
+    let mut to_return = "\"".to_string();
+    for c in value.chars() {
+        match c {
+            '\\' => to_return.push_str("\\\\"),
+            '"' => to_return.push_str("\\\""),
+            '\n' => to_return.push_str("\\n"),
+            '\r' => to_return.push_str("\\r"),
+            '\t' => to_return.push_str("\\t"),
+            '\u{2028}' | '\u{2029}' => to_return.push_str(&format!("\\u{:04x}", c as u32)),
+            c if (c as u32) < 0x20 => to_return.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => to_return.push(c),
+        }
+    }
+    to_return.push('"');
+    return to_return;
+}
+
+// Indent every line of text by one more level.
+fn indent(text: &str) -> String {
+    let mut to_return = String::new();
+    for line in text.lines() {
+        if !line.is_empty() {
+            to_return.push_str("    ");
+        }
+        to_return.push_str(line);
+        to_return.push('\n');
+    }
+    return to_return;
+}
+
+// Whether node is a literal or a variable, whose evaluation can only be seen
+// through the NameError of a variable that is not bound.
+#[allow(clippy::borrowed_box)]
+fn is_constant(node: &Box<dyn Node>) -> bool {
+    return match node.get_type() {
+        NodeType::IntegralExpression
+        | NodeType::StringExpression
+        | NodeType::BoolExpression
+        | NodeType::IdentifierExpression => true,
+        NodeType::PrefixExpression => {
+            let expression = node.downcast_ref::<PrefixExpression>().unwrap();
+            expression.token.token_type == TokenType::Minus
+                && expression.right.get_type() == NodeType::IntegralExpression
+        }
+        _ => false,
+    };
+}
+
+// The expression of a block which is only an expression statement.
+#[allow(clippy::borrowed_box)]
+fn single_expression(block: &Box<dyn Node>) -> Option<&Box<dyn Node>> {
+    let block = block.downcast_ref::<BlockStatement>().unwrap();
+    if block.statements.len() != 1
+        || block.statements[0].get_type() != NodeType::ExpressionStatement
+    {
+        return None;
+    }
+    return Some(
+        &block.statements[0]
+            .downcast_ref::<ExpressionStatement>()
+            .unwrap()
+            .value,
+    );
+}
+
+// The expressions of the consequence and alternative of an if.
+type Branches<'a> = (&'a Box<dyn Node>, Option<&'a Box<dyn Node>>);
+
+// The branches of an if which are each a single expression, so that it can
+// be written as a ternary.
+#[allow(clippy::borrowed_box)]
+fn ternary_branches(node: &Box<dyn Node>) -> Option<Branches<'_>> {
+    let expression = node.downcast_ref::<IfElseExpression>().unwrap();
+    let consequence = single_expression(&expression.consequence)?;
+    let alternative = match &expression.alternative {
+        Some(alternative) => Some(single_expression(alternative)?),
+        None => None,
+    };
+    return Some((consequence, alternative));
+}
+
+// Whether node returns from the function it is in from inside an
+// expression, which is done with a $Return.
+#[allow(clippy::borrowed_box)]
+fn escapes(node: &Box<dyn Node>) -> bool {
+    return match node.get_type() {
+        NodeType::ReturnStatement | NodeType::PostfixExpression => true,
+        NodeType::FunctionExpression => false,
+        _ => children(node).iter().any(|child| escapes(child)),
+    };
+}
+
+// The if of an else block which is only an if.
+#[allow(clippy::borrowed_box)]
+fn else_if(block: &Box<dyn Node>) -> Option<&IfElseExpression> {
+    let value = single_expression(block)?;
+    return value.downcast_ref::<IfElseExpression>();
+}
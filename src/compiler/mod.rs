@@ -0,0 +1,267 @@
+/*
+Author: Jedda Boyle
+Contains: Compiler
+Translates a program into another language so that it can run without the
+interpreter. A program is compiled together with the prelude and every
+module it imports, which are found and parsed before anything is compiled.
+The backends share the loading of modules and the analysis of which names
+each scope binds, since a let binds a name in the enviroment of the
+function it is in rather than in the block it is written in.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+pub mod c;
+pub mod js;
+
+use super::ast::children;
+use super::ast::ExportStatement;
+use super::ast::IfElseExpression;
+use super::ast::ImportStatement;
+use super::ast::LetStatement;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::ast::TryExpression;
+use super::interpreter::parse_source;
+use super::interpreter::PRELUDE;
+use super::parser::Program;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+// ================================================================================
+// Targets.
+// ================================================================================
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Target {
+    C,
+    Js,
+}
+
+pub const TARGETS: [&str; 2] = ["c", "js"];
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Target> {
+        return match name {
+            "c" => Some(Target::C),
+            "js" => Some(Target::Js),
+            _ => None,
+        };
+    }
+}
+
+// Compile the program in input, read from path if it was read from a file,
+// into a program in the language of target. The errors are returned as
+// messages.
+pub fn compile(
+    target: Target,
+    input: &str,
+    path: Option<PathBuf>,
+    prelude: bool,
+) -> Result<String, Vec<String>> {
+    let units = Units::load(input, path, prelude).map_err(|error| vec![error])?;
+    return match target {
+        Target::C => c::compile(&units),
+        Target::Js => js::compile(&units),
+    };
+}
+
+// ================================================================================
+// Units.
+// ================================================================================
+
+// A parsed file of source code.
+pub struct Unit {
+    pub name: String, // The path of the file as it was imported, or the name of the source.
+    pub path: Option<PathBuf>, // Imports are relative to this file.
+    pub program: Program,
+    pub imports: HashMap<String, usize>, // The module each import path refers to.
+}
+
+// A program, the prelude and the modules they import.
+pub struct Units {
+    pub prelude: Option<Unit>,
+    pub modules: Vec<Unit>,
+    pub main: Unit,
+    canonical: HashMap<PathBuf, usize>, // The module loaded from each file.
+}
+
+impl Units {
+    // Parse input, read from path if it was read from a file, the prelude if
+    // it is wanted and every module they import.
+    pub fn load(input: &str, path: Option<PathBuf>, prelude: bool) -> Result<Units, String> {
+        let name = match &path {
+            Some(path) => path.display().to_string(),
+            None => "<stdin>".to_string(),
+        };
+        let mut units = Units {
+            prelude: None,
+            modules: Vec::new(),
+            main: empty_unit(),
+            canonical: HashMap::new(),
+        };
+        if prelude {
+            let mut unit = parse_unit(PRELUDE, "prelude".to_string(), None)?;
+            units.load_imports(&mut unit)?;
+            units.prelude = Some(unit);
+        }
+        let mut main = parse_unit(input, name, path)?;
+        units.load_imports(&mut main)?;
+        units.main = main;
+        return Ok(units);
+    }
+
+    // Load the modules imported by unit, and those they import, and record
+    // which module each of its imports refers to.
+    fn load_imports(&mut self, unit: &mut Unit) -> Result<(), String> {
+        let mut paths = Vec::new();
+        for statement in unit.program.statements.iter() {
+            find_imports(statement, &mut paths);
+        }
+        for path in paths {
+            if unit.imports.contains_key(&path) {
+                continue;
+            }
+            // Imports are relative to the directory of the importing file,
+            // as they are in the interpreter.
+            let relative = match unit.path.as_ref().and_then(|base| base.parent()) {
+                Some(directory) => directory.join(&path),
+                None => PathBuf::from(&path),
+            };
+            let canonical = fs::canonicalize(&relative)
+                .map_err(|error| format!("Could not import {}: {}", path, error))?;
+            if let Some(index) = self.canonical.get(&canonical) {
+                unit.imports.insert(path, *index);
+                continue;
+            }
+            let input = fs::read_to_string(&canonical)
+                .map_err(|error| format!("Could not import {}: {}", path, error))?;
+
+            // The module is given its index before its imports are loaded so
+            // that a cycle of imports refers back to it.
+            let index = self.modules.len();
+            self.canonical.insert(canonical, index);
+            self.modules.push(empty_unit());
+            let mut module = parse_unit(
+                &input,
+                relative.display().to_string(),
+                Some(relative.clone()),
+            )?;
+            self.load_imports(&mut module)?;
+            self.modules[index] = module;
+            unit.imports.insert(path, index);
+        }
+        return Ok(());
+    }
+}
+
+fn parse_unit(input: &str, name: String, path: Option<PathBuf>) -> Result<Unit, String> {
+    let program = parse_source(input, &name).map_err(|error| error.to_string())?;
+    return Ok(Unit {
+        name,
+        path,
+        program,
+        imports: HashMap::new(),
+    });
+}
+
+fn empty_unit() -> Unit {
+    return Unit {
+        name: String::new(),
+        path: None,
+        program: Program {
+            statements: Vec::new(),
+        },
+        imports: HashMap::new(),
+    };
+}
+
+// Add the paths of the imports in node, in the order they are written.
+#[allow(clippy::borrowed_box)]
+fn find_imports(node: &Box<dyn Node>, paths: &mut Vec<String>) {
+    if node.get_type() == NodeType::ImportStatement {
+        paths.push(node.downcast_ref::<ImportStatement>().unwrap().path.clone());
+        return;
+    }
+    for child in children(node) {
+        find_imports(child, paths);
+    }
+}
+
+// ================================================================================
+// Scopes.
+// ================================================================================
+
+// A name bound by a let or an import.
+pub struct Binding {
+    pub name: String,
+    // Whether the name is first bound inside a block, such as the body of
+    // an if, rather than by one of the statements of the scope itself.
+    pub nested: bool,
+}
+
+// The names bound in the scope of statements, which are the body of a
+// function, a catch block or a file, in the order they are first bound.
+// The bodies of functions and catch blocks are scopes of their own.
+pub fn bindings(statements: &Vec<Box<dyn Node>>) -> Vec<Binding> {
+    let mut to_return = Vec::new();
+    for statement in statements.iter() {
+        find_bindings(statement, false, &mut to_return);
+    }
+    return to_return;
+}
+
+fn bind(name: &String, nested: bool, bindings: &mut Vec<Binding>) {
+    if !bindings.iter().any(|binding| &binding.name == name) {
+        bindings.push(Binding {
+            name: name.clone(),
+            nested,
+        });
+    }
+}
+
+#[allow(clippy::borrowed_box)]
+fn find_bindings(node: &Box<dyn Node>, nested: bool, bindings: &mut Vec<Binding>) {
+    match node.get_type() {
+        NodeType::LetStatement => {
+            // The value is evaluated before the name is bound.
+            let statement = node.downcast_ref::<LetStatement>().unwrap();
+            find_bindings(&statement.value, nested, bindings);
+            bind(&statement.identifier.token.literal, nested, bindings);
+        }
+        NodeType::ExportStatement => {
+            let statement = &node.downcast_ref::<ExportStatement>().unwrap().statement;
+            find_bindings(&statement.value, nested, bindings);
+            bind(&statement.identifier.token.literal, nested, bindings);
+        }
+        NodeType::ImportStatement => {
+            let statement = node.downcast_ref::<ImportStatement>().unwrap();
+            bind(&statement.alias.token.literal, nested, bindings);
+        }
+        NodeType::FunctionExpression => {}
+        NodeType::IfElseExpression => {
+            let expression = node.downcast_ref::<IfElseExpression>().unwrap();
+            find_bindings(&expression.condition, nested, bindings);
+            find_bindings(&expression.consequence, true, bindings);
+            if let Some(alternative) = &expression.alternative {
+                find_bindings(alternative, true, bindings);
+            }
+        }
+        NodeType::TryExpression => {
+            let expression = node.downcast_ref::<TryExpression>().unwrap();
+            find_bindings(&expression.body, true, bindings);
+            if let Some(finally_body) = &expression.finally_body {
+                find_bindings(finally_body, true, bindings);
+            }
+        }
+        _ => {
+            for child in children(node) {
+                find_bindings(child, nested, bindings);
+            }
+        }
+    }
+}
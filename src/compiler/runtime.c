@@ -0,0 +1,1498 @@
+// ================================================================================
+// Runtime.
+// ================================================================================
+
+// The runtime gives compiled programs the semantics of the interpreter.
+// A value is a tag and either an integer, a boolean, the index of a builtin
+// or a pointer to an object on the heap. Objects are freed by a mark and
+// sweep collector whose roots are the value stack, on which every function
+// keeps its variables and temporaries, and the globals of each file. The
+// variables a closure uses from the functions around it are kept in an
+// environment on the heap instead. Errors are raised with longjmp to the
+// innermost try, or to rm_main which reports them as run does.
+
+#include <inttypes.h>
+#include <setjmp.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#if defined(__GNUC__)
+#define RM_NORETURN __attribute__((noreturn))
+#else
+#define RM_NORETURN
+#endif
+
+// The interpreter's default call depth limit.
+#define RM_MAX_DEPTH 512
+
+// The number of values on the value stack.
+#define RM_STACK_SIZE (1 << 20)
+
+// The bytes allocated before the first collection.
+#define RM_HEAP_START (1 << 20)
+
+// Building with -DRM_GC_STRESS=1 collects before every allocation, which
+// finds values that are not kept where the collector can see them.
+#ifndef RM_GC_STRESS
+#define RM_GC_STRESS 0
+#endif
+
+#define RM_MAX_ROOTS 256
+
+typedef enum {
+    RM_UNBOUND, // A variable which has not been bound yet.
+    RM_NULL,
+    RM_INTEGER,
+    RM_BOOLEAN,
+    RM_STRING,
+    RM_ARRAY,
+    RM_FUNCTION,
+    RM_BUILTIN,
+    RM_RESULT,
+    RM_ERROR,
+    RM_MODULE,
+    RM_ENV, // An environment, kept on the value stack so it is not collected.
+} Type;
+
+typedef struct Object {
+    struct Object *next; // Every object, to be swept.
+    size_t size;
+    Type type;
+    int marked;
+} Object;
+
+typedef struct {
+    Type type;
+    union {
+        int64_t integer;
+        int boolean;
+        int builtin;
+        Object *object;
+    } as;
+} Value;
+
+typedef struct {
+    Object header;
+    size_t length;
+    char chars[];
+} String;
+
+typedef struct {
+    Object header;
+    size_t length;
+    Value items[];
+} Array;
+
+// The variables of a call which closures use.
+typedef struct Env {
+    Object header;
+    struct Env *parent;
+    int size;
+    Value slots[];
+} Env;
+
+typedef struct Closure Closure;
+typedef Value (*Code)(Closure *self, int argc, Value *argv);
+
+struct Closure {
+    Object header;
+    Code code;
+    Env *env;
+    const char *text; // As the interpreter prints the function.
+};
+
+typedef struct {
+    Object header;
+    int ok;
+    Value value;
+} Result;
+
+// An error, which is also the value a catch block receives for an error that
+// was not thrown. value is the value given to throw or exit.
+typedef struct {
+    Object header;
+    int kind;
+    Value message;
+    Value value;
+} Error;
+
+// A module of the program and the globals it exports.
+typedef struct Import {
+    const char *path;
+    void (*body)(void);
+    Value *globals;
+    int count;
+    const char *const *names;
+    const int *slots;
+    int loading;
+    Value module;
+} Import;
+
+typedef struct {
+    Object header;
+    Import *import;
+} Module;
+
+enum {
+    RM_RUNTIME_ERROR,
+    RM_TYPE_ERROR,
+    RM_NAME_ERROR,
+    RM_DIVISION_BY_ZERO_ERROR,
+    RM_THROWN,
+    RM_ASSERTION_ERROR,
+    RM_DEPTH_LIMIT_ERROR,
+    RM_EXIT,
+    RM_KINDS,
+};
+
+static const char *const rm_kind_names[RM_KINDS] = {
+    "RuntimeError", "TypeError", "NameError", "DivisionByZeroError",
+    "Error", "AssertionError", "DepthLimitError", "Exit",
+};
+
+// A try, or the top level of the program, which an error jumps to.
+typedef struct Handler {
+    struct Handler *previous;
+    Value *sp;
+    int depth;
+    jmp_buf jump;
+} Handler;
+
+typedef struct {
+    char *data;
+    size_t length;
+    size_t capacity;
+} Buffer;
+
+static Object *rm_objects = NULL;
+static size_t rm_allocated = 0;
+static size_t rm_threshold = RM_HEAP_START;
+
+static Value rm_stack[RM_STACK_SIZE];
+static Value *rm_sp = rm_stack;
+static int rm_depth = 0;
+
+static Value *rm_roots[RM_MAX_ROOTS];
+static int rm_root_sizes[RM_MAX_ROOTS];
+static int rm_root_count = 0;
+
+static Handler *rm_handler = NULL;
+static Value rm_raised; // The error being raised.
+static Value rm_kinds[RM_KINDS]; // The names of the kinds as strings.
+
+// In the order of the interpreter's builtins, followed by the functions of
+// the prelude which are written in the runtime. Compiled code refers to them
+// by index.
+static const char *const rm_builtin_names[] = {
+    "ok",     "err",       "is_ok",       "unwrap_or", "len",  "first", "last",  "rest", "push",
+    "args",   "env",       "exit",        "puts",      "print", "assert", "assert_eq",
+    "assert_error", "range", "map", "filter", "reduce", "zip", "any", "all",
+};
+
+static Import *rm_importing[RM_MAX_ROOTS];
+static int rm_importing_count = 0;
+
+static int rm_argc = 0;
+static char **rm_argv = NULL;
+static Buffer rm_results = {NULL, 0, 0};
+
+RM_NORETURN void rm_fail(int kind, const char *message);
+
+// ================================================================================
+// Values.
+// ================================================================================
+
+static inline Value rm_unbound(void) {
+    Value value;
+    value.type = RM_UNBOUND;
+    value.as.integer = 0;
+    return value;
+}
+
+static inline Value rm_null(void) {
+    Value value;
+    value.type = RM_NULL;
+    value.as.integer = 0;
+    return value;
+}
+
+static inline Value rm_integer(int64_t integer) {
+    Value value;
+    value.type = RM_INTEGER;
+    value.as.integer = integer;
+    return value;
+}
+
+static inline Value rm_boolean(int boolean) {
+    Value value;
+    value.type = RM_BOOLEAN;
+    value.as.integer = 0;
+    value.as.boolean = boolean != 0;
+    return value;
+}
+
+static inline Value rm_builtin(int builtin) {
+    Value value;
+    value.type = RM_BUILTIN;
+    value.as.integer = 0;
+    value.as.builtin = builtin;
+    return value;
+}
+
+static inline Value rm_object(Type type, void *object) {
+    Value value;
+    value.type = type;
+    value.as.object = (Object *)object;
+    return value;
+}
+
+#define RM_AS(type, value) ((type *)(value).as.object)
+
+// The name of the type of a value, as the interpreter prints it.
+const char *rm_type(Value value) {
+    switch (value.type) {
+    case RM_INTEGER:
+        return "Integer";
+    case RM_BOOLEAN:
+        return "Boolean";
+    case RM_STRING:
+        return "String";
+    case RM_ARRAY:
+        return "Array";
+    case RM_FUNCTION:
+        return "Function";
+    case RM_BUILTIN:
+        return "Builtin";
+    case RM_RESULT:
+        return "Result";
+    case RM_ERROR:
+        return "Error";
+    case RM_MODULE:
+        return "Module";
+    default:
+        return "Null";
+    }
+}
+
+// ================================================================================
+// Collector.
+// ================================================================================
+
+// Keep count values from being collected.
+void rm_root(Value *values, int count) {
+    rm_roots[rm_root_count] = values;
+    rm_root_sizes[rm_root_count] = count;
+    rm_root_count++;
+}
+
+static void rm_mark(Value value);
+
+static void rm_mark_object(Object *object) {
+    if (object == NULL || object->marked) {
+        return;
+    }
+    object->marked = 1;
+    switch (object->type) {
+    case RM_ARRAY: {
+        Array *array = (Array *)object;
+        for (size_t i = 0; i < array->length; i++) {
+            rm_mark(array->items[i]);
+        }
+        break;
+    }
+    case RM_ENV: {
+        Env *env = (Env *)object;
+        rm_mark_object((Object *)env->parent);
+        for (int i = 0; i < env->size; i++) {
+            rm_mark(env->slots[i]);
+        }
+        break;
+    }
+    case RM_FUNCTION:
+        rm_mark_object((Object *)((Closure *)object)->env);
+        break;
+    case RM_RESULT:
+        rm_mark(((Result *)object)->value);
+        break;
+    case RM_ERROR:
+        rm_mark(((Error *)object)->message);
+        rm_mark(((Error *)object)->value);
+        break;
+    default:
+        break;
+    }
+}
+
+static void rm_mark(Value value) {
+    if (value.type >= RM_STRING && value.type != RM_BUILTIN) {
+        rm_mark_object(value.as.object);
+    }
+}
+
+static void rm_collect(void) {
+    for (Value *value = rm_stack; value < rm_sp; value++) {
+        rm_mark(*value);
+    }
+    for (int i = 0; i < rm_root_count; i++) {
+        for (int j = 0; j < rm_root_sizes[i]; j++) {
+            rm_mark(rm_roots[i][j]);
+        }
+    }
+    rm_mark(rm_raised);
+
+    Object **link = &rm_objects;
+    while (*link != NULL) {
+        Object *object = *link;
+        if (object->marked) {
+            object->marked = 0;
+            link = &object->next;
+        } else {
+            *link = object->next;
+            rm_allocated -= object->size;
+            free(object);
+        }
+    }
+    if (rm_threshold < rm_allocated * 2) {
+        rm_threshold = rm_allocated * 2;
+    }
+}
+
+static void *rm_allocate(Type type, size_t size) {
+    if (RM_GC_STRESS || rm_allocated + size > rm_threshold) {
+        rm_collect();
+    }
+    Object *object = malloc(size);
+    if (object == NULL) {
+        fprintf(stderr, "Out of memory.\n");
+        exit(1);
+    }
+    object->next = rm_objects;
+    object->size = size;
+    object->type = type;
+    object->marked = 0;
+    rm_objects = object;
+    rm_allocated += size;
+    return object;
+}
+
+// ================================================================================
+// Objects.
+// ================================================================================
+
+Value rm_string(const char *chars, size_t length) {
+    String *string = rm_allocate(RM_STRING, sizeof(String) + length + 1);
+    string->length = length;
+    memcpy(string->chars, chars, length);
+    string->chars[length] = '\0';
+    return rm_object(RM_STRING, string);
+}
+
+// An array of the count values at items, which are on the value stack.
+Value rm_array(int count, Value *items) {
+    Array *array = rm_allocate(RM_ARRAY, sizeof(Array) + count * sizeof(Value));
+    array->length = count;
+    for (int i = 0; i < count; i++) {
+        array->items[i] = items[i];
+    }
+    return rm_object(RM_ARRAY, array);
+}
+
+// A new environment of size unbound variables, kept in slot.
+Env *rm_env(Value *slot, Env *parent, int size) {
+    Env *env = rm_allocate(RM_ENV, sizeof(Env) + size * sizeof(Value));
+    env->parent = parent;
+    env->size = size;
+    for (int i = 0; i < size; i++) {
+        env->slots[i] = rm_unbound();
+    }
+    *slot = rm_object(RM_ENV, env);
+    return env;
+}
+
+Value rm_closure(Code code, Env *env, const char *text) {
+    Closure *closure = rm_allocate(RM_FUNCTION, sizeof(Closure));
+    closure->code = code;
+    closure->env = env;
+    closure->text = text;
+    return rm_object(RM_FUNCTION, closure);
+}
+
+static Value rm_result(int ok, Value value) {
+    Result *result = rm_allocate(RM_RESULT, sizeof(Result));
+    result->ok = ok;
+    result->value = value;
+    return rm_object(RM_RESULT, result);
+}
+
+// ================================================================================
+// Printing.
+// ================================================================================
+
+static void rm_append(Buffer *buffer, const char *data, size_t length) {
+    if (buffer->length + length + 1 > buffer->capacity) {
+        size_t capacity = buffer->capacity == 0 ? 64 : buffer->capacity * 2;
+        while (buffer->length + length + 1 > capacity) {
+            capacity *= 2;
+        }
+        buffer->data = realloc(buffer->data, capacity);
+        if (buffer->data == NULL) {
+            fprintf(stderr, "Out of memory.\n");
+            exit(1);
+        }
+        buffer->capacity = capacity;
+    }
+    memcpy(buffer->data + buffer->length, data, length);
+    buffer->length += length;
+    buffer->data[buffer->length] = '\0';
+}
+
+static void rm_append_text(Buffer *buffer, const char *text) {
+    rm_append(buffer, text, strlen(text));
+}
+
+// Add value as puts prints it.
+void rm_show(Buffer *buffer, Value value) {
+    char number[32];
+    switch (value.type) {
+    case RM_INTEGER:
+        snprintf(number, sizeof(number), "%" PRId64, value.as.integer);
+        rm_append_text(buffer, number);
+        break;
+    case RM_BOOLEAN:
+        rm_append_text(buffer, value.as.boolean ? "true" : "false");
+        break;
+    case RM_STRING:
+        rm_append(buffer, RM_AS(String, value)->chars, RM_AS(String, value)->length);
+        break;
+    case RM_ARRAY: {
+        Array *array = RM_AS(Array, value);
+        rm_append_text(buffer, "[");
+        for (size_t i = 0; i < array->length; i++) {
+            if (i != 0) {
+                rm_append_text(buffer, ", ");
+            }
+            rm_show(buffer, array->items[i]);
+        }
+        rm_append_text(buffer, "]");
+        break;
+    }
+    case RM_FUNCTION:
+        rm_append_text(buffer, RM_AS(Closure, value)->text);
+        break;
+    case RM_BUILTIN:
+        rm_append_text(buffer, "Builtin ");
+        rm_append_text(buffer, rm_builtin_names[value.as.builtin]);
+        break;
+    case RM_RESULT:
+        rm_append_text(buffer, RM_AS(Result, value)->ok ? "ok(" : "err(");
+        rm_show(buffer, RM_AS(Result, value)->value);
+        rm_append_text(buffer, ")");
+        break;
+    case RM_ERROR:
+        rm_append_text(buffer, rm_kind_names[RM_AS(Error, value)->kind]);
+        rm_append_text(buffer, ": ");
+        rm_show(buffer, RM_AS(Error, value)->message);
+        break;
+    case RM_MODULE:
+        rm_append_text(buffer, "Module ");
+        rm_append_text(buffer, RM_AS(Module, value)->import->path);
+        break;
+    default:
+        rm_append_text(buffer, "null");
+        break;
+    }
+}
+
+// Structural equality, as used by assert_eq.
+static int rm_equal(Value left, Value right) {
+    if (strcmp(rm_type(left), rm_type(right)) != 0) {
+        return 0;
+    }
+    switch (left.type) {
+    case RM_INTEGER:
+        return left.as.integer == right.as.integer;
+    case RM_BOOLEAN:
+        return left.as.boolean == right.as.boolean;
+    case RM_STRING:
+        return RM_AS(String, left)->length == RM_AS(String, right)->length &&
+               memcmp(RM_AS(String, left)->chars, RM_AS(String, right)->chars,
+                      RM_AS(String, left)->length) == 0;
+    case RM_ARRAY: {
+        Array *a = RM_AS(Array, left);
+        Array *b = RM_AS(Array, right);
+        if (a->length != b->length) {
+            return 0;
+        }
+        for (size_t i = 0; i < a->length; i++) {
+            if (!rm_equal(a->items[i], b->items[i])) {
+                return 0;
+            }
+        }
+        return 1;
+    }
+    case RM_RESULT:
+        return RM_AS(Result, left)->ok == RM_AS(Result, right)->ok &&
+               rm_equal(RM_AS(Result, left)->value, RM_AS(Result, right)->value);
+    case RM_BUILTIN:
+        return left.as.builtin == right.as.builtin;
+    case RM_NULL:
+    case RM_UNBOUND:
+        return 1;
+    default:
+        return left.as.object == right.as.object;
+    }
+}
+
+// ================================================================================
+// Errors.
+// ================================================================================
+
+void rm_push_handler(Handler *handler) {
+    handler->previous = rm_handler;
+    handler->sp = rm_sp;
+    handler->depth = rm_depth;
+    rm_handler = handler;
+}
+
+void rm_pop_handler(Handler *handler) {
+    rm_handler = handler->previous;
+}
+
+// Jump to the innermost handler with error, leaving the calls inside it.
+RM_NORETURN void rm_raise(Value error) {
+    Handler *handler = rm_handler;
+    rm_raised = error;
+    rm_handler = handler->previous;
+    rm_sp = handler->sp;
+    rm_depth = handler->depth;
+    longjmp(handler->jump, 1);
+}
+
+static Value rm_error(int kind, const char *message, size_t length, Value value) {
+    Error *error = rm_allocate(RM_ERROR, sizeof(Error));
+    error->kind = kind;
+    error->message = rm_null();
+    error->value = value;
+    // The error is kept in rm_raised while its message is allocated.
+    rm_raised = rm_object(RM_ERROR, error);
+    error->message = rm_string(message, length);
+    return rm_raised;
+}
+
+RM_NORETURN void rm_fail(int kind, const char *message) {
+    rm_raise(rm_error(kind, message, strlen(message), rm_unbound()));
+}
+
+// Raise an error whose message is in buffer.
+RM_NORETURN static void rm_fail_buffer(int kind, Buffer *buffer) {
+    Value error = rm_error(kind, buffer->data, buffer->length, rm_unbound());
+    free(buffer->data);
+    rm_raise(error);
+}
+
+// Raise an error whose message is value, as it is printed, between before and after.
+RM_NORETURN static void rm_fail_value(int kind, const char *before, Value value, const char *after) {
+    Buffer buffer = {NULL, 0, 0};
+    rm_append_text(&buffer, before);
+    rm_show(&buffer, value);
+    rm_append_text(&buffer, after);
+    rm_fail_buffer(kind, &buffer);
+}
+
+// Raise an error whose message is the type of value between before and after.
+RM_NORETURN static void rm_fail_type(const char *before, Value value, const char *after) {
+    Buffer buffer = {NULL, 0, 0};
+    rm_append_text(&buffer, before);
+    rm_append_text(&buffer, rm_type(value));
+    rm_append_text(&buffer, after);
+    rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+}
+
+// The errors which end the program and can not be caught.
+static int rm_stopping(Value error) {
+    int kind = RM_AS(Error, error)->kind;
+    return kind == RM_DEPTH_LIMIT_ERROR || kind == RM_EXIT;
+}
+
+// The error raised by throw value. Throwing a caught error raises it again.
+RM_NORETURN void rm_throw(Value value) {
+    if (value.type == RM_ERROR) {
+        rm_raise(value);
+    }
+    Buffer buffer = {NULL, 0, 0};
+    rm_show(&buffer, value);
+    Value error = rm_error(RM_THROWN, buffer.data, buffer.length, value);
+    free(buffer.data);
+    rm_raise(error);
+}
+
+// The error a try caught, which is raised again if it ends the program.
+Value rm_caught(void) {
+    if (rm_stopping(rm_raised)) {
+        rm_raise(rm_raised);
+    }
+    return rm_raised;
+}
+
+// The value a catch block receives for the error a try caught.
+Value rm_catch(void) {
+    Value error = rm_caught();
+    if (RM_AS(Error, error)->value.type != RM_UNBOUND) {
+        return RM_AS(Error, error)->value;
+    }
+    return error;
+}
+
+// The value of a variable, which must have been bound.
+static inline Value rm_var(Value value, const char *name) {
+    if (value.type == RM_UNBOUND) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, "Variable ");
+        rm_append_text(&buffer, name);
+        rm_append_text(&buffer, " is not in scope.");
+        rm_fail_buffer(RM_NAME_ERROR, &buffer);
+    }
+    return value;
+}
+
+// A variable which is bound nowhere.
+Value rm_undefined(const char *name) {
+    return rm_var(rm_unbound(), name);
+}
+
+// ================================================================================
+// Operators.
+// ================================================================================
+
+// The error raised by an operator given operands it does not support.
+RM_NORETURN static void rm_operands(const char *operator, Value left, Value right) {
+    const char *type = rm_type(left);
+    if (strcmp(type, rm_type(right)) == 0 && (left.type == RM_BOOLEAN || left.type == RM_STRING)) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, "Operator ");
+        rm_append_text(&buffer, operator);
+        rm_append_text(&buffer, left.type == RM_BOOLEAN ? " is not supported for booleans."
+                                                         : " is not supported for strings.");
+        rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+    }
+    rm_fail(RM_TYPE_ERROR, "The operand types don't match.");
+}
+
+static inline int rm_integers(Value left, Value right) {
+    return left.type == RM_INTEGER && right.type == RM_INTEGER;
+}
+
+static inline Value rm_checked(int overflow, int64_t result) {
+    if (overflow) {
+        rm_fail(RM_RUNTIME_ERROR, "Integer overflow.");
+    }
+    return rm_integer(result);
+}
+
+#if defined(__GNUC__)
+#define RM_ADD_OVERFLOW(a, b, result) __builtin_add_overflow(a, b, result)
+#define RM_SUB_OVERFLOW(a, b, result) __builtin_sub_overflow(a, b, result)
+#define RM_MUL_OVERFLOW(a, b, result) __builtin_mul_overflow(a, b, result)
+#else
+static int rm_add_overflow(int64_t a, int64_t b, int64_t *result) {
+    if ((b > 0 && a > INT64_MAX - b) || (b < 0 && a < INT64_MIN - b)) {
+        return 1;
+    }
+    *result = a + b;
+    return 0;
+}
+
+static int rm_sub_overflow(int64_t a, int64_t b, int64_t *result) {
+    if ((b < 0 && a > INT64_MAX + b) || (b > 0 && a < INT64_MIN + b)) {
+        return 1;
+    }
+    *result = a - b;
+    return 0;
+}
+
+static int rm_mul_overflow(int64_t a, int64_t b, int64_t *result) {
+    if (a > 0 ? (b > 0 ? a > INT64_MAX / b : b < INT64_MIN / a)
+              : (b > 0 ? a < INT64_MIN / b : a != 0 && b < INT64_MAX / a)) {
+        return 1;
+    }
+    *result = a * b;
+    return 0;
+}
+
+#define RM_ADD_OVERFLOW(a, b, result) rm_add_overflow(a, b, result)
+#define RM_SUB_OVERFLOW(a, b, result) rm_sub_overflow(a, b, result)
+#define RM_MUL_OVERFLOW(a, b, result) rm_mul_overflow(a, b, result)
+#endif
+
+Value rm_add(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        int64_t result = 0;
+        int overflow = RM_ADD_OVERFLOW(left.as.integer, right.as.integer, &result);
+        return rm_checked(overflow, result);
+    }
+    if (left.type == RM_STRING && right.type == RM_STRING) {
+        String *a = RM_AS(String, left);
+        String *b = RM_AS(String, right);
+        // Both strings are on the value stack while the result is allocated.
+        String *string = rm_allocate(RM_STRING, sizeof(String) + a->length + b->length + 1);
+        string->length = a->length + b->length;
+        memcpy(string->chars, a->chars, a->length);
+        memcpy(string->chars + a->length, b->chars, b->length);
+        string->chars[string->length] = '\0';
+        return rm_object(RM_STRING, string);
+    }
+    rm_operands("+", left, right);
+}
+
+static inline Value rm_sub(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        int64_t result = 0;
+        int overflow = RM_SUB_OVERFLOW(left.as.integer, right.as.integer, &result);
+        return rm_checked(overflow, result);
+    }
+    rm_operands("-", left, right);
+}
+
+static inline Value rm_mul(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        int64_t result = 0;
+        int overflow = RM_MUL_OVERFLOW(left.as.integer, right.as.integer, &result);
+        return rm_checked(overflow, result);
+    }
+    rm_operands("*", left, right);
+}
+
+// Integer division rounds towards zero.
+static inline Value rm_div(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        if (right.as.integer == 0) {
+            rm_fail(RM_DIVISION_BY_ZERO_ERROR, "Division by zero.");
+        }
+        int overflow = left.as.integer == INT64_MIN && right.as.integer == -1;
+        return rm_checked(overflow, overflow ? 0 : left.as.integer / right.as.integer);
+    }
+    rm_operands("/", left, right);
+}
+
+static inline Value rm_lt(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        return rm_boolean(left.as.integer < right.as.integer);
+    }
+    rm_operands("<", left, right);
+}
+
+static inline Value rm_gt(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        return rm_boolean(left.as.integer > right.as.integer);
+    }
+    rm_operands(">", left, right);
+}
+
+// Only integers, booleans and strings can be compared.
+static int rm_compare(const char *operator, Value left, Value right) {
+    if (left.type != right.type) {
+        rm_operands(operator, left, right);
+    }
+    switch (left.type) {
+    case RM_INTEGER:
+        return left.as.integer == right.as.integer;
+    case RM_BOOLEAN:
+        return left.as.boolean == right.as.boolean;
+    case RM_STRING:
+        return RM_AS(String, left)->length == RM_AS(String, right)->length &&
+               memcmp(RM_AS(String, left)->chars, RM_AS(String, right)->chars,
+                      RM_AS(String, left)->length) == 0;
+    default:
+        rm_operands(operator, left, right);
+    }
+}
+
+static inline Value rm_eq(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        return rm_boolean(left.as.integer == right.as.integer);
+    }
+    return rm_boolean(rm_compare("==", left, right));
+}
+
+static inline Value rm_ne(Value left, Value right) {
+    if (rm_integers(left, right)) {
+        return rm_boolean(left.as.integer != right.as.integer);
+    }
+    return rm_boolean(!rm_compare("!=", left, right));
+}
+
+static inline Value rm_neg(Value right) {
+    if (right.type == RM_INTEGER) {
+        return rm_checked(right.as.integer == INT64_MIN, -right.as.integer);
+    }
+    rm_fail(RM_TYPE_ERROR, "Error: Prefix operand is not an integer as expected");
+}
+
+static inline Value rm_not(Value right) {
+    if (right.type == RM_BOOLEAN) {
+        return rm_boolean(!right.as.boolean);
+    }
+    rm_fail(RM_TYPE_ERROR, "Error: Prefix operand is not a boolean as expected");
+}
+
+// The condition of an if.
+static inline int rm_test(Value condition) {
+    if (condition.type == RM_BOOLEAN) {
+        return condition.as.boolean;
+    }
+    rm_fail(RM_TYPE_ERROR, "Condition is not of boolean type.");
+}
+
+static inline Value rm_index(Value left, Value index) {
+    if (index.type != RM_INTEGER) {
+        rm_fail_value(RM_TYPE_ERROR, "Index ", index, " is not an integer.");
+    }
+    if (left.type != RM_ARRAY) {
+        rm_fail_type("", left, " can not be indexed.");
+    }
+    Array *array = RM_AS(Array, left);
+    if (index.as.integer < 0 || (uint64_t)index.as.integer >= array->length) {
+        rm_fail_value(RM_RUNTIME_ERROR, "Index ", index, " is out of range.");
+    }
+    return array->items[index.as.integer];
+}
+
+// The member called name of a module or a caught error. source is the
+// expression the object came from.
+Value rm_member(Value object, const char *name, const char *source) {
+    if (object.type == RM_ERROR && strcmp(name, "message") == 0) {
+        return RM_AS(Error, object)->message;
+    }
+    if (object.type == RM_ERROR && strcmp(name, "kind") == 0) {
+        return rm_kinds[RM_AS(Error, object)->kind];
+    }
+    if (object.type == RM_MODULE) {
+        Import *import = RM_AS(Module, object)->import;
+        for (int i = 0; i < import->count; i++) {
+            Value value = import->globals[import->slots[i]];
+            if (strcmp(import->names[i], name) == 0 && value.type != RM_UNBOUND) {
+                return value;
+            }
+        }
+    }
+    Buffer buffer = {NULL, 0, 0};
+    rm_append_text(&buffer, source);
+    rm_append_text(&buffer, " has no member ");
+    rm_append_text(&buffer, name);
+    rm_append_text(&buffer, ".");
+    rm_fail_buffer(RM_NAME_ERROR, &buffer);
+}
+
+// The ? operator, which gives the value of an ok result and returns false
+// for an err result so that it is returned.
+static inline int rm_question(Value result, Value *value) {
+    if (result.type != RM_RESULT) {
+        rm_fail_value(RM_TYPE_ERROR, "Operator ? expects a result but was given ", result, ".");
+    }
+    *value = RM_AS(Result, result)->value;
+    return RM_AS(Result, result)->ok;
+}
+
+// ================================================================================
+// Calls.
+// ================================================================================
+
+typedef Value (*BuiltinFunction)(int argc, Value *argv);
+
+static Value rm_call_builtin(int builtin, int argc, Value *argv);
+
+// Check that callee can be called. source is the expression it came from.
+static inline Value rm_function(Value callee, const char *source) {
+    if (callee.type != RM_FUNCTION && callee.type != RM_BUILTIN) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, source);
+        rm_append_text(&buffer, " is not a function.");
+        rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+    }
+    return callee;
+}
+
+// Call callee with the argc arguments at argv, which are on the value stack.
+static inline Value rm_call(Value callee, int argc, Value *argv, const char *source) {
+    if (callee.type == RM_FUNCTION) {
+        Closure *closure = RM_AS(Closure, callee);
+        return closure->code(closure, argc, argv);
+    }
+    if (callee.type == RM_BUILTIN) {
+        return rm_call_builtin(callee.as.builtin, argc, argv);
+    }
+    rm_function(callee, source);
+    return rm_null();
+}
+
+// Start a call of self with a frame of size values on the value stack. The
+// closure is kept in the first and the arguments for its parameters in
+// those after it.
+static inline Value *rm_enter(Closure *self, int size, int parameters, int argc, Value *argv) {
+    if (++rm_depth > RM_MAX_DEPTH || size > rm_stack + RM_STACK_SIZE - rm_sp) {
+        Buffer buffer = {NULL, 0, 0};
+        char message[64];
+        snprintf(message, sizeof(message), "Call depth limit of %d exceeded.", RM_MAX_DEPTH);
+        rm_append_text(&buffer, message);
+        rm_fail_buffer(RM_DEPTH_LIMIT_ERROR, &buffer);
+    }
+    Value *frame = rm_sp;
+    rm_sp += size;
+    frame[0] = rm_object(RM_FUNCTION, self);
+    for (int i = 1; i < size; i++) {
+        frame[i] = i <= parameters && i <= argc ? argv[i - 1] : rm_unbound();
+    }
+    return frame;
+}
+
+// End the call whose frame is frame, returning value.
+static inline Value rm_leave(Value *frame, Value value) {
+    rm_sp = frame;
+    rm_depth--;
+    return value;
+}
+
+// The frame of the top level of a file.
+Value *rm_frame(int size) {
+    Value *frame = rm_sp;
+    rm_sp += size;
+    for (int i = 0; i < size; i++) {
+        frame[i] = rm_unbound();
+    }
+    return frame;
+}
+
+static inline Value rm_pop(Value *frame, Value value) {
+    rm_sp = frame;
+    return value;
+}
+
+// ================================================================================
+// Builtins.
+// ================================================================================
+
+static void rm_arguments(const char *name, int argc, int expected) {
+    if (argc != expected) {
+        char message[128];
+        snprintf(message, sizeof(message), "%s expects %d arguments but was given %d.", name,
+                 expected, argc);
+        rm_fail(RM_TYPE_ERROR, message);
+    }
+}
+
+static Result *rm_expect_result(const char *name, Value value) {
+    if (value.type != RM_RESULT) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, name);
+        rm_append_text(&buffer, " expects a result but was given ");
+        rm_show(&buffer, value);
+        rm_append_text(&buffer, ".");
+        rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+    }
+    return RM_AS(Result, value);
+}
+
+static Array *rm_expect_array(const char *name, Value value) {
+    if (value.type != RM_ARRAY) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, name);
+        rm_append_text(&buffer, " expects an array but was given ");
+        rm_append_text(&buffer, rm_type(value));
+        rm_append_text(&buffer, ".");
+        rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+    }
+    return RM_AS(Array, value);
+}
+
+static Value rm_builtin_ok(int argc, Value *argv) {
+    rm_arguments("ok", argc, 1);
+    return rm_result(1, argv[0]);
+}
+
+static Value rm_builtin_err(int argc, Value *argv) {
+    rm_arguments("err", argc, 1);
+    return rm_result(0, argv[0]);
+}
+
+static Value rm_builtin_is_ok(int argc, Value *argv) {
+    rm_arguments("is_ok", argc, 1);
+    return rm_boolean(rm_expect_result("is_ok", argv[0])->ok);
+}
+
+static Value rm_builtin_unwrap_or(int argc, Value *argv) {
+    rm_arguments("unwrap_or", argc, 2);
+    Result *result = rm_expect_result("unwrap_or", argv[0]);
+    return result->ok ? result->value : argv[1];
+}
+
+// The length of a string is the number of characters in it.
+static Value rm_builtin_len(int argc, Value *argv) {
+    rm_arguments("len", argc, 1);
+    if (argv[0].type == RM_STRING) {
+        String *string = RM_AS(String, argv[0]);
+        int64_t length = 0;
+        for (size_t i = 0; i < string->length; i++) {
+            if ((string->chars[i] & 0xC0) != 0x80) {
+                length++;
+            }
+        }
+        return rm_integer(length);
+    }
+    return rm_integer(rm_expect_array("len", argv[0])->length);
+}
+
+static Value rm_builtin_first(int argc, Value *argv) {
+    rm_arguments("first", argc, 1);
+    Array *array = rm_expect_array("first", argv[0]);
+    return array->length != 0 ? array->items[0] : rm_null();
+}
+
+static Value rm_builtin_last(int argc, Value *argv) {
+    rm_arguments("last", argc, 1);
+    Array *array = rm_expect_array("last", argv[0]);
+    return array->length != 0 ? array->items[array->length - 1] : rm_null();
+}
+
+static Value rm_builtin_rest(int argc, Value *argv) {
+    rm_arguments("rest", argc, 1);
+    Array *array = rm_expect_array("rest", argv[0]);
+    if (array->length == 0) {
+        return rm_array(0, NULL);
+    }
+    return rm_array(array->length - 1, array->items + 1);
+}
+
+// The new array is allocated before the old one is read, which is on the
+// value stack so it is not collected in between.
+static Value rm_builtin_push(int argc, Value *argv) {
+    rm_arguments("push", argc, 2);
+    size_t length = rm_expect_array("push", argv[0])->length;
+    Array *array = rm_allocate(RM_ARRAY, sizeof(Array) + (length + 1) * sizeof(Value));
+    array->length = length + 1;
+    memcpy(array->items, RM_AS(Array, argv[0])->items, length * sizeof(Value));
+    array->items[length] = argv[1];
+    return rm_object(RM_ARRAY, array);
+}
+
+// The functions of the prelude which loop over arrays. Each keeps the array it
+// builds in a frame on the value stack, with the arguments of the function it
+// calls after it, so that neither is collected during the call.
+
+// A new array of length nulls.
+static Value rm_new_array(uint64_t length) {
+    if (length > (SIZE_MAX - sizeof(Array)) / sizeof(Value)) {
+        fprintf(stderr, "Out of memory.\n");
+        exit(1);
+    }
+    Array *array = rm_allocate(RM_ARRAY, sizeof(Array) + length * sizeof(Value));
+    array->length = length;
+    for (size_t i = 0; i < length; i++) {
+        array->items[i] = rm_null();
+    }
+    return rm_object(RM_ARRAY, array);
+}
+
+static int64_t rm_expect_integer(const char *name, Value value) {
+    if (value.type != RM_INTEGER) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, name);
+        rm_append_text(&buffer, " expects an integer but was given ");
+        rm_append_text(&buffer, rm_type(value));
+        rm_append_text(&buffer, ".");
+        rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+    }
+    return value.as.integer;
+}
+
+static void rm_expect_function(const char *name, Value value) {
+    if (value.type != RM_FUNCTION && value.type != RM_BUILTIN) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, name);
+        rm_append_text(&buffer, " expects a function but was given ");
+        rm_append_text(&buffer, rm_type(value));
+        rm_append_text(&buffer, ".");
+        rm_fail_buffer(RM_TYPE_ERROR, &buffer);
+    }
+}
+
+static Value rm_builtin_range(int argc, Value *argv) {
+    rm_arguments("range", argc, 2);
+    int64_t start = rm_expect_integer("range", argv[0]);
+    int64_t end = rm_expect_integer("range", argv[1]);
+    // The length is found without overflowing for any start and end.
+    uint64_t length = start < end ? (uint64_t)end - (uint64_t)start : 0;
+    Value value = rm_new_array(length);
+    Array *array = RM_AS(Array, value);
+    for (size_t i = 0; i < array->length; i++) {
+        array->items[i] = rm_integer(start + (int64_t)i);
+    }
+    return value;
+}
+
+static Value rm_builtin_map(int argc, Value *argv) {
+    rm_arguments("map", argc, 2);
+    size_t length = rm_expect_array("map", argv[0])->length;
+    rm_expect_function("map", argv[1]);
+    Value *frame = rm_frame(2);
+    frame[0] = rm_new_array(length);
+    for (size_t i = 0; i < length; i++) {
+        frame[1] = RM_AS(Array, argv[0])->items[i];
+        Value value = rm_call(argv[1], 1, frame + 1, "");
+        RM_AS(Array, frame[0])->items[i] = value;
+    }
+    return rm_pop(frame, frame[0]);
+}
+
+// The elements which are kept are moved to the front of a new array as long
+// as arr, which is then shortened.
+static Value rm_builtin_filter(int argc, Value *argv) {
+    rm_arguments("filter", argc, 2);
+    size_t length = rm_expect_array("filter", argv[0])->length;
+    rm_expect_function("filter", argv[1]);
+    Value *frame = rm_frame(2);
+    frame[0] = rm_new_array(length);
+    size_t count = 0;
+    for (size_t i = 0; i < length; i++) {
+        frame[1] = RM_AS(Array, argv[0])->items[i];
+        if (rm_test(rm_call(argv[1], 1, frame + 1, ""))) {
+            RM_AS(Array, frame[0])->items[count++] = frame[1];
+        }
+    }
+    RM_AS(Array, frame[0])->length = count;
+    return rm_pop(frame, frame[0]);
+}
+
+static Value rm_builtin_reduce(int argc, Value *argv) {
+    rm_arguments("reduce", argc, 3);
+    size_t length = rm_expect_array("reduce", argv[0])->length;
+    rm_expect_function("reduce", argv[2]);
+    Value *frame = rm_frame(2);
+    frame[0] = argv[1];
+    for (size_t i = 0; i < length; i++) {
+        frame[1] = RM_AS(Array, argv[0])->items[i];
+        frame[0] = rm_call(argv[2], 2, frame, "");
+    }
+    return rm_pop(frame, frame[0]);
+}
+
+static Value rm_builtin_zip(int argc, Value *argv) {
+    rm_arguments("zip", argc, 2);
+    size_t a = rm_expect_array("zip", argv[0])->length;
+    size_t b = rm_expect_array("zip", argv[1])->length;
+    size_t length = a < b ? a : b;
+    Value *frame = rm_frame(3);
+    frame[0] = rm_new_array(length);
+    for (size_t i = 0; i < length; i++) {
+        frame[1] = RM_AS(Array, argv[0])->items[i];
+        frame[2] = RM_AS(Array, argv[1])->items[i];
+        Value pair = rm_array(2, frame + 1);
+        RM_AS(Array, frame[0])->items[i] = pair;
+    }
+    return rm_pop(frame, frame[0]);
+}
+
+// Whether f returns expected for any element of arr, stopping at the first
+// one it does.
+static int rm_find(const char *name, int argc, Value *argv, int expected) {
+    rm_arguments(name, argc, 2);
+    size_t length = rm_expect_array(name, argv[0])->length;
+    rm_expect_function(name, argv[1]);
+    Value *frame = rm_frame(1);
+    for (size_t i = 0; i < length; i++) {
+        frame[0] = RM_AS(Array, argv[0])->items[i];
+        if (rm_test(rm_call(argv[1], 1, frame, "")) == expected) {
+            rm_pop(frame, rm_null());
+            return 1;
+        }
+    }
+    rm_pop(frame, rm_null());
+    return 0;
+}
+
+static Value rm_builtin_any(int argc, Value *argv) {
+    return rm_boolean(rm_find("any", argc, argv, 1));
+}
+
+static Value rm_builtin_all(int argc, Value *argv) {
+    return rm_boolean(!rm_find("all", argc, argv, 0));
+}
+
+static Value rm_builtin_args(int argc, Value *argv) {
+    rm_arguments("args", argc, 0);
+    (void)argv;
+    // Each string is kept on the value stack until the array holds them.
+    Value *frame = rm_frame(rm_argc > 1 ? rm_argc - 1 : 0);
+    for (int i = 1; i < rm_argc; i++) {
+        frame[i - 1] = rm_string(rm_argv[i], strlen(rm_argv[i]));
+    }
+    return rm_pop(frame, rm_array(rm_argc > 1 ? rm_argc - 1 : 0, frame));
+}
+
+static Value rm_builtin_env(int argc, Value *argv) {
+    rm_arguments("env", argc, 1);
+    if (argv[0].type != RM_STRING) {
+        rm_fail_type("env expects a string but was given ", argv[0], ".");
+    }
+    const char *value = getenv(RM_AS(String, argv[0])->chars);
+    return value != NULL ? rm_string(value, strlen(value)) : rm_null();
+}
+
+// End the program with an exit status, which is the value of the error.
+static Value rm_builtin_exit(int argc, Value *argv) {
+    if (argc > 1) {
+        rm_arguments("exit", argc, 1);
+    }
+    Value code = argc != 0 ? argv[0] : rm_integer(0);
+    if (code.type != RM_INTEGER) {
+        rm_fail_type("exit expects an integer but was given ", code, ".");
+    }
+    char message[64];
+    snprintf(message, sizeof(message), "exit(%" PRId64 ")", code.as.integer);
+    rm_raise(rm_error(RM_EXIT, message, strlen(message), code));
+}
+
+static void rm_write_arguments(int argc, Value *argv, const char *end) {
+    Buffer buffer = {NULL, 0, 0};
+    for (int i = 0; i < argc; i++) {
+        if (i != 0) {
+            rm_append_text(&buffer, " ");
+        }
+        rm_show(&buffer, argv[i]);
+    }
+    rm_append_text(&buffer, end);
+    fwrite(buffer.data, 1, buffer.length, stdout);
+    free(buffer.data);
+}
+
+static Value rm_builtin_puts(int argc, Value *argv) {
+    rm_write_arguments(argc, argv, "\n");
+    return rm_null();
+}
+
+static Value rm_builtin_print(int argc, Value *argv) {
+    rm_write_arguments(argc, argv, "");
+    return rm_null();
+}
+
+static Value rm_builtin_assert(int argc, Value *argv) {
+    if (argc != 1 && argc != 2) {
+        rm_arguments("assert", argc, 1);
+    }
+    if (argv[0].type != RM_BOOLEAN) {
+        rm_fail_type("assert expects a boolean but was given ", argv[0], ".");
+    }
+    if (!argv[0].as.boolean) {
+        if (argc == 2) {
+            rm_fail_value(RM_ASSERTION_ERROR, "", argv[1], "");
+        }
+        rm_fail(RM_ASSERTION_ERROR, "assertion failed.");
+    }
+    return rm_null();
+}
+
+static Value rm_builtin_assert_eq(int argc, Value *argv) {
+    if (argc != 2 && argc != 3) {
+        rm_arguments("assert_eq", argc, 2);
+    }
+    Value actual = argv[0];
+    Value expected = argv[1];
+    if (rm_equal(actual, expected)) {
+        return rm_null();
+    }
+    // The types are shown when they differ as 1 and "1" print the same.
+    int types = strcmp(rm_type(actual), rm_type(expected)) != 0;
+    Buffer buffer = {NULL, 0, 0};
+    if (argc == 3) {
+        rm_show(&buffer, argv[2]);
+        rm_append_text(&buffer, ": ");
+    }
+    rm_append_text(&buffer, "expected ");
+    rm_show(&buffer, expected);
+    if (types) {
+        rm_append_text(&buffer, " (");
+        rm_append_text(&buffer, rm_type(expected));
+        rm_append_text(&buffer, ")");
+    }
+    rm_append_text(&buffer, " but was ");
+    rm_show(&buffer, actual);
+    if (types) {
+        rm_append_text(&buffer, " (");
+        rm_append_text(&buffer, rm_type(actual));
+        rm_append_text(&buffer, ")");
+    }
+    rm_append_text(&buffer, ".");
+    rm_fail_buffer(RM_ASSERTION_ERROR, &buffer);
+}
+
+static Value rm_builtin_assert_error(int argc, Value *argv) {
+    if (argc != 1 && argc != 2) {
+        rm_arguments("assert_error", argc, 1);
+    }
+    if (argv[0].type != RM_FUNCTION) {
+        rm_fail_type("assert_error expects a function but was given ", argv[0], ".");
+    }
+    Handler handler;
+    rm_push_handler(&handler);
+    if (setjmp(handler.jump) == 0) {
+        Value result = rm_call(argv[0], 0, rm_sp, "");
+        rm_pop_handler(&handler);
+        rm_fail_value(RM_ASSERTION_ERROR, "expected an error but the function returned ", result,
+                      ".");
+    }
+    Value error = rm_caught();
+    Error *raised = RM_AS(Error, error);
+    if (argc == 2) {
+        Buffer kind = {NULL, 0, 0};
+        rm_show(&kind, argv[1]);
+        if (strcmp(kind.data, rm_kind_names[raised->kind]) != 0) {
+            Buffer buffer = {NULL, 0, 0};
+            rm_append_text(&buffer, "expected ");
+            rm_append_text(&buffer, kind.data);
+            rm_append_text(&buffer, " but the function raised ");
+            rm_append_text(&buffer, rm_kind_names[raised->kind]);
+            rm_append_text(&buffer, ": ");
+            rm_show(&buffer, raised->message);
+            free(kind.data);
+            rm_fail_buffer(RM_ASSERTION_ERROR, &buffer);
+        }
+        free(kind.data);
+    }
+    return raised->value.type != RM_UNBOUND ? raised->value : error;
+}
+
+// In the order of rm_builtin_names.
+static const BuiltinFunction rm_builtin_functions[] = {
+    rm_builtin_ok,     rm_builtin_err,       rm_builtin_is_ok,       rm_builtin_unwrap_or,
+    rm_builtin_len,    rm_builtin_first,     rm_builtin_last,        rm_builtin_rest,
+    rm_builtin_push,   rm_builtin_args,      rm_builtin_env,         rm_builtin_exit,
+    rm_builtin_puts,   rm_builtin_print,     rm_builtin_assert,      rm_builtin_assert_eq,
+    rm_builtin_assert_error,
+    rm_builtin_range,  rm_builtin_map,       rm_builtin_filter,      rm_builtin_reduce,
+    rm_builtin_zip,    rm_builtin_any,       rm_builtin_all,
+};
+
+static Value rm_call_builtin(int builtin, int argc, Value *argv) {
+    return rm_builtin_functions[builtin](argc, argv);
+}
+
+// ================================================================================
+// Programs.
+// ================================================================================
+
+// Print the value of a statement at the top level unless it is null. They
+// are printed once the program ends, as they are by the interpreter.
+void rm_print(Value value) {
+    if (value.type != RM_NULL) {
+        rm_show(&rm_results, value);
+        rm_append_text(&rm_results, "\n");
+    }
+}
+
+// The module of import, whose body is run the first time it is imported. A
+// module which fails is run again the next time it is imported.
+Value rm_import(Import *import) {
+    if (import->module.type == RM_MODULE) {
+        return import->module;
+    }
+    if (import->loading) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_append_text(&buffer, "Import cycle detected: ");
+        int start = 0;
+        while (rm_importing[start] != import) {
+            start++;
+        }
+        for (int i = start; i < rm_importing_count; i++) {
+            rm_append_text(&buffer, rm_importing[i]->path);
+            rm_append_text(&buffer, " -> ");
+        }
+        rm_append_text(&buffer, import->path);
+        rm_append_text(&buffer, ".");
+        rm_fail_buffer(RM_RUNTIME_ERROR, &buffer);
+    }
+    import->loading = 1;
+    rm_importing[rm_importing_count++] = import;
+    Handler handler;
+    rm_push_handler(&handler);
+    if (setjmp(handler.jump) != 0) {
+        import->loading = 0;
+        rm_importing_count--;
+        rm_raise(rm_raised);
+    }
+    import->body();
+    rm_pop_handler(&handler);
+    import->loading = 0;
+    rm_importing_count--;
+
+    Module *module = rm_allocate(RM_MODULE, sizeof(Module));
+    module->import = import;
+    import->module = rm_object(RM_MODULE, module);
+    return import->module;
+}
+
+// The value program returned, which is unbound unless it ended with a return.
+static Value rm_returned;
+
+// Run program after initialize, which creates the constants and runs the
+// prelude, and print its results as run does. The exit status is that of run.
+int rm_main(int argc, char **argv, void (*initialize)(void), Value (*program)(void)) {
+    static int status;
+    static Value error;
+    rm_argc = argc;
+    rm_argv = argv;
+    rm_raised = rm_null();
+    rm_returned = rm_unbound();
+    error = rm_unbound();
+    rm_root(&rm_returned, 1);
+    rm_root(&error, 1);
+    rm_root(rm_kinds, RM_KINDS);
+    for (int i = 0; i < RM_KINDS; i++) {
+        rm_kinds[i] = rm_string(rm_kind_names[i], strlen(rm_kind_names[i]));
+    }
+
+    Handler handler;
+    rm_push_handler(&handler);
+    status = 0;
+    if (setjmp(handler.jump) == 0) {
+        initialize();
+        rm_returned = program();
+        rm_pop_handler(&handler);
+    } else if (RM_AS(Error, rm_raised)->kind == RM_EXIT) {
+        status = (int)RM_AS(Error, rm_raised)->value.as.integer;
+    } else {
+        error = rm_raised;
+        status = 1;
+    }
+
+    // Returning an err fails the program like throwing it.
+    if (rm_returned.type == RM_RESULT && !RM_AS(Result, rm_returned)->ok) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_show(&buffer, rm_returned);
+        error = rm_error(RM_THROWN, buffer.data, buffer.length, rm_returned);
+        free(buffer.data);
+        rm_returned = rm_unbound();
+        status = 1;
+    }
+
+    if (rm_returned.type != RM_UNBOUND) {
+        rm_print(rm_returned);
+        if (rm_returned.type == RM_INTEGER) {
+            status = (int)rm_returned.as.integer;
+        } else if (rm_returned.type == RM_BOOLEAN && !rm_returned.as.boolean) {
+            status = 1;
+        }
+    }
+    fflush(stdout);
+    if (rm_results.length != 0) {
+        fwrite(rm_results.data, 1, rm_results.length, stdout);
+    }
+    fflush(stdout);
+    if (error.type == RM_ERROR) {
+        Buffer buffer = {NULL, 0, 0};
+        rm_show(&buffer, error);
+        fprintf(stderr, "%s\n", buffer.data);
+        free(buffer.data);
+    }
+    return status;
+}
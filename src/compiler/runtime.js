@@ -0,0 +1,717 @@
+// ================================================================================
+// Runtime.
+// ================================================================================
+
+// The runtime gives compiled programs the semantics of the interpreter.
+// Integers, booleans, strings, null and arrays are the JavaScript values and
+// functions are JavaScript functions. The operators check the types of their
+// operands and raise the errors the interpreter raises. Integers are numbers,
+// so a result that is not a safe integer raises the error the interpreter
+// raises when a result does not fit in 64 bits.
+
+"use strict";
+
+// An error raised by rust-monkey code. kind is the name of its ErrorKind
+// and value is the value given to throw, if it was thrown.
+class $Error {
+    constructor(kind, message, value) {
+        this.kind = kind;
+        this.message = message;
+        this.value = value;
+    }
+}
+
+// A caught error that was not thrown by a throw statement.
+class $ErrorValue {
+    constructor(error) {
+        this.error = error;
+    }
+}
+
+class $Result {
+    constructor(ok, value) {
+        this.ok = ok;
+        this.value = value;
+    }
+}
+
+class $Module {
+    constructor(path, members) {
+        this.path = path;
+        this.members = members;
+    }
+}
+
+// Raised by a return that has to leave a nested function, and by ?, to
+// return value from the function that encloses it.
+class $Return {
+    constructor(value) {
+        this.value = value;
+    }
+}
+
+const $node = typeof process !== "undefined" && process.stdout !== undefined;
+
+// Errors which end the program and can not be caught.
+const $STOPPING = ["DepthLimitError", "Exit"];
+
+// Convert the errors raised by JavaScript itself into those the interpreter raises.
+function $error(error) {
+    if (error instanceof $Error || error instanceof $Return) {
+        return error;
+    }
+    if (error instanceof RangeError) {
+        return new $Error("DepthLimitError", "Call depth limit exceeded.");
+    }
+    if (error instanceof ReferenceError) {
+        return new $Error("NameError", error.message + ".");
+    }
+    if (error instanceof TypeError) {
+        return new $Error("TypeError", error.message + ".");
+    }
+    throw error;
+}
+
+// ================================================================================
+// Values.
+// ================================================================================
+
+// The name of the type of a value, as the interpreter prints it.
+function $type(value) {
+    if (value === null || value === undefined) {
+        return "Null";
+    }
+    if (typeof value === "number") {
+        return "Integer";
+    }
+    if (typeof value === "boolean") {
+        return "Boolean";
+    }
+    if (typeof value === "string") {
+        return "String";
+    }
+    if (Array.isArray(value)) {
+        return "Array";
+    }
+    if (typeof value === "function") {
+        return $builtins.has(value) ? "Builtin" : "Function";
+    }
+    if (value instanceof $ErrorValue) {
+        return "Error";
+    }
+    if (value instanceof $Module) {
+        return "Module";
+    }
+    return "Result";
+}
+
+// A value as puts prints it.
+function $show(value) {
+    switch ($type(value)) {
+        case "Null":
+            return "null";
+        case "Integer":
+        case "Boolean":
+        case "String":
+            return String(value);
+        case "Array":
+            return "[" + value.map($show).join(", ") + "]";
+        case "Function":
+            return "Func";
+        case "Builtin":
+            return "Builtin " + $builtins.get(value);
+        case "Error":
+            return value.error.kind + ": " + value.error.message;
+        case "Module":
+            return "Module " + value.path;
+        default:
+            return (value.ok ? "ok(" : "err(") + $show(value.value) + ")";
+    }
+}
+
+// Structural equality, as used by assert_eq.
+function $equal(left, right) {
+    if (Array.isArray(left) && Array.isArray(right)) {
+        return left.length === right.length && left.every((x, i) => $equal(x, right[i]));
+    }
+    if (left instanceof $Result && right instanceof $Result) {
+        return left.ok === right.ok && $equal(left.value, right.value);
+    }
+    if (left instanceof $ErrorValue && right instanceof $ErrorValue) {
+        return left.error.kind === right.error.kind && left.error.message === right.error.message;
+    }
+    if ($type(left) === "Null" && $type(right) === "Null") {
+        return true;
+    }
+    return left === right;
+}
+
+// ================================================================================
+// Operators.
+// ================================================================================
+
+function $checked(value) {
+    if (!Number.isSafeInteger(value)) {
+        throw new $Error("RuntimeError", "Integer overflow.");
+    }
+    return value;
+}
+
+function $integers(left, right) {
+    return typeof left === "number" && typeof right === "number";
+}
+
+// The error raised by an operator given operands it does not support.
+function $operands(operator, left, right) {
+    const type = $type(left) === $type(right) ? $type(left) : null;
+    if (type === "Boolean") {
+        return new $Error("TypeError", "Operator " + operator + " is not supported for booleans.");
+    }
+    if (type === "String") {
+        return new $Error("TypeError", "Operator " + operator + " is not supported for strings.");
+    }
+    return new $Error("TypeError", "The operand types don't match.");
+}
+
+function $add(left, right) {
+    if ($integers(left, right)) {
+        return $checked(left + right);
+    }
+    if (typeof left === "string" && typeof right === "string") {
+        return left + right;
+    }
+    throw $operands("+", left, right);
+}
+
+function $sub(left, right) {
+    if ($integers(left, right)) {
+        return $checked(left - right);
+    }
+    throw $operands("-", left, right);
+}
+
+function $mul(left, right) {
+    if ($integers(left, right)) {
+        return $checked(left * right);
+    }
+    throw $operands("*", left, right);
+}
+
+// Integer division rounds towards zero.
+function $div(left, right) {
+    if ($integers(left, right)) {
+        if (right === 0) {
+            throw new $Error("DivisionByZeroError", "Division by zero.");
+        }
+        return $checked(Math.trunc(left / right));
+    }
+    throw $operands("/", left, right);
+}
+
+function $lt(left, right) {
+    if ($integers(left, right)) {
+        return left < right;
+    }
+    throw $operands("<", left, right);
+}
+
+function $gt(left, right) {
+    if ($integers(left, right)) {
+        return left > right;
+    }
+    throw $operands(">", left, right);
+}
+
+// Only integers, booleans and strings can be compared.
+function $comparable(left, right) {
+    const type = $type(left);
+    return type === $type(right) && (type === "Integer" || type === "Boolean" || type === "String");
+}
+
+function $eq(left, right) {
+    if ($comparable(left, right)) {
+        return left === right;
+    }
+    throw $operands("==", left, right);
+}
+
+function $ne(left, right) {
+    if ($comparable(left, right)) {
+        return left !== right;
+    }
+    throw $operands("!=", left, right);
+}
+
+function $neg(right) {
+    if (typeof right === "number") {
+        return $checked(-right);
+    }
+    throw new $Error("TypeError", "Error: Prefix operand is not an integer as expected");
+}
+
+function $not(right) {
+    if (typeof right === "boolean") {
+        return !right;
+    }
+    throw new $Error("TypeError", "Error: Prefix operand is not a boolean as expected");
+}
+
+// The condition of an if.
+function $test(condition) {
+    if (typeof condition === "boolean") {
+        return condition;
+    }
+    throw new $Error("TypeError", "Condition is not of boolean type.");
+}
+
+function $index(left, index) {
+    if (typeof index !== "number") {
+        throw new $Error("TypeError", "Index " + $show(index) + " is not an integer.");
+    }
+    if (!Array.isArray(left)) {
+        throw new $Error("TypeError", $type(left) + " can not be indexed.");
+    }
+    if (index < 0 || index >= left.length) {
+        throw new $Error("RuntimeError", "Index " + index + " is out of range.");
+    }
+    return left[index];
+}
+
+// The member called name of a module or a caught error. source is the
+// expression the object came from.
+function $member(object, name, source) {
+    if (object instanceof $ErrorValue && (name === "message" || name === "kind")) {
+        return object.error[name];
+    }
+    if (object instanceof $Module && Object.prototype.hasOwnProperty.call(object.members, name)) {
+        return object.members[name];
+    }
+    throw new $Error("NameError", source + " has no member " + name + ".");
+}
+
+// The ? operator.
+function $unwrap(result) {
+    if (!(result instanceof $Result)) {
+        throw new $Error("TypeError", "Operator ? expects a result but was given " + $show(result) + ".");
+    }
+    if (result.ok) {
+        return result.value;
+    }
+    throw new $Return(result);
+}
+
+// ================================================================================
+// Errors.
+// ================================================================================
+
+// The error raised by throw value. Throwing a caught error raises it again.
+function $throw(value) {
+    if (value instanceof $ErrorValue) {
+        return value.error;
+    }
+    return new $Error("Error", $show(value), value);
+}
+
+// The value a catch block receives for error. Returns and the errors which
+// end the program are passed on.
+function $catch(error) {
+    error = $error(error);
+    if (error instanceof $Return || $STOPPING.includes(error.kind)) {
+        throw error;
+    }
+    return error.value !== undefined ? error.value : new $ErrorValue(error);
+}
+
+// The value of a function that was left by a $Return.
+function $return(error) {
+    if (error instanceof $Return) {
+        return error.value;
+    }
+    throw error;
+}
+
+// ================================================================================
+// Builtins.
+// ================================================================================
+
+function $arguments(name, args, expected) {
+    if (args.length !== expected) {
+        throw new $Error("TypeError", name + " expects " + expected + " arguments but was given " + args.length + ".");
+    }
+}
+
+function $result(name, value) {
+    if (!(value instanceof $Result)) {
+        throw new $Error("TypeError", name + " expects a result but was given " + $show(value) + ".");
+    }
+    return value;
+}
+
+function $array(name, value) {
+    if (!Array.isArray(value)) {
+        throw new $Error("TypeError", name + " expects an array but was given " + $type(value) + ".");
+    }
+    return value;
+}
+
+function $integer(name, value) {
+    if (typeof value !== "number") {
+        throw new $Error("TypeError", name + " expects an integer but was given " + $type(value) + ".");
+    }
+    return value;
+}
+
+function $function(name, value) {
+    if (typeof value !== "function") {
+        throw new $Error("TypeError", name + " expects a function but was given " + $type(value) + ".");
+    }
+    return value;
+}
+
+function $write(text) {
+    if ($node) {
+        process.stdout.write(text);
+    } else {
+        console.log(text.replace(/\n$/, ""));
+    }
+}
+
+function $writeError(text) {
+    if ($node) {
+        process.stderr.write(text);
+    } else {
+        console.error(text.replace(/\n$/, ""));
+    }
+}
+
+const ok = (...args) => {
+    $arguments("ok", args, 1);
+    return new $Result(true, args[0]);
+};
+
+const err = (...args) => {
+    $arguments("err", args, 1);
+    return new $Result(false, args[0]);
+};
+
+const is_ok = (...args) => {
+    $arguments("is_ok", args, 1);
+    return $result("is_ok", args[0]).ok;
+};
+
+const unwrap_or = (...args) => {
+    $arguments("unwrap_or", args, 2);
+    const result = $result("unwrap_or", args[0]);
+    return result.ok ? result.value : args[1];
+};
+
+const len = (...args) => {
+    $arguments("len", args, 1);
+    if (typeof args[0] === "string") {
+        return Array.from(args[0]).length;
+    }
+    return $array("len", args[0]).length;
+};
+
+const first = (...args) => {
+    $arguments("first", args, 1);
+    const array = $array("first", args[0]);
+    return array.length !== 0 ? array[0] : null;
+};
+
+const last = (...args) => {
+    $arguments("last", args, 1);
+    const array = $array("last", args[0]);
+    return array.length !== 0 ? array[array.length - 1] : null;
+};
+
+const rest = (...args) => {
+    $arguments("rest", args, 1);
+    return $array("rest", args[0]).slice(1);
+};
+
+const push = (...args) => {
+    $arguments("push", args, 2);
+    return $array("push", args[0]).concat([args[1]]);
+};
+
+// The functions of the prelude which are written in the runtime. The
+// prelude binds them to their names.
+
+const $range = (...args) => {
+    $arguments("range", args, 2);
+    const start = $integer("range", args[0]);
+    const end = $integer("range", args[1]);
+    const result = [];
+    for (let i = start; i < end; i++) {
+        result.push(i);
+    }
+    return result;
+};
+
+const $map = (...args) => {
+    $arguments("map", args, 2);
+    const array = $array("map", args[0]);
+    const f = $function("map", args[1]);
+    const result = [];
+    for (const x of array) {
+        result.push(f(x));
+    }
+    return result;
+};
+
+const $filter = (...args) => {
+    $arguments("filter", args, 2);
+    const array = $array("filter", args[0]);
+    const f = $function("filter", args[1]);
+    const result = [];
+    for (const x of array) {
+        if ($test(f(x))) {
+            result.push(x);
+        }
+    }
+    return result;
+};
+
+const $reduce = (...args) => {
+    $arguments("reduce", args, 3);
+    const array = $array("reduce", args[0]);
+    const f = $function("reduce", args[2]);
+    let result = args[1];
+    for (const x of array) {
+        result = f(result, x);
+    }
+    return result;
+};
+
+const $zip = (...args) => {
+    $arguments("zip", args, 2);
+    const a = $array("zip", args[0]);
+    const b = $array("zip", args[1]);
+    const result = [];
+    for (let i = 0; i < a.length && i < b.length; i++) {
+        result.push([a[i], b[i]]);
+    }
+    return result;
+};
+
+const $any = (...args) => {
+    $arguments("any", args, 2);
+    const array = $array("any", args[0]);
+    const f = $function("any", args[1]);
+    for (const x of array) {
+        if ($test(f(x))) {
+            return true;
+        }
+    }
+    return false;
+};
+
+const $all = (...args) => {
+    $arguments("all", args, 2);
+    const array = $array("all", args[0]);
+    const f = $function("all", args[1]);
+    for (const x of array) {
+        if (!$test(f(x))) {
+            return false;
+        }
+    }
+    return true;
+};
+
+const args = (...args) => {
+    $arguments("args", args, 0);
+    return $node ? process.argv.slice(2) : [];
+};
+
+const env = (...args) => {
+    $arguments("env", args, 1);
+    if (typeof args[0] !== "string") {
+        throw new $Error("TypeError", "env expects a string but was given " + $type(args[0]) + ".");
+    }
+    const value = $node ? process.env[args[0]] : undefined;
+    return value !== undefined ? value : null;
+};
+
+const exit = (...args) => {
+    if (args.length > 1) {
+        $arguments("exit", args, 1);
+    }
+    const code = args.length !== 0 ? args[0] : 0;
+    if (typeof code !== "number") {
+        throw new $Error("TypeError", "exit expects an integer but was given " + $type(code) + ".");
+    }
+    throw new $Error("Exit", "exit(" + code + ")", code);
+};
+
+const puts = (...args) => {
+    $write(args.map($show).join(" ") + "\n");
+    return null;
+};
+
+const print = (...args) => {
+    $write(args.map($show).join(" "));
+    return null;
+};
+
+const assert = (...args) => {
+    if (args.length !== 1 && args.length !== 2) {
+        $arguments("assert", args, 1);
+    }
+    if (typeof args[0] !== "boolean") {
+        throw new $Error("TypeError", "assert expects a boolean but was given " + $type(args[0]) + ".");
+    }
+    if (!args[0]) {
+        throw new $Error("AssertionError", args.length === 2 ? $show(args[1]) : "assertion failed.");
+    }
+    return null;
+};
+
+const assert_eq = (...args) => {
+    if (args.length !== 2 && args.length !== 3) {
+        $arguments("assert_eq", args, 2);
+    }
+    const [actual, expected] = args;
+    if ($equal(actual, expected)) {
+        return null;
+    }
+    // The types are shown when they differ as 1 and "1" print the same.
+    let message = "expected " + $show(expected) + " but was " + $show(actual) + ".";
+    if ($type(actual) !== $type(expected)) {
+        message =
+            "expected " + $show(expected) + " (" + $type(expected) + ") but was " +
+            $show(actual) + " (" + $type(actual) + ").";
+    }
+    if (args.length === 3) {
+        message = $show(args[2]) + ": " + message;
+    }
+    throw new $Error("AssertionError", message);
+};
+
+const assert_error = (...args) => {
+    if (args.length !== 1 && args.length !== 2) {
+        $arguments("assert_error", args, 1);
+    }
+    if ($type(args[0]) !== "Function") {
+        throw new $Error("TypeError", "assert_error expects a function but was given " + $type(args[0]) + ".");
+    }
+    let result;
+    try {
+        result = args[0]();
+    } catch (error) {
+        error = $error(error);
+        if ($STOPPING.includes(error.kind)) {
+            throw error;
+        }
+        if (args.length === 2 && $show(args[1]) !== error.kind) {
+            throw new $Error(
+                "AssertionError",
+                "expected " + $show(args[1]) + " but the function raised " + error.kind + ": " + error.message
+            );
+        }
+        return error.value !== undefined ? error.value : new $ErrorValue(error);
+    }
+    throw new $Error("AssertionError", "expected an error but the function returned " + $show(result) + ".");
+};
+
+const $builtins = new Map([
+    [ok, "ok"],
+    [err, "err"],
+    [is_ok, "is_ok"],
+    [unwrap_or, "unwrap_or"],
+    [len, "len"],
+    [first, "first"],
+    [last, "last"],
+    [rest, "rest"],
+    [push, "push"],
+    [$range, "range"],
+    [$map, "map"],
+    [$filter, "filter"],
+    [$reduce, "reduce"],
+    [$zip, "zip"],
+    [$any, "any"],
+    [$all, "all"],
+    [args, "args"],
+    [env, "env"],
+    [exit, "exit"],
+    [puts, "puts"],
+    [print, "print"],
+    [assert, "assert"],
+    [assert_eq, "assert_eq"],
+    [assert_error, "assert_error"],
+]);
+
+// ================================================================================
+// Programs.
+// ================================================================================
+
+// The values of the statements at the top level which are not null. They
+// are printed once the program ends, as they are by the interpreter.
+const $results = [];
+
+function $print(value) {
+    if ($type(value) !== "Null") {
+        $results.push($show(value) + "\n");
+    }
+}
+
+// A module is evaluated the first time it is imported. body is given the
+// object to add the exported bindings to.
+function $module(path, body) {
+    let module = null;
+    return () => {
+        if (module === null) {
+            const members = Object.create(null);
+            try {
+                body(members);
+            } catch (error) {
+                $return($error(error));
+            }
+            module = new $Module(path, members);
+        }
+        return module;
+    };
+}
+
+// Run the top level of a program, which returns undefined unless it ends
+// with a return, and set the exit status as the interpreter does.
+function $run(main) {
+    let status = 0;
+    let value;
+    let error;
+    try {
+        value = main();
+    } catch (raised) {
+        error = $error(raised);
+        if (error instanceof $Return && error.value instanceof $Result && !error.value.ok) {
+            // Returning an err fails the program like throwing it.
+            error = $throw(error.value);
+            status = 1;
+        } else if (error instanceof $Return) {
+            value = error.value;
+            error = undefined;
+        } else if (error.kind === "Exit") {
+            status = error.value;
+            error = undefined;
+        } else {
+            status = 1;
+        }
+    }
+    if (value !== undefined) {
+        $print(value);
+        if (typeof value === "number") {
+            status = value;
+        } else if (value === false) {
+            status = 1;
+        }
+    }
+    if ($results.length !== 0) {
+        $write($results.join(""));
+    }
+    if (error !== undefined) {
+        $writeError(error.kind + ": " + error.message + "\n");
+    }
+    if ($node) {
+        process.exitCode = status;
+    }
+}
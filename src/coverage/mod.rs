@@ -0,0 +1,450 @@
+/*
+Author: Jedda Boyle
+Contains: Coverage
+Coverage is a hook which counts how often each statement is run and which
+way each if expression goes. An if without an else has an implicit else
+branch which is taken when its condition is false.
+Only code evaluated from files is recorded, which is the program and the
+modules it imports but not the prelude or source read from stdin. Each file
+is parsed again the first time it is seen so that the statements which were
+never run are reported too.
+A report is written in the LCOV format, which can be read back and merged
+with the reports of other runs, or as the annotated source of each file.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::children;
+use super::ast::node_span;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::enviroment::Enviroment;
+use super::hook::Hook;
+use super::interpreter;
+use super::lexer::token::Span;
+use super::parser::Program;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+// ================================================================================
+// Report.
+// ================================================================================
+
+// The coverage of one file.
+#[derive(Clone, Default)]
+pub struct FileReport {
+    pub lines: BTreeMap<usize, u64>, // Times the statements on a line were run.
+    // Times each branch was taken by line, the number of the if on the line
+    // and the branch, which is 0 for the consequence and 1 for the
+    // alternative. None if the if was never evaluated.
+    pub branches: BTreeMap<(usize, usize, usize), Option<u64>>,
+}
+
+impl FileReport {
+    pub fn lines_hit(&self) -> usize {
+        return self.lines.values().filter(|count| **count != 0).count();
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        return self
+            .branches
+            .values()
+            .filter(|count| count.unwrap_or(0) != 0)
+            .count();
+    }
+
+    fn merge(&mut self, other: &FileReport) {
+        for (line, count) in other.lines.iter() {
+            *self.lines.entry(*line).or_insert(0) += count;
+        }
+        for (branch, count) in other.branches.iter() {
+            let total = self.branches.entry(*branch).or_insert(None);
+            if count.is_some() {
+                *total = Some(total.unwrap_or(0) + count.unwrap());
+            }
+        }
+    }
+}
+
+// The coverage of every file by path.
+#[derive(Clone, Default)]
+pub struct Report {
+    pub files: BTreeMap<String, FileReport>,
+}
+
+fn percent(hit: usize, found: usize) -> String {
+    if found == 0 {
+        return "100.0%".to_string();
+    }
+    return format!("{:.1}%", hit as f64 * 100.0 / found as f64);
+}
+
+impl Report {
+    pub fn new() -> Self {
+        return Report {
+            files: BTreeMap::new(),
+        };
+    }
+
+    // Add the counts of other to this report.
+    pub fn merge(&mut self, other: &Report) {
+        for (path, file) in other.files.iter() {
+            self.files.entry(path.clone()).or_default().merge(file);
+        }
+    }
+
+    // Read a report in the LCOV format. Records other than the lines and
+    // branches are ignored.
+    pub fn parse_lcov(text: &str) -> Result<Report, String> {
+        let mut to_return = Report::new();
+        let mut file: Option<(String, FileReport)> = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = || format!("line {}: invalid record {}", i + 1, line);
+            let (record, value) = match line.find(':') {
+                Some(colon) => (&line[..colon], &line[colon + 1..]),
+                None => (line, ""),
+            };
+            match record {
+                "SF" => file = Some((value.to_string(), FileReport::default())),
+                "end_of_record" => match file.take() {
+                    Some((path, report)) => {
+                        to_return.files.entry(path).or_default().merge(&report);
+                    }
+                    None => return Err(error()),
+                },
+                "DA" | "BRDA" if file.is_none() => return Err(error()),
+                "DA" => {
+                    let fields: Vec<&str> = value.split(',').collect();
+                    let line = fields.first().and_then(|field| field.parse::<usize>().ok());
+                    let count = fields.get(1).and_then(|field| field.parse::<u64>().ok());
+                    if line.is_none() || count.is_none() {
+                        return Err(error());
+                    }
+                    let report = &mut file.as_mut().unwrap().1;
+                    *report.lines.entry(line.unwrap()).or_insert(0) += count.unwrap();
+                }
+                "BRDA" => {
+                    let fields: Vec<&str> = value.split(',').collect();
+                    let numbers: Vec<usize> = fields
+                        .iter()
+                        .take(3)
+                        .filter_map(|field| field.parse::<usize>().ok())
+                        .collect();
+                    if fields.len() != 4 || numbers.len() != 3 {
+                        return Err(error());
+                    }
+                    let count = match fields[3] {
+                        "-" => None,
+                        taken => match taken.parse::<u64>() {
+                            Ok(count) => Some(count),
+                            Err(_) => return Err(error()),
+                        },
+                    };
+                    let mut branch = FileReport::default();
+                    branch
+                        .branches
+                        .insert((numbers[0], numbers[1], numbers[2]), count);
+                    file.as_mut().unwrap().1.merge(&branch);
+                }
+                _ => {}
+            }
+        }
+        if file.is_some() {
+            return Err("missing end_of_record".to_string());
+        }
+        return Ok(to_return);
+    }
+
+    pub fn to_lcov(&self) -> String {
+        let mut to_return = String::new();
+        for (path, file) in self.files.iter() {
+            to_return.push_str(&format!("TN:\nSF:{}\n", path));
+            for ((line, block, branch), count) in file.branches.iter() {
+                let taken = match count {
+                    Some(count) => count.to_string(),
+                    None => "-".to_string(),
+                };
+                to_return.push_str(&format!("BRDA:{},{},{},{}\n", line, block, branch, taken));
+            }
+            to_return.push_str(&format!("BRF:{}\n", file.branches.len()));
+            to_return.push_str(&format!("BRH:{}\n", file.branches_hit()));
+            for (line, count) in file.lines.iter() {
+                to_return.push_str(&format!("DA:{},{}\n", line, count));
+            }
+            to_return.push_str(&format!("LF:{}\n", file.lines.len()));
+            to_return.push_str(&format!("LH:{}\n", file.lines_hit()));
+            to_return.push_str("end_of_record\n");
+        }
+        return to_return;
+    }
+
+    // The source of the file at path with the number of times each line was
+    // run in the margin, ##### for lines that were never run and - for lines
+    // without a statement. The branches of the ifs on a line follow it.
+    pub fn annotate(&self, path: &str, source: &str) -> String {
+        let file = match self.files.get(path) {
+            Some(file) => file,
+            None => return String::new(),
+        };
+        let mut to_return = format!(
+            "{}\nLines: {} of {} ({})\nBranches: {} of {} ({})\n\n",
+            path,
+            file.lines_hit(),
+            file.lines.len(),
+            percent(file.lines_hit(), file.lines.len()),
+            file.branches_hit(),
+            file.branches.len(),
+            percent(file.branches_hit(), file.branches.len())
+        );
+        for (i, text) in source.lines().enumerate() {
+            let line = i + 1;
+            let count = match file.lines.get(&line) {
+                Some(0) => "#####".to_string(),
+                Some(count) => count.to_string(),
+                None => "-".to_string(),
+            };
+            to_return.push_str(&format!("{:>9}:{:>5}:{}\n", count, line, text));
+            for ((_, block, branch), taken) in file.branches.range((line, 0, 0)..(line + 1, 0, 0)) {
+                let taken = match taken {
+                    None => "never executed".to_string(),
+                    Some(0) => "never taken".to_string(),
+                    Some(count) => format!("taken {}", count),
+                };
+                to_return.push_str(&format!(
+                    "{:>15} if {} branch {} {}\n",
+                    "", block, branch, taken
+                ));
+            }
+        }
+        return to_return;
+    }
+
+    // The lines and branches hit and found in every file.
+    pub fn summary(&self) -> String {
+        let mut lines = (0, 0);
+        let mut branches = (0, 0);
+        for file in self.files.values() {
+            lines = (lines.0 + file.lines_hit(), lines.1 + file.lines.len());
+            branches = (
+                branches.0 + file.branches_hit(),
+                branches.1 + file.branches.len(),
+            );
+        }
+        return format!(
+            "Lines: {} of {} ({})\nBranches: {} of {} ({})",
+            lines.0,
+            lines.1,
+            percent(lines.0, lines.1),
+            branches.0,
+            branches.1,
+            percent(branches.0, branches.1)
+        );
+    }
+}
+
+// ================================================================================
+// Recording.
+// ================================================================================
+
+// An if expression, whether it has an else and how often each way was taken.
+struct If {
+    span: Span,
+    alternative: Option<Span>,
+    evaluated: u64,
+    consequences: u64,
+    alternatives: u64,
+}
+
+// The counts of a file that is being run. Nodes are found by their span
+// since the file evaluated is parsed separately.
+struct FileRecord {
+    statements: HashMap<Span, u64>,
+    ifs: Vec<If>,
+    ifs_by_span: HashMap<Span, usize>,
+    branches: HashMap<Span, (usize, usize)>, // The if and branch of a block.
+}
+
+fn is_statement(kind: &NodeType) -> bool {
+    return matches!(
+        kind,
+        NodeType::LetStatement
+            | NodeType::ReturnStatement
+            | NodeType::ExpressionStatement
+            | NodeType::ThrowStatement
+            | NodeType::ImportStatement
+            | NodeType::ExportStatement
+    );
+}
+
+#[allow(clippy::borrowed_box)]
+impl FileRecord {
+    fn new(program: &Program) -> Self {
+        let mut to_return = FileRecord {
+            statements: HashMap::new(),
+            ifs: Vec::new(),
+            ifs_by_span: HashMap::new(),
+            branches: HashMap::new(),
+        };
+        for statement in program.statements.iter() {
+            to_return.statements.insert(node_span(&**statement), 0);
+            to_return.collect(statement);
+        }
+        return to_return;
+    }
+
+    // Add the statements in the blocks and the ifs of node and all the nodes
+    // below it.
+    fn collect(&mut self, node: &Box<dyn Node>) {
+        let kind = node.get_type();
+        if kind == NodeType::BlockStatement {
+            for statement in children(node) {
+                self.statements.insert(node_span(&**statement), 0);
+            }
+        }
+        if kind == NodeType::IfElseExpression {
+            let blocks = children(node);
+            let alternative = blocks.get(2).map(|block| node_span(&***block));
+            self.ifs_by_span.insert(node_span(&**node), self.ifs.len());
+            self.branches
+                .insert(node_span(&**blocks[1]), (self.ifs.len(), 0));
+            if let Some(alternative) = alternative {
+                self.branches.insert(alternative, (self.ifs.len(), 1));
+            }
+            self.ifs.push(If {
+                span: node_span(&**node),
+                alternative,
+                evaluated: 0,
+                consequences: 0,
+                alternatives: 0,
+            });
+        }
+        for child in children(node) {
+            self.collect(child);
+        }
+    }
+
+    fn report(&self) -> FileReport {
+        let mut to_return = FileReport::default();
+        for (span, count) in self.statements.iter() {
+            let line = to_return.lines.entry(span.line).or_insert(0);
+            *line = (*line).max(*count);
+        }
+        // Number the ifs on each line from the left.
+        let mut ifs: Vec<&If> = self.ifs.iter().collect();
+        ifs.sort_by_key(|branch| (branch.span.line, branch.span.column));
+        let mut block = 0;
+        for (i, branch) in ifs.iter().enumerate() {
+            if i != 0 && ifs[i - 1].span.line == branch.span.line {
+                block += 1;
+            } else {
+                block = 0;
+            }
+            let mut alternatives = branch.alternatives;
+            if branch.alternative.is_none() {
+                alternatives = branch.evaluated - branch.consequences;
+            }
+            for (number, count) in [branch.consequences, alternatives].iter().enumerate() {
+                let count = if branch.evaluated == 0 {
+                    None
+                } else {
+                    Some(*count)
+                };
+                to_return
+                    .branches
+                    .insert((branch.span.line, block, number), count);
+            }
+        }
+        return to_return;
+    }
+}
+
+pub struct Coverage {
+    files: HashMap<PathBuf, Option<FileRecord>>, // None if the file could not be parsed again.
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        return Coverage {
+            files: HashMap::new(),
+        };
+    }
+
+    fn file(&mut self, path: PathBuf) -> Option<&mut FileRecord> {
+        let record = self.files.entry(path).or_insert_with_key(|path| {
+            let input = fs::read_to_string(path).ok()?;
+            let program = interpreter::parse_source(&input, "").ok()?;
+            return Some(FileRecord::new(&program));
+        });
+        return record.as_mut();
+    }
+
+    // The report of what has been run so far. Files are named by their
+    // canonical path so that the reports of runs from different
+    // directories can be merged.
+    pub fn report(&self) -> Report {
+        let mut to_return = Report::new();
+        for (path, record) in self.files.iter() {
+            if record.is_none() {
+                continue;
+            }
+            let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            let mut file = Report::new();
+            file.files.insert(
+                canonical.display().to_string(),
+                record.as_ref().unwrap().report(),
+            );
+            to_return.merge(&file);
+        }
+        return to_return;
+    }
+}
+
+impl Hook for Coverage {
+    fn before_node(&mut self, node: &dyn Node, env: &Rc<RefCell<Enviroment>>) {
+        let kind = node.get_type();
+        if !is_statement(&kind)
+            && kind != NodeType::IfElseExpression
+            && kind != NodeType::BlockStatement
+        {
+            return;
+        }
+        let path = match env.borrow().path() {
+            Some(path) => path,
+            None => return,
+        };
+        let file = match self.file(path) {
+            Some(file) => file,
+            None => return,
+        };
+        let span = node_span(node);
+        if is_statement(&kind) {
+            if let Some(count) = file.statements.get_mut(&span) {
+                *count += 1;
+            }
+        } else if kind == NodeType::IfElseExpression {
+            if let Some(i) = file.ifs_by_span.get(&span) {
+                file.ifs[*i].evaluated += 1;
+            }
+        } else if let Some((i, branch)) = file.branches.get(&span) {
+            if *branch == 0 {
+                file.ifs[*i].consequences += 1;
+            } else {
+                file.ifs[*i].alternatives += 1;
+            }
+        }
+    }
+}
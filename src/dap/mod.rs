@@ -0,0 +1,523 @@
+/*
+Author: Jedda Boyle
+Contains: Debug adapter.
+Speaks the Debug Adapter Protocol over stdin and stdout so that editors can
+drive the debugger. Messages are framed like those of the language server.
+The program runs on the thread reading the messages, so requests are read
+before it starts, while it is paused and after it has exited. Its output is
+sent to the client as output events.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::lsp::read_message;
+use super::lsp::write_message;
+use super::cli::print_results;
+
+use super::debugger::describe;
+use super::debugger::Debugger;
+use super::debugger::Frontend;
+use super::debugger::Reason;
+use super::debugger::Resume;
+use super::interpreter::Interpreter;
+use super::json::Json;
+use super::object;
+use super::object::Object;
+use super::output::Output;
+use super::parser::Program;
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::prelude::BufRead;
+use std::io::prelude::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+// The program is the only thread.
+const THREAD_ID: i64 = 1;
+
+// ================================================================================
+// Connection.
+// ================================================================================
+
+struct Connection {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    seq: i64,           // Sequence number of the next message sent.
+    disconnected: bool, // Whether the client disconnected or closed the input.
+}
+
+type Shared = Rc<RefCell<Connection>>;
+
+// Read the next message, or None once the client has gone.
+fn read(connection: &Shared) -> Option<Json> {
+    let mut connection = connection.borrow_mut();
+    loop {
+        let body = match read_message(connection.input.as_mut()) {
+            Ok(Some(body)) => body,
+            _ => {
+                connection.disconnected = true;
+                return None;
+            }
+        };
+        // Messages that are not JSON cannot be answered as they have no seq.
+        if let Ok(message) = Json::parse(&body) {
+            return Some(message);
+        }
+    }
+}
+
+fn send(connection: &Shared, kind: &str, mut members: Vec<(String, Json)>) {
+    let mut connection = connection.borrow_mut();
+    let mut message = vec![
+        ("seq".to_string(), Json::number(connection.seq)),
+        ("type".to_string(), Json::string(kind)),
+    ];
+    message.append(&mut members);
+    connection.seq += 1;
+    let _ = write_message(connection.output.as_mut(), &Json::Object(message));
+}
+
+fn event(connection: &Shared, event: &str, body: Json) {
+    send(
+        connection,
+        "event",
+        vec![
+            ("event".to_string(), Json::string(event)),
+            ("body".to_string(), body),
+        ],
+    );
+}
+
+// Answer a request with a body or an error message.
+fn respond(connection: &Shared, request: &Json, result: Result<Json, String>) {
+    let mut members = vec![
+        (
+            "request_seq".to_string(),
+            request.get("seq").cloned().unwrap_or(Json::Null),
+        ),
+        ("command".to_string(), Json::string(command(request))),
+        ("success".to_string(), Json::Bool(result.is_ok())),
+    ];
+    match result {
+        Ok(Json::Null) => {}
+        Ok(body) => members.push(("body".to_string(), body)),
+        Err(message) => members.push(("message".to_string(), Json::string(&message))),
+    }
+    send(connection, "response", members);
+}
+
+fn command(request: &Json) -> &str {
+    return request.get("command").and_then(Json::as_str).unwrap_or("");
+}
+
+fn argument<'a>(request: &'a Json, name: &str) -> Option<&'a Json> {
+    return request
+        .get("arguments")
+        .and_then(|arguments| arguments.get(name));
+}
+
+// Sends the output of the program as output events.
+struct Events {
+    connection: Shared,
+}
+
+impl Output for Events {
+    fn write(&mut self, text: &str) {
+        let body = Json::object(vec![
+            ("category", Json::string("stdout")),
+            ("output", Json::string(text)),
+        ]);
+        event(&self.connection, "output", body);
+    }
+
+    fn write_error(&mut self, text: &str) {
+        let body = Json::object(vec![
+            ("category", Json::string("stderr")),
+            ("output", Json::string(text)),
+        ]);
+        event(&self.connection, "output", body);
+    }
+}
+
+// ================================================================================
+// Requests.
+// ================================================================================
+
+fn capabilities() -> Json {
+    return Json::object(vec![
+        ("supportsConfigurationDoneRequest", Json::Bool(true)),
+        ("supportsEvaluateForHovers", Json::Bool(true)),
+        ("supportsTerminateRequest", Json::Bool(true)),
+    ]);
+}
+
+fn threads() -> Json {
+    let thread = Json::object(vec![
+        ("id", Json::number(THREAD_ID)),
+        ("name", Json::string("main")),
+    ]);
+    return Json::object(vec![("threads", Json::Array(vec![thread]))]);
+}
+
+// Replace the breakpoints, reporting those on a line without a statement as
+// not verified.
+fn set_breakpoints(debugger: &mut Debugger, request: &Json) -> Json {
+    let lines: Vec<usize> = argument(request, "breakpoints")
+        .and_then(Json::as_array)
+        .map(|breakpoints| {
+            breakpoints
+                .iter()
+                .filter_map(|breakpoint| breakpoint.get("line").and_then(Json::as_i64))
+                .map(|line| line as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    debugger.breakpoints.clear();
+    let mut breakpoints = Vec::new();
+    for line in lines {
+        let verified = debugger.lines.contains(&line);
+        if verified {
+            debugger.breakpoints.insert(line);
+        }
+        breakpoints.push(Json::object(vec![
+            ("verified", Json::Bool(verified)),
+            ("line", Json::number(line as i64)),
+        ]));
+    }
+    return Json::object(vec![("breakpoints", Json::Array(breakpoints))]);
+}
+
+// The program a launch request asks for.
+struct Launch {
+    program: Program,
+    interpreter: Interpreter,
+    debugger: Rc<RefCell<Debugger>>,
+}
+
+fn launch(connection: &Shared, request: &Json) -> Result<Launch, String> {
+    let path = match argument(request, "program").and_then(Json::as_str) {
+        Some(path) => path.to_string(),
+        None => return Err("launch expects the path of a program.".to_string()),
+    };
+    let input =
+        fs::read_to_string(&path).map_err(|error| format!("Could not read {}: {}", path, error))?;
+    let prelude = !argument(request, "noPrelude")
+        .and_then(Json::as_bool)
+        .unwrap_or(false);
+    let interpreter = if prelude {
+        Interpreter::new()
+    } else {
+        Interpreter::without_prelude()
+    };
+    interpreter.set_output(Box::new(Events {
+        connection: connection.clone(),
+    }));
+    let args = argument(request, "args")
+        .and_then(Json::as_array)
+        .map(|args| {
+            args.iter()
+                .filter_map(|arg| arg.as_str().map(|arg| arg.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    interpreter.set_args(args);
+    interpreter.set_path(PathBuf::from(&path));
+    let program = interpreter
+        .parse(&input, false)
+        .map_err(|errors| format!("Could not parse {}:\n\t{}", path, errors.join("\n\t")))?;
+
+    let stop_on_entry = argument(request, "stopOnEntry")
+        .and_then(Json::as_bool)
+        .unwrap_or(false);
+    let frontend = Adapter {
+        connection: connection.clone(),
+        path: path.clone(),
+        references: Vec::new(),
+    };
+    let debugger = Debugger::new(
+        &program,
+        interpreter.enviroment.clone(),
+        Box::new(frontend),
+        stop_on_entry,
+    );
+    let debugger = Rc::new(RefCell::new(debugger));
+    interpreter.add_hook(debugger.clone());
+    return Ok(Launch {
+        program,
+        interpreter,
+        debugger,
+    });
+}
+
+// ================================================================================
+// Adapter.
+// ================================================================================
+
+// Answers the requests made while the program is paused.
+struct Adapter {
+    connection: Shared,
+    path: String,
+    // The variables shown by each variablesReference, which count from 1.
+    // They are only valid until the program resumes.
+    references: Vec<Vec<(String, Rc<dyn Object>)>>,
+}
+
+impl Adapter {
+    // Keep variables so the client can ask for them, returning their reference.
+    fn reference(&mut self, variables: Vec<(String, Rc<dyn Object>)>) -> i64 {
+        self.references.push(variables);
+        return self.references.len() as i64;
+    }
+
+    // The reference of the elements of a value which has them, otherwise 0.
+    fn children(&mut self, value: &Rc<dyn Object>) -> i64 {
+        match value.downcast_ref::<object::Array>() {
+            Some(array) if !array.elements.is_empty() => {
+                let elements = array
+                    .elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| (i.to_string(), element.clone()))
+                    .collect();
+                return self.reference(elements);
+            }
+            _ => return 0,
+        }
+    }
+
+    // The frame a request names, counted from the outermost, or the innermost.
+    fn frame(&self, debugger: &Debugger, request: &Json) -> Result<usize, String> {
+        let frame = match argument(request, "frameId").and_then(Json::as_i64) {
+            Some(frame) => frame as usize,
+            None => return Ok(debugger.frames.len() - 1),
+        };
+        if frame >= debugger.frames.len() {
+            return Err(format!("There is no frame {}.", frame));
+        }
+        return Ok(frame);
+    }
+
+    fn stack_trace(&self, debugger: &Debugger) -> Json {
+        let mut frames = Vec::new();
+        for (id, frame) in debugger.frames.iter().enumerate().rev() {
+            let mut members = vec![
+                ("id", Json::number(id as i64)),
+                ("name", Json::string(&frame.name())),
+                ("line", Json::number(frame.span.line as i64)),
+                ("column", Json::number(frame.span.column as i64)),
+            ];
+            // Calls of the prelude and of modules have no statement of the
+            // program running.
+            if frame.span.line != 0 {
+                let name = PathBuf::from(&self.path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                members.push((
+                    "source",
+                    Json::object(vec![
+                        ("name", Json::string(&name)),
+                        ("path", Json::string(&self.path)),
+                    ]),
+                ));
+            } else {
+                members.push(("presentationHint", Json::string("subtle")));
+            }
+            frames.push(Json::object(members));
+        }
+        return Json::object(vec![
+            ("totalFrames", Json::number(frames.len() as i64)),
+            ("stackFrames", Json::Array(frames)),
+        ]);
+    }
+
+    fn scopes(&mut self, debugger: &Debugger, frame: usize) -> Json {
+        let mut scopes = Vec::new();
+        for scope in debugger.scopes(frame) {
+            let reference = self.reference(scope.variables);
+            scopes.push(Json::object(vec![
+                ("name", Json::string(scope.name)),
+                ("variablesReference", Json::number(reference)),
+                // The prelude is large and the same in every program.
+                ("expensive", Json::Bool(scope.name == "Prelude")),
+            ]));
+        }
+        return Json::object(vec![("scopes", Json::Array(scopes))]);
+    }
+
+    fn variables(&mut self, request: &Json) -> Result<Json, String> {
+        let reference = argument(request, "variablesReference")
+            .and_then(Json::as_i64)
+            .unwrap_or(0);
+        if reference < 1 || reference as usize > self.references.len() {
+            return Err(format!("There are no variables {}.", reference));
+        }
+        let variables = self.references[reference as usize - 1].clone();
+        let mut to_return = Vec::new();
+        for (name, value) in variables.iter() {
+            let children = self.children(value);
+            to_return.push(Json::object(vec![
+                ("name", Json::string(name)),
+                ("value", Json::string(&describe(value))),
+                ("variablesReference", Json::number(children)),
+            ]));
+        }
+        return Ok(Json::object(vec![("variables", Json::Array(to_return))]));
+    }
+
+    fn evaluate(&mut self, debugger: &Debugger, request: &Json) -> Result<Json, String> {
+        let frame = self.frame(debugger, request)?;
+        let expression = argument(request, "expression")
+            .and_then(Json::as_str)
+            .unwrap_or("");
+        let value = debugger.evaluate(frame, expression);
+        if let Some(error) = value.downcast_ref::<object::Error>() {
+            return Err(format!("{}: {}", error.kind, error.message));
+        }
+        let children = self.children(&value);
+        return Ok(Json::object(vec![
+            ("result", Json::string(&describe(&value))),
+            ("variablesReference", Json::number(children)),
+        ]));
+    }
+}
+
+impl Frontend for Adapter {
+    fn paused(&mut self, debugger: &mut Debugger, reason: Reason) -> Resume {
+        self.references.clear();
+        let body = Json::object(vec![
+            ("reason", Json::string(&reason.to_string())),
+            ("threadId", Json::number(THREAD_ID)),
+            ("allThreadsStopped", Json::Bool(true)),
+        ]);
+        event(&self.connection, "stopped", body);
+        loop {
+            let request = match read(&self.connection) {
+                Some(request) => request,
+                None => return Resume::Stop,
+            };
+            let (result, resume) = match command(&request) {
+                "threads" => (Ok(threads()), None),
+                "stackTrace" => (Ok(self.stack_trace(debugger)), None),
+                "scopes" => match self.frame(debugger, &request) {
+                    Ok(frame) => (Ok(self.scopes(debugger, frame)), None),
+                    Err(error) => (Err(error), None),
+                },
+                "variables" => (self.variables(&request), None),
+                "evaluate" => (self.evaluate(debugger, &request), None),
+                "setBreakpoints" => (Ok(set_breakpoints(debugger, &request)), None),
+                "continue" => {
+                    let body = Json::object(vec![("allThreadsContinued", Json::Bool(true))]);
+                    (Ok(body), Some(Resume::Continue))
+                }
+                "next" => (Ok(Json::Null), Some(Resume::StepOver)),
+                "stepIn" => (Ok(Json::Null), Some(Resume::StepIn)),
+                "stepOut" => (Ok(Json::Null), Some(Resume::StepOut)),
+                "disconnect" | "terminate" => {
+                    self.connection.borrow_mut().disconnected = command(&request) == "disconnect";
+                    (Ok(Json::Null), Some(Resume::Stop))
+                }
+                other => (Err(format!("{} is not supported.", other)), None),
+            };
+            respond(&self.connection, &request, result);
+            if let Some(resume) = resume {
+                return resume;
+            }
+        }
+    }
+}
+
+// ================================================================================
+// Session.
+// ================================================================================
+
+// Serve a debug session until the client disconnects. Returns the exit status.
+pub fn serve(input: Box<dyn BufRead>, output: Box<dyn Write>) -> i32 {
+    let connection = Rc::new(RefCell::new(Connection {
+        input,
+        output,
+        seq: 1,
+        disconnected: false,
+    }));
+
+    // Configure the session until the client is done and has launched a program.
+    let mut session: Option<Launch> = None;
+    let mut configured = false;
+    while !configured || session.is_none() {
+        let request = match read(&connection) {
+            Some(request) => request,
+            None => return 0,
+        };
+        let result = match command(&request) {
+            "initialize" => {
+                respond(&connection, &request, Ok(capabilities()));
+                event(&connection, "initialized", Json::object(Vec::new()));
+                continue;
+            }
+            "launch" => match launch(&connection, &request) {
+                Ok(launched) => {
+                    session = Some(launched);
+                    Ok(Json::Null)
+                }
+                Err(error) => Err(error),
+            },
+            "setBreakpoints" => match session.as_ref() {
+                Some(launched) => Ok(set_breakpoints(
+                    &mut launched.debugger.borrow_mut(),
+                    &request,
+                )),
+                None => Err("Breakpoints can only be set once a program is launched.".to_string()),
+            },
+            "configurationDone" => {
+                configured = true;
+                Ok(Json::Null)
+            }
+            "threads" => Ok(threads()),
+            "disconnect" => {
+                respond(&connection, &request, Ok(Json::Null));
+                return 0;
+            }
+            other => Err(format!(
+                "{} is not supported before the program runs.",
+                other
+            )),
+        };
+        respond(&connection, &request, result);
+    }
+
+    // Run the program, pausing as the client asks.
+    let launched = session.unwrap();
+    let results = launched.interpreter.eval_program(&launched.program);
+    let stopped = launched.debugger.borrow().stopped();
+    if !stopped {
+        print_results(&results, &launched.interpreter);
+    }
+    let status = launched.interpreter.exit_status(&results);
+    event(
+        &connection,
+        "exited",
+        Json::object(vec![("exitCode", Json::number(status as i64))]),
+    );
+    event(&connection, "terminated", Json::object(Vec::new()));
+
+    // Answer the client until it disconnects.
+    while !connection.borrow().disconnected {
+        let request = match read(&connection) {
+            Some(request) => request,
+            None => break,
+        };
+        let result = match command(&request) {
+            "disconnect" => {
+                connection.borrow_mut().disconnected = true;
+                Ok(Json::Null)
+            }
+            "threads" => Ok(Json::object(vec![("threads", Json::Array(Vec::new()))])),
+            _ => Err("The program has exited.".to_string()),
+        };
+        respond(&connection, &request, result);
+    }
+    return 0;
+}
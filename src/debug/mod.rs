@@ -0,0 +1,199 @@
+/*
+Author: Jedda Boyle
+Contains: Terminal debugger.
+The frontend of rust-monkey debug. When the program pauses the current line
+and the watch expressions are printed and commands are read from stdin
+until one of them resumes the program.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::debugger::describe;
+use super::debugger::Debugger;
+use super::debugger::Frontend;
+use super::debugger::Reason;
+use super::debugger::Resume;
+
+use std::io;
+use std::io::prelude::Write;
+
+const PROMPT: &str = "(debug) ";
+
+pub const HELP: &str = "Commands:
+  c, continue        Run to the next breakpoint.
+  s, step            Run to the next statement, stepping into calls.
+  n, next            Run to the next statement, stepping over calls.
+  o, out             Run until the current call returns.
+  b, break <line>    Set a breakpoint.
+  d, delete <line>   Remove a breakpoint.
+  breakpoints        List the breakpoints.
+  p, print <expr>    Evaluate an expression in the selected frame.
+  w, watch <expr>    Print an expression every time the program pauses.
+  unwatch <n>        Remove watch expression n.
+  env                Print the enviroment chain of the selected frame.
+  bt, backtrace      Print the calls that are running.
+  f, frame <n>       Select frame n of the backtrace.
+  l, list            Print the source around the current line.
+  h, help            Print this help.
+  q, quit            Stop the program.";
+
+pub struct Terminal {
+    lines: Vec<String>,   // The source of the program.
+    watches: Vec<String>, // Expressions printed at every pause.
+    frame: usize,         // The selected frame, counted from the innermost.
+}
+
+impl Terminal {
+    pub fn new(source: &str) -> Self {
+        return Terminal {
+            lines: source.lines().map(|line| line.to_string()).collect(),
+            watches: Vec::new(),
+            frame: 0,
+        };
+    }
+
+    fn print_line(&self, line: usize, current: bool) {
+        if line == 0 || line > self.lines.len() {
+            return;
+        }
+        let marker = if current { "->" } else { "  " };
+        println!("{} {:>4} {}", marker, line, self.lines[line - 1]);
+    }
+
+    fn print_watches(&self, debugger: &Debugger) {
+        let frame = debugger.frames.len() - 1 - self.frame;
+        for (i, watch) in self.watches.iter().enumerate() {
+            println!(
+                "{}: {} = {}",
+                i,
+                watch,
+                describe(&debugger.evaluate(frame, watch))
+            );
+        }
+    }
+
+    fn print_backtrace(&self, debugger: &Debugger) {
+        for (i, frame) in debugger.frames.iter().rev().enumerate() {
+            let marker = if i == self.frame { "*" } else { " " };
+            print!("{} #{} {}", marker, i, frame.name());
+            // Calls of the prelude and of modules have no statement of the
+            // program running.
+            if frame.span.line != 0 {
+                print!(" at {}", frame.span);
+            }
+            if frame.call_span.line != 0 {
+                print!(", called from {}", frame.call_span);
+            }
+            println!();
+        }
+    }
+
+    fn print_enviroment(&self, debugger: &Debugger) {
+        let frame = debugger.frames.len() - 1 - self.frame;
+        for scope in debugger.scopes(frame) {
+            // The prelude is the same in every program and only clutters the output.
+            if scope.name == "Prelude" {
+                continue;
+            }
+            println!("{}:", scope.name);
+            for (name, value) in scope.variables.iter() {
+                println!("  {} = {}", name, describe(value));
+            }
+        }
+    }
+
+    // Run a command, returning how to resume if it resumes the program.
+    fn command(&mut self, debugger: &mut Debugger, line: &str) -> Option<Resume> {
+        let line = line.trim();
+        let mut parts = line.splitn(2, ' ');
+        let command = parts.next().unwrap();
+        let argument = parts.next().unwrap_or("").trim();
+        let frame = debugger.frames.len() - 1 - self.frame;
+        match command {
+            "" => {}
+            "c" | "continue" => return Some(Resume::Continue),
+            "s" | "step" => return Some(Resume::StepIn),
+            "n" | "next" => return Some(Resume::StepOver),
+            "o" | "out" => return Some(Resume::StepOut),
+            "q" | "quit" => return Some(Resume::Stop),
+            "b" | "break" | "d" | "delete" => match argument.parse::<usize>() {
+                Ok(number) if command.starts_with('b') => {
+                    debugger.breakpoints.insert(number);
+                    println!("Breakpoint at line {}.", number);
+                }
+                Ok(number) => {
+                    if !debugger.breakpoints.remove(&number) {
+                        println!("There is no breakpoint at line {}.", number);
+                    }
+                }
+                Err(_) => println!("{} expects a line number.", command),
+            },
+            "breakpoints" => {
+                for line in debugger.breakpoints.iter() {
+                    self.print_line(*line, false);
+                }
+            }
+            "p" | "print" => {
+                let value = debugger.evaluate(frame, argument);
+                println!("{}", describe(&value));
+            }
+            "w" | "watch" => {
+                self.watches.push(argument.to_string());
+                self.print_watches(debugger);
+            }
+            "unwatch" => match argument.parse::<usize>() {
+                Ok(number) if number < self.watches.len() => {
+                    self.watches.remove(number);
+                }
+                _ => println!("There is no watch expression {}.", argument),
+            },
+            "env" => self.print_enviroment(debugger),
+            "bt" | "backtrace" => self.print_backtrace(debugger),
+            "f" | "frame" => match argument.parse::<usize>() {
+                Ok(number) if number < debugger.frames.len() => {
+                    self.frame = number;
+                    let frame = &debugger.frames[debugger.frames.len() - 1 - number];
+                    self.print_line(frame.span.line, true);
+                }
+                _ => println!("There is no frame {}.", argument),
+            },
+            "l" | "list" => {
+                let current = debugger.frames[frame].span.line;
+                for line in current.saturating_sub(5).max(1)..current + 6 {
+                    self.print_line(line, line == current);
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            _ => println!(
+                "Unknown command {}. Type help for a list of commands.",
+                command
+            ),
+        }
+        return None;
+    }
+}
+
+impl Frontend for Terminal {
+    fn paused(&mut self, debugger: &mut Debugger, reason: Reason) -> Resume {
+        self.frame = 0;
+        let frame = debugger.frames.last().unwrap();
+        println!("Paused at {} in {} ({}).", frame.span, frame.name(), reason);
+        self.print_line(frame.span.line, true);
+        self.print_watches(debugger);
+        loop {
+            print!("{}", PROMPT);
+            let _ = io::stdout().flush();
+            let mut line = String::new();
+            // Stop the program when stdin is closed.
+            match io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => return Resume::Stop,
+                Ok(_) => {}
+            }
+            if let Some(resume) = self.command(debugger, &line) {
+                return resume;
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+/*
+Author: Jedda Boyle
+Contains: Debugger
+The debugger is a hook which pauses the program before statements. It keeps
+the line breakpoints, the stack of calls and the enviroment each call is
+running in. When the program pauses a frontend, such as the terminal
+debugger or the debug adapter, is asked how to go on and can inspect the
+frames and evaluate expressions in them in the meantime.
+Only the statements of the program being debugged are paused at, not those
+of the prelude or of imported modules.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::children;
+use super::ast::node_span;
+use super::ast::start_span;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::enviroment::Enviroment;
+use super::hook::Hook;
+use super::interpreter;
+use super::lexer::token::Span;
+use super::object;
+use super::object::Object;
+use super::parser::Program;
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+// ================================================================================
+// Types.
+// ================================================================================
+
+// Why the program paused.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Reason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Reason::Entry => write!(fmt, "entry"),
+            Reason::Breakpoint => write!(fmt, "breakpoint"),
+            Reason::Step => write!(fmt, "step"),
+        };
+    }
+}
+
+// How to go on after a pause.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Resume {
+    Continue, // Run to the next breakpoint.
+    StepIn,   // Pause at the next statement.
+    StepOver, // Pause at the next statement of this call or its caller.
+    StepOut,  // Pause at the next statement of the caller.
+    Stop,     // Interrupt the program.
+}
+
+// A call that is running. The first frame is the top level of the program.
+pub struct Frame {
+    pub function: Option<String>, // Name of the let the function was bound to.
+    pub call_span: Span,          // Where the function was called.
+    pub span: Span,               // The statement being run.
+    pub env: Rc<RefCell<Enviroment>>,
+}
+
+impl Frame {
+    pub fn name(&self) -> String {
+        return self
+            .function
+            .clone()
+            .unwrap_or_else(|| "<anonymous>".to_string());
+    }
+}
+
+// The variables of one enviroment in the chain of a frame.
+pub struct Scope {
+    pub name: &'static str,
+    pub variables: Vec<(String, Rc<dyn Object>)>, // Sorted by name.
+}
+
+pub trait Frontend {
+    // Called when the program pauses before the statement at the span of the
+    // last frame. Returns how to go on.
+    fn paused(&mut self, debugger: &mut Debugger, reason: Reason) -> Resume;
+}
+
+enum Mode {
+    Entry,       // Pause at the first statement.
+    Run,         // Pause at breakpoints.
+    Step(usize), // Pause at a statement when there are at most this many frames.
+    Stopped,     // Never pause again.
+}
+
+// A value on one line. Functions are shown by their parameters rather than
+// their whole body.
+pub fn describe(value: &Rc<dyn Object>) -> String {
+    if let Some(function) = value.downcast_ref::<object::Function>() {
+        let parameters: Vec<String> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.to_source())
+            .collect();
+        return format!("fn({})", parameters.join(", "));
+    }
+    if let Some(builtin) = value.downcast_ref::<object::Builtin>() {
+        return format!("builtin {}", builtin.name);
+    }
+    return value.to_string().replace('\n', " ");
+}
+
+// ================================================================================
+// Debugger.
+// ================================================================================
+
+pub struct Debugger {
+    pub breakpoints: BTreeSet<usize>, // Lines to pause at.
+    pub frames: Vec<Frame>,           // Outermost first.
+    pub lines: BTreeSet<usize>,       // Lines with a statement to pause at.
+    statements: HashSet<usize>,       // Addresses of the statements that can be paused at.
+    mode: Mode,
+    frontend: Option<Box<dyn Frontend>>,
+}
+
+// Identify a node by its address, which does not change while the tree lives.
+fn address(node: &dyn Node) -> usize {
+    return node as *const dyn Node as *const () as usize;
+}
+
+// The statements that can be paused at and the lines they start on.
+struct Statements {
+    addresses: HashSet<usize>,
+    lines: BTreeSet<usize>,
+}
+
+#[allow(clippy::borrowed_box)]
+impl Statements {
+    fn add(&mut self, statement: &Box<dyn Node>) {
+        self.addresses.insert(address(&**statement));
+        self.lines.insert(start_span(statement).line);
+    }
+
+    // Add the statements in the blocks of node and all the nodes below it.
+    fn collect(&mut self, node: &Box<dyn Node>) {
+        if node.get_type() == NodeType::BlockStatement {
+            for statement in children(node) {
+                self.add(statement);
+            }
+        }
+        for child in children(node) {
+            self.collect(child);
+        }
+    }
+}
+
+impl Debugger {
+    // Debug program which is evaluated in env. The program pauses at its
+    // first statement if stop_on_entry is set.
+    pub fn new(
+        program: &Program,
+        env: Rc<RefCell<Enviroment>>,
+        frontend: Box<dyn Frontend>,
+        stop_on_entry: bool,
+    ) -> Self {
+        let mut statements = Statements {
+            addresses: HashSet::new(),
+            lines: BTreeSet::new(),
+        };
+        for statement in program.statements.iter() {
+            statements.add(statement);
+            statements.collect(statement);
+        }
+        return Debugger {
+            breakpoints: BTreeSet::new(),
+            lines: statements.lines,
+            frames: vec![Frame {
+                function: Some("<program>".to_string()),
+                call_span: Span::default(),
+                span: Span::default(),
+                env,
+            }],
+            statements: statements.addresses,
+            mode: if stop_on_entry {
+                Mode::Entry
+            } else {
+                Mode::Run
+            },
+            frontend: Some(frontend),
+        };
+    }
+
+    // Whether the frontend stopped the program.
+    pub fn stopped(&self) -> bool {
+        return matches!(self.mode, Mode::Stopped);
+    }
+
+    // Evaluate source in the enviroment of a frame, returning the value of
+    // its last statement. The counters of the paused program are restored
+    // afterwards so that a limit the expression exceeds does not stop it.
+    pub fn evaluate(&self, frame: usize, source: &str) -> Rc<dyn Object> {
+        // The semicolon after a single expression can be left out.
+        let mut source = source.trim().to_string();
+        if !source.ends_with(';') && !source.ends_with('}') {
+            source.push(';');
+        }
+        let program = match interpreter::parse_source(&source, "the expression") {
+            Ok(program) => program,
+            Err(error) => return error,
+        };
+        let env = self.frames[frame].env.clone();
+        let state = env.borrow().state.clone();
+        let counters = state.borrow().counters();
+        let mut result: Rc<dyn Object> = Rc::new(object::Null {});
+        for statement in program.statements.iter() {
+            result = statement.eval(env.clone());
+            if let Some(value) = result.downcast_ref::<object::ReturnValue>() {
+                result = value.value.clone();
+                break;
+            }
+            if result.get_type() == object::ObjectType::Error {
+                break;
+            }
+        }
+        state.borrow_mut().restore(counters);
+        return result;
+    }
+
+    // The enviroment chain of a frame, innermost first. The outermost
+    // enviroment holds the prelude and the one inside it the globals.
+    pub fn scopes(&self, frame: usize) -> Vec<Scope> {
+        let mut chain = vec![self.frames[frame].env.clone()];
+        loop {
+            let outer = chain.last().unwrap().borrow().outer.clone();
+            match outer {
+                Some(outer) => chain.push(outer),
+                None => break,
+            }
+        }
+        let mut to_return = Vec::new();
+        for (i, env) in chain.iter().enumerate() {
+            let name = if i == chain.len() - 1 {
+                "Prelude"
+            } else if i == chain.len() - 2 {
+                "Globals"
+            } else if i == 0 {
+                "Locals"
+            } else {
+                "Closure"
+            };
+            // Every function keeps an enviroment of its own around the one it
+            // was defined in, which is empty unless the program is a module.
+            if name == "Closure" && env.borrow().variables.is_empty() {
+                continue;
+            }
+            let mut variables: Vec<(String, Rc<dyn Object>)> = env
+                .borrow()
+                .variables
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            variables.sort_by(|a, b| a.0.cmp(&b.0));
+            to_return.push(Scope { name, variables });
+        }
+        return to_return;
+    }
+
+    // Decide whether to pause before the statement at span.
+    fn pause_reason(&self, span: Span, same_line: bool) -> Option<Reason> {
+        match self.mode {
+            Mode::Entry => return Some(Reason::Entry),
+            Mode::Step(limit) if self.frames.len() <= limit => return Some(Reason::Step),
+            Mode::Stopped => return None,
+            _ => {}
+        }
+        // A line with several statements only pauses at the first of them.
+        if self.breakpoints.contains(&span.line) && !same_line {
+            return Some(Reason::Breakpoint);
+        }
+        return None;
+    }
+}
+
+impl Hook for Debugger {
+    fn before_node(&mut self, node: &dyn Node, env: &Rc<RefCell<Enviroment>>) {
+        if !self.statements.contains(&address(node)) {
+            return;
+        }
+        let span = node_span(node);
+        let frame = self.frames.last_mut().unwrap();
+        let same_line = frame.span.line == span.line;
+        frame.span = span;
+        frame.env = env.clone();
+
+        let reason = match self.pause_reason(span, same_line) {
+            Some(reason) => reason,
+            None => return,
+        };
+        let mut frontend = self.frontend.take().unwrap();
+        let resume = frontend.paused(self, reason);
+        self.frontend = Some(frontend);
+        self.mode = match resume {
+            Resume::Continue => Mode::Run,
+            Resume::StepIn => Mode::Step(usize::MAX),
+            Resume::StepOver => Mode::Step(self.frames.len()),
+            Resume::StepOut => Mode::Step(self.frames.len() - 1),
+            Resume::Stop => {
+                env.borrow().state.borrow().interrupt.interrupt();
+                Mode::Stopped
+            }
+        };
+    }
+
+    fn enter_call(
+        &mut self,
+        function: &object::Function,
+        span: Span,
+        env: &Rc<RefCell<Enviroment>>,
+    ) {
+        self.frames.push(Frame {
+            function: function.name.clone(),
+            call_span: span,
+            span: Span::default(),
+            env: env.clone(),
+        });
+    }
+
+    fn exit_call(&mut self, _function: &object::Function, _result: &Rc<dyn Object>) {
+        self.frames.pop();
+    }
+}
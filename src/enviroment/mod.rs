@@ -9,10 +9,13 @@ Chained enviroments are used for nested scopes.
 // Imports
 // ================================================================================
 
+use super::interpreter::ExecutionLimits;
+use super::interpreter::ExecutionState;
 use super::object::Object;
 
 use std::collections::HashMap;
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 // ================================================================================
@@ -20,8 +23,11 @@ use std::rc::Rc;
 // ================================================================================
 
 pub struct Enviroment {
-    pub variables: HashMap<String, Rc<Object>>,
+    pub variables: HashMap<String, Rc<dyn Object>>,
     pub outer: Option<Rc<RefCell<Enviroment>>>,
+    pub state: Rc<RefCell<ExecutionState>>, // Shared with the outer enviroment.
+    pub path: Option<PathBuf>, // File evaluated in this enviroment, if it is the top level of one.
+    pub exports: Vec<String>,  // Names made visible to importers by export.
 }
 
 // ================================================================================
@@ -30,32 +36,64 @@ pub struct Enviroment {
 
 impl Enviroment {
     pub fn new(outer: Option<Rc<RefCell<Enviroment>>>) -> Self {
+        // Nested scopes share the execution state of their outer scope.
+        let state = match outer.as_ref() {
+            Some(outer) => outer.borrow().state.clone(),
+            None => Rc::new(RefCell::new(ExecutionState::new(ExecutionLimits::new()))),
+        };
         let to_return = Enviroment {
             variables: HashMap::new(),
-            outer: outer,
+            outer,
+            state,
+            path: None,
+            exports: Vec::new(),
         };
         return to_return;
     }
 
-    pub fn insert(&mut self, key: String, value: Rc<Object>) {
+    // Create the top level enviroment of a module. It is nested in the
+    // prelude so that it shares the execution state of the program importing
+    // it but none of its variables.
+    pub fn new_module(prelude: Rc<RefCell<Enviroment>>, path: PathBuf) -> Self {
+        let mut to_return = Enviroment::new(Some(prelude));
+        to_return.path = Some(path);
+        return to_return;
+    }
+
+    pub fn insert(&mut self, key: String, value: Rc<dyn Object>) {
         self.variables.insert(key, value);
     }
 
+    // Return the names of every variable in scope, including outer scopes.
+    pub fn names(&self) -> Vec<String> {
+        let mut to_return: Vec<String> = self.variables.keys().cloned().collect();
+        if let Some(outer) = &self.outer {
+            to_return.extend(outer.borrow().names());
+        }
+        return to_return;
+    }
+
+    // Return the file being evaluated, which is the path of the nearest
+    // enviroment in the chain that has one.
+    pub fn path(&self) -> Option<PathBuf> {
+        if self.path.is_some() {
+            return self.path.clone();
+        }
+        if let Some(outer) = &self.outer {
+            return outer.borrow().path();
+        }
+        return None;
+    }
+
     // Return object that has the variable name 'key'.
-    pub fn get(&self, key: &String) -> Option<Rc<Object>> {
+    pub fn get(&self, key: &String) -> Option<Rc<dyn Object>> {
         let to_return = self.variables.get(key);
-        if to_return.is_some() {
-            return Some(to_return.unwrap().clone());
+        if let Some(to_return) = to_return {
+            return Some(to_return.clone());
         }
         // Variable not found so seach in the outer scope.
-        if self.outer.is_some() {
-            return self
-                .outer
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .get(key)
-                .map(|x| x.clone());
+        if let Some(outer) = &self.outer {
+            return outer.borrow().get(key);
         }
         return None;
     }
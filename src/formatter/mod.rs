@@ -0,0 +1,395 @@
+/*
+Author: Jedda Boyle
+Contains: Formatter
+The formatter prints a parsed program back out as canonical rust-monkey
+source. Blocks are indented by four spaces, binary operators are surrounded
+by spaces and only the parentheses needed to keep the precedence of the
+parse tree are printed. Calls and arrays that do not fit on a line are
+wrapped with one element per line.
+Comments are kept: a comment on its own line is printed before the
+statement that followed it and a comment after code is printed at the end
+of the line of the statement it was in. A single blank line is kept where
+the source had one or more.
+Formatting a formatted program gives the same program back.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::ArrayExpression;
+use super::ast::BlockStatement;
+use super::ast::BoolExpression;
+use super::ast::CallExpression;
+use super::ast::ExportStatement;
+use super::ast::ExpressionStatement;
+use super::ast::FunctionExpression;
+use super::ast::IfElseExpression;
+use super::ast::ImportStatement;
+use super::ast::IndexExpression;
+use super::ast::InfixExpression;
+use super::ast::LetStatement;
+use super::ast::MemberExpression;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::ast::PostfixExpression;
+use super::ast::PrefixExpression;
+use super::ast::ReturnStatement;
+use super::ast::ThrowStatement;
+use super::ast::TryExpression;
+use super::ast::precedence;
+use super::ast::start_span;
+
+use super::lexer::token::Precedence;
+use super::lexer::token::Span;
+use super::lexer::Comment;
+use super::lexer::Lexer;
+use super::parser::Parser;
+use super::parser::Program;
+
+// ================================================================================
+// Layout.
+// ================================================================================
+
+const INDENT: &str = "    ";
+
+// Calls and arrays are wrapped when a line would be longer than this.
+pub const MAX_WIDTH: usize = 80;
+
+fn is_before(a: Span, b: Span) -> bool {
+    return (a.line, a.column) < (b.line, b.column);
+}
+
+// The column text ends on when it starts at column.
+fn end_column(column: usize, text: &str) -> usize {
+    return match text.rfind('\n') {
+        Some(index) => text[index + 1..].chars().count(),
+        None => column + text.chars().count(),
+    };
+}
+
+// Whether every line of text is at most MAX_WIDTH long when it starts at column.
+fn fits(column: usize, text: &str) -> bool {
+    let mut column = column;
+    for line in text.split('\n') {
+        if column + line.chars().count() > MAX_WIDTH {
+            return false;
+        }
+        column = 0;
+    }
+    return true;
+}
+
+// ================================================================================
+// Formatter.
+// ================================================================================
+
+// Format source code. The parse errors are returned if it does not parse.
+pub fn format_source(input: &str) -> Result<String, Vec<String>> {
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program().unwrap();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().clone());
+    }
+    let mut formatter = Formatter {
+        source: input.lines().map(|line| line.to_string()).collect(),
+        comments: parser.lexer.comments.clone(),
+        next_comment: 0,
+        indent: 0,
+    };
+    return Ok(formatter.format_program(&program));
+}
+
+struct Formatter {
+    source: Vec<String>,    // Lines of the source, used to find blank lines.
+    comments: Vec<Comment>, // Comments of the source in order.
+    next_comment: usize,    // The first comment that has not been printed.
+    indent: usize,
+}
+
+#[allow(clippy::borrowed_box)]
+impl Formatter {
+    fn format_program(&mut self, program: &Program) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        // The lexer skips a #! line so it is copied as it is.
+        let shebang = self.source.first().filter(|line| line.starts_with("#!"));
+        if let Some(shebang) = shebang {
+            lines.push(shebang.clone());
+        }
+        let end = Span {
+            line: usize::MAX,
+            column: usize::MAX,
+        };
+        let first = lines.is_empty();
+        self.format_statements(&program.statements, end, &mut lines, first);
+        if lines.is_empty() {
+            return String::new();
+        }
+        return format!("{}\n", lines.join("\n"));
+    }
+
+    fn indentation(&self) -> String {
+        return INDENT.repeat(self.indent);
+    }
+
+    // Whether the source line before line is blank.
+    fn follows_blank_line(&self, line: usize) -> bool {
+        return line >= 2
+            && self
+                .source
+                .get(line - 2)
+                .is_some_and(|text| text.trim().is_empty());
+    }
+
+    // Add each statement to lines followed by the comments before end.
+    fn format_statements(
+        &mut self,
+        statements: &Vec<Box<dyn Node>>,
+        end: Span,
+        lines: &mut Vec<String>,
+        mut first: bool,
+    ) {
+        for statement in statements.iter() {
+            let start = start_span(statement);
+            self.format_comments(start, lines, &mut first);
+            if !first && self.follows_blank_line(start.line) {
+                lines.push(String::new());
+            }
+            first = false;
+            let text = format!("{}{};", self.indentation(), self.statement(statement));
+            lines.extend(text.split('\n').map(|line| line.to_string()));
+        }
+        self.format_comments(end, lines, &mut first);
+    }
+
+    // Add the comments that come before end to lines.
+    fn format_comments(&mut self, end: Span, lines: &mut Vec<String>, first: &mut bool) {
+        while self.next_comment < self.comments.len()
+            && is_before(self.comments[self.next_comment].span, end)
+        {
+            let comment = self.comments[self.next_comment].clone();
+            self.next_comment += 1;
+            if comment.trailing && !lines.is_empty() {
+                let last = lines.last_mut().unwrap();
+                last.push(' ');
+                last.push_str(&comment.text);
+                continue;
+            }
+            if !*first && self.follows_blank_line(comment.span.line) {
+                lines.push(String::new());
+            }
+            *first = false;
+            lines.push(format!("{}{}", self.indentation(), comment.text));
+        }
+    }
+
+    // ================================================================================
+    // Statements.
+    // ================================================================================
+
+    // Format a statement without its semicolon. Every line after the first
+    // is indented.
+    fn statement(&mut self, node: &Box<dyn Node>) -> String {
+        let column = self.indent * INDENT.len();
+        return match node.get_type() {
+            NodeType::LetStatement => {
+                self.let_statement(node.downcast_ref::<LetStatement>().unwrap(), column)
+            }
+            NodeType::ReturnStatement => {
+                let statement = node.downcast_ref::<ReturnStatement>().unwrap();
+                self.keyword_statement("return ", &statement.value, column)
+            }
+            NodeType::ThrowStatement => {
+                let statement = node.downcast_ref::<ThrowStatement>().unwrap();
+                self.keyword_statement("throw ", &statement.value, column)
+            }
+            NodeType::ImportStatement => {
+                let statement = node.downcast_ref::<ImportStatement>().unwrap();
+                format!(
+                    "import \"{}\" as {}",
+                    statement.path, statement.alias.token.literal
+                )
+            }
+            NodeType::ExportStatement => {
+                let statement = node.downcast_ref::<ExportStatement>().unwrap();
+                let prefix = "export ";
+                let text = self.let_statement(&statement.statement, column + prefix.len());
+                format!("{}{}", prefix, text)
+            }
+            NodeType::ExpressionStatement => {
+                let statement = node.downcast_ref::<ExpressionStatement>().unwrap();
+                self.expression(&statement.value, column)
+            }
+            _ => self.expression(node, column),
+        };
+    }
+
+    fn let_statement(&mut self, statement: &LetStatement, column: usize) -> String {
+        let prefix = format!("let {} = ", statement.identifier.token.literal);
+        let value = self.expression(&statement.value, column + prefix.len());
+        return format!("{}{}", prefix, value);
+    }
+
+    fn keyword_statement(&mut self, keyword: &str, value: &Box<dyn Node>, column: usize) -> String {
+        let value = self.expression(value, column + keyword.len());
+        return format!("{}{}", keyword, value);
+    }
+
+    fn block(&mut self, node: &Box<dyn Node>) -> String {
+        let block = node.downcast_ref::<BlockStatement>().unwrap();
+        let mut lines = vec!["{".to_string()];
+        self.indent += 1;
+        self.format_statements(&block.statements, block.end, &mut lines, true);
+        self.indent -= 1;
+        if lines.len() == 1 && lines[0] == "{" {
+            return "{}".to_string();
+        }
+        lines.push(format!("{}}}", self.indentation()));
+        return lines.join("\n");
+    }
+
+    // ================================================================================
+    // Expressions.
+    // ================================================================================
+
+    // Format an expression which starts at column.
+    fn expression(&mut self, node: &Box<dyn Node>, column: usize) -> String {
+        return match node.get_type() {
+            NodeType::InfixExpression => {
+                let infix = node.downcast_ref::<InfixExpression>().unwrap();
+                // Operators are left associative so the right operand needs
+                // parentheses when its precedence is equal.
+                let precedence = infix.token.get_precedence() as u8;
+                let left = self.operand(&infix.left, column, precedence);
+                let operator = format!(" {} ", infix.token);
+                let right_column = end_column(column, &left) + operator.len();
+                let right = self.operand(&infix.right, right_column, precedence + 1);
+                format!("{}{}{}", left, operator, right)
+            }
+            NodeType::PrefixExpression => {
+                let prefix = node.downcast_ref::<PrefixExpression>().unwrap();
+                let operator = prefix.token.to_string();
+                let right = self.operand(
+                    &prefix.right,
+                    column + operator.len(),
+                    Precedence::Prefix as u8,
+                );
+                format!("{}{}", operator, right)
+            }
+            NodeType::PostfixExpression => {
+                let postfix = node.downcast_ref::<PostfixExpression>().unwrap();
+                let left = self.operand(&postfix.left, column, Precedence::Postfix as u8);
+                format!("{}?", left)
+            }
+            NodeType::CallExpression => {
+                let call = node.downcast_ref::<CallExpression>().unwrap();
+                let func = self.operand(&call.func_identifier, column, Precedence::Call as u8);
+                let arguments = self.list(&call.arguments, end_column(column, &func), "(", ")");
+                format!("{}{}", func, arguments)
+            }
+            NodeType::IndexExpression => {
+                let index = node.downcast_ref::<IndexExpression>().unwrap();
+                let left = self.operand(&index.left, column, Precedence::Call as u8);
+                let value = self.expression(&index.index, end_column(column, &left) + 1);
+                format!("{}[{}]", left, value)
+            }
+            NodeType::MemberExpression => {
+                let member = node.downcast_ref::<MemberExpression>().unwrap();
+                let object = self.operand(&member.object, column, Precedence::Call as u8);
+                format!("{}.{}", object, member.member.token.literal)
+            }
+            NodeType::ArrayExpression => {
+                let array = node.downcast_ref::<ArrayExpression>().unwrap();
+                self.list(&array.elements, column, "[", "]")
+            }
+            NodeType::IfElseExpression => {
+                let ifelse = node.downcast_ref::<IfElseExpression>().unwrap();
+                let condition = self.expression(&ifelse.condition, column + 4);
+                let mut to_return =
+                    format!("if ({}) {}", condition, self.block(&ifelse.consequence));
+                if let Some(alternative) = &ifelse.alternative {
+                    let alternative = self.block(alternative);
+                    to_return.push_str(&format!(" else {}", alternative));
+                }
+                to_return
+            }
+            NodeType::FunctionExpression => {
+                let func = node.downcast_ref::<FunctionExpression>().unwrap();
+                let parameters: Vec<String> =
+                    func.parameters.iter().map(|x| x.to_string()).collect();
+                format!("fn({}) {}", parameters.join(", "), self.block(&func.body))
+            }
+            NodeType::TryExpression => {
+                let expression = node.downcast_ref::<TryExpression>().unwrap();
+                let mut to_return = format!("try {}", self.block(&expression.body));
+                if let Some(catch_body) = &expression.catch_body {
+                    let identifier = &expression.catch_identifier.as_ref().unwrap().token;
+                    let body = self.block(catch_body);
+                    to_return.push_str(&format!(" catch ({}) {}", identifier.literal, body));
+                }
+                if let Some(finally_body) = &expression.finally_body {
+                    let body = self.block(finally_body);
+                    to_return.push_str(&format!(" finally {}", body));
+                }
+                to_return
+            }
+            NodeType::BoolExpression => {
+                let boolean = node.downcast_ref::<BoolExpression>().unwrap();
+                boolean.value.to_string()
+            }
+            // Identifiers, integers and strings are printed as they were written.
+            _ => node.to_string(),
+        };
+    }
+
+    // Format an expression that is parsed with the given precedence, adding
+    // parentheses if it binds less tightly.
+    fn operand(&mut self, node: &Box<dyn Node>, column: usize, min_precedence: u8) -> String {
+        if precedence(node) < min_precedence {
+            return format!("({})", self.expression(node, column + 1));
+        }
+        return self.expression(node, column);
+    }
+
+    // Format comma separated expressions between open and close. They are put
+    // on one line if it fits and otherwise one per line.
+    fn list(
+        &mut self,
+        nodes: &Vec<Box<dyn Node>>,
+        column: usize,
+        open: &str,
+        close: &str,
+    ) -> String {
+        let next_comment = self.next_comment;
+        let mut items: Vec<String> = Vec::new();
+        let mut item_column = column + open.len();
+        for node in nodes.iter() {
+            let item = self.expression(node, item_column);
+            item_column = end_column(item_column, &item) + 2;
+            items.push(item);
+        }
+        let flat = format!("{}{}{}", open, items.join(", "), close);
+        if nodes.is_empty() || fits(column, &flat) {
+            return flat;
+        }
+
+        // Format the items again indented on their own lines, giving back the
+        // comments that were printed in the blocks of the first attempt.
+        self.next_comment = next_comment;
+        self.indent += 1;
+        let indentation = self.indentation();
+        let mut items: Vec<String> = Vec::new();
+        for node in nodes.iter() {
+            let item = self.expression(node, indentation.len());
+            items.push(format!("{}{}", indentation, item));
+        }
+        self.indent -= 1;
+        return format!(
+            "{}\n{}\n{}{}",
+            open,
+            items.join(",\n"),
+            self.indentation(),
+            close
+        );
+    }
+}
@@ -0,0 +1,103 @@
+/*
+Author: Jedda Boyle
+Contains: Hook
+A hook observes evaluation for tools such as the debugger. It is told about
+every node before and after it is evaluated and about every call of a
+function written in rust-monkey.
+A hook is not told about evaluation it causes itself, such as the debugger
+evaluating a watch expression while the program is paused.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::Node;
+use super::enviroment::Enviroment;
+use super::lexer::token::Span;
+use super::object;
+use super::object::Object;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// ================================================================================
+// Hook trait.
+// ================================================================================
+
+pub trait Hook {
+    // Called before node is evaluated in env.
+    fn before_node(&mut self, _node: &dyn Node, _env: &Rc<RefCell<Enviroment>>) {}
+
+    // Called once node has been evaluated.
+    fn after_node(
+        &mut self,
+        _node: &dyn Node,
+        _env: &Rc<RefCell<Enviroment>>,
+        _result: &Rc<dyn Object>,
+    ) {
+    }
+
+    // Called when the call at span enters function, with the enviroment its
+    // body is evaluated in.
+    fn enter_call(
+        &mut self,
+        _function: &object::Function,
+        _span: Span,
+        _env: &Rc<RefCell<Enviroment>>,
+    ) {
+    }
+
+    // Called when the call of function returns result.
+    fn exit_call(&mut self, _function: &object::Function, _result: &Rc<dyn Object>) {}
+}
+
+pub type Hooks = Vec<Rc<RefCell<dyn Hook>>>;
+
+// ================================================================================
+// Calling the hooks.
+// ================================================================================
+
+// A hook which is already running is evaluating code itself and is skipped.
+
+pub fn before_node(hooks: &Hooks, node: &dyn Node, env: &Rc<RefCell<Enviroment>>) {
+    for hook in hooks.iter() {
+        if let Ok(mut hook) = hook.try_borrow_mut() {
+            hook.before_node(node, env);
+        }
+    }
+}
+
+pub fn after_node(
+    hooks: &Hooks,
+    node: &dyn Node,
+    env: &Rc<RefCell<Enviroment>>,
+    result: &Rc<dyn Object>,
+) {
+    for hook in hooks.iter() {
+        if let Ok(mut hook) = hook.try_borrow_mut() {
+            hook.after_node(node, env, result);
+        }
+    }
+}
+
+pub fn enter_call(
+    hooks: &Hooks,
+    function: &object::Function,
+    span: Span,
+    env: &Rc<RefCell<Enviroment>>,
+) {
+    for hook in hooks.iter() {
+        if let Ok(mut hook) = hook.try_borrow_mut() {
+            hook.enter_call(function, span, env);
+        }
+    }
+}
+
+pub fn exit_call(hooks: &Hooks, function: &object::Function, result: &Rc<dyn Object>) {
+    for hook in hooks.iter() {
+        if let Ok(mut hook) = hook.try_borrow_mut() {
+            hook.exit_call(function, result);
+        }
+    }
+}
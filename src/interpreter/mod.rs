@@ -0,0 +1,647 @@
+/*
+Author: Jedda Boyle
+Contains: Interpreter
+The interpreter ties the lexer, parser and evaluator together and owns the
+global enviroment of a session.
+It also holds the output that programs write to, the execution limits which
+stop runaway programs from hanging or overflowing the stack of the host
+process, and the interrupt flag which lets a host cancel an evaluation from
+a signal handler or another thread.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::call_function;
+use super::builtins;
+use super::enviroment::Enviroment;
+use super::hook::Hook;
+use super::hook::Hooks;
+use super::lexer::token::Span;
+use super::lexer::Lexer;
+use super::object;
+use super::object::ErrorKind;
+use super::object::Object;
+use super::object::ObjectType;
+use super::output;
+use super::output::Output;
+use super::parser::Parser;
+use super::parser::Program;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+// ================================================================================
+// Execution limits.
+// ================================================================================
+
+// Each limit is optional; None means the limit is not enforced.
+#[derive(Clone, Copy)]
+pub struct ExecutionLimits {
+    pub max_steps: Option<u64>,       // Number of nodes that may be evaluated.
+    pub max_depth: Option<usize>,     // Number of nested function calls.
+    pub max_allocations: Option<u64>, // Number of objects, elements and characters allocated.
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionLimits {
+    // The default limits only bound the call depth so that deep recursion
+    // returns an error instead of overflowing the stack.
+    pub fn new() -> Self {
+        ExecutionLimits {
+            max_steps: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_allocations: None,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        ExecutionLimits {
+            max_steps: None,
+            max_depth: None,
+            max_allocations: None,
+        }
+    }
+}
+
+const DEFAULT_MAX_DEPTH: usize = 512;
+
+// ================================================================================
+// Interrupt handle.
+// ================================================================================
+
+// A handle that can be sent to other threads to cancel a running evaluation.
+// The evaluator polls the flag before evaluating each node.
+#[derive(Clone)]
+pub struct InterruptHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl Default for InterruptHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        InterruptHandle {
+            flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Request that the running evaluation stops as soon as possible.
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    // Withdraw a pending interrupt request.
+    pub fn clear(&self) {
+        self.flag.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        return self.flag.load(Ordering::SeqCst);
+    }
+
+    // Consume a pending interrupt request.
+    fn take(&self) -> bool {
+        return self.flag.swap(false, Ordering::SeqCst);
+    }
+}
+
+// ================================================================================
+// Execution state.
+// ================================================================================
+
+// The counters of an evaluation, saved by a host which evaluates code in the
+// middle of another evaluation and restored afterwards, so that the limits the
+// inner evaluation runs into do not stop the outer one.
+#[derive(Clone)]
+pub struct Counters {
+    steps: u64,
+    depth: usize,
+    allocations: u64,
+    exceeded: Option<Rc<dyn Object>>,
+}
+
+// The execution state is shared by every enviroment created by an interpreter
+// so that the evaluator can account for the work done by a program.
+pub struct ExecutionState {
+    pub limits: ExecutionLimits,
+    pub steps: u64,
+    pub depth: usize,
+    pub allocations: u64,
+    pub interrupt: InterruptHandle,
+    pub args: Vec<String>, // Command line arguments given to the script.
+    pub returned: bool,    // Whether the program returned from the top level.
+    // Modules that have been imported, by canonical path, and the files
+    // currently being evaluated, outermost first, to detect import cycles.
+    pub modules: HashMap<PathBuf, Rc<dyn Object>>,
+    pub importing: Vec<PathBuf>,
+    pub output: Box<dyn Output>, // Where puts, print and the host write to.
+    pub hooks: Hooks,            // Observe the evaluation, see hook/mod.rs.
+    // Once a limit has been exceeded the error is kept so that every node
+    // evaluated afterwards returns it and the program unwinds.
+    exceeded: Option<Rc<dyn Object>>,
+}
+
+impl ExecutionState {
+    pub fn new(limits: ExecutionLimits) -> Self {
+        ExecutionState {
+            limits,
+            steps: 0,
+            depth: 0,
+            allocations: 0,
+            interrupt: InterruptHandle::new(),
+            args: Vec::new(),
+            returned: false,
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            output: Box::new(output::Stdout {}),
+            hooks: Vec::new(),
+            exceeded: None,
+        }
+    }
+
+    // Clear the counters before a new top level evaluation.
+    pub fn reset(&mut self) {
+        self.steps = 0;
+        self.depth = 0;
+        self.allocations = 0;
+        self.returned = false;
+        self.exceeded = None;
+    }
+
+    pub fn counters(&self) -> Counters {
+        return Counters {
+            steps: self.steps,
+            depth: self.depth,
+            allocations: self.allocations,
+            exceeded: self.exceeded.clone(),
+        };
+    }
+
+    pub fn restore(&mut self, counters: Counters) {
+        self.steps = counters.steps;
+        self.depth = counters.depth;
+        self.allocations = counters.allocations;
+        self.exceeded = counters.exceeded;
+    }
+
+    // Return the error of the limit that has been exceeded if there is one.
+    pub fn exceeded(&self) -> Option<Rc<dyn Object>> {
+        return self.exceeded.clone();
+    }
+
+    // Record the evaluation of a node.
+    pub fn step(&mut self) -> Option<Rc<dyn Object>> {
+        if self.exceeded.is_some() {
+            return self.exceeded.clone();
+        }
+        if self.interrupt.take() {
+            return self.exceed(
+                ErrorKind::Interrupted,
+                "Evaluation interrupted.".to_string(),
+            );
+        }
+        self.steps += 1;
+        if self.limits.max_steps.is_some_and(|max| self.steps > max) {
+            return self.exceed(
+                ErrorKind::StepLimit,
+                format!("Step limit of {} exceeded.", self.steps - 1),
+            );
+        }
+        return None;
+    }
+
+    // Record the allocation of count objects. Builtins also count each element
+    // of the arrays they build and concatenation each character of its string.
+    pub fn allocate(&mut self, count: u64) -> Option<Rc<dyn Object>> {
+        if self.exceeded.is_some() {
+            return self.exceeded.clone();
+        }
+        self.allocations = self.allocations.saturating_add(count);
+        let max = self.limits.max_allocations;
+        if let Some(max) = max.filter(|&max| self.allocations > max) {
+            return self.exceed(
+                ErrorKind::AllocationLimit,
+                format!("Allocation limit of {} exceeded.", max),
+            );
+        }
+        return None;
+    }
+
+    // Record entering a function call.
+    pub fn enter_call(&mut self) -> Option<Rc<dyn Object>> {
+        if self.exceeded.is_some() {
+            return self.exceeded.clone();
+        }
+        self.depth += 1;
+        if self.limits.max_depth.is_some_and(|max| self.depth > max) {
+            return self.exceed(
+                ErrorKind::DepthLimit,
+                format!("Call depth limit of {} exceeded.", self.depth - 1),
+            );
+        }
+        return None;
+    }
+
+    // Record leaving a function call.
+    pub fn exit_call(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn exceed(&mut self, kind: ErrorKind, message: String) -> Option<Rc<dyn Object>> {
+        let error: Rc<dyn Object> = Rc::new(object::Error::new(kind, message));
+        self.exceeded = Some(error.clone());
+        return Some(error);
+    }
+}
+
+// ================================================================================
+// Modules.
+// ================================================================================
+
+// Load the module at path, relative to the file being evaluated in env.
+// Each module is evaluated once in its own enviroment and then cached.
+pub fn import_module(path: &String, env: &Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let base = env.borrow().path();
+    let relative = match base.as_ref().and_then(|base| base.parent()) {
+        Some(directory) => directory.join(path),
+        None => PathBuf::from(path),
+    };
+    let canonical = fs::canonicalize(&relative);
+    if let Err(canonical) = canonical {
+        return Rc::new(object::Error::new(
+            ErrorKind::Runtime,
+            format!("Could not import {}: {}", path, canonical),
+        ));
+    }
+    let canonical = canonical.unwrap();
+
+    let state = env.borrow().state.clone();
+    let cached = state.borrow().modules.get(&canonical).cloned();
+    if let Some(cached) = cached {
+        return cached;
+    }
+    if state.borrow().importing.contains(&canonical) {
+        let mut cycle: Vec<String> = state
+            .borrow()
+            .importing
+            .iter()
+            .skip_while(|x| **x != canonical)
+            .map(|x| x.display().to_string())
+            .collect();
+        cycle.push(canonical.display().to_string());
+        return Rc::new(object::Error::new(
+            ErrorKind::Runtime,
+            format!("Import cycle detected: {}.", cycle.join(" -> ")),
+        ));
+    }
+
+    state.borrow_mut().importing.push(canonical.clone());
+    let module = eval_module(&canonical, root_enviroment(env));
+    state.borrow_mut().importing.pop();
+
+    if module.get_type() == ObjectType::Module {
+        state.borrow_mut().modules.insert(canonical, module.clone());
+    }
+    return module;
+}
+
+// Evaluate the file at path in a new enviroment and return it as a module,
+// or the error that stopped it.
+fn eval_module(path: &PathBuf, root: Rc<RefCell<Enviroment>>) -> Rc<dyn Object> {
+    let input = fs::read_to_string(path);
+    if let Err(input) = input {
+        return Rc::new(object::Error::new(
+            ErrorKind::Runtime,
+            format!("Could not import {}: {}", path.display(), input),
+        ));
+    }
+    let input = input.unwrap();
+
+    let program = match parse_source(&input, &path.display().to_string()) {
+        Ok(program) => program,
+        Err(error) => return error,
+    };
+
+    // Modules see the prelude but not the variables of their importer.
+    let env = Rc::new(RefCell::new(Enviroment::new_module(root, path.clone())));
+    for statement in program.statements.iter() {
+        let result = statement.eval(env.clone());
+        // A return at the top level of a module stops its evaluation.
+        if result.get_type() == ObjectType::ReturnValue {
+            break;
+        }
+        if result.get_type() == ObjectType::Error {
+            return result;
+        }
+    }
+    return Rc::new(object::Module {
+        path: path.clone(),
+        env,
+    });
+}
+
+// The outermost enviroment of a chain, which holds the prelude.
+fn root_enviroment(env: &Rc<RefCell<Enviroment>>) -> Rc<RefCell<Enviroment>> {
+    let outer = env.borrow().outer.clone();
+    return match outer {
+        Some(outer) => root_enviroment(&outer),
+        None => env.clone(),
+    };
+}
+
+// Parse source code, returning the parser if there were errors so that the
+// caller can report them.
+fn parse_input(input: &str) -> Result<Program, Box<Parser>> {
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program().unwrap();
+    if !parser.errors().is_empty() {
+        return Err(Box::new(parser));
+    }
+    return Ok(program);
+}
+
+// The result of returning value from the top level. Returning an err, as ?
+// does with one, fails the program like an uncaught throw of it.
+fn returned_value(value: Rc<dyn Object>) -> Rc<dyn Object> {
+    let is_err = value
+        .downcast_ref::<object::ResultValue>()
+        .is_some_and(|result| !result.is_ok);
+    if is_err {
+        return Rc::new(object::Error::thrown(value));
+    }
+    return value;
+}
+
+// Parse source code, named by name in errors, returning a SyntaxError if it
+// does not parse.
+pub fn parse_source(input: &str, name: &str) -> Result<Program, Rc<dyn Object>> {
+    return parse_input(input).map_err(|parser| {
+        Rc::new(object::Error::new(
+            ErrorKind::Syntax,
+            format!(
+                "Could not parse {}:\n\t{}",
+                name,
+                parser.errors().join("\n\t")
+            ),
+        )) as Rc<dyn Object>
+    });
+}
+
+// ================================================================================
+// Interpreter.
+// ================================================================================
+
+// The functions written in rust-monkey which are available to every program.
+pub const PRELUDE: &str = include_str!("prelude.rm");
+
+pub struct Interpreter {
+    pub enviroment: Rc<RefCell<Enviroment>>, // The globals of the program.
+    pub prelude: Rc<RefCell<Enviroment>>,    // Encloses the globals and every module.
+    state: Rc<RefCell<ExecutionState>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    // Create an interpreter with the standard prelude loaded.
+    pub fn new() -> Self {
+        let interpreter = Interpreter::without_prelude();
+        for function in builtins::library() {
+            interpreter
+                .prelude
+                .borrow_mut()
+                .insert(function.name, function.value);
+        }
+        let result = interpreter.add_prelude("prelude", PRELUDE);
+        if result.is_err() {
+            panic!(
+                "The prelude failed to load: {}",
+                result.err().unwrap().to_string()
+            );
+        }
+        return interpreter;
+    }
+
+    // Create an interpreter with an empty prelude.
+    pub fn without_prelude() -> Self {
+        let prelude = Rc::new(RefCell::new(Enviroment::new(None)));
+        let enviroment = Rc::new(RefCell::new(Enviroment::new(Some(prelude.clone()))));
+        let state = prelude.borrow().state.clone();
+        Interpreter {
+            enviroment,
+            prelude,
+            state,
+        }
+    }
+
+    // Evaluate source code into the prelude so that its bindings are available
+    // to every program and module run by the interpreter. name identifies the
+    // source in errors.
+    pub fn add_prelude(&self, name: &str, input: &str) -> Result<(), Rc<dyn Object>> {
+        let program = parse_source(input, name)?;
+        self.state.borrow_mut().reset();
+        for statement in program.statements.iter() {
+            let result = statement.eval(self.prelude.clone());
+            if result.get_type() == ObjectType::Error {
+                return Err(result);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn with_limits(limits: ExecutionLimits) -> Self {
+        let interpreter = Interpreter::new();
+        interpreter.set_limits(limits);
+        return interpreter;
+    }
+
+    pub fn limits(&self) -> ExecutionLimits {
+        return self.state.borrow().limits;
+    }
+
+    pub fn set_limits(&self, limits: ExecutionLimits) {
+        self.state.borrow_mut().limits = limits;
+    }
+
+    // Return a handle which can interrupt evaluations run by this interpreter.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        return self.state.borrow().interrupt.clone();
+    }
+
+    // Set the arguments returned by the args builtin.
+    pub fn set_args(&self, args: Vec<String>) {
+        self.state.borrow_mut().args = args;
+    }
+
+    // Set where puts, print and the host write output and diagnostics.
+    pub fn set_output(&self, output: Box<dyn Output>) {
+        self.state.borrow_mut().output = output;
+    }
+
+    // Add a hook which observes the evaluation.
+    pub fn add_hook(&self, hook: Rc<RefCell<dyn Hook>>) {
+        self.state.borrow_mut().hooks.push(hook);
+    }
+
+    // Write program output.
+    pub fn write(&self, text: &str) {
+        self.state.borrow_mut().output.write(text);
+    }
+
+    // Write a diagnostic.
+    pub fn write_error(&self, text: &str) {
+        self.state.borrow_mut().output.write_error(text);
+    }
+
+    // Set the file being evaluated so that its imports are resolved relative
+    // to it and importing it again is reported as a cycle.
+    pub fn set_path(&self, path: PathBuf) {
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        self.state.borrow_mut().importing = vec![canonical];
+        self.enviroment.borrow_mut().path = Some(path);
+    }
+
+    // Remove every global binding.
+    pub fn reset(&self) {
+        self.enviroment.borrow_mut().variables.clear();
+    }
+
+    // Parse a string of source code into a program.
+    // The parse errors are written to the output as diagnostics and returned
+    // if there were any. With debug the parsed statements are written too.
+    pub fn parse(&self, input: &str, debug: bool) -> Result<Program, Vec<String>> {
+        let program = parse_input(input).map_err(|parser| {
+            parser.print_parse_errors(self.state.borrow_mut().output.as_mut());
+            parser.errors().clone()
+        })?;
+        if debug {
+            let mut state = self.state.borrow_mut();
+            for statement in program.statements.iter() {
+                state.output.write(&format!("{}\n", statement.to_string()));
+            }
+        }
+        return Ok(program);
+    }
+
+    // Evaluate each statement of a program in the global enviroment.
+    // The result of every statement is returned so the caller can display them.
+    // Evaluation stops at the first statement that raises an uncaught error
+    // or returns from the top level of the program.
+    pub fn eval_program(&self, program: &Program) -> Vec<Rc<dyn Object>> {
+        self.state.borrow_mut().reset();
+        let mut results: Vec<Rc<dyn Object>> = Vec::new();
+        for statement in program.statements.iter() {
+            let result = statement.eval(self.enviroment.clone());
+            if result.get_type() == ObjectType::ReturnValue {
+                self.state.borrow_mut().returned = true;
+                let value = result.downcast_ref::<object::ReturnValue>().unwrap();
+                results.push(returned_value(value.value.clone()));
+                break;
+            }
+            let stop = result.get_type() == ObjectType::Error;
+            results.push(result);
+            if stop {
+                break;
+            }
+        }
+        return results;
+    }
+
+    // Call the test function bound to name in the globals with no arguments.
+    // Like any call it runs in a new enviroment, nested in the one the
+    // function was defined in, so tests do not see each other's bindings.
+    pub fn run_test(&self, name: &String) -> Rc<dyn Object> {
+        self.state.borrow_mut().reset();
+        let function = self.enviroment.borrow().get(name);
+        return match function {
+            Some(function) if function.get_type() == ObjectType::Function => {
+                call_function(function, Vec::new(), Span::default())
+            }
+            _ => Rc::new(object::Error::new(
+                ErrorKind::Name,
+                format!("{} is not a function.", name),
+            )),
+        };
+    }
+
+    // Return the code given to exit if the results of eval_program show that
+    // the program called it.
+    pub fn exit_code(&self, results: &Vec<Rc<dyn Object>>) -> Option<i32> {
+        let error = results.last()?.downcast_ref::<object::Error>()?;
+        if error.kind != ErrorKind::Exit {
+            return None;
+        }
+        return error
+            .value
+            .as_ref()
+            .and_then(|value| value.downcast_ref::<object::Integer>())
+            .map(|code| code.value as i32);
+    }
+
+    // Return the exit status of a program given the results of eval_program.
+    // It is the code given to exit, 1 for an uncaught error, or the value
+    // returned from the top level: an integer or false for 1. Otherwise it is 0.
+    pub fn exit_status(&self, results: &Vec<Rc<dyn Object>>) -> i32 {
+        let code = self.exit_code(results);
+        if let Some(code) = code {
+            return code;
+        }
+        let last = results.last();
+        if last.is_none() {
+            return 0;
+        }
+        let last = last.unwrap();
+        if last.get_type() == ObjectType::Error {
+            return 1;
+        }
+        if !self.state.borrow().returned {
+            return 0;
+        }
+        if let Some(integer) = last.downcast_ref::<object::Integer>() {
+            return integer.value as i32;
+        }
+        if let Some(boolean) = last.downcast_ref::<object::Boolean>() {
+            return if boolean.value { 0 } else { 1 };
+        }
+        return 0;
+    }
+
+    // Parse and evaluate a string of source code returning the value of the
+    // last statement.
+    // The parse errors are returned as a SyntaxError rather than written.
+    pub fn eval(&self, input: &str) -> Rc<dyn Object> {
+        let program = match parse_input(input) {
+            Ok(program) => program,
+            Err(parser) => {
+                let errors = parser.errors().join("\n");
+                return Rc::new(object::Error::new(ErrorKind::Syntax, errors));
+            }
+        };
+        let results = self.eval_program(&program);
+        return results
+            .last()
+            .cloned()
+            .unwrap_or_else(|| Rc::new(object::Null {}));
+    }
+}
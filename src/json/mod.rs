@@ -0,0 +1,327 @@
+/*
+Author: Jedda Boyle
+Contains: Json
+A small JSON value used by the tools which talk to other programs, such as
+the JSON output of the linter and the messages of the language server.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use std::fmt;
+
+// ================================================================================
+// Json value.
+// ================================================================================
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>), // Members are kept in the order they were added.
+}
+
+impl Json {
+    pub fn string(value: &str) -> Self {
+        return Json::Str(value.to_string());
+    }
+
+    pub fn number(value: i64) -> Self {
+        return Json::Number(value as f64);
+    }
+
+    // Build an object from its members.
+    pub fn object(members: Vec<(&str, Json)>) -> Self {
+        return Json::Object(
+            members
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        );
+    }
+
+    // Return the member with the given name if this is an object.
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        if let Json::Object(members) = self {
+            return members
+                .iter()
+                .find(|(member, _)| member == name)
+                .map(|(_, value)| value);
+        }
+        return None;
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Json::Str(value) = self {
+            return Some(value);
+        }
+        return None;
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        if let Json::Number(value) = self {
+            return Some(*value as i64);
+        }
+        return None;
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Json::Bool(value) = self {
+            return Some(*value);
+        }
+        return None;
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        if let Json::Array(values) = self {
+            return Some(values);
+        }
+        return None;
+    }
+
+    // Parse JSON text into a value.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut reader = Reader {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let value = reader.read_value()?;
+        reader.skip_whitespace();
+        if reader.position != reader.chars.len() {
+            return Err(reader.error("unexpected text after the value"));
+        }
+        return Ok(value);
+    }
+}
+
+// ================================================================================
+// Parsing.
+// ================================================================================
+
+struct Reader {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Reader {
+    fn error(&self, message: &str) -> String {
+        return format!("{} at character {}", message, self.position);
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| c == ' ' || c == '\t' || c == '\n' || c == '\r')
+        {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected '{}'", c)));
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+    fn read_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for c in keyword.chars() {
+            self.expect(c)?;
+        }
+        return Ok(value);
+    }
+
+    fn read_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => return self.read_keyword("null", Json::Null),
+            Some('t') => return self.read_keyword("true", Json::Bool(true)),
+            Some('f') => return self.read_keyword("false", Json::Bool(false)),
+            Some('"') => return Ok(Json::Str(self.read_string()?)),
+            Some('[') => return self.read_array(),
+            Some('{') => return self.read_object(),
+            Some(c) if c == '-' || c.is_ascii_digit() => return self.read_number(),
+            Some(_) => return Err(self.error("unexpected character")),
+            None => return Err(self.error("unexpected end of text")),
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        match text.parse::<f64>() {
+            Ok(value) => return Ok(Json::Number(value)),
+            Err(_) => return Err(self.error("invalid number")),
+        }
+    }
+
+    fn read_hex(&mut self) -> Result<u32, String> {
+        if self.position + 4 > self.chars.len() {
+            return Err(self.error("unexpected end of text"));
+        }
+        let text: String = self.chars[self.position..self.position + 4]
+            .iter()
+            .collect();
+        self.position += 4;
+        return u32::from_str_radix(&text, 16).map_err(|_| self.error("invalid escape"));
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            self.position += 1;
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some(c) => c,
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    self.position += 1;
+                    match escaped {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        'r' => value.push('\r'),
+                        'b' => value.push('\u{8}'),
+                        'f' => value.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.read_hex()?;
+                            // Characters outside the basic plane are written as
+                            // a surrogate pair.
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.chars[self.position..].starts_with(&['\\', 'u'])
+                            {
+                                self.position += 2;
+                                let low = self.read_hex()?;
+                                code = 0x10000
+                                    + ((code - 0xd800) << 10)
+                                    + (low.wrapping_sub(0xdc00) & 0x3ff);
+                            }
+                            value.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        c => value.push(c),
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn read_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.read_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn read_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.read_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            members.push((name, self.read_value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+// Write a string between quotes with the characters JSON requires escaped.
+fn write_string(fmt: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    write!(fmt, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(fmt, "\\\"")?,
+            '\\' => write!(fmt, "\\\\")?,
+            '\n' => write!(fmt, "\\n")?,
+            '\r' => write!(fmt, "\\r")?,
+            '\t' => write!(fmt, "\\t")?,
+            c if (c as u32) < 0x20 => write!(fmt, "\\u{:04x}", c as u32)?,
+            c => write!(fmt, "{}", c)?,
+        }
+    }
+    return write!(fmt, "\"");
+}
+
+// Json is displayed as compact JSON text.
+impl fmt::Display for Json {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => return write!(fmt, "null"),
+            Json::Bool(value) => return write!(fmt, "{}", value),
+            Json::Number(value) => return write!(fmt, "{}", value),
+            Json::Str(value) => return write_string(fmt, value),
+            Json::Array(values) => {
+                write!(fmt, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        write!(fmt, ",")?;
+                    }
+                    write!(fmt, "{}", value)?;
+                }
+                return write!(fmt, "]");
+            }
+            Json::Object(members) => {
+                write!(fmt, "{{")?;
+                for (i, (name, value)) in members.iter().enumerate() {
+                    if i != 0 {
+                        write!(fmt, ",")?;
+                    }
+                    write_string(fmt, name)?;
+                    write!(fmt, ":{}", value)?;
+                }
+                return write!(fmt, "}}");
+            }
+        }
+    }
+}
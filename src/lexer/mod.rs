@@ -9,47 +9,85 @@ The lexer splits strings of code into tokens.
 // ================================================================================
 
 pub mod token;
+use token::Span;
 use token::Token;
 
 // ================================================================================
 // Lexer struct
 // ================================================================================
 
+// A // comment. The lexer skips comments but records them so that tools
+// such as the formatter can put them back.
+#[derive(Clone)]
+pub struct Comment {
+    pub text: String, // Including the // and without the newline.
+    pub span: Span,
+    pub trailing: bool, // Whether the comment follows code on the same line.
+}
+
 pub struct Lexer {
-    input: String,
+    input: Vec<char>,     // Kept as chars so that reading the next one is constant time.
     position: usize,      // current position in input (points to current char)
     read_position: usize, // current reading position in input (after current char)
     val: char,            // current char under examination
     eof: bool,
+    line: usize,            // line of the current char
+    column: usize,          // column of the current char
+    last_token_line: usize, // line the last token ended on
+    pub comments: Vec<Comment>,
 }
 
 // ================================================================================
 // Lexer implementation.
 // ================================================================================
 
+// Characters that make up keywords, identifiers and integers.
+fn is_word_char(val: char) -> bool {
+    return val.is_alphanumeric() || val == '_';
+}
+
 impl Lexer {
-    pub fn new(input_string: &String) -> Self {
-        if input_string.len() == 0 {
-            println!("Error: Lexer input string has zero length.");
-        }
-        Lexer {
-            input: input_string.clone(),
+    // Empty input produces only the Eof token.
+    pub fn new(input_string: &str) -> Self {
+        let mut lexer = Lexer {
+            input: input_string.chars().collect(),
             position: 0,
             read_position: 1,
-            val: input_string.chars().nth(0).unwrap(),
-            eof: false,
+            val: input_string.chars().next().unwrap_or(' '),
+            eof: input_string.is_empty(),
+            line: 1,
+            column: 1,
+            last_token_line: 0,
+            comments: Vec::new(),
+        };
+        lexer.skip_shebang();
+        return lexer;
+    }
+
+    // Skip a #! line at the start of the input so scripts can be executable.
+    fn skip_shebang(self: &mut Lexer) {
+        if self.val != '#' || self.peak_char() != Some('!') {
+            return;
         }
+        while self.val != '\n' && self.read_char().is_some() {}
     }
 
     // Return next char to be read.
     fn peak_char(self: &mut Lexer) -> Option<char> {
-        return self.input.chars().nth(self.read_position);
+        return self.input.get(self.read_position).copied();
     }
 
     fn read_char(self: &mut Lexer) -> Option<char> {
-        let val = self.input.chars().nth(self.read_position);
-        if val.is_some() {
-            self.val = val.unwrap();
+        let val = self.input.get(self.read_position).copied();
+        if let Some(val) = val {
+            // Move the span past the current char.
+            if self.val == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.val = val;
             self.position = self.read_position;
             self.read_position += 1;
         } else {
@@ -58,15 +96,43 @@ impl Lexer {
         return val;
     }
 
-    // Move lexer forward until current char is not a whitespace character.
+    // Move lexer forward until current char is not a whitespace character
+    // or part of a // comment.
     fn skip_whitespace(self: &mut Lexer) {
-        while (self.val == ' ' || self.val == '\t' || self.val == '\n' || self.val == '\r')
-            && !self.eof
-        {
-            self.read_char();
+        loop {
+            while (self.val == ' ' || self.val == '\t' || self.val == '\n' || self.val == '\r')
+                && !self.eof
+            {
+                self.read_char();
+            }
+            if self.eof || self.val != '/' || self.peak_char() != Some('/') {
+                return;
+            }
+            self.read_comment();
         }
     }
 
+    // Record the comment starting at the current char and move to the end of
+    // its line.
+    fn read_comment(self: &mut Lexer) {
+        let span = Span {
+            line: self.line,
+            column: self.column,
+        };
+        let mut text = String::new();
+        while self.val != '\n' {
+            text.push(self.val);
+            if self.read_char().is_none() {
+                break;
+            }
+        }
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            span,
+            trailing: self.last_token_line == span.line,
+        });
+    }
+
     fn read_literal(self: &mut Lexer) -> Option<String> {
         // Skip forward to next non-whitespace char.
         self.skip_whitespace();
@@ -79,13 +145,27 @@ impl Lexer {
         // Read current char into string.
         let mut to_return = self.val.to_string();
 
+        // Read string literals including the quotes. Escaped characters are
+        // kept as they are and converted by the parser.
+        if self.val == '"' {
+            while self.read_char().is_some() {
+                to_return.push(self.val);
+                if self.val == '\\' {
+                    if self.read_char().is_some() {
+                        to_return.push(self.val);
+                    }
+                } else if self.val == '"' {
+                    self.read_char();
+                    break;
+                }
+            }
+            return Some(to_return);
+        }
+
         // Get non alphanumeric tokens.
-        if !self.val.is_alphanumeric() {
+        if !is_word_char(self.val) {
             // Check for two character == and != tokens.
-            if self.val == '=' && self.peak_char().unwrap_or('0') == '=' {
-                self.read_char();
-                to_return.push(self.val);
-            } else if self.val == '!' && self.peak_char().unwrap_or('0') == '=' {
+            if (self.val == '=' || self.val == '!') && self.peak_char().unwrap_or('0') == '=' {
                 self.read_char();
                 to_return.push(self.val);
             }
@@ -96,7 +176,7 @@ impl Lexer {
         // Read multi-char alphanumeric tokens.
         // if read_char returns None use ! as default value which is not alphanumeric so
         // loop isn't executed.
-        while self.read_char().unwrap_or('!').is_alphanumeric() {
+        while is_word_char(self.read_char().unwrap_or('!')) {
             to_return.push(self.val);
         }
 
@@ -104,11 +184,17 @@ impl Lexer {
     }
 
     pub fn next_token(self: &mut Lexer) -> Option<Token> {
+        // Record where the token starts.
+        self.skip_whitespace();
+        let span = Span {
+            line: self.line,
+            column: self.column,
+        };
+
         let literal = self.read_literal();
-        if literal.is_some() {
-            return Some(Token::new(literal.unwrap()));
-        } else {
-            return Some(Token::new("EOF".to_string()));
-        }
+        self.last_token_line = self.line;
+        let mut token = Token::new(literal.unwrap_or_else(|| "EOF".to_string()));
+        token.span = span;
+        return Some(token);
     }
 }
@@ -6,7 +6,7 @@ Define language tokens.
 
 use std::fmt;
 
-#[derive(Copy, Clone, PartialEq, Hash, Eq)]
+#[derive(Copy, Clone, PartialEq, Hash, Eq, Debug)]
 pub enum TokenType {
     Eof,
     Illegal,
@@ -14,6 +14,7 @@ pub enum TokenType {
     // Identifiers + literals
     Int,
     Ident,
+    Str,
 
     // Operators
     Assign,
@@ -26,16 +27,20 @@ pub enum TokenType {
     Gt,
     Equal,
     NotEqual,
+    Question,
 
     // Delimiters
     Comma,
     SemiColon,
+    Dot,
 
     // Brackets
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     // Keywords
     Let,
@@ -45,36 +50,74 @@ pub enum TokenType {
     If,
     Else,
     Return,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Import,
+    Export,
+    As,
 }
 
+// The keywords recognised by Token::new.
+pub const KEYWORDS: [&str; 14] = [
+    "let", "fn", "true", "false", "if", "else", "return", "throw", "try", "catch", "finally",
+    "import", "export", "as",
+];
+
 #[derive(Copy, Clone)]
 pub enum Precedence {
-    // Assign a precedence to each operator. 0 is lowest and 6 is highest.
+    // Assign a precedence to each operator. 0 is lowest and 7 is highest.
     Lowest = 0,
     Equals = 1,
     LessGreater = 2,
     Sum = 3,
     Product = 4,
     Prefix = 5,
-    Call = 6,
+    Postfix = 6,
+    Call = 7,
+}
+
+// Location of a token in the source code. Lines and columns start at 1;
+// tokens that were not produced by the lexer have a span of 0:0.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(fmt, "line {}, column {}", self.line, self.column);
+    }
 }
 
 #[derive(Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
+    pub span: Span,
 }
 
-fn is_int(literal: &String) -> bool {
-    return literal.chars().all(|x| x.is_digit(10));
+fn is_int(literal: &str) -> bool {
+    return literal.chars().all(|x| x.is_ascii_digit());
 }
 
-fn is_identifier(literal: &String) -> bool {
-    return literal.chars().all(|x| x.is_ascii_alphabetic());
+// Identifiers start with a letter or underscore which may be followed by digits.
+fn is_identifier(literal: &str) -> bool {
+    let first = literal.chars().next();
+    return first.is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && literal
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '_');
 }
 
-impl Token {
+// String literals keep their quotes and escape sequences.
+fn is_string(literal: &str) -> bool {
+    return literal.len() >= 2 && literal.starts_with('"') && literal.ends_with('"');
+}
 
+impl Token {
     // Convert a sring into a token.
     // If string is invalid returns TokenType::Illegal
     pub fn new(literal: String) -> Self {
@@ -101,12 +144,16 @@ impl Token {
             token_type = TokenType::Equal;
         } else if literal == "!=" {
             token_type = TokenType::NotEqual;
+        } else if literal == "?" {
+            token_type = TokenType::Question;
         }
         // Delimeters
         else if literal == "," {
             token_type = TokenType::Comma;
         } else if literal == ";" {
             token_type = TokenType::SemiColon;
+        } else if literal == "." {
+            token_type = TokenType::Dot;
         }
         // Brackets
         else if literal == "(" {
@@ -117,6 +164,10 @@ impl Token {
             token_type = TokenType::LBrace;
         } else if literal == "}" {
             token_type = TokenType::RBrace;
+        } else if literal == "[" {
+            token_type = TokenType::LBracket;
+        } else if literal == "]" {
+            token_type = TokenType::RBracket;
         }
         // Keywords
         else if literal == "let" {
@@ -133,6 +184,20 @@ impl Token {
             token_type = TokenType::True;
         } else if literal == "false" {
             token_type = TokenType::False;
+        } else if literal == "throw" {
+            token_type = TokenType::Throw;
+        } else if literal == "try" {
+            token_type = TokenType::Try;
+        } else if literal == "catch" {
+            token_type = TokenType::Catch;
+        } else if literal == "finally" {
+            token_type = TokenType::Finally;
+        } else if literal == "import" {
+            token_type = TokenType::Import;
+        } else if literal == "export" {
+            token_type = TokenType::Export;
+        } else if literal == "as" {
+            token_type = TokenType::As;
         }
         // Identifiers + literals
         else if literal == "EOF" {
@@ -141,11 +206,14 @@ impl Token {
             token_type = TokenType::Int;
         } else if is_identifier(&literal) {
             token_type = TokenType::Ident;
+        } else if is_string(&literal) {
+            token_type = TokenType::Str;
         }
 
         return Token {
-            token_type: token_type,
-            literal: literal,
+            token_type,
+            literal,
+            span: Span::default(),
         };
     }
 
@@ -170,13 +238,16 @@ impl Token {
             TokenType::Minus     => Precedence::Sum,
             TokenType::Slash     => Precedence::Product,
             TokenType::Asterisk  => Precedence::Product,
-            TokenType::LParen    => Precedence::Call
+            TokenType::Question  => Precedence::Postfix,
+            TokenType::LParen    => Precedence::Call,
+            TokenType::LBracket  => Precedence::Call,
+            TokenType::Dot       => Precedence::Call
         };
         let result = map.get(&self.token_type);
         if result.is_none() {
             return Precedence::Lowest;
         }
-        return result.unwrap().clone();
+        return *result.unwrap();
 
         // return None;
         // return Some(.clone());
@@ -206,12 +277,16 @@ impl fmt::Display for Token {
             return write!(fmt, "==");
         } else if self.token_type == TokenType::NotEqual {
             return write!(fmt, "!=");
+        } else if self.token_type == TokenType::Question {
+            return write!(fmt, "?");
         }
         // Delimeters
         else if self.token_type == TokenType::SemiColon {
             return write!(fmt, ";");
         } else if self.token_type == TokenType::Comma {
             return write!(fmt, ",");
+        } else if self.token_type == TokenType::Dot {
+            return write!(fmt, ".");
         }
         // Brackets
         else if self.token_type == TokenType::LParen {
@@ -222,6 +297,10 @@ impl fmt::Display for Token {
             return write!(fmt, "{{");
         } else if self.token_type == TokenType::RBrace {
             return write!(fmt, "}}");
+        } else if self.token_type == TokenType::LBracket {
+            return write!(fmt, "[");
+        } else if self.token_type == TokenType::RBracket {
+            return write!(fmt, "]");
         }
         // Keywords
         else if self.token_type == TokenType::Let {
@@ -238,11 +317,26 @@ impl fmt::Display for Token {
             return write!(fmt, "True");
         } else if self.token_type == TokenType::False {
             return write!(fmt, "False");
+        } else if self.token_type == TokenType::Throw {
+            return write!(fmt, "Throw");
+        } else if self.token_type == TokenType::Try {
+            return write!(fmt, "Try");
+        } else if self.token_type == TokenType::Catch {
+            return write!(fmt, "Catch");
+        } else if self.token_type == TokenType::Finally {
+            return write!(fmt, "Finally");
+        } else if self.token_type == TokenType::Import {
+            return write!(fmt, "Import");
+        } else if self.token_type == TokenType::Export {
+            return write!(fmt, "Export");
+        } else if self.token_type == TokenType::As {
+            return write!(fmt, "As");
         }
         // Identifiers + literals
-        else if self.token_type == TokenType::Int {
-            return write!(fmt, "{}", self.literal);
-        } else if self.token_type == TokenType::Ident {
+        else if self.token_type == TokenType::Int
+            || self.token_type == TokenType::Ident
+            || self.token_type == TokenType::Str
+        {
             return write!(fmt, "{}", self.literal);
         } else if self.token_type == TokenType::Eof {
             return write!(fmt, "Eof");
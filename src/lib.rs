@@ -0,0 +1,46 @@
+/*
+Author: Jedda Boyle
+Contains: The rust-monkey library.
+Exposes the interpreter components so that rust-monkey can be embedded
+in other programs as well as run from the command line.
+*/
+
+// The code base uses explicit returns throughout.
+#![allow(clippy::needless_return)]
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+// interpreter components.
+pub mod analysis;
+pub mod ast;
+pub mod builtins;
+pub mod cli;
+pub mod compiler;
+pub mod coverage;
+pub mod dap;
+pub mod debug;
+pub mod debugger;
+pub mod enviroment;
+pub mod formatter;
+pub mod hook;
+pub mod interpreter;
+pub mod json;
+pub mod lexer;
+pub mod linter;
+pub mod lsp;
+pub mod object;
+pub mod output;
+pub mod parser;
+pub mod profiler;
+pub mod repl;
+pub mod testing;
+pub mod tracer;
+
+// Used for constant hash-map in lexer/token.rs
+#[macro_use]
+extern crate maplit;
+// Used to downcast traits to actual type in object/mod.rs and ast/mod.rs
+#[macro_use]
+extern crate downcast_rs;
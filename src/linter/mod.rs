@@ -0,0 +1,332 @@
+/*
+Author: Jedda Boyle
+Contains: Linter
+The linter walks a parsed program and reports code which is legal but
+probably wrong. Each rule has a stable ID which is used to turn it off or
+change its severity in a project's lint configuration.
+Names are resolved by the analysis module.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::analysis::analyse;
+use super::analysis::Analysis;
+use super::analysis::SymbolKind;
+use super::ast::children;
+use super::ast::start_span;
+use super::ast::BlockStatement;
+use super::ast::BoolExpression;
+use super::ast::IfElseExpression;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::json::Json;
+use super::lexer::token::Span;
+use super::lexer::Lexer;
+use super::parser::Parser;
+use super::parser::Program;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+// ================================================================================
+// Rules.
+// ================================================================================
+
+pub const UNUSED_VARIABLE: &str = "unused-variable";
+pub const SHADOWED_PARAMETER: &str = "shadowed-parameter";
+pub const UNREACHABLE_CODE: &str = "unreachable-code";
+pub const CONSTANT_CONDITION: &str = "constant-condition";
+pub const ARGUMENT_COUNT: &str = "argument-count";
+
+// Every rule and what it reports.
+pub const RULES: [(&str, &str); 5] = [
+    (
+        UNUSED_VARIABLE,
+        "A let binding that is never read. Names starting with _ are ignored.",
+    ),
+    (
+        SHADOWED_PARAMETER,
+        "A function parameter with the name of a binding in an outer scope.",
+    ),
+    (
+        UNREACHABLE_CODE,
+        "A statement after a return or throw in the same block.",
+    ),
+    (
+        CONSTANT_CONDITION,
+        "An if whose condition is the literal true or false.",
+    ),
+    (
+        ARGUMENT_COUNT,
+        "A call to a known function with the wrong number of arguments.",
+    ),
+];
+
+fn is_rule(id: &str) -> bool {
+    return RULES.iter().any(|(rule, _)| *rule == id);
+}
+
+// ================================================================================
+// Configuration.
+// ================================================================================
+
+// The lint configuration of a project is read from this file in the directory
+// of the file being linted or the nearest directory above it.
+pub const CONFIG_FILE: &str = ".rust-monkey-lint";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Severity::Off => write!(fmt, "off"),
+            Severity::Warning => write!(fmt, "warning"),
+            Severity::Error => write!(fmt, "error"),
+        };
+    }
+}
+
+// The severity of each rule. Rules are warnings unless configured otherwise.
+pub struct Config {
+    severities: HashMap<String, Severity>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Config {
+    pub fn new() -> Self {
+        return Config {
+            severities: HashMap::new(),
+        };
+    }
+
+    // Parse a configuration file. Each line sets the severity of a rule:
+    //     unused-variable = off
+    //     argument-count = error
+    // and # starts a comment.
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split('=').map(|part| part.trim()).collect();
+            if parts.len() != 2 {
+                return Err(format!(
+                    "line {}: expected <rule> = <severity>.",
+                    number + 1
+                ));
+            }
+            if !is_rule(parts[0]) {
+                return Err(format!("line {}: unknown rule {}.", number + 1, parts[0]));
+            }
+            let severity = match parts[1] {
+                "off" => Severity::Off,
+                "warning" => Severity::Warning,
+                "error" => Severity::Error,
+                _ => {
+                    return Err(format!(
+                        "line {}: unknown severity {}, expected off, warning or error.",
+                        number + 1,
+                        parts[1]
+                    ))
+                }
+            };
+            config.severities.insert(parts[0].to_string(), severity);
+        }
+        return Ok(config);
+    }
+
+    pub fn severity(&self, rule: &str) -> Severity {
+        return *self.severities.get(rule).unwrap_or(&Severity::Warning);
+    }
+}
+
+// Return the configuration file that applies to files in directory.
+pub fn find_config(directory: &Path) -> Option<PathBuf> {
+    return directory
+        .ancestors()
+        .map(|directory| directory.join(CONFIG_FILE))
+        .find(|path| path.is_file());
+}
+
+// ================================================================================
+// Diagnostics.
+// ================================================================================
+
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn to_json(&self, file: &str) -> Json {
+        return Json::object(vec![
+            ("file", Json::string(file)),
+            ("line", Json::number(self.span.line as i64)),
+            ("column", Json::number(self.span.column as i64)),
+            ("rule", Json::string(self.rule)),
+            ("severity", Json::string(&self.severity.to_string())),
+            ("message", Json::string(&self.message)),
+        ]);
+    }
+}
+
+// Diagnostics are displayed as line:column: severity[rule]: message.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            fmt,
+            "{}:{}: {}[{}]: {}",
+            self.span.line, self.span.column, self.severity, self.rule, self.message
+        );
+    }
+}
+
+// Lint source code. The parse errors are returned if it does not parse.
+pub fn lint_source(input: &str, config: &Config) -> Result<Vec<Diagnostic>, Vec<String>> {
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program().unwrap();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().clone());
+    }
+    return Ok(lint_program(&program, config));
+}
+
+// Lint a program returning the diagnostics in the order they appear.
+pub fn lint_program(program: &Program, config: &Config) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        config,
+        diagnostics: Vec::new(),
+    };
+    linter.bindings(&analyse(program));
+    linter.statements(&program.statements);
+    linter
+        .diagnostics
+        .sort_by_key(|diagnostic| (diagnostic.span.line, diagnostic.span.column));
+    return linter.diagnostics;
+}
+
+// ================================================================================
+// Linter.
+// ================================================================================
+
+struct Linter<'a> {
+    config: &'a Config,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[allow(clippy::borrowed_box)]
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: &'static str, span: Span, message: String) {
+        let severity = self.config.severity(rule);
+        if severity == Severity::Off {
+            return;
+        }
+        self.diagnostics.push(Diagnostic {
+            rule,
+            severity,
+            span,
+            message,
+        });
+    }
+
+    // Report the rules about how names are bound and used.
+    fn bindings(&mut self, analysis: &Analysis) {
+        for symbol in analysis.symbols.iter() {
+            if symbol.kind == SymbolKind::Let
+                && !symbol.exported
+                && symbol.references.is_empty()
+                && !symbol.name.starts_with('_')
+            {
+                self.report(
+                    UNUSED_VARIABLE,
+                    symbol.span,
+                    format!("The variable {} is never read.", symbol.name),
+                );
+            }
+            if symbol.kind == SymbolKind::Parameter && symbol.shadows {
+                self.report(
+                    SHADOWED_PARAMETER,
+                    symbol.span,
+                    format!(
+                        "The parameter {} shadows a binding in an outer scope.",
+                        symbol.name
+                    ),
+                );
+            }
+        }
+        for call in analysis.calls.iter() {
+            let symbol = &analysis.symbols[call.symbol];
+            let arity = symbol.arity.filter(|&arity| arity != call.arguments);
+            if let Some(arity) = arity {
+                self.report(
+                    ARGUMENT_COUNT,
+                    call.span,
+                    format!(
+                        "{} takes {} arguments but is given {}.",
+                        symbol.name, arity, call.arguments
+                    ),
+                );
+            }
+        }
+    }
+
+    // Walk the statements of a block, reporting the first statement after a
+    // return or throw.
+    fn statements(&mut self, statements: &Vec<Box<dyn Node>>) {
+        let mut returned = false;
+        for statement in statements.iter() {
+            if returned {
+                self.report(
+                    UNREACHABLE_CODE,
+                    start_span(statement),
+                    "This statement is never reached.".to_string(),
+                );
+                returned = false;
+            }
+            self.node(statement);
+            let statement_type = statement.get_type();
+            if statement_type == NodeType::ReturnStatement
+                || statement_type == NodeType::ThrowStatement
+            {
+                returned = true;
+            }
+        }
+    }
+
+    fn node(&mut self, node: &Box<dyn Node>) {
+        if let Some(block) = node.downcast_ref::<BlockStatement>() {
+            self.statements(&block.statements);
+            return;
+        }
+        if let Some(ifelse) = node.downcast_ref::<IfElseExpression>() {
+            if let Some(condition) = ifelse.condition.downcast_ref::<BoolExpression>() {
+                self.report(
+                    CONSTANT_CONDITION,
+                    condition.token.span,
+                    format!("The condition is always {}.", condition.value),
+                );
+            }
+        }
+        for child in children(node) {
+            self.node(child);
+        }
+    }
+}
@@ -0,0 +1,540 @@
+/*
+Author: Jedda Boyle
+Contains: Language server.
+Speaks the Language Server Protocol over stdin and stdout so that editors can
+show the parse errors and lint problems of a file as it is edited, and look
+up the bindings of names with hover, go to definition, find references,
+document symbols and completion.
+Messages are JSON-RPC with a Content-Length header. Documents are synced in
+full and analysed again for every request, as source files are small.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::analysis::analyse;
+use super::analysis::Analysis;
+use super::analysis::Symbol;
+use super::analysis::SymbolKind;
+use super::builtins;
+use super::json::Json;
+use super::lexer::token::Span;
+use super::lexer::token::KEYWORDS;
+use super::lexer::Lexer;
+use super::linter;
+use super::parser::Parser;
+use super::parser::Program;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::prelude::BufRead;
+use std::io::prelude::Write;
+use std::path::PathBuf;
+
+// ================================================================================
+// Protocol constants.
+// ================================================================================
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+const SEVERITY_ERROR: i64 = 1;
+const SEVERITY_WARNING: i64 = 2;
+
+const SYMBOL_FUNCTION: i64 = 12;
+const SYMBOL_VARIABLE: i64 = 13;
+
+const COMPLETION_FUNCTION: i64 = 3;
+const COMPLETION_VARIABLE: i64 = 6;
+const COMPLETION_KEYWORD: i64 = 14;
+
+const TEXT_DOCUMENT_SYNC_FULL: i64 = 1;
+
+// ================================================================================
+// Transport.
+// ================================================================================
+
+// The debug adapter frames its messages in the same way.
+
+// Read the body of the next message, or None at the end of the input.
+pub fn read_message(input: &mut dyn BufRead) -> io::Result<Option<String>> {
+    let mut length: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap().trim();
+        let value = parts.next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            length = value.parse().ok();
+        }
+    }
+    let length = match length {
+        Some(length) => length,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message without a Content-Length header",
+            ))
+        }
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    return Ok(Some(String::from_utf8_lossy(&body).to_string()));
+}
+
+pub fn write_message(output: &mut dyn Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    return output.flush();
+}
+
+// ================================================================================
+// Positions.
+// ================================================================================
+
+// LSP positions count lines and characters from 0 where spans count from 1.
+fn position(span: Span) -> Json {
+    return Json::object(vec![
+        ("line", Json::number(span.line.saturating_sub(1) as i64)),
+        (
+            "character",
+            Json::number(span.column.saturating_sub(1) as i64),
+        ),
+    ]);
+}
+
+// The range of length characters starting at span.
+fn range(span: Span, length: usize) -> Json {
+    let end = Span {
+        line: span.line,
+        column: span.column + length,
+    };
+    return Json::object(vec![("start", position(span)), ("end", position(end))]);
+}
+
+fn name_range(symbol: &Symbol, span: Span) -> Json {
+    return range(span, symbol.name.chars().count());
+}
+
+fn location(uri: &str, symbol: &Symbol, span: Span) -> Json {
+    return Json::object(vec![
+        ("uri", Json::string(uri)),
+        ("range", name_range(symbol, span)),
+    ]);
+}
+
+// ================================================================================
+// Documents.
+// ================================================================================
+
+struct Document {
+    program: Program,
+    errors: Vec<(String, Span)>,
+}
+
+impl Document {
+    fn new(text: &str) -> Self {
+        let mut parser = Parser::new(Lexer::new(text));
+        let program = parser.parse_program().unwrap();
+        let errors = parser
+            .errors()
+            .iter()
+            .cloned()
+            .zip(parser.error_spans().iter().cloned())
+            .collect();
+        return Document { program, errors };
+    }
+}
+
+// The path of a file URI, used to find the lint configuration.
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = String::from_utf8_lossy(&bytes[i + 1..i + 3]).to_string();
+            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    return Some(PathBuf::from(String::from_utf8_lossy(&decoded).to_string()));
+}
+
+fn lint_config(uri: &str) -> linter::Config {
+    let config_path = uri_path(uri)
+        .and_then(|path| path.parent().map(|parent| parent.to_path_buf()))
+        .and_then(|directory| linter::find_config(&directory));
+    return config_path
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| linter::Config::parse(&text).ok())
+        .unwrap_or_default();
+}
+
+// ================================================================================
+// Server.
+// ================================================================================
+
+struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+// Read requests from input and write responses to output until the client
+// sends exit or closes the input. Returns the exit status.
+pub fn serve(input: &mut dyn BufRead, output: &mut dyn Write) -> i32 {
+    let mut server = Server {
+        documents: HashMap::new(),
+        shutdown: false,
+    };
+    loop {
+        let body = match read_message(input) {
+            Ok(Some(body)) => body,
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("rust-monkey lsp: {}", error);
+                return 1;
+            }
+        };
+        let message = match Json::parse(&body) {
+            Ok(message) => message,
+            Err(error) => {
+                let response = error_response(&Json::Null, PARSE_ERROR, &error);
+                if write_message(output, &response).is_err() {
+                    return 1;
+                }
+                continue;
+            }
+        };
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        if method == "exit" {
+            break;
+        }
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let mut messages = Vec::new();
+        match message.get("id") {
+            // Requests have an id and are answered.
+            Some(id) => {
+                let response = match server.request(method, &params) {
+                    Ok(result) => Json::object(vec![
+                        ("jsonrpc", Json::string("2.0")),
+                        ("id", id.clone()),
+                        ("result", result),
+                    ]),
+                    Err((code, error)) => error_response(id, code, &error),
+                };
+                messages.push(response);
+            }
+            None => messages = server.notification(method, &params),
+        }
+        for message in messages.iter() {
+            if write_message(output, message).is_err() {
+                return 1;
+            }
+        }
+    }
+    // The exit status tells the client whether it shut the server down first.
+    if server.shutdown {
+        return 0;
+    }
+    return 1;
+}
+
+fn error_response(id: &Json, code: i64, message: &str) -> Json {
+    return Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("id", id.clone()),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::number(code)),
+                ("message", Json::string(message)),
+            ]),
+        ),
+    ]);
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    return Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string(method)),
+        ("params", params),
+    ]);
+}
+
+fn document_uri(params: &Json) -> &str {
+    return params
+        .get("textDocument")
+        .and_then(|document| document.get("uri"))
+        .and_then(Json::as_str)
+        .unwrap_or("");
+}
+
+// The span of the position in the params of a request.
+fn params_span(params: &Json) -> Span {
+    let position = params.get("position");
+    let get = |name: &str| {
+        position
+            .and_then(|position| position.get(name))
+            .and_then(Json::as_i64)
+            .unwrap_or(0) as usize
+    };
+    return Span {
+        line: get("line") + 1,
+        column: get("character") + 1,
+    };
+}
+
+impl Server {
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, "The server is shut down.".to_string()));
+        }
+        match method {
+            "initialize" => return Ok(initialize()),
+            "shutdown" => {
+                self.shutdown = true;
+                return Ok(Json::Null);
+            }
+            "textDocument/hover" => return Ok(self.hover(params)),
+            "textDocument/definition" => return Ok(self.definition(params)),
+            "textDocument/references" => return Ok(self.references(params)),
+            "textDocument/documentSymbol" => return Ok(self.document_symbols(params)),
+            "textDocument/completion" => return Ok(self.completion(params)),
+            _ => return Err((METHOD_NOT_FOUND, format!("Unknown method {}.", method))),
+        }
+    }
+
+    // Handle a notification, returning the notifications to send back.
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = document_uri(params).to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|document| document.get("text"))
+                    .and_then(Json::as_str)
+                    .unwrap_or("");
+                self.documents.insert(uri.clone(), Document::new(text));
+                return vec![self.diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                // The last change holds the whole text.
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                if let Some(text) = text {
+                    self.documents.insert(uri.clone(), Document::new(text));
+                    return vec![self.diagnostics(&uri)];
+                }
+                return Vec::new();
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![self.diagnostics(&uri)];
+            }
+            _ => return Vec::new(),
+        }
+    }
+
+    // The parse errors of a document, or the lint problems if it parses.
+    fn diagnostics(&self, uri: &String) -> Json {
+        let mut diagnostics = Vec::new();
+        if let Some(document) = self.documents.get(uri) {
+            for (message, span) in document.errors.iter() {
+                diagnostics.push(diagnostic(*span, SEVERITY_ERROR, None, message));
+            }
+            if document.errors.is_empty() {
+                let config = lint_config(uri);
+                for problem in linter::lint_program(&document.program, &config) {
+                    let severity = match problem.severity {
+                        linter::Severity::Error => SEVERITY_ERROR,
+                        _ => SEVERITY_WARNING,
+                    };
+                    diagnostics.push(diagnostic(
+                        problem.span,
+                        severity,
+                        Some(problem.rule),
+                        &problem.message,
+                    ));
+                }
+            }
+        }
+        return notification(
+            "textDocument/publishDiagnostics",
+            Json::object(vec![
+                ("uri", Json::string(uri)),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        );
+    }
+
+    // Analyse the document of a request and find the symbol at its position.
+    fn symbol_at(&self, params: &Json) -> Option<(Analysis, usize)> {
+        let document = self.documents.get(document_uri(params))?;
+        let analysis = analyse(&document.program);
+        let symbol = analysis.symbol_at(params_span(params))?;
+        return Some((analysis, symbol));
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let (analysis, symbol) = match self.symbol_at(params) {
+            Some(found) => found,
+            None => return Json::Null,
+        };
+        let symbol = &analysis.symbols[symbol];
+        let contents = Json::object(vec![
+            ("kind", Json::string("markdown")),
+            (
+                "value",
+                Json::string(&format!("```monkey\n{}\n```", symbol.detail)),
+            ),
+        ]);
+        return Json::object(vec![("contents", contents)]);
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let (analysis, symbol) = match self.symbol_at(params) {
+            Some(found) => found,
+            None => return Json::Null,
+        };
+        let symbol = &analysis.symbols[symbol];
+        // The prelude has no document to go to.
+        if symbol.kind == SymbolKind::Prelude {
+            return Json::Null;
+        }
+        return location(document_uri(params), symbol, symbol.span);
+    }
+
+    fn references(&self, params: &Json) -> Json {
+        let (analysis, symbol) = match self.symbol_at(params) {
+            Some(found) => found,
+            None => return Json::Array(Vec::new()),
+        };
+        let symbol = &analysis.symbols[symbol];
+        let uri = document_uri(params);
+        let include_declaration = params
+            .get("context")
+            .and_then(|context| context.get("includeDeclaration"))
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let mut locations = Vec::new();
+        if include_declaration && symbol.kind != SymbolKind::Prelude {
+            locations.push(location(uri, symbol, symbol.span));
+        }
+        for reference in symbol.references.iter() {
+            locations.push(location(uri, symbol, *reference));
+        }
+        return Json::Array(locations);
+    }
+
+    // The lets and imports of the top level of a document.
+    fn document_symbols(&self, params: &Json) -> Json {
+        let document = match self.documents.get(document_uri(params)) {
+            Some(document) => document,
+            None => return Json::Array(Vec::new()),
+        };
+        let analysis = analyse(&document.program);
+        let mut symbols = Vec::new();
+        for symbol in analysis.symbols.iter().filter(|symbol| symbol.scope == 1) {
+            let kind = match symbol.arity {
+                Some(_) => SYMBOL_FUNCTION,
+                None => SYMBOL_VARIABLE,
+            };
+            symbols.push(Json::object(vec![
+                ("name", Json::string(&symbol.name)),
+                ("detail", Json::string(&symbol.detail)),
+                ("kind", Json::number(kind)),
+                ("range", name_range(symbol, symbol.span)),
+                ("selectionRange", name_range(symbol, symbol.span)),
+            ]));
+        }
+        return Json::Array(symbols);
+    }
+
+    // The names in scope at the position, then the builtins and keywords.
+    fn completion(&self, params: &Json) -> Json {
+        let document = match self.documents.get(document_uri(params)) {
+            Some(document) => document,
+            None => return Json::Array(Vec::new()),
+        };
+        let analysis = analyse(&document.program);
+        let mut items: Vec<(String, i64, String)> = Vec::new();
+        for symbol in analysis.visible_at(params_span(params)) {
+            let kind = match symbol.arity {
+                Some(_) => COMPLETION_FUNCTION,
+                None => COMPLETION_VARIABLE,
+            };
+            items.push((symbol.name.clone(), kind, symbol.detail.clone()));
+        }
+        for name in builtins::names() {
+            items.push((name, COMPLETION_FUNCTION, "builtin".to_string()));
+        }
+        for keyword in KEYWORDS.iter() {
+            items.push((keyword.to_string(), COMPLETION_KEYWORD, String::new()));
+        }
+        let mut completions = Vec::new();
+        for (i, (label, kind, detail)) in items.iter().enumerate() {
+            if items[..i].iter().any(|(other, _, _)| other == label) {
+                continue;
+            }
+            completions.push(Json::object(vec![
+                ("label", Json::string(label)),
+                ("kind", Json::number(*kind)),
+                ("detail", Json::string(detail)),
+            ]));
+        }
+        return Json::Array(completions);
+    }
+}
+
+fn diagnostic(span: Span, severity: i64, rule: Option<&str>, message: &str) -> Json {
+    let mut members = vec![
+        ("range", range(span, 1)),
+        ("severity", Json::number(severity)),
+        ("source", Json::string("rust-monkey")),
+        ("message", Json::string(message)),
+    ];
+    if let Some(rule) = rule {
+        members.push(("code", Json::string(rule)));
+    }
+    return Json::object(members);
+}
+
+fn initialize() -> Json {
+    let capabilities = Json::object(vec![
+        ("textDocumentSync", Json::number(TEXT_DOCUMENT_SYNC_FULL)),
+        ("hoverProvider", Json::Bool(true)),
+        ("definitionProvider", Json::Bool(true)),
+        ("referencesProvider", Json::Bool(true)),
+        ("documentSymbolProvider", Json::Bool(true)),
+        ("completionProvider", Json::object(Vec::new())),
+    ]);
+    let server_info = Json::object(vec![
+        ("name", Json::string("rust-monkey")),
+        ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+    ]);
+    return Json::object(vec![
+        ("capabilities", capabilities),
+        ("serverInfo", server_info),
+    ]);
+}
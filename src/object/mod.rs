@@ -12,8 +12,11 @@ The object classes contain all the data.
 
 use super::ast::Node;
 use super::enviroment::Enviroment;
+use super::lexer::token::Span;
 use downcast_rs::Downcast; // Crate used to downcast objects to their actual type.
 use std::cell::RefCell;
+use std::fmt;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 // ================================================================================
@@ -25,8 +28,85 @@ pub enum ObjectType {
     Integer,
     Boolean,
     Null,
+    Str,
+    Array,
     Error,
+    ErrorValue,
     Function,
+    Builtin,
+    Module,
+    Result,
+    ReturnValue,
+}
+
+// Errors raised by exceeding an execution limit or by an interrupt are
+// distinguished from ordinary runtime errors so that hosts can tell them apart.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ErrorKind {
+    Runtime,
+    Type,
+    Name,
+    DivisionByZero,
+    Syntax,    // Source code that could not be parsed.
+    Thrown,    // A value raised by a throw statement.
+    Assertion, // Raised by assert, assert_eq and assert_error when they fail.
+    StepLimit,
+    DepthLimit,
+    AllocationLimit,
+    Interrupted,
+    Exit, // Raised by exit(code) to end the program.
+}
+
+impl ErrorKind {
+    // Errors that stop the whole program rather than just the current expression.
+    // These cannot be caught by a try expression.
+    pub fn stops_execution(&self) -> bool {
+        return *self == ErrorKind::StepLimit
+            || *self == ErrorKind::DepthLimit
+            || *self == ErrorKind::AllocationLimit
+            || *self == ErrorKind::Interrupted
+            || *self == ErrorKind::Exit;
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ObjectType::Integer => "Integer",
+            ObjectType::Boolean => "Boolean",
+            ObjectType::Null => "Null",
+            ObjectType::Str => "String",
+            ObjectType::Array => "Array",
+            ObjectType::Error => "Error",
+            ObjectType::ErrorValue => "Error",
+            ObjectType::Function => "Function",
+            ObjectType::Builtin => "Builtin",
+            ObjectType::Module => "Module",
+            ObjectType::Result => "Result",
+            ObjectType::ReturnValue => "ReturnValue",
+        };
+        return write!(fmt, "{}", name);
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ErrorKind::Runtime => "RuntimeError",
+            ErrorKind::Type => "TypeError",
+            ErrorKind::Name => "NameError",
+            ErrorKind::DivisionByZero => "DivisionByZeroError",
+            ErrorKind::Syntax => "SyntaxError",
+            ErrorKind::Thrown => "Error",
+            ErrorKind::Assertion => "AssertionError",
+            ErrorKind::StepLimit => "StepLimitError",
+            ErrorKind::DepthLimit => "DepthLimitError",
+            ErrorKind::AllocationLimit => "AllocationLimitError",
+            ErrorKind::Interrupted => "Interrupted",
+            ErrorKind::Exit => "Exit",
+        };
+        return write!(fmt, "{}", name);
+    }
 }
 
 pub trait Object: Downcast {
@@ -50,14 +130,199 @@ pub struct Boolean {
 
 pub struct Null {}
 
+pub struct Str {
+    pub value: String,
+}
+
+pub struct Array {
+    pub elements: Vec<Rc<dyn Object>>,
+}
+
+// An Error is raised and passed up the AST until it is caught or reaches the
+// top level of the program.
 pub struct Error {
+    pub kind: ErrorKind,
     pub message: String,
+    pub trace: Vec<Frame>, // Function calls the error passed through, innermost first.
+    pub value: Option<Rc<dyn Object>>, // The value given to a throw statement.
+}
+
+// A native error that has been caught. Unlike an Error it is an ordinary value
+// which can be stored and passed around, and it is raised again by throw.
+pub struct ErrorValue {
+    pub error: Rc<dyn Object>, // object::Error
+}
+
+// A function call recorded in the stack trace of an error.
+#[derive(Clone)]
+pub struct Frame {
+    pub function: Option<String>, // Name of the let the function was bound to.
+    pub span: Span,               // Location of the call.
+    pub arguments: Vec<Rc<dyn Object>>,
 }
 
 pub struct Function {
+    pub name: Option<String>, // Name of the let the function was bound to.
+    pub span: Span,           // Where the function was defined.
     pub env: Rc<RefCell<Enviroment>>,
-    pub body: Rc<Box<Node>>,
-    pub parameters: Rc<Vec<Box<Node>>>,
+    pub body: Rc<Box<dyn Node>>,
+    pub parameters: Rc<Vec<Box<dyn Node>>>,
+}
+
+// A function implemented in rust. It is given the evaluated arguments and the
+// enviroment it was called from.
+pub type BuiltinFunction = fn(Vec<Rc<dyn Object>>, Rc<RefCell<Enviroment>>) -> Rc<dyn Object>;
+
+pub struct Builtin {
+    pub name: String,
+    pub func: BuiltinFunction,
+}
+
+// A file loaded by an import statement. Only the names it exports can be
+// accessed as members.
+pub struct Module {
+    pub path: PathBuf,
+    pub env: Rc<RefCell<Enviroment>>,
+}
+
+// The value built by ok(v) or err(v).
+pub struct ResultValue {
+    pub is_ok: bool,
+    pub value: Rc<dyn Object>,
+}
+
+// Wraps the value of a return statement, or of ? applied to an err, so that
+// it is passed up through the enclosing blocks to the function call.
+pub struct ReturnValue {
+    pub value: Rc<dyn Object>,
+}
+
+// ================================================================================
+// Equality.
+// ================================================================================
+
+// Whether two values are the same. Integers, booleans, strings and null
+// are compared by value and arrays and results by their contents. Other
+// values, such as functions, are only equal to themselves.
+pub fn equal(left: &Rc<dyn Object>, right: &Rc<dyn Object>) -> bool {
+    if left.get_type() != right.get_type() {
+        return false;
+    }
+    if let (Some(left), Some(right)) = (
+        left.downcast_ref::<Integer>(),
+        right.downcast_ref::<Integer>(),
+    ) {
+        return left.value == right.value;
+    }
+    if let (Some(left), Some(right)) = (
+        left.downcast_ref::<Boolean>(),
+        right.downcast_ref::<Boolean>(),
+    ) {
+        return left.value == right.value;
+    }
+    if let (Some(left), Some(right)) = (left.downcast_ref::<Str>(), right.downcast_ref::<Str>()) {
+        return left.value == right.value;
+    }
+    if left.get_type() == ObjectType::Null {
+        return true;
+    }
+    if let (Some(left), Some(right)) = (left.downcast_ref::<Array>(), right.downcast_ref::<Array>())
+    {
+        return left.elements.len() == right.elements.len()
+            && left
+                .elements
+                .iter()
+                .zip(right.elements.iter())
+                .all(|(left, right)| equal(left, right));
+    }
+    if let (Some(left), Some(right)) = (
+        left.downcast_ref::<ResultValue>(),
+        right.downcast_ref::<ResultValue>(),
+    ) {
+        return left.is_ok == right.is_ok && equal(&left.value, &right.value);
+    }
+    return Rc::ptr_eq(left, right);
+}
+
+// ================================================================================
+// Errors.
+// ================================================================================
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: String) -> Self {
+        Error {
+            kind,
+            message,
+            trace: Vec::new(),
+            value: None,
+        }
+    }
+
+    // Wrap a value given to a throw statement.
+    pub fn thrown(value: Rc<dyn Object>) -> Self {
+        Error {
+            kind: ErrorKind::Thrown,
+            message: value.to_string(),
+            trace: Vec::new(),
+            value: Some(value),
+        }
+    }
+
+    // Return a copy of the error with a call added to the bottom of its stack trace.
+    pub fn with_frame(&self, frame: Frame) -> Self {
+        let mut trace = self.trace.clone();
+        trace.push(frame);
+        Error {
+            kind: self.kind,
+            message: self.message.clone(),
+            trace,
+            value: self.value.clone(),
+        }
+    }
+
+    // Format the stack trace with the most recent call last.
+    // Runs of calls from the same place, as in deep recursion, are collapsed.
+    pub fn traceback(&self) -> String {
+        let mut to_return = "Traceback (most recent call last):\n".to_string();
+        let mut previous: Option<&Frame> = None;
+        let mut repeated = 0;
+        for frame in self.trace.iter().rev() {
+            if previous.is_some_and(|x| x.function == frame.function && x.span == frame.span) {
+                repeated += 1;
+                continue;
+            }
+            if repeated != 0 {
+                to_return.push_str(&format!(
+                    "  [Previous call repeated {} more times]\n",
+                    repeated
+                ));
+                repeated = 0;
+            }
+            to_return.push_str(&format!("  {}\n", frame));
+            previous = Some(frame);
+        }
+        if repeated != 0 {
+            to_return.push_str(&format!(
+                "  [Previous call repeated {} more times]\n",
+                repeated
+            ));
+        }
+        to_return.push_str(&format!("{}: {}", self.kind, self.message));
+        return to_return;
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let arguments: Vec<String> = self.arguments.iter().map(|x| x.to_string()).collect();
+        return write!(
+            fmt,
+            "{}, in {}({})",
+            self.span,
+            self.function.as_ref().map_or("<anonymous>", |x| x.as_str()),
+            arguments.join(", ")
+        );
+    }
 }
 
 // ================================================================================
@@ -90,7 +355,28 @@ impl Object for Null {
     }
 
     fn to_string(&self) -> String {
-        return format!("null");
+        return "null".to_string();
+    }
+}
+
+impl Object for Str {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::Str;
+    }
+
+    fn to_string(&self) -> String {
+        return self.value.clone();
+    }
+}
+
+impl Object for Array {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::Array;
+    }
+
+    fn to_string(&self) -> String {
+        let elements: Vec<String> = self.elements.iter().map(|x| x.to_string()).collect();
+        return format!("[{}]", elements.join(", "));
     }
 }
 
@@ -100,7 +386,36 @@ impl Object for Error {
     }
 
     fn to_string(&self) -> String {
-        return format!("{}", self.message);
+        if !self.trace.is_empty() {
+            return self.traceback();
+        }
+        return self.message.to_string();
+    }
+}
+
+impl ErrorValue {
+    pub fn error(&self) -> &Error {
+        return self.error.downcast_ref::<Error>().unwrap();
+    }
+
+    // Look up one of the fields scripts can read from a caught error.
+    pub fn get_member(&self, name: &str) -> Option<Rc<dyn Object>> {
+        let value = match name {
+            "message" => self.error().message.clone(),
+            "kind" => self.error().kind.to_string(),
+            _ => return None,
+        };
+        return Some(Rc::new(Str { value }));
+    }
+}
+
+impl Object for ErrorValue {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::ErrorValue;
+    }
+
+    fn to_string(&self) -> String {
+        return format!("{}: {}", self.error().kind, self.error().message);
     }
 }
 
@@ -110,16 +425,70 @@ impl Object for Function {
     }
 
     fn to_string(&self) -> String {
-        let mut to_return = format!("Func (");
+        let mut to_return = "Func (".to_string();
         for par in self.parameters.iter().by_ref() {
             to_return.push_str(&par.to_string());
             to_return.push(',')
         }
-        if self.parameters.len() != 0 {
+        if !self.parameters.is_empty() {
             to_return.pop(); // Remove trailing comma
         }
-        to_return.push_str(&")\n".to_string());
+        to_return.push_str(")\n");
         to_return.push_str(&self.body.to_string());
         return to_return;
     }
 }
+
+impl Object for Builtin {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::Builtin;
+    }
+
+    fn to_string(&self) -> String {
+        return format!("Builtin {}", self.name);
+    }
+}
+
+impl Module {
+    // Look up an exported binding of the module.
+    pub fn get_member(&self, name: &String) -> Option<Rc<dyn Object>> {
+        let env = self.env.borrow();
+        if !env.exports.contains(name) {
+            return None;
+        }
+        return env.variables.get(name).cloned();
+    }
+}
+
+impl Object for Module {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::Module;
+    }
+
+    fn to_string(&self) -> String {
+        return format!("Module {}", self.path.display());
+    }
+}
+
+impl Object for ResultValue {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::Result;
+    }
+
+    fn to_string(&self) -> String {
+        if self.is_ok {
+            return format!("ok({})", self.value.to_string());
+        }
+        return format!("err({})", self.value.to_string());
+    }
+}
+
+impl Object for ReturnValue {
+    fn get_type(&self) -> ObjectType {
+        return ObjectType::ReturnValue;
+    }
+
+    fn to_string(&self) -> String {
+        return self.value.to_string();
+    }
+}
@@ -0,0 +1,107 @@
+/*
+Author: Jedda Boyle
+Contains: Output
+Everything the interpreter writes goes through an Output so that hosts can
+capture it. Program output, such as the values printed by puts, is kept
+separate from diagnostics, such as parse errors and uncaught errors, which
+go to stderr unless the output captures them.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use std::cell::RefCell;
+use std::io;
+use std::io::prelude::Write;
+use std::rc::Rc;
+
+// ================================================================================
+// Output trait.
+// ================================================================================
+
+pub trait Output {
+    // Write program output.
+    fn write(&mut self, text: &str);
+
+    // Write a diagnostic.
+    fn write_error(&mut self, text: &str) {
+        let mut stderr = io::stderr();
+        let _ = stderr.write_all(text.as_bytes());
+        let _ = stderr.flush();
+    }
+}
+
+// ================================================================================
+// Outputs.
+// ================================================================================
+
+// Writes to the stdout of the process. Write errors, such as a closed pipe,
+// are ignored.
+pub struct Stdout {}
+
+impl Output for Stdout {
+    fn write(&mut self, text: &str) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+// Collects the output and diagnostics in memory. Clones share the same
+// buffers so the host can keep one to read what was written.
+#[derive(Clone, Default)]
+pub struct Buffer {
+    output: Rc<RefCell<Vec<u8>>>,
+    errors: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Buffer {
+    pub fn new() -> Self {
+        return Buffer::default();
+    }
+
+    // Return the program output written so far.
+    pub fn contents(&self) -> Vec<u8> {
+        return self.output.borrow().clone();
+    }
+
+    // Return the diagnostics written so far.
+    pub fn errors(&self) -> Vec<u8> {
+        return self.errors.borrow().clone();
+    }
+
+    pub fn clear(&self) {
+        self.output.borrow_mut().clear();
+        self.errors.borrow_mut().clear();
+    }
+}
+
+impl Output for Buffer {
+    fn write(&mut self, text: &str) {
+        self.output.borrow_mut().extend_from_slice(text.as_bytes());
+    }
+
+    fn write_error(&mut self, text: &str) {
+        self.errors.borrow_mut().extend_from_slice(text.as_bytes());
+    }
+}
+
+// Passes the program output to a function. Diagnostics go to stderr.
+pub struct Callback {
+    func: Box<dyn FnMut(&str)>,
+}
+
+impl Callback {
+    pub fn new(func: impl FnMut(&str) + 'static) -> Self {
+        return Callback {
+            func: Box::new(func),
+        };
+    }
+}
+
+impl Output for Callback {
+    fn write(&mut self, text: &str) {
+        (self.func)(text);
+    }
+}
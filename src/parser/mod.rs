@@ -12,24 +12,35 @@ can be evaluated.
 // ================================================================================
 
 use super::lexer::token::Precedence;
+use super::lexer::token::Span;
 use super::lexer::token::Token;
 use super::lexer::token::TokenType;
 use super::lexer::Lexer;
+use super::output::Output;
 
 use super::ast::BlockStatement;
+use super::ast::ExportStatement;
 use super::ast::ExpressionStatement;
+use super::ast::ImportStatement;
 use super::ast::LetStatement;
 use super::ast::Node;
 use super::ast::ReturnStatement;
+use super::ast::ThrowStatement;
 
+use super::ast::ArrayExpression;
 use super::ast::BoolExpression;
 use super::ast::CallExpression;
 use super::ast::FunctionExpression;
 use super::ast::IdentifierExpression;
 use super::ast::IfElseExpression;
+use super::ast::IndexExpression;
 use super::ast::InfixExpression;
 use super::ast::IntegralExpression;
+use super::ast::MemberExpression;
+use super::ast::PostfixExpression;
 use super::ast::PrefixExpression;
+use super::ast::StringExpression;
+use super::ast::TryExpression;
 
 use std::rc::Rc;
 
@@ -38,7 +49,7 @@ use std::rc::Rc;
 // ================================================================================
 
 pub struct Program {
-    pub statements: Vec<Box<Node>>,
+    pub statements: Vec<Box<dyn Node>>,
 }
 
 pub struct Parser {
@@ -46,6 +57,8 @@ pub struct Parser {
     pub token: Token,
     pub next_token: Token,
     errors: Vec<String>, // Keep a vector of strings which record all the erros incounted in the parsing.
+    error_spans: Vec<Span>, // Where each error was found.
+    block_depth: usize,  // Number of block statements being parsed, 0 at the top level.
 }
 
 // ================================================================================
@@ -54,37 +67,33 @@ pub struct Parser {
 
 impl Parser {
     pub fn new(mut lexer: Lexer) -> Self {
+        // The lexer always produces a token, Eof once the input is exhausted.
         let token = lexer.next_token();
         let next_token = lexer.next_token();
-        if token.is_none() || next_token.is_none() {
-            println!("Error, parser given empty lexer.")
-        }
         Parser {
-            lexer: lexer,
+            lexer,
             token: token.unwrap(),
             next_token: next_token.unwrap(),
             errors: Vec::new(),
+            error_spans: Vec::new(),
+            block_depth: 0,
         }
     }
 
     // Parse tokens given by the lexer to return a program which is a vector of statements.
-    pub fn parse_program(&mut self, debug: bool) -> Option<Program> {
+    pub fn parse_program(&mut self) -> Option<Program> {
         let mut program = Program {
             statements: Vec::new(),
         };
-        let mut statement: Option<Box<Node>>;
+        let mut statement: Option<Box<dyn Node>>;
 
         while !self.token_is(TokenType::Eof) {
             statement = self.parse_statement();
-            if !statement.is_none() {
-                if debug {
-                    println!("{}", statement.as_ref().unwrap().to_string());
-                }
-                program.statements.push(statement.unwrap());
+            if let Some(statement) = statement {
+                program.statements.push(statement);
             }
             self.advance_tokens();
         }
-        self.print_parse_errors();
         return Some(program);
     }
 
@@ -93,7 +102,7 @@ impl Parser {
     // ================================================================================
 
     fn token_type(&self) -> TokenType {
-        return self.token.token_type.clone();
+        return self.token.token_type;
     }
 
     fn token_is(&mut self, token_type: TokenType) -> bool {
@@ -135,27 +144,41 @@ impl Parser {
 
     fn log_parse_error(&mut self, error_message: String) {
         self.errors.push(error_message);
+        self.error_spans.push(self.token.span);
     }
 
     fn log_next_token_error(&mut self, expected_token_type: TokenType) {
         let expected_token = Token {
             token_type: expected_token_type,
             literal: "".to_string(),
+            span: Span::default(),
         };
         self.errors.push(format!(
             "Expected next token to be {} but got {} instead",
             expected_token,
             self.next_token.clone()
         ));
+        self.error_spans.push(self.next_token.span);
+    }
+
+    // Return the errors encountered while parsing.
+    pub fn errors(&self) -> &Vec<String> {
+        return &self.errors;
+    }
+
+    // Return where each of the errors was found.
+    pub fn error_spans(&self) -> &Vec<Span> {
+        return &self.error_spans;
     }
 
-    pub fn print_parse_errors(&mut self) {
-        if self.errors.len() == 0 {
+    // Write the errors encountered while parsing as diagnostics.
+    pub fn print_parse_errors(&self, output: &mut dyn Output) {
+        if self.errors.is_empty() {
             return;
         }
-        println!("Woops! We ran into some monkey business here!");
-        for err in self.errors.iter().as_ref() {
-            println!("\t{}", err);
+        output.write_error("Woops! We ran into some monkey business here!\n");
+        for err in self.errors.iter() {
+            output.write_error(&format!("\t{}\n", err));
         }
     }
 
@@ -163,15 +186,20 @@ impl Parser {
     // Functions for parsing statements.
     // ================================================================================
 
-    fn parse_statement(&mut self) -> Option<Box<Node>> {
+    fn parse_statement(&mut self) -> Option<Box<dyn Node>> {
         match self.token_type() {
             TokenType::Let => self.parse_let_statement(),
             TokenType::Return => self.parse_return_statement(),
+            TokenType::Throw => self.parse_throw_statement(),
+            TokenType::Import => self.parse_import_statement(),
+            TokenType::Export => self.parse_export_statement(),
             _ => self.parse_expression_statement(),
         }
     }
 
-    fn parse_let_statement(&mut self) -> Option<Box<Node>> {
+    fn parse_let_statement(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
         // Parse identifier.
         if !self.advance_tokens_if_next_token_is(TokenType::Ident) {
             self.log_parse_error("Let statement is missing an identifier.".to_string());
@@ -202,14 +230,16 @@ impl Parser {
         }
 
         let to_return = LetStatement {
-            token: Token::new("let".to_string()),
+            token,
             value: value.unwrap(),
-            identifier: identifier,
+            identifier,
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_return_statement(&mut self) -> Option<Box<Node>> {
+    fn parse_return_statement(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
         // Parse return expression.
         self.advance_tokens();
         let value = self.parse_expression(Precedence::Lowest);
@@ -225,13 +255,95 @@ impl Parser {
         }
 
         let to_return = ReturnStatement {
-            token: Token::new("return".to_string()),
+            token,
+            value: value.unwrap(),
+        };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_throw_statement(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
+        // Parse thrown expression.
+        self.advance_tokens();
+        let value = self.parse_expression(Precedence::Lowest);
+        if value.is_none() {
+            self.log_parse_error("Throw statement missing a value expression.".to_string());
+            return None;
+        }
+
+        // Check that line ends in a semicolon.
+        if !self.advance_tokens_if_next_token_is(TokenType::SemiColon) {
+            self.log_parse_error("Line does not end in a semicolon.".to_string());
+            return None;
+        }
+
+        let to_return = ThrowStatement {
+            token,
             value: value.unwrap(),
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_expression_statement(&mut self) -> Option<Box<Node>> {
+    fn parse_import_statement(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
+        // Parse the path of the module.
+        if !self.advance_tokens_if_next_token_is(TokenType::Str) {
+            self.log_next_token_error(TokenType::Str);
+            return None;
+        }
+        let path = self.token.literal[1..self.token.literal.len() - 1].to_string();
+
+        // Parse the name the module is bound to.
+        if !self.advance_tokens_if_next_token_is(TokenType::As) {
+            self.log_next_token_error(TokenType::As);
+            return None;
+        }
+        if !self.advance_tokens_if_next_token_is(TokenType::Ident) {
+            self.log_next_token_error(TokenType::Ident);
+            return None;
+        }
+        let alias = IdentifierExpression {
+            token: self.token.clone(),
+        };
+
+        // Check that line ends in a semicolon.
+        if !self.advance_tokens_if_next_token_is(TokenType::SemiColon) {
+            self.log_parse_error("Line does not end in a semicolon.".to_string());
+            return None;
+        }
+
+        let to_return = ImportStatement { token, path, alias };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_export_statement(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+        if self.block_depth != 0 {
+            self.log_parse_error("Export is only allowed at the top level.".to_string());
+            return None;
+        }
+
+        // Only let statements can be exported.
+        if !self.advance_tokens_if_next_token_is(TokenType::Let) {
+            self.log_next_token_error(TokenType::Let);
+            return None;
+        }
+        let statement = self.parse_let_statement()?;
+
+        let to_return = ExportStatement {
+            token,
+            statement: statement.downcast::<LetStatement>().ok().unwrap(),
+        };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_expression_statement(&mut self) -> Option<Box<dyn Node>> {
+        // The statement starts where its expression does.
+        let mut token = Token::new("(".to_string());
+        token.span = self.token.span;
+
         // Parse expression.
         let value = self.parse_expression(Precedence::Lowest);
         if value.is_none() {
@@ -246,29 +358,38 @@ impl Parser {
         }
 
         let to_return = ExpressionStatement {
-            token: Token::new("(".to_string()),
+            token,
             value: value.unwrap(),
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_block_statement(&mut self) -> Option<Box<Node>> {
+    fn parse_block_statement(&mut self) -> Option<Box<dyn Node>> {
         let mut to_return = BlockStatement {
-            token: Token::new("(".to_string()),
+            token: self.token.clone(),
             statements: Vec::new(),
+            end: Span::default(),
         };
 
         // Move over opening LBrace.
         self.advance_tokens();
 
         // Parse each statement in the block.
+        self.block_depth += 1;
         while !self.token_is(TokenType::RBrace) {
+            if self.token_is(TokenType::Eof) {
+                self.log_parse_error("Block statement missing a closing brace.".to_string());
+                self.block_depth -= 1;
+                return None;
+            }
             let statement = self.parse_statement();
-            if !statement.is_none() {
-                to_return.statements.push(statement.unwrap());
+            if let Some(statement) = statement {
+                to_return.statements.push(statement);
             }
             self.advance_tokens();
         }
+        self.block_depth -= 1;
+        to_return.end = self.token.span;
         return Some(Box::new(to_return));
     }
 
@@ -276,17 +397,20 @@ impl Parser {
     // Functions for parsing expressions.
     // ================================================================================
 
-    fn parse_expression(&mut self, precedence: Precedence) -> Option<Box<Node>> {
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<Box<dyn Node>> {
         let mut left_expression = match self.token_type() {
             TokenType::Ident => self.parse_identifier_expression(),
             TokenType::Int => self.parse_integral_expression(),
+            TokenType::Str => self.parse_string_expression(),
             TokenType::Bang => self.parse_prefix_expression(),
             TokenType::Minus => self.parse_prefix_expression(),
             TokenType::True => self.parse_bool_expression(),
             TokenType::False => self.parse_bool_expression(),
             TokenType::LParen => self.parse_grouped_expression(),
+            TokenType::LBracket => self.parse_array_expression(),
             TokenType::If => self.parse_ifelse_expression(),
             TokenType::Function => self.parse_func_expression(),
+            TokenType::Try => self.parse_try_expression(),
             _ => None,
         };
 
@@ -303,6 +427,15 @@ impl Parser {
             if self.next_token_is(TokenType::LParen) {
                 self.advance_tokens();
                 left_expression = self.parse_call_expression(left_expression.unwrap());
+            } else if self.next_token_is(TokenType::LBracket) {
+                self.advance_tokens();
+                left_expression = self.parse_index_expression(left_expression.unwrap());
+            } else if self.next_token_is(TokenType::Question) {
+                self.advance_tokens();
+                left_expression = self.parse_postfix_expression(left_expression.unwrap());
+            } else if self.next_token_is(TokenType::Dot) {
+                self.advance_tokens();
+                left_expression = self.parse_member_expression(left_expression.unwrap());
             } else if self.next_token.is_operator() {
                 self.advance_tokens();
                 left_expression = self.parse_infix_expression(left_expression.unwrap());
@@ -314,14 +447,14 @@ impl Parser {
         return left_expression;
     }
 
-    fn parse_identifier_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_identifier_expression(&mut self) -> Option<Box<dyn Node>> {
         let to_return = IdentifierExpression {
             token: self.token.clone(),
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_bool_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_bool_expression(&mut self) -> Option<Box<dyn Node>> {
         let to_return = BoolExpression {
             token: self.token.clone(),
             value: self.token_is(TokenType::True),
@@ -329,7 +462,7 @@ impl Parser {
         return Some(Box::new(to_return));
     }
 
-    fn parse_integral_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_integral_expression(&mut self) -> Option<Box<dyn Node>> {
         // Covert literal into integral.
         let value_result = self.token.literal.parse::<i64>();
         if value_result.is_err() {
@@ -344,7 +477,36 @@ impl Parser {
         return Some(Box::new(to_return));
     }
 
-    fn parse_prefix_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_string_expression(&mut self) -> Option<Box<dyn Node>> {
+        // Remove the quotes and convert escaped characters.
+        let literal = &self.token.literal[1..self.token.literal.len() - 1];
+        let mut value = String::new();
+        let mut chars = literal.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('"') => value.push('"'),
+                Some('\\') => value.push('\\'),
+                _ => {
+                    self.log_parse_error("Invalid escape sequence in string.".to_string());
+                    return None;
+                }
+            }
+        }
+
+        let to_return = StringExpression {
+            token: self.token.clone(),
+            value,
+        };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_prefix_expression(&mut self) -> Option<Box<dyn Node>> {
         // Record prefix operator.
         let token = self.token.clone();
 
@@ -357,13 +519,13 @@ impl Parser {
         }
 
         let to_return = PrefixExpression {
-            token: token,
+            token,
             right: right.unwrap(),
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_infix_expression(&mut self, left: Box<Node>) -> Option<Box<Node>> {
+    fn parse_infix_expression(&mut self, left: Box<dyn Node>) -> Option<Box<dyn Node>> {
         // Record infix operator and its precedence.
         let token = self.token.clone();
         let precedence = self.token_precedence();
@@ -377,14 +539,14 @@ impl Parser {
         }
 
         let to_return = InfixExpression {
-            token: token,
+            token,
             right: right.unwrap(),
-            left: left,
+            left,
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_grouped_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_grouped_expression(&mut self) -> Option<Box<dyn Node>> {
         // Mover over opening bracket.
         self.advance_tokens();
 
@@ -396,7 +558,43 @@ impl Parser {
         return to_return;
     }
 
-    fn parse_ifelse_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_array_expression(&mut self) -> Option<Box<dyn Node>> {
+        let mut to_return = ArrayExpression {
+            token: self.token.clone(),
+            elements: Vec::new(),
+        };
+        // Move over opening bracket.
+        self.advance_tokens();
+
+        // Check if the array has any elements.
+        if self.token_is(TokenType::RBracket) {
+            return Some(Box::new(to_return));
+        }
+
+        // Parse the elements.
+        loop {
+            let element = self.parse_expression(Precedence::Lowest);
+            if element.is_none() {
+                self.log_parse_error("Error in parsing array element.".to_string());
+                return None;
+            }
+            to_return.elements.push(element.unwrap());
+            if !self.advance_tokens_if_next_token_is(TokenType::Comma) {
+                break;
+            }
+            self.advance_tokens();
+        }
+
+        if !self.advance_tokens_if_next_token_is(TokenType::RBracket) {
+            self.log_next_token_error(TokenType::RBracket);
+            return None;
+        }
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_ifelse_expression(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
         // Move forward until the condition.
         if !self.advance_tokens_if_next_token_is(TokenType::LParen) {
             self.log_next_token_error(TokenType::LParen);
@@ -429,7 +627,7 @@ impl Parser {
         }
 
         // Parse 'else' block statement.
-        let mut alternative: Option<Box<Node>> = None;
+        let mut alternative: Option<Box<dyn Node>> = None;
         if self.next_token_is(TokenType::Else) {
             self.advance_tokens();
             if !self.advance_tokens_if_next_token_is(TokenType::LBrace) {
@@ -440,15 +638,17 @@ impl Parser {
         }
 
         let to_return = IfElseExpression {
-            token: Token::new("if".to_string()),
+            token,
             condition: condition.unwrap(),
             consequence: consequence.unwrap(),
-            alternative: alternative,
+            alternative,
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_func_expression(&mut self) -> Option<Box<Node>> {
+    fn parse_func_expression(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
         // Move over opening brace.
         if !self.advance_tokens_if_next_token_is(TokenType::LParen) {
             self.log_next_token_error(TokenType::LParen);
@@ -457,7 +657,7 @@ impl Parser {
         self.advance_tokens();
 
         // Parse parameters.
-        let mut parameters: Vec<Box<Node>> = Vec::new();
+        let mut parameters: Vec<Box<dyn Node>> = Vec::new();
         while !self.token_is(TokenType::RParen) {
             parameters.push(self.parse_identifier_expression().unwrap());
             if self.next_token_is(TokenType::RParen) {
@@ -482,14 +682,14 @@ impl Parser {
         }
 
         let to_return = FunctionExpression {
-            token: Token::new("fn".to_string()),
+            token,
             body: Rc::new(body.unwrap()),
             parameters: Rc::new(parameters),
         };
         return Some(Box::new(to_return));
     }
 
-    fn parse_call_expression(&mut self, func: Box<Node>) -> Option<Box<Node>> {
+    fn parse_call_expression(&mut self, func: Box<dyn Node>) -> Option<Box<dyn Node>> {
         // Parse function name.
         let mut to_return = CallExpression {
             token: self.token.clone(),
@@ -532,4 +732,118 @@ impl Parser {
 
         return Some(Box::new(to_return));
     }
+
+    fn parse_index_expression(&mut self, left: Box<dyn Node>) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
+        // Parse the index between the brackets.
+        self.advance_tokens();
+        let index = self.parse_expression(Precedence::Lowest);
+        if index.is_none() {
+            self.log_parse_error("Index expression missing an index.".to_string());
+            return None;
+        }
+        if !self.advance_tokens_if_next_token_is(TokenType::RBracket) {
+            self.log_next_token_error(TokenType::RBracket);
+            return None;
+        }
+
+        let to_return = IndexExpression {
+            token,
+            left,
+            index: index.unwrap(),
+        };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_postfix_expression(&mut self, left: Box<dyn Node>) -> Option<Box<dyn Node>> {
+        let to_return = PostfixExpression {
+            token: self.token.clone(),
+            left,
+        };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_member_expression(&mut self, object: Box<dyn Node>) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
+        // Parse member name.
+        if !self.advance_tokens_if_next_token_is(TokenType::Ident) {
+            self.log_next_token_error(TokenType::Ident);
+            return None;
+        }
+        let to_return = MemberExpression {
+            token,
+            object,
+            member: IdentifierExpression {
+                token: self.token.clone(),
+            },
+        };
+        return Some(Box::new(to_return));
+    }
+
+    fn parse_try_expression(&mut self) -> Option<Box<dyn Node>> {
+        let token = self.token.clone();
+
+        // Parse the block that may fail.
+        if !self.advance_tokens_if_next_token_is(TokenType::LBrace) {
+            self.log_next_token_error(TokenType::LBrace);
+            return None;
+        }
+        let body = self.parse_block_statement();
+        if body.is_none() {
+            self.log_parse_error("Try expression missing a block statement.".to_string());
+            return None;
+        }
+
+        // Parse 'catch (identifier)' block statement.
+        let mut catch_identifier: Option<IdentifierExpression> = None;
+        let mut catch_body: Option<Box<dyn Node>> = None;
+        if self.advance_tokens_if_next_token_is(TokenType::Catch) {
+            if !self.advance_tokens_if_next_token_is(TokenType::LParen) {
+                self.log_next_token_error(TokenType::LParen);
+                return None;
+            }
+            if !self.advance_tokens_if_next_token_is(TokenType::Ident) {
+                self.log_next_token_error(TokenType::Ident);
+                return None;
+            }
+            catch_identifier = Some(IdentifierExpression {
+                token: self.token.clone(),
+            });
+            if !self.advance_tokens_if_next_token_is(TokenType::RParen) {
+                self.log_next_token_error(TokenType::RParen);
+                return None;
+            }
+            if !self.advance_tokens_if_next_token_is(TokenType::LBrace) {
+                self.log_next_token_error(TokenType::LBrace);
+                return None;
+            }
+            catch_body = self.parse_block_statement();
+        }
+
+        // Parse 'finally' block statement.
+        let mut finally_body: Option<Box<dyn Node>> = None;
+        if self.advance_tokens_if_next_token_is(TokenType::Finally) {
+            if !self.advance_tokens_if_next_token_is(TokenType::LBrace) {
+                self.log_next_token_error(TokenType::LBrace);
+                return None;
+            }
+            finally_body = self.parse_block_statement();
+        }
+
+        if catch_body.is_none() && finally_body.is_none() {
+            self.log_parse_error("Try expression missing a catch or finally block.".to_string());
+            return None;
+        }
+
+        let to_return = TryExpression {
+            token,
+            body: body.unwrap(),
+            catch_identifier,
+            catch_body,
+            finally_body,
+        };
+        return Some(Box::new(to_return));
+    }
 }
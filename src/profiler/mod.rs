@@ -0,0 +1,216 @@
+/*
+Author: Jedda Boyle
+Contains: Profiler
+The profiler is a hook which times every call of a function written in
+rust-monkey. A function is identified by the name of the let it was bound to
+and the span it was defined at, so two functions with the same name are kept
+apart. For each function it counts the calls and the time spent in them,
+both including and excluding the functions they call.
+The report is a table sorted by exclusive time, or the folded stacks read
+by flamegraph tools, with one line per stack of calls and its exclusive
+time in microseconds.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::enviroment::Enviroment;
+use super::hook::Hook;
+use super::lexer::token::Span;
+use super::object;
+use super::object::Object;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+// ================================================================================
+// Types.
+// ================================================================================
+
+// The totals of one function.
+pub struct Entry {
+    pub name: String, // Name of the let the function was bound to.
+    pub span: Span,   // Where the function was defined.
+    pub calls: u64,
+    pub inclusive: Duration, // Time in the function and the functions it called.
+    pub exclusive: Duration, // Time in the function itself.
+    active: usize,           // Calls of the function that have not returned.
+}
+
+impl Entry {
+    // The name and definition of the function, such as fib (3:11).
+    pub fn label(&self) -> String {
+        if self.span.line == 0 {
+            return self.name.clone();
+        }
+        return format!("{} ({}:{})", self.name, self.span.line, self.span.column);
+    }
+}
+
+// A call that has not returned.
+struct Activation {
+    entry: usize,
+    start: Instant,
+    children: Duration, // Time in the calls made from this one.
+}
+
+// ================================================================================
+// Profiler.
+// ================================================================================
+
+pub struct Profiler {
+    pub entries: Vec<Entry>, // The first entry is the top level of the program.
+    index: HashMap<(String, Span), usize>,
+    stack: Vec<Activation>,
+    stacks: HashMap<Vec<usize>, Duration>, // Exclusive time of each stack of entries.
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    // Start profiling. The top level of the program is timed from now until
+    // finish is called.
+    pub fn new() -> Self {
+        let mut profiler = Profiler {
+            entries: Vec::new(),
+            index: HashMap::new(),
+            stack: Vec::new(),
+            stacks: HashMap::new(),
+        };
+        profiler.enter("<program>".to_string(), Span::default());
+        return profiler;
+    }
+
+    fn enter(&mut self, name: String, span: Span) {
+        let key = (name, span);
+        let entry = match self.index.get(&key) {
+            Some(entry) => *entry,
+            None => {
+                self.entries.push(Entry {
+                    name: key.0.clone(),
+                    span,
+                    calls: 0,
+                    inclusive: Duration::ZERO,
+                    exclusive: Duration::ZERO,
+                    active: 0,
+                });
+                self.index.insert(key, self.entries.len() - 1);
+                self.entries.len() - 1
+            }
+        };
+        self.entries[entry].calls += 1;
+        self.entries[entry].active += 1;
+        self.stack.push(Activation {
+            entry,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn exit(&mut self) {
+        let path: Vec<usize> = self.stack.iter().map(|call| call.entry).collect();
+        let call = self.stack.pop().unwrap();
+        let elapsed = call.start.elapsed();
+        let exclusive = elapsed.saturating_sub(call.children);
+        let entry = &mut self.entries[call.entry];
+        entry.exclusive += exclusive;
+        entry.active -= 1;
+        // A recursive call is already counted in the inclusive time of the
+        // outermost call of the function.
+        if entry.active == 0 {
+            entry.inclusive += elapsed;
+        }
+        *self.stacks.entry(path).or_insert(Duration::ZERO) += exclusive;
+        if let Some(caller) = self.stack.last_mut() {
+            caller.children += elapsed;
+        }
+    }
+
+    // Stop timing the program. Calls that are still running, because the
+    // program was interrupted, are stopped too.
+    pub fn finish(&mut self) {
+        while !self.stack.is_empty() {
+            self.exit();
+        }
+    }
+
+    // The entries sorted by exclusive time, longest first.
+    pub fn sorted(&self) -> Vec<&Entry> {
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            b.exclusive
+                .cmp(&a.exclusive)
+                .then(b.inclusive.cmp(&a.inclusive))
+                .then(a.label().cmp(&b.label()))
+        });
+        return entries;
+    }
+
+    // A table of the entries sorted by exclusive time.
+    pub fn table(&self) -> String {
+        let mut to_return = format!(
+            "{:>10} {:>14} {:>14}  {}\n",
+            "calls", "inclusive ms", "exclusive ms", "function"
+        );
+        for entry in self.sorted() {
+            to_return.push_str(&format!(
+                "{:>10} {:>14.3} {:>14.3}  {}\n",
+                entry.calls,
+                entry.inclusive.as_secs_f64() * 1000.0,
+                entry.exclusive.as_secs_f64() * 1000.0,
+                entry.label()
+            ));
+        }
+        return to_return;
+    }
+
+    // The folded stacks, one line per stack of calls such as
+    // <program>;main (1:12);fib (3:11) 1520, sorted by stack.
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .filter(|(_, time)| time.as_micros() != 0)
+            .map(|(path, time)| {
+                let labels: Vec<String> = path
+                    .iter()
+                    .map(|entry| self.entries[*entry].label().replace(';', ","))
+                    .collect();
+                format!("{} {}", labels.join(";"), time.as_micros())
+            })
+            .collect();
+        lines.sort();
+        let mut to_return = lines.join("\n");
+        if !to_return.is_empty() {
+            to_return.push('\n');
+        }
+        return to_return;
+    }
+}
+
+impl Hook for Profiler {
+    fn enter_call(
+        &mut self,
+        function: &object::Function,
+        _span: Span,
+        _env: &Rc<RefCell<Enviroment>>,
+    ) {
+        let name = function
+            .name
+            .clone()
+            .unwrap_or_else(|| "<anonymous>".to_string());
+        self.enter(name, function.span);
+    }
+
+    fn exit_call(&mut self, _function: &object::Function, _result: &Rc<dyn Object>) {
+        self.exit();
+    }
+}
@@ -0,0 +1,514 @@
+/*
+Author: Jedda Boyle
+Contains: Line editor.
+Reads lines from an interactive terminal with cursor movement, persistent
+history, reverse search and tab completion. The terminal is put into raw
+mode while a line is being read and restored before returning so that
+Ctrl-C can interrupt evaluation as usual. Both modes are read from the
+terminal once when the editor is created.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use std::fs;
+use std::io;
+use std::io::prelude::Read;
+use std::io::prelude::Write;
+use std::path::PathBuf;
+
+// The most entries kept in the history file.
+pub const HISTORY_SIZE: usize = 1000;
+
+const CTRL_A: u8 = 1;
+const CTRL_B: u8 = 2;
+const CTRL_C: u8 = 3;
+const CTRL_D: u8 = 4;
+const CTRL_E: u8 = 5;
+const CTRL_F: u8 = 6;
+const CTRL_G: u8 = 7;
+const CTRL_H: u8 = 8;
+const TAB: u8 = 9;
+const LINE_FEED: u8 = 10;
+const CTRL_K: u8 = 11;
+const CTRL_L: u8 = 12;
+const ENTER: u8 = 13;
+const CTRL_N: u8 = 14;
+const CTRL_P: u8 = 16;
+const CTRL_R: u8 = 18;
+const CTRL_U: u8 = 21;
+const CTRL_W: u8 = 23;
+const ESCAPE: u8 = 27;
+const BACKSPACE: u8 = 127;
+
+// ================================================================================
+// Terminal.
+// ================================================================================
+
+// The settings of the terminal attached to stdin as it was found and in raw
+// mode, where keys are read one at a time without being echoed.
+#[cfg(unix)]
+struct Terminal {
+    cooked: libc::termios,
+    raw: libc::termios,
+}
+
+#[cfg(unix)]
+impl Terminal {
+    // Returns None if stdin is not a terminal.
+    fn new() -> Option<Terminal> {
+        let mut cooked: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut cooked) } != 0 {
+            return None;
+        }
+        let mut raw = cooked;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        return Some(Terminal { cooked, raw });
+    }
+
+    fn set(settings: &libc::termios) -> bool {
+        return unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, settings) } == 0;
+    }
+
+    fn enable_raw_mode(&self) -> Option<RawMode> {
+        if !Terminal::set(&self.raw) {
+            return None;
+        }
+        return Some(RawMode {
+            cooked: self.cooked,
+        });
+    }
+}
+
+// Raw mode is only supported on unix.
+#[cfg(not(unix))]
+struct Terminal {}
+
+#[cfg(not(unix))]
+impl Terminal {
+    fn new() -> Option<Terminal> {
+        return None;
+    }
+
+    fn enable_raw_mode(&self) -> Option<RawMode> {
+        return None;
+    }
+}
+
+// Keeps the terminal in raw mode until dropped.
+#[cfg(unix)]
+struct RawMode {
+    cooked: libc::termios,
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        Terminal::set(&self.cooked);
+    }
+}
+
+#[cfg(not(unix))]
+struct RawMode {}
+
+fn read_byte() -> Option<u8> {
+    let mut byte = [0u8; 1];
+    match io::stdin().read(&mut byte) {
+        Ok(1) => Some(byte[0]),
+        _ => None,
+    }
+}
+
+// Read the rest of a UTF-8 character that starts with first.
+fn read_char(first: u8) -> Option<char> {
+    let length = match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return None,
+    };
+    let mut bytes = vec![first];
+    for _ in 1..length {
+        bytes.push(read_byte()?);
+    }
+    return String::from_utf8(bytes).ok()?.chars().next();
+}
+
+// Write errors, such as a closed terminal, are ignored as they are by
+// output::Stdout.
+fn write(output: &str) {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(output.as_bytes());
+    let _ = stdout.flush();
+}
+
+// ================================================================================
+// Keys.
+// ================================================================================
+
+enum Key {
+    Char(char),
+    Control(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Delete,
+    Unknown,
+}
+
+fn read_key() -> Option<Key> {
+    let byte = read_byte()?;
+    if byte == ESCAPE {
+        return Some(read_escape_sequence());
+    }
+    if byte < 32 || byte == BACKSPACE {
+        return Some(Key::Control(byte));
+    }
+    return Some(match read_char(byte) {
+        Some(c) => Key::Char(c),
+        None => Key::Unknown,
+    });
+}
+
+// Decode the ANSI escape sequences sent by the arrow, home, end and delete keys.
+fn read_escape_sequence() -> Key {
+    let introducer = read_byte();
+    if introducer != Some(b'[') && introducer != Some(b'O') {
+        return Key::Unknown;
+    }
+    let mut parameter = String::new();
+    loop {
+        let byte = match read_byte() {
+            Some(byte) => byte,
+            None => return Key::Unknown,
+        };
+        match byte {
+            b'0'..=b'9' | b';' => parameter.push(byte as char),
+            b'A' => return Key::Up,
+            b'B' => return Key::Down,
+            b'C' => return Key::Right,
+            b'D' => return Key::Left,
+            b'H' => return Key::Home,
+            b'F' => return Key::End,
+            b'~' => {
+                return match parameter.as_str() {
+                    "1" | "7" => Key::Home,
+                    "4" | "8" => Key::End,
+                    "3" => Key::Delete,
+                    _ => Key::Unknown,
+                }
+            }
+            _ => return Key::Unknown,
+        }
+    }
+}
+
+// ================================================================================
+// Editor.
+// ================================================================================
+
+pub enum Input {
+    Line(String),
+    // Ctrl-C was pressed.
+    Interrupted,
+    // Ctrl-D was pressed on an empty line or stdin was closed.
+    Eof,
+}
+
+enum Search {
+    // Run the line that was found.
+    Accept,
+    // Keep editing the line that was found.
+    Edit,
+    // Go back to the line as it was before the search.
+    Cancel,
+}
+
+pub struct Editor {
+    terminal: Option<Terminal>,
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+    line: Vec<char>,
+    cursor: usize,
+}
+
+impl Editor {
+    // history_path is read for the history of earlier sessions and new
+    // entries are appended to it.
+    pub fn new(history_path: Option<PathBuf>) -> Editor {
+        let mut history: Vec<String> = Vec::new();
+        if let Some(history_path) = &history_path {
+            let contents = fs::read_to_string(history_path).unwrap_or_default();
+            history = contents
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.to_string())
+                .collect();
+        }
+        if history.len() > HISTORY_SIZE {
+            history.drain(..history.len() - HISTORY_SIZE);
+        }
+        return Editor {
+            terminal: Terminal::new(),
+            history,
+            history_path,
+            line: Vec::new(),
+            cursor: 0,
+        };
+    }
+
+    // The history entries, oldest first.
+    pub fn history(&self) -> &[String] {
+        return &self.history;
+    }
+
+    // Remember line in this session and in the history file.
+    pub fn add_history(&mut self, line: &String) {
+        if line.trim().is_empty() || self.history.last() == Some(line) {
+            return;
+        }
+        self.history.push(line.clone());
+        if self.history.len() > HISTORY_SIZE {
+            self.history.remove(0);
+        }
+        if let Some(history_path) = &self.history_path {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(history_path);
+            if let Ok(mut file) = file {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    // Read a line from the terminal. completions returns every name that can
+    // be completed with Tab.
+    pub fn read_line(&mut self, prompt: &str, completions: &dyn Fn() -> Vec<String>) -> Input {
+        let raw_mode = self.terminal.as_ref().and_then(Terminal::enable_raw_mode);
+        if raw_mode.is_none() {
+            return read_plain_line(prompt);
+        }
+
+        self.line.clear();
+        self.cursor = 0;
+        // The line being written is kept while browsing the history.
+        let mut history_index = self.history.len();
+        let mut draft: Vec<char> = Vec::new();
+        self.refresh(prompt);
+
+        loop {
+            let key = match read_key() {
+                Some(key) => key,
+                None => return Input::Eof,
+            };
+            match key {
+                Key::Char(c) => {
+                    self.line.insert(self.cursor, c);
+                    self.cursor += 1;
+                }
+                Key::Control(ENTER) | Key::Control(LINE_FEED) => {
+                    write("\r\n");
+                    return Input::Line(self.line.iter().collect());
+                }
+                Key::Control(CTRL_C) => {
+                    write("^C\r\n");
+                    return Input::Interrupted;
+                }
+                Key::Control(CTRL_D) => {
+                    if self.line.is_empty() {
+                        write("\r\n");
+                        return Input::Eof;
+                    }
+                    if self.cursor < self.line.len() {
+                        self.line.remove(self.cursor);
+                    }
+                }
+                Key::Delete if self.cursor < self.line.len() => {
+                    self.line.remove(self.cursor);
+                }
+                Key::Control(BACKSPACE) | Key::Control(CTRL_H) if self.cursor > 0 => {
+                    self.cursor -= 1;
+                    self.line.remove(self.cursor);
+                }
+                Key::Left | Key::Control(CTRL_B) => {
+                    self.cursor = self.cursor.saturating_sub(1);
+                }
+                Key::Right | Key::Control(CTRL_F) => {
+                    self.cursor = (self.cursor + 1).min(self.line.len());
+                }
+                Key::Home | Key::Control(CTRL_A) => self.cursor = 0,
+                Key::End | Key::Control(CTRL_E) => self.cursor = self.line.len(),
+                Key::Control(CTRL_K) => self.line.truncate(self.cursor),
+                Key::Control(CTRL_U) => {
+                    self.line.drain(..self.cursor);
+                    self.cursor = 0;
+                }
+                Key::Control(CTRL_W) => {
+                    let start = self.word_start();
+                    self.line.drain(start..self.cursor);
+                    self.cursor = start;
+                }
+                Key::Control(CTRL_L) => write("\x1b[H\x1b[2J"),
+                Key::Up | Key::Control(CTRL_P) if history_index > 0 => {
+                    if history_index == self.history.len() {
+                        draft = self.line.clone();
+                    }
+                    history_index -= 1;
+                    self.set_line(self.history[history_index].chars().collect());
+                }
+                Key::Down | Key::Control(CTRL_N) if history_index < self.history.len() => {
+                    history_index += 1;
+                    if history_index == self.history.len() {
+                        self.set_line(draft.clone());
+                    } else {
+                        self.set_line(self.history[history_index].chars().collect());
+                    }
+                }
+                Key::Control(CTRL_R) => match self.reverse_search() {
+                    Search::Accept => {
+                        write("\r\n");
+                        return Input::Line(self.line.iter().collect());
+                    }
+                    Search::Edit | Search::Cancel => {}
+                },
+                Key::Control(TAB) => self.complete(prompt, completions()),
+                _ => {}
+            }
+            self.refresh(prompt);
+        }
+    }
+
+    fn set_line(&mut self, line: Vec<char>) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+
+    // Redraw the prompt and line and put the cursor back in place.
+    fn refresh(&self, prompt: &str) {
+        let line: String = self.line.iter().collect();
+        let mut output = format!("\r{}{}\x1b[K", prompt, line);
+        let after_cursor = self.line.len() - self.cursor;
+        if after_cursor > 0 {
+            output.push_str(&format!("\x1b[{}D", after_cursor));
+        }
+        write(&output);
+    }
+
+    // The index where the identifier before the cursor starts.
+    fn word_start(&self) -> usize {
+        let mut start = self.cursor;
+        while start > 0 && (self.line[start - 1].is_alphanumeric() || self.line[start - 1] == '_') {
+            start -= 1;
+        }
+        return start;
+    }
+
+    // Complete the identifier before the cursor. A unique match is inserted,
+    // otherwise the longest common prefix is inserted or the matches listed.
+    fn complete(&mut self, prompt: &str, completions: Vec<String>) {
+        let start = self.word_start();
+        let prefix: String = self.line[start..self.cursor].iter().collect();
+        let mut matches: Vec<String> = completions
+            .into_iter()
+            .filter(|name| name.starts_with(&prefix))
+            .collect();
+        matches.sort();
+        matches.dedup();
+
+        if matches.is_empty() {
+            write("\x07");
+            return;
+        }
+
+        let mut common: String = matches[0].clone();
+        for name in matches.iter() {
+            while !name.starts_with(&common) {
+                common.pop();
+            }
+        }
+
+        if common.len() > prefix.len() {
+            for c in common[prefix.len()..].chars() {
+                self.line.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+        } else if matches.len() > 1 {
+            write(&format!("\r\n{}\r\n", matches.join("  ")));
+            self.refresh(prompt);
+        }
+    }
+
+    // Search the history backwards for entries containing the typed text.
+    // Ctrl-R again moves to the next older match.
+    fn reverse_search(&mut self) -> Search {
+        let original = self.line.clone();
+        let mut query = String::new();
+        let mut found: Option<usize> = None;
+        loop {
+            let shown: String = match found {
+                Some(index) => self.history[index].clone(),
+                None => String::new(),
+            };
+            write(&format!("\r(reverse-i-search)`{}': {}\x1b[K", query, shown));
+
+            let key = match read_key() {
+                Some(key) => key,
+                None => return Search::Cancel,
+            };
+            match key {
+                Key::Char(c) => {
+                    query.push(c);
+                    found = self.search_history(&query, self.history.len());
+                }
+                Key::Control(BACKSPACE) | Key::Control(CTRL_H) => {
+                    query.pop();
+                    found = self.search_history(&query, self.history.len());
+                }
+                Key::Control(CTRL_R) => {
+                    let before = found.unwrap_or(self.history.len());
+                    found = self.search_history(&query, before).or(found);
+                }
+                Key::Control(CTRL_C) | Key::Control(CTRL_G) => {
+                    self.set_line(original);
+                    return Search::Cancel;
+                }
+                Key::Control(ENTER) | Key::Control(LINE_FEED) => {
+                    self.set_line(shown.chars().collect());
+                    return Search::Accept;
+                }
+                _ => {
+                    if found.is_some() {
+                        self.set_line(shown.chars().collect());
+                    }
+                    return Search::Edit;
+                }
+            }
+        }
+    }
+
+    // The newest history entry before index that contains query.
+    fn search_history(&self, query: &str, before: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+        return (0..before)
+            .rev()
+            .find(|&index| self.history[index].contains(query));
+    }
+}
+
+// Used when the terminal cannot be put into raw mode.
+fn read_plain_line(prompt: &str) -> Input {
+    write(prompt);
+    let mut line = String::new();
+    return match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => Input::Eof,
+        Ok(_) => Input::Line(line.trim_end_matches(['\n', '\r']).to_string()),
+    };
+}
@@ -0,0 +1,268 @@
+/*
+Author: Jedda Boyle
+Contains: REPL
+The read-eval-print loop reads statements from stdin and evaluates them in
+the interpreter's global enviroment.
+Input is buffered across lines until it forms a complete statement so that
+functions can be written over several lines.
+Lines starting with a colon are meta-commands used to inspect the session.
+When stdin is a terminal lines are read with the line editor in editor.rs.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+pub mod editor;
+
+use self::editor::Editor;
+use self::editor::Input;
+use super::cli::print_program;
+use super::cli::print_tokens;
+use super::cli::eval;
+use super::cli::DEBUG;
+
+use super::builtins;
+use super::interpreter::Interpreter;
+use super::lexer::token::TokenType;
+use super::lexer::token::KEYWORDS;
+use super::lexer::Lexer;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::Write;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Instant;
+
+const PROMPT: &str = ">>>";
+const CONTINUATION_PROMPT: &str = "...";
+
+// ================================================================================
+// Input buffering.
+// ================================================================================
+
+// Input is complete when every bracket and string has been closed and it ends
+// with a semicolon. Extra closing brackets make the input complete so that the
+// parser can report them.
+pub fn is_complete(input: &str) -> bool {
+    let trimmed = input.trim_end();
+    if trimmed.is_empty() {
+        return true;
+    }
+
+    let mut depth: i64 = 0;
+    let mut lexer = Lexer::new(trimmed);
+    loop {
+        let token = lexer.next_token().unwrap();
+        match token.token_type {
+            TokenType::Eof => break,
+            TokenType::LBrace | TokenType::LParen | TokenType::LBracket => depth += 1,
+            TokenType::RBrace | TokenType::RParen | TokenType::RBracket => depth -= 1,
+            // An unterminated string is lexed as an illegal token.
+            TokenType::Illegal if token.literal.starts_with('"') => return false,
+            _ => {}
+        }
+    }
+    return depth < 0 || (depth == 0 && trimmed.ends_with(';'));
+}
+
+// A blank line submits input which is balanced but missing its semicolon so
+// that the parser can report the error instead of waiting forever.
+fn is_balanced(input: &str) -> bool {
+    return is_complete(&format!("{};", input.trim_end()));
+}
+
+// ================================================================================
+// Meta-commands.
+// ================================================================================
+
+const HELP: &str = "Commands:
+  :env           List the global bindings and their types.
+  :tokens <src>  Print the tokens produced by the lexer.
+  :ast <src>     Print the parse tree.
+  :load <file>   Evaluate a file in the current session.
+  :reset         Remove every global binding.
+  :time <expr>   Evaluate an expression and report how long it took.
+  :save <file>   Write the input accepted this session to a file.
+  :help          Print this message.
+  :quit          Leave the REPL.";
+
+// Run a meta-command, writing what it prints to the output of the
+// interpreter. Returns the exit status if the REPL should stop.
+// history holds the input accepted this session.
+pub fn run_command(
+    line: &str,
+    interpreter: &Interpreter,
+    history: &mut Vec<String>,
+) -> Option<i32> {
+    let line = line.trim();
+    let (command, argument) = match line.find(' ') {
+        Some(index) => (&line[..index], line[index + 1..].trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        ":quit" => return Some(0),
+        ":help" => interpreter.write(&format!("{}\n", HELP)),
+        ":env" => print_enviroment(interpreter),
+        ":tokens" => print_tokens(argument, interpreter),
+        ":ast" => print_ast(argument, interpreter),
+        ":reset" => {
+            interpreter.reset();
+            history.clear();
+        }
+        ":load" => {
+            let input = fs::read_to_string(argument);
+            if let Err(input) = input {
+                interpreter.write(&format!("Could not read {}: {}\n", argument, input));
+                return None;
+            }
+            return evaluate(&input.unwrap(), interpreter, history);
+        }
+        ":time" => {
+            let start = Instant::now();
+            let code = evaluate(&format!("{}\n", argument), interpreter, history);
+            interpreter.write(&format!("Evaluated in {:?}\n", start.elapsed()));
+            return code;
+        }
+        ":save" => {
+            let result = fs::write(argument, history.concat());
+            if let Err(result) = result {
+                interpreter.write(&format!("Could not write {}: {}\n", argument, result));
+            }
+        }
+        _ => interpreter.write(&format!(
+            "Unknown command {}. Type :help for a list of commands.\n",
+            command
+        )),
+    }
+    return None;
+}
+
+fn print_enviroment(interpreter: &Interpreter) {
+    let enviroment = interpreter.enviroment.borrow();
+    let mut names: Vec<&String> = enviroment.variables.keys().collect();
+    names.sort();
+    for name in names {
+        interpreter.write(&format!(
+            "{}: {}\n",
+            name,
+            enviroment.variables[name].get_type()
+        ));
+    }
+}
+
+fn print_ast(input: &str, interpreter: &Interpreter) {
+    if let Ok(program) = interpreter.parse(input, false) {
+        print_program(&program, interpreter);
+    }
+}
+
+// Evaluate input and return the exit status if it called exit. Input that
+// parses is added to history, which :save writes.
+fn evaluate(input: &str, interpreter: &Interpreter, history: &mut Vec<String>) -> Option<i32> {
+    if let Ok(mut program) = interpreter.parse(input, DEBUG) {
+        if !input.trim().is_empty() {
+            history.push(input.to_string());
+        }
+        let results = eval(&mut program, interpreter);
+        return interpreter.exit_code(&results);
+    }
+    return None;
+}
+
+// ================================================================================
+// Loop.
+// ================================================================================
+
+const HISTORY_FILE: &str = ".rust_monkey_history";
+
+fn history_path() -> Option<PathBuf> {
+    return env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+}
+
+// Everything that can be completed: keywords, builtins and the names bound in
+// the global enviroment.
+fn completions(interpreter: &Interpreter) -> Vec<String> {
+    let mut to_return: Vec<String> = KEYWORDS.iter().map(|keyword| keyword.to_string()).collect();
+    to_return.extend(builtins::names());
+    to_return.extend(interpreter.enviroment.borrow().names());
+    return to_return;
+}
+
+fn read_input(editor: &mut Option<Editor>, prompt: &str, interpreter: &Interpreter) -> Input {
+    if editor.is_some() {
+        let editor = editor.as_mut().unwrap();
+        let input = editor.read_line(prompt, &|| completions(interpreter));
+        if let Input::Line(line) = &input {
+            editor.add_history(line);
+        }
+        return input;
+    }
+
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+    let mut line = String::new();
+    return match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => Input::Eof,
+        Ok(_) => Input::Line(line.trim_end_matches(['\n', '\r']).to_string()),
+    };
+}
+
+// Run the REPL until :quit is entered, exit is called or stdin is closed.
+// Returns the exit status.
+pub fn run(interpreter: &Interpreter) -> i32 {
+    let interrupt = interpreter.interrupt_handle();
+    let mut buffer = String::new();
+    let mut history: Vec<String> = Vec::new();
+    let mut editor: Option<Editor> = None;
+    if io::stdin().is_terminal() {
+        editor = Some(Editor::new(history_path()));
+    }
+
+    loop {
+        let prompt = if buffer.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+        let line = match read_input(&mut editor, prompt, interpreter) {
+            Input::Line(line) => line,
+            // Ctrl-C discards the statement being written.
+            Input::Interrupted => {
+                buffer.clear();
+                continue;
+            }
+            Input::Eof => break,
+        };
+
+        if buffer.is_empty() && line.trim_start().starts_with(':') {
+            // Commands that evaluate code can be interrupted too.
+            interrupt.clear();
+            let code = run_command(&line, interpreter, &mut history);
+            if let Some(code) = code {
+                return code;
+            }
+            continue;
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+        let submit = is_complete(&buffer) || (line.trim().is_empty() && is_balanced(&buffer));
+        if !submit {
+            continue;
+        }
+
+        // Ignore any Ctrl-C pressed while waiting for input.
+        interrupt.clear();
+        let code = evaluate(&buffer, interpreter, &mut history);
+        buffer.clear();
+        if let Some(code) = code {
+            return code;
+        }
+    }
+    println!();
+    return 0;
+}
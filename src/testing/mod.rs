@@ -0,0 +1,125 @@
+/*
+Author: Jedda Boyle
+Contains: Testing
+Tests are functions bound at the top level of a file by a let whose name
+starts with test_, such as let test_add = fn() { assert_eq(1 + 1, 2); };
+A test passes unless calling it raises an error. The assert, assert_eq and
+assert_error builtins raise an AssertionError when they fail.
+Test files are those whose names end in _test.rm.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::ExportStatement;
+use super::ast::LetStatement;
+use super::ast::NodeType;
+use super::interpreter::Interpreter;
+use super::lexer::token::Span;
+use super::object;
+use super::object::ErrorKind;
+use super::object::Object;
+use super::parser::Program;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+pub const PREFIX: &str = "test_";
+pub const SUFFIX: &str = "_test.rm";
+
+// ================================================================================
+// Discovery.
+// ================================================================================
+
+// The test files at path. A file is returned as it is and a directory is
+// searched recursively, skipping hidden directories. The files are sorted.
+pub fn discover(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut to_return = Vec::new();
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if entry.is_dir() && !name.starts_with('.') {
+            to_return.extend(discover(&entry)?);
+        } else if entry.is_file() && name.ends_with(SUFFIX) {
+            to_return.push(entry);
+        }
+    }
+    return Ok(to_return);
+}
+
+// The names of the tests of a program in the order they are defined.
+pub fn test_names(program: &Program) -> Vec<String> {
+    let mut to_return = Vec::new();
+    for statement in program.statements.iter() {
+        let statement: &LetStatement = match statement.get_type() {
+            NodeType::LetStatement => statement.downcast_ref::<LetStatement>().unwrap(),
+            NodeType::ExportStatement => {
+                &statement
+                    .downcast_ref::<ExportStatement>()
+                    .unwrap()
+                    .statement
+            }
+            _ => continue,
+        };
+        let name = &statement.identifier.token.literal;
+        if name.starts_with(PREFIX)
+            && statement.value.get_type() == NodeType::FunctionExpression
+            && !to_return.contains(name)
+        {
+            to_return.push(name.clone());
+        }
+    }
+    return to_return;
+}
+
+// ================================================================================
+// Running.
+// ================================================================================
+
+pub struct Failure {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Span, // The innermost call in the stack trace, such as the failed assert.
+    pub traceback: String,
+}
+
+impl Failure {
+    pub fn new(error: &object::Error) -> Self {
+        let span = error
+            .trace
+            .iter()
+            .map(|frame| frame.span)
+            .find(|span| span.line != 0)
+            .unwrap_or_default();
+        return Failure {
+            kind: error.kind,
+            message: error.message.clone(),
+            span,
+            traceback: error.traceback(),
+        };
+    }
+
+    // The failure of a result, if it is an error.
+    pub fn from_result(result: &Rc<dyn Object>) -> Option<Self> {
+        return result.downcast_ref::<object::Error>().map(Failure::new);
+    }
+}
+
+// Run the test called name in interpreter, which has evaluated the file
+// that defines it, and return why it failed if it did.
+pub fn run_test(interpreter: &Interpreter, name: &String) -> Option<Failure> {
+    return Failure::from_result(&interpreter.run_test(name));
+}
@@ -0,0 +1,176 @@
+/*
+Author: Jedda Boyle
+Contains: Tracer
+The tracer is a hook which logs every node once it has been evaluated with
+its NodeType, where it starts in the source, the depth of the enviroment it
+was evaluated in and the value it produced. Nodes are logged after their
+children, so the value of an expression follows the values it was built
+from. Lines are indented by the number of calls that are running.
+Nodes are shown by their to_string, the bracketed form printed by the ast
+command, and values by their to_string on one line. The log can also be
+written as JSON lines for other tools to read.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use super::ast::node_span;
+use super::ast::Node;
+use super::ast::NodeType;
+use super::ast::NODE_TYPES;
+use super::debugger::describe;
+use super::enviroment::Enviroment;
+use super::hook::Hook;
+use super::json::Json;
+use super::lexer::token::Span;
+use super::object;
+use super::object::Object;
+
+use std::cell::RefCell;
+use std::io::prelude::Write;
+use std::rc::Rc;
+
+// Longer nodes are cut short so that each fits on a line.
+const MAX_NODE_LENGTH: usize = 60;
+
+// ================================================================================
+// Tracer.
+// ================================================================================
+
+pub struct Tracer {
+    output: Box<dyn Write>,
+    kinds: Vec<String>, // The NodeTypes to log, or every one if empty.
+    json: bool,
+    calls: usize, // The number of calls running.
+}
+
+// Check that every name in kinds is a NodeType, returning the first that
+// is not.
+pub fn check_kinds(kinds: &[String]) -> Result<(), String> {
+    for kind in kinds.iter() {
+        if !NODE_TYPES
+            .iter()
+            .any(|node_type| &format!("{:?}", node_type) == kind)
+        {
+            return Err(kind.clone());
+        }
+    }
+    return Ok(());
+}
+
+// The names of every NodeType.
+pub fn kind_names() -> Vec<String> {
+    return NODE_TYPES
+        .iter()
+        .map(|node_type| format!("{:?}", node_type))
+        .collect();
+}
+
+// The number of enviroments in the chain of env, counting env and the prelude.
+fn enviroment_depth(env: &Rc<RefCell<Enviroment>>) -> usize {
+    let mut depth = 1;
+    let mut outer = env.borrow().outer.clone();
+    while let Some(env) = outer {
+        depth += 1;
+        outer = env.borrow().outer.clone();
+    }
+    return depth;
+}
+
+fn node_text(node: &dyn Node) -> String {
+    let text = node.to_string().replace('\n', " ");
+    let text = text.trim();
+    if text.chars().count() <= MAX_NODE_LENGTH {
+        return text.to_string();
+    }
+    let mut to_return: String = text.chars().take(MAX_NODE_LENGTH - 3).collect();
+    to_return.push_str("...");
+    return to_return;
+}
+
+// An error is shown by its message rather than its whole traceback.
+fn value_text(value: &Rc<dyn Object>) -> String {
+    if let Some(error) = value.downcast_ref::<object::Error>() {
+        return format!("{}: {}", error.kind, error.message.replace('\n', " "));
+    }
+    return describe(value);
+}
+
+impl Tracer {
+    // Log to output the nodes whose NodeType is one of kinds, or every node
+    // if kinds is empty, as text or as JSON lines.
+    pub fn new(output: Box<dyn Write>, kinds: Vec<String>, json: bool) -> Self {
+        return Tracer {
+            output,
+            kinds,
+            json,
+            calls: 0,
+        };
+    }
+
+    fn log(
+        &mut self,
+        kind: &NodeType,
+        span: Span,
+        depth: usize,
+        node: String,
+        value: &Rc<dyn Object>,
+    ) {
+        let line = if self.json {
+            Json::object(vec![
+                ("kind", Json::string(&format!("{:?}", kind))),
+                ("line", Json::number(span.line as i64)),
+                ("column", Json::number(span.column as i64)),
+                ("calls", Json::number(self.calls as i64)),
+                ("env_depth", Json::number(depth as i64)),
+                ("node", Json::string(&node)),
+                ("type", Json::string(&value.get_type().to_string())),
+                ("value", Json::string(&value_text(value))),
+            ])
+            .to_string()
+        } else {
+            format!(
+                "{}{:?} {}:{} env {}: {} => {}",
+                "  ".repeat(self.calls),
+                kind,
+                span.line,
+                span.column,
+                depth,
+                node,
+                value_text(value)
+            )
+        };
+        // A trace that can not be written is not worth stopping the program for.
+        let _ = writeln!(self.output, "{}", line);
+    }
+}
+
+impl Hook for Tracer {
+    fn after_node(
+        &mut self,
+        node: &dyn Node,
+        env: &Rc<RefCell<Enviroment>>,
+        result: &Rc<dyn Object>,
+    ) {
+        let kind = node.get_type();
+        if !self.kinds.is_empty() && !self.kinds.contains(&format!("{:?}", kind)) {
+            return;
+        }
+        let depth = enviroment_depth(env);
+        self.log(&kind, node_span(node), depth, node_text(node), result);
+    }
+
+    fn enter_call(
+        &mut self,
+        _function: &object::Function,
+        _span: Span,
+        _env: &Rc<RefCell<Enviroment>>,
+    ) {
+        self.calls += 1;
+    }
+
+    fn exit_call(&mut self, _function: &object::Function, _result: &Rc<dyn Object>) {
+        self.calls -= 1;
+    }
+}
@@ -0,0 +1,119 @@
+/*
+Author: Jedda Boyle
+Contains: C backend tests.
+fib.rm and the benchmark programs in tests/c are compiled to C, built with
+the system cc and run, and their output and exit status are compared with
+those of the interpreter. Each program is built a second time with the
+collector running before every allocation, which finds values the compiled
+code does not keep where the collector can see them. The time each takes is
+printed, which cargo test shows with --nocapture.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::time::Duration;
+use std::time::Instant;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// fib.rm and the programs in tests/c.
+fn programs() -> Vec<PathBuf> {
+    let mut to_return: Vec<PathBuf> = fs::read_dir("tests/c")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rm"))
+        .collect();
+    to_return.sort();
+    to_return.insert(0, PathBuf::from("fib.rm"));
+    to_return
+}
+
+// Run the binary with args and return its stdout, its exit status and how
+// long it ran for.
+fn run(program: &str, args: &[&str]) -> (String, i32, Duration) {
+    let start = Instant::now();
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.code().unwrap(),
+        start.elapsed(),
+    )
+}
+
+// Build the C program in source into an executable at path.
+fn build(source: &str, path: &str, flags: &[&str]) {
+    let output = Command::new("cc")
+        .args(["-std=c99", "-O2", "-Wall", "-o", path, source])
+        .args(flags)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{} did not build:\n{}",
+        source,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn cc_matches_interpreter() {
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("cc is not installed so the compiled programs were not run.");
+        return;
+    }
+    let binary = env!("CARGO_BIN_EXE_rust-monkey");
+    for path in programs() {
+        let source = path.to_str().unwrap();
+        let stem = env::temp_dir().join(format!(
+            "rust-monkey-{}-{}",
+            process::id(),
+            path.file_stem().unwrap().to_str().unwrap()
+        ));
+        let compiled = format!("{}.c", stem.to_str().unwrap());
+        let executable = stem.to_str().unwrap().to_string();
+        let stressed = format!("{}-stress", executable);
+        let (_, status, _) = run(
+            binary,
+            &["compile", "--target", "c", "-o", &compiled, source],
+        );
+        assert_eq!(status, 0, "{} did not compile", source);
+        build(&compiled, &executable, &[]);
+        build(&compiled, &stressed, &["-DRM_GC_STRESS=1"]);
+
+        let (stdout, status, interpreted) = run(binary, &["run", source]);
+        let (actual_stdout, actual_status, native) = run(&executable, &[]);
+        let stress = run(&stressed, &[]);
+        for file in [&compiled, &executable, &stressed] {
+            fs::remove_file(file).unwrap();
+        }
+        assert_eq!(
+            (&actual_stdout, actual_status),
+            (&stdout, status),
+            "{} behaves differently when compiled",
+            source
+        );
+        assert_eq!(
+            (&stress.0, stress.1),
+            (&stdout, status),
+            "{} behaves differently when collecting before every allocation",
+            source
+        );
+        eprintln!(
+            "{}: interpreter {:?}, compiled {:?}",
+            source, interpreted, native
+        );
+    }
+}
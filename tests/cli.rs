@@ -0,0 +1,106 @@
+/*
+Author: Jedda Boyle
+Contains: Command line tests.
+Runs the rust-monkey binary and checks what the subcommands write and their
+exit status.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// Run rust-monkey with args from the package root and return its output and
+// exit status.
+fn run(args: &[&str]) -> (String, i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.code().unwrap(),
+    )
+}
+
+#[test]
+fn dumps() {
+    let (output, status) = run(&["tokens", "fib.rm"]);
+    assert_eq!(status, 0);
+    assert!(output.starts_with("2:1\tLet\tlet\n"), "{}", output);
+    let (output, status) = run(&["ast", "fib.rm"]);
+    assert_eq!(status, 0);
+    assert!(output.starts_with("[Let fibonacci = "), "{}", output);
+}
+
+// Closing the pipe the output is written to, as head does, is not an error.
+#[test]
+fn dumps_to_a_closed_pipe() {
+    for command in ["tokens", "ast"].iter() {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+            .args([command, "tests/js/control.rm"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(
+            output.status.code(),
+            Some(0),
+            "{}: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[test]
+fn tests_from_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+        .args(["test", "-"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"let test_one = fn() { assert_eq(1, 1); };\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let written = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(written.starts_with("-\n  ok    test_one\n"), "{}", written);
+}
+
+#[test]
+fn script_arguments_and_exit_status() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+        .args(["run", "-", "--", "a", "b c"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"puts(args());\nexit(len(args()) + 1);\nputs(1);\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[a, b c]\n");
+    assert_eq!(output.status.code(), Some(3));
+}
@@ -0,0 +1,176 @@
+/*
+Author: Jedda Boyle
+Contains: Coverage tests.
+A program is run with coverage and its report is compared with the expected
+LCOV records, read back and merged with the report of another run.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::coverage::Coverage;
+use rust_monkey::coverage::Report;
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::output::Buffer;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::rc::Rc;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+const SIGN: &str = "let sign = fn(n) {
+    if (n < 0) {
+        return -1;
+    };
+    if (n == 0) { 0; } else { 1; };
+};
+let unused = fn() {
+    if (true) { puts(1); };
+};
+";
+
+// A directory of its own for each test.
+fn directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("rust-monkey-{}-{}", name, process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::canonicalize(directory).unwrap()
+}
+
+// Run SIGN followed by calls in the file at path and return the report.
+fn run(path: &Path, calls: &str) -> Report {
+    fs::write(path, format!("{}{}", SIGN, calls)).unwrap();
+    let interpreter = Interpreter::new();
+    interpreter.set_output(Box::new(Buffer::new()));
+    interpreter.set_path(path.to_path_buf());
+    let coverage = Rc::new(RefCell::new(Coverage::new()));
+    interpreter.add_hook(coverage.clone());
+    interpreter.eval(&fs::read_to_string(path).unwrap());
+    let report = coverage.borrow().report();
+    report
+}
+
+// The LCOV report of sign.rm with the counts of each record.
+fn lcov(path: &Path, branches: [&str; 6], lines: [u64; 8], hit: (usize, usize)) -> String {
+    let lines = [1, 2, 3, 5, 7, 8, 10, 11]
+        .iter()
+        .zip(lines.iter())
+        .map(|(line, count)| format!("DA:{},{}\n", line, count))
+        .collect::<String>();
+    format!(
+        "TN:\nSF:{}\nBRDA:2,0,0,{}\nBRDA:2,0,1,{}\nBRDA:5,0,0,{}\nBRDA:5,0,1,{}\n\
+         BRDA:8,0,0,{}\nBRDA:8,0,1,{}\nBRF:6\nBRH:{}\n{}LF:8\nLH:{}\nend_of_record\n",
+        path.display(),
+        branches[0],
+        branches[1],
+        branches[2],
+        branches[3],
+        branches[4],
+        branches[5],
+        hit.0,
+        lines,
+        hit.1
+    )
+}
+
+#[test]
+fn statements_and_branches() {
+    let directory = directory("coverage");
+    let path = directory.join("sign.rm");
+    let report = run(&path, "sign(5);\nsign(0);\n");
+    let file = &report.files[&path.display().to_string()];
+    assert_eq!(file.lines_hit(), 6);
+    assert_eq!(file.branches_hit(), 3);
+    // The if without an else goes the implicit way both times, and the if in
+    // the function that is never called is never evaluated.
+    assert_eq!(
+        report.to_lcov(),
+        lcov(
+            &path,
+            ["0", "2", "1", "1", "-", "-"],
+            [1, 2, 0, 2, 1, 0, 1, 1],
+            (3, 6)
+        )
+    );
+    assert_eq!(
+        report.summary(),
+        "Lines: 6 of 8 (75.0%)\nBranches: 3 of 6 (50.0%)"
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn merging_runs() {
+    let directory = directory("coverage-merge");
+    let path = directory.join("sign.rm");
+    let mut report = run(&path, "sign(5);\nsign(0);\n");
+    let other = run(&path, "sign(-1);\n");
+    report.merge(&other);
+    let expected = lcov(
+        &path,
+        ["1", "2", "1", "1", "-", "-"],
+        [2, 3, 1, 2, 2, 0, 2, 1],
+        (4, 7),
+    );
+    assert_eq!(report.to_lcov(), expected);
+
+    // A report read back from LCOV is the same report.
+    let parsed = Report::parse_lcov(&report.to_lcov()).unwrap();
+    assert_eq!(parsed.to_lcov(), expected);
+    let mut twice = Report::parse_lcov(&other.to_lcov()).unwrap();
+    twice.merge(&Report::parse_lcov(&other.to_lcov()).unwrap());
+    let file = &twice.files[&path.display().to_string()];
+    assert_eq!((file.lines[&10], file.branches[&(2, 0, 0)]), (2, Some(2)));
+
+    assert_eq!(
+        Report::parse_lcov("DA:1,1\n").err().unwrap(),
+        "line 1: invalid record DA:1,1"
+    );
+    assert_eq!(
+        Report::parse_lcov("SF:a.rm\nBRDA:1,0,0\n").err().unwrap(),
+        "line 2: invalid record BRDA:1,0,0"
+    );
+    assert_eq!(
+        Report::parse_lcov("SF:a.rm\nDA:1,1\n").err().unwrap(),
+        "missing end_of_record"
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+// run --coverage adds the counts of each run to the report in the file.
+#[test]
+fn coverage_file() {
+    let directory = directory("coverage-file");
+    let path = directory.join("sign.rm");
+    let info = directory.join("sign.info");
+    fs::write(&path, format!("{}sign(5);\nsign(0);\n", SIGN)).unwrap();
+    for _ in 0..2 {
+        let status = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+            .arg("run")
+            .arg("--coverage")
+            .arg(&info)
+            .arg(&path)
+            .output()
+            .unwrap()
+            .status;
+        assert_eq!(status.code(), Some(0));
+    }
+    assert_eq!(
+        fs::read_to_string(&info).unwrap(),
+        lcov(
+            &path,
+            ["0", "4", "2", "2", "-", "-"],
+            [2, 4, 0, 4, 2, 0, 2, 2],
+            (3, 6)
+        )
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
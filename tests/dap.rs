@@ -0,0 +1,80 @@
+/*
+Author: Jedda Boyle
+Contains: Debug adapter tests.
+Replays a recorded session against rust-monkey dap. In the recording lines
+starting with -> are sent by the client and lines starting with <- are the
+messages the adapter is expected to send back, in order.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::json::Json;
+
+use std::fs;
+use std::io::prelude::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+// ================================================================================
+// Replay.
+// ================================================================================
+
+const SESSION: &str = "tests/dap/session.txt";
+
+// Split the output of the adapter into messages.
+fn messages(mut text: &str) -> Vec<Json> {
+    let mut messages = Vec::new();
+    while !text.is_empty() {
+        let header_end = text.find("\r\n\r\n").unwrap();
+        let length: usize = text[..header_end]
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let body = &text[header_end + 4..header_end + 4 + length];
+        messages.push(Json::parse(body).unwrap());
+        text = &text[header_end + 4 + length..];
+    }
+    messages
+}
+
+#[test]
+fn recorded_session() {
+    let session = fs::read_to_string(SESSION).unwrap();
+    let mut input = String::new();
+    let mut expected = Vec::new();
+    for line in session.lines() {
+        if let Some(body) = line.strip_prefix("-> ") {
+            input.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+        } else if let Some(body) = line.strip_prefix("<- ") {
+            expected.push(Json::parse(body).unwrap());
+        } else {
+            assert!(line.trim().is_empty(), "bad line in session: {}", line);
+        }
+    }
+
+    // The program in the session is given relative to the package root.
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+        .arg("dap")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let received = messages(&String::from_utf8(output.stdout).unwrap());
+
+    for (i, (received, expected)) in received.iter().zip(expected.iter()).enumerate() {
+        assert_eq!(received, expected, "message {} differs", i);
+    }
+    assert_eq!(received.len(), expected.len());
+    assert_eq!(output.status.code(), Some(0));
+}
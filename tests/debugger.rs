@@ -0,0 +1,122 @@
+/*
+Author: Jedda Boyle
+Contains: Debugger tests.
+A frontend evaluates expressions while the program is paused and the program
+goes on as if they had not been evaluated.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::debugger::Debugger;
+use rust_monkey::debugger::Frontend;
+use rust_monkey::debugger::Reason;
+use rust_monkey::debugger::Resume;
+use rust_monkey::interpreter::ExecutionLimits;
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::object;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// A frontend which evaluates expressions at the first pause and records their
+// values.
+struct Evaluate {
+    expressions: Vec<&'static str>,
+    values: Rc<RefCell<Vec<String>>>,
+}
+
+impl Frontend for Evaluate {
+    fn paused(&mut self, debugger: &mut Debugger, _reason: Reason) -> Resume {
+        let frame = debugger.frames.len() - 1;
+        for expression in self.expressions.drain(..) {
+            let value = debugger.evaluate(frame, expression);
+            let value = match value.downcast_ref::<object::Error>() {
+                Some(error) => format!("{}: {}", error.kind, error.message),
+                None => value.to_string(),
+            };
+            self.values.borrow_mut().push(value);
+        }
+        Resume::Continue
+    }
+}
+
+// Debug source, evaluating expressions when it pauses at its second line,
+// and return the values of the expressions and the value of the program.
+fn debug(
+    limits: ExecutionLimits,
+    source: &str,
+    expressions: Vec<&'static str>,
+) -> (Vec<String>, String) {
+    let interpreter = Interpreter::with_limits(limits);
+    let program = interpreter.parse(source, false).unwrap();
+    let values = Rc::new(RefCell::new(Vec::new()));
+    let frontend = Evaluate {
+        expressions,
+        values: values.clone(),
+    };
+    let mut debugger = Debugger::new(
+        &program,
+        interpreter.enviroment.clone(),
+        Box::new(frontend),
+        false,
+    );
+    debugger.breakpoints.insert(2);
+    interpreter.add_hook(Rc::new(RefCell::new(debugger)));
+    let results = interpreter.eval_program(&program);
+    let values = values.borrow().clone();
+    (values, results.last().unwrap().to_string())
+}
+
+const COUNT: &str = "let count = fn(n) { if (n == 0) { 0; } else { count(n - 1); }; };\ncount(30);";
+
+#[test]
+fn evaluate_restores_the_counters() {
+    let limits = ExecutionLimits {
+        max_steps: Some(1000),
+        max_depth: Some(40),
+        max_allocations: None,
+    };
+    let (values, result) = debug(limits, COUNT, vec![]);
+    assert!(values.is_empty());
+    assert_eq!(result, "0");
+
+    // The expressions exceed each limit, which does not stop the program.
+    let (values, result) = debug(
+        limits,
+        COUNT,
+        vec![
+            "any(range(0, 100000), fn(x) { false; });",
+            "let deep = fn(n) { if (n == 0) { 0; } else { deep(n - 1); }; }; deep(50);",
+            "1 + 2",
+        ],
+    );
+    assert_eq!(
+        values,
+        vec![
+            "StepLimitError: Step limit of 1000 exceeded.",
+            "DepthLimitError: Call depth limit of 40 exceeded.",
+            "3",
+        ]
+    );
+    assert_eq!(result, "0");
+}
+
+#[test]
+fn evaluate_does_not_spend_the_steps_of_the_program() {
+    let limits = ExecutionLimits {
+        max_steps: Some(600),
+        max_depth: None,
+        max_allocations: None,
+    };
+    // Each count(30) takes most of the steps, so the program would run out
+    // if the steps of the expression were kept.
+    let (values, result) = debug(limits, COUNT, vec!["count(30)"]);
+    assert_eq!(values, vec!["0"]);
+    assert_eq!(result, "0");
+}
@@ -0,0 +1,74 @@
+/*
+Author: Jedda Boyle
+Contains: Line editor tests.
+The history is loaded from and appended to the history file and kept to
+HISTORY_SIZE entries, without a terminal.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::repl::editor::Editor;
+use rust_monkey::repl::editor::HISTORY_SIZE;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// A history file in a temporary directory of its own.
+fn history_file(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("rust-monkey-editor-{}-{}", process::id(), name));
+    fs::create_dir_all(&directory).unwrap();
+    directory.join("history")
+}
+
+#[test]
+fn history_is_loaded_and_saved() {
+    let path = history_file("saved");
+    fs::write(&path, "let a = 1;\n\n   \na * 2;\n").unwrap();
+    let mut editor = Editor::new(Some(path.clone()));
+    assert_eq!(editor.history(), ["let a = 1;", "a * 2;"]);
+
+    editor.add_history(&"puts(a);".to_string());
+    // Blank lines and repeats of the last entry are not added.
+    editor.add_history(&"puts(a);".to_string());
+    editor.add_history(&"  ".to_string());
+    assert_eq!(editor.history(), ["let a = 1;", "a * 2;", "puts(a);"]);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "let a = 1;\n\n   \na * 2;\nputs(a);\n"
+    );
+
+    let editor = Editor::new(Some(path.clone()));
+    assert_eq!(editor.history(), ["let a = 1;", "a * 2;", "puts(a);"]);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn history_is_truncated() {
+    let path = history_file("truncated");
+    let lines: Vec<String> = (0..HISTORY_SIZE + 5).map(|i| format!("{};", i)).collect();
+    fs::write(&path, lines.join("\n") + "\n").unwrap();
+    let mut editor = Editor::new(Some(path.clone()));
+    assert_eq!(editor.history(), &lines[5..]);
+
+    editor.add_history(&"new;".to_string());
+    assert_eq!(editor.history().len(), HISTORY_SIZE);
+    assert_eq!(editor.history()[0], "6;");
+    assert_eq!(editor.history()[HISTORY_SIZE - 1], "new;");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn history_without_a_file() {
+    let mut editor = Editor::new(None);
+    assert!(editor.history().is_empty());
+    editor.add_history(&"1;".to_string());
+    assert_eq!(editor.history(), ["1;"]);
+}
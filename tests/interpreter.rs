@@ -0,0 +1,128 @@
+/*
+Author: Jedda Boyle
+Contains: Interpreter tests.
+Programs are evaluated by an embedded interpreter and the value of their
+last statement, or the error they raised, is compared with the expected one.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::object;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// The value of the last statement of source as it is printed, or the kind
+// and message of the error it raised.
+fn eval(interpreter: &Interpreter, source: &str) -> String {
+    let result = interpreter.eval(source);
+    match result.downcast_ref::<object::Error>() {
+        Some(error) => format!("{}: {}", error.kind, error.message),
+        None => result.to_string(),
+    }
+}
+
+#[test]
+fn integer_overflow() {
+    let interpreter = Interpreter::new();
+    let cases = [
+        (
+            "let a = -9223372036854775807 - 1; -a;",
+            "RuntimeError: Integer overflow.",
+        ),
+        ("-(-9223372036854775807);", "9223372036854775807"),
+        (
+            "9223372036854775807 + 1;",
+            "RuntimeError: Integer overflow.",
+        ),
+        (
+            "-9223372036854775807 - 2;",
+            "RuntimeError: Integer overflow.",
+        ),
+        (
+            "3037000500 * 3037000500;",
+            "RuntimeError: Integer overflow.",
+        ),
+        (
+            "try { let a = -9223372036854775807 - 1; -a; } catch (e) { e.message; };",
+            "Integer overflow.",
+        ),
+    ];
+    for (source, expected) in cases.iter() {
+        assert_eq!(eval(&interpreter, source), *expected, "{}", source);
+    }
+}
+
+#[test]
+fn returning_an_err_fails_the_program() {
+    let cases = [
+        ("err(\"x\")?;", "err(x)", 1),
+        ("return err(1); puts(2);", "err(1)", 1),
+        ("ok(2)?;", "2", 0),
+        ("return ok(3);", "ok(3)", 0),
+        ("err(4);", "err(4)", 0),
+    ];
+    for (source, expected, status) in cases.iter() {
+        let interpreter = Interpreter::new();
+        let program = interpreter.parse(source, false).unwrap();
+        let results = interpreter.eval_program(&program);
+        let last = results.last().unwrap();
+        assert_eq!(last.to_string(), *expected, "{}", source);
+        assert_eq!(
+            last.get_type() == object::ObjectType::Error,
+            *status != 0,
+            "{}",
+            source
+        );
+        assert_eq!(interpreter.exit_status(&results), *status, "{}", source);
+    }
+}
+
+#[test]
+fn process_builtins() {
+    let interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "args();"), "[]");
+    interpreter.set_args(vec!["one".to_string(), "two three".to_string()]);
+    assert_eq!(eval(&interpreter, "args();"), "[one, two three]");
+    assert_eq!(eval(&interpreter, "len(args());"), "2");
+
+    let path = std::env::var("PATH").unwrap();
+    assert_eq!(eval(&interpreter, "env(\"PATH\");"), path);
+    assert_eq!(
+        eval(&interpreter, "env(\"RUST_MONKEY_UNSET_VARIABLE\");"),
+        "null"
+    );
+    assert_eq!(
+        eval(&interpreter, "env(1);"),
+        "TypeError: env expects a string but was given Integer."
+    );
+    assert_eq!(
+        eval(&interpreter, "exit(\"x\");"),
+        "TypeError: exit expects an integer but was given String."
+    );
+}
+
+#[test]
+fn exit_status() {
+    let cases = [
+        ("exit(3); puts(1);", 3),
+        ("exit();", 0),
+        ("let f = fn() { exit(4); }; f(); 1;", 4),
+        ("try { exit(5); } catch (e) { 1; };", 5),
+        ("1;", 0),
+    ];
+    for (source, status) in cases.iter() {
+        let interpreter = Interpreter::new();
+        let program = interpreter.parse(source, false).unwrap();
+        let results = interpreter.eval_program(&program);
+        assert_eq!(interpreter.exit_status(&results), *status, "{}", source);
+    }
+    let interpreter = Interpreter::new();
+    let program = interpreter.parse("1;", false).unwrap();
+    let results = interpreter.eval_program(&program);
+    assert_eq!(interpreter.exit_code(&results), None);
+}
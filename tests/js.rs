@@ -0,0 +1,102 @@
+/*
+Author: Jedda Boyle
+Contains: JavaScript backend tests.
+Each program in tests/js is compiled and the modules and program it
+compiles to are compared with the golden file next to it, without the
+runtime and prelude which are the same for every program. Run with
+UPDATE_GOLDEN=1 to write the golden files instead. When node is installed
+the whole compiled program is run too and its output and exit status are
+compared with those of the interpreter.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::compiler::js;
+use rust_monkey::compiler::Units;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// The programs in tests/js, which import the modules in tests/js/lib.
+fn programs() -> Vec<PathBuf> {
+    let mut to_return: Vec<PathBuf> = fs::read_dir("tests/js")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rm"))
+        .collect();
+    to_return.sort();
+    to_return
+}
+
+// Run the binary with args and return its stdout and exit status.
+fn run(program: &str, args: &[&str]) -> (String, i32) {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.code().unwrap(),
+    )
+}
+
+#[test]
+fn golden_files() {
+    let update = env::var("UPDATE_GOLDEN").is_ok();
+    for path in programs() {
+        let input = fs::read_to_string(&path).unwrap();
+        let units = Units::load(&input, Some(path.clone()), true).unwrap();
+        let code = js::compile_program(&units).unwrap();
+        let golden = path.with_extension("js");
+        if update {
+            fs::write(&golden, &code).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden).unwrap_or_default();
+        assert_eq!(
+            code,
+            expected,
+            "{} does not compile to {}",
+            path.display(),
+            golden.display()
+        );
+    }
+}
+
+#[test]
+fn node_matches_interpreter() {
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("node is not installed so the compiled programs were not run.");
+        return;
+    }
+    for path in programs() {
+        let source = path.to_str().unwrap();
+        let compiled = env::temp_dir().join(format!(
+            "rust-monkey-{}-{}.js",
+            process::id(),
+            path.file_stem().unwrap().to_str().unwrap()
+        ));
+        let compiled = compiled.to_str().unwrap();
+        let binary = env!("CARGO_BIN_EXE_rust-monkey");
+        let (_, status) = run(
+            binary,
+            &["compile", "--target", "js", "-o", compiled, source],
+        );
+        assert_eq!(status, 0, "{} did not compile", source);
+
+        let expected = run(binary, &["run", source]);
+        let actual = run("node", &[compiled]);
+        fs::remove_file(compiled).unwrap();
+        assert_eq!(actual, expected, "{} behaves differently in node", source);
+    }
+}
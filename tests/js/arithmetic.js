@@ -0,0 +1,46 @@
+// ================================================================================
+// tests/js/arithmetic.rm.
+// ================================================================================
+
+$run(() => {
+    let $r1, $r2;
+    $print($div(7, 2));
+    $print($div(-7, 2));
+    $print($div(7, -2));
+    $print($div(-7, -2));
+    $print($mul($div(1, 3), 3));
+    $print($neg($sub(3, 10)));
+    $print(($r2 = $div(6, 2), $sub(($r1 = $mul(4, 5), $add($mul(2, 3), $r1)), $r2)));
+    let divide = (a, b) => {
+        let $r1;
+        try {
+            return $div(a, b);
+        } catch ($e) {
+            let error = $catch($e);
+            return ($r1 = $member(error, "message", "error"), $add($add($member(error, "kind", "error"), ": "), $r1));
+        }
+    };
+    $print(divide(10, 0));
+    $print(divide(10, 3));
+    let overflow;
+    try {
+        overflow = $mul(3037000500, 3037000500);
+    } catch ($e) {
+        let error = $catch($e);
+        overflow = $member(error, "message", "error");
+    }
+    $print(overflow);
+    let mismatch;
+    try {
+        mismatch = $add(1, "one");
+    } catch ($e) {
+        let error = $catch($e);
+        mismatch = $member(error, "kind", "error");
+    }
+    $print(mismatch);
+    $print($eq("monkey", "monkey"));
+    $print($ne(1, 2));
+    $print($eq(true, false));
+    $print($index([1, [2, 3], "four"], 1));
+    $print(len("héllo"));
+});
@@ -0,0 +1,35 @@
+// ================================================================================
+// tests/js/closures.rm.
+// ================================================================================
+
+$run(() => {
+    let make_counter = (start) => {
+        let step = (n) => $add(n, 1);
+        return (times) => {
+            let count = start;
+            let loop = (i, count) => {
+                if ($lt(i, times)) {
+                    return loop($add(i, 1), step(count));
+                } else {
+                    return count;
+                }
+            };
+            return loop(0, count);
+        };
+    };
+    let counter = make_counter(10);
+    $print(counter(5));
+    let add = (a) => (b) => $add(a, b);
+    let add_two = add(2);
+    $print(map([1, 2, 3], add_two));
+    $print(reduce(map(range(1, 6), (x) => $mul(x, x)), 0, (a, b) => $add(a, b)));
+    $print(filter(["monkey", "js", "closure"], (word) => $gt(len(word), 2)));
+    $print(compose(add_two, add(3))(1));
+    let greeting = "hello";
+    let greet = (name) => $add($add(greeting, ", "), name);
+    greeting = "goodbye";
+    $print(greet("monkey"));
+    let class$ = "reserved words are renamed";
+    $print(class$);
+    $print(len(map(range(0, 10000), (x) => $mul(x, x))));
+});
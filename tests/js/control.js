@@ -0,0 +1,108 @@
+// ================================================================================
+// tests/js/control.rm.
+// ================================================================================
+
+$run(() => {
+    let $r1, $r2, $r3, $r4, $r5;
+    let sign = (n) => {
+        if ($lt(n, 0)) {
+            return "negative";
+        } else if ($eq(n, 0)) {
+            return "zero";
+        } else {
+            return "positive";
+        }
+    };
+    $print(map([-5, 0, 5], sign));
+    let describe = (n) => {
+        let big;
+        let parity = $eq($mul($div(n, 2), 2), n) ? "even" : "odd";
+        let size;
+        if ($gt(n, 100)) {
+            big = "big";
+            size = big;
+        } else {
+            size = "small";
+        }
+        return $add($add(parity, " and "), size);
+    };
+    $print(describe(7));
+    $print(describe(1000));
+    let first_negative = (numbers) => {
+        let check = (i) => {
+            try {
+                puts("checking", i, (() => {
+                    if ($lt($index(numbers, i), 0)) {
+                        throw new $Return($index(numbers, i));
+                    } else {
+                        return "ok";
+                    }
+                })());
+                return check($add(i, 1));
+            } catch ($e) {
+                return $return($e);
+            }
+        };
+        return check(0);
+    };
+    $print(first_negative([3, 1, -4, 1]));
+    let parse_digit = (text) => {
+        if ($eq(text, "0")) {
+            return ok(0);
+        } else if ($eq(text, "1")) {
+            return ok(1);
+        } else {
+            return err($add("not a digit: ", text));
+        }
+    };
+    let add_digits = (a, b) => {
+        try {
+            let $r1;
+            return ok(($r1 = $unwrap(parse_digit(b)), $add($unwrap(parse_digit(a)), $r1)));
+        } catch ($e) {
+            return $return($e);
+        }
+    };
+    $print(add_digits("1", "1"));
+    $print(add_digits("1", "x"));
+    let value = false ? 1 : null;
+    $print(value);
+    let thrown;
+    try {
+        throw $throw([1, 2]);
+    } catch ($e) {
+        let error = $catch($e);
+        thrown = error;
+    } finally {
+        puts("finally");
+    }
+    $print(thrown);
+    let named = (name) => {
+        puts(name);
+        return len(name);
+    };
+    $print(($r1 = named("right"), $add(named("left"), $r1)));
+    $print(($r4 = named("d"), $lt(($r3 = ($r2 = named("c"), $sub(named("b"), $r2)), $mul(named("a"), $r3)), $r4)));
+    let failed;
+    try {
+        failed = ($r5 = named(1), $add(named("not run"), $r5));
+    } catch ($e) {
+        let error = $catch($e);
+        failed = $member(error, "message", "error");
+    }
+    $print(failed);
+    let both = () => {
+        let $r1;
+        return ($r1 = (() => {
+            throw $throw("B");
+        })(), $add((() => {
+            throw $throw("A");
+        })(), $r1));
+    };
+    try {
+        $print(both());
+    } catch ($e) {
+        let error = $catch($e);
+        $print(error);
+    }
+});
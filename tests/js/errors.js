@@ -0,0 +1,10 @@
+// ================================================================================
+// tests/js/errors.rm.
+// ================================================================================
+
+$run(() => {
+    let half = (n) => $div(n, 2);
+    $print(half(10));
+    $print(half("ten"));
+    $print(puts("not reached"));
+});
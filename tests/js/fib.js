@@ -0,0 +1,15 @@
+// ================================================================================
+// tests/js/fib.rm.
+// ================================================================================
+
+$run(() => {
+    let fibonacci = (n) => {
+        let $r1;
+        if ($lt(n, 2)) {
+            return n;
+        } else {
+            return ($r1 = fibonacci($sub(n, 2)), $add(fibonacci($sub(n, 1)), $r1));
+        }
+    };
+    $print(fibonacci(12));
+});
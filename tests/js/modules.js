@@ -0,0 +1,31 @@
+// ================================================================================
+// Module tests/js/lib/shapes.rm.
+// ================================================================================
+
+const $module_1 = $module("tests/js/lib/shapes.rm", ($exports) => {
+    puts("loading shapes");
+    let area = (rectangle) => {
+        let $r1;
+        return ($r1 = $index(rectangle, 1), $mul($index(rectangle, 0), $r1));
+    };
+    $exports.area = area;
+    let perimeter = (rectangle) => {
+        let $r1;
+        return $mul(2, ($r1 = $index(rectangle, 1), $add($index(rectangle, 0), $r1)));
+    };
+    $exports.perimeter = perimeter;
+    let unit = [1, 1];
+    $exports.unit = unit;
+});
+
+// ================================================================================
+// tests/js/modules.rm.
+// ================================================================================
+
+$run(() => {
+    let shapes = $module_1();
+    let again = $module_1();
+    $print($member(shapes, "area", "shapes")([3, 4]));
+    $print($member(again, "perimeter", "again")([3, 4]));
+    $print($member(shapes, "unit", "shapes"));
+});
@@ -0,0 +1,16 @@
+// ================================================================================
+// tests/js/propagate.rm.
+// ================================================================================
+
+$run(() => {
+    let parse = (value) => {
+        if ($lt(value, 0)) {
+            return err("negative");
+        } else {
+            return ok($mul(value, 2));
+        }
+    };
+    $print(puts($unwrap(parse(1))));
+    $print($unwrap(parse(-1)));
+    $print(puts("not reached"));
+});
@@ -0,0 +1,10 @@
+// ================================================================================
+// tests/js/status.rm.
+// ================================================================================
+
+$run(() => {
+    let code = () => 3;
+    $print(puts("returning"));
+    return code();
+    $print(puts("not reached"));
+});
@@ -0,0 +1,153 @@
+/*
+Author: Jedda Boyle
+Contains: Execution limit tests.
+Programs which run into the step, call depth or allocation limit, or which
+are interrupted from another thread, stop with an error that try does not
+catch.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::interpreter::ExecutionLimits;
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::object;
+
+use std::thread;
+use std::time::Duration;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// The kind and message of the error source raised, or the value of its last
+// statement as it is printed.
+fn eval(interpreter: &Interpreter, source: &str) -> String {
+    let result = interpreter.eval(source);
+    match result.downcast_ref::<object::Error>() {
+        Some(error) => format!("{}: {}", error.kind, error.message),
+        None => result.to_string(),
+    }
+}
+
+fn limited(
+    max_steps: Option<u64>,
+    max_depth: Option<usize>,
+    max_allocations: Option<u64>,
+) -> Interpreter {
+    Interpreter::with_limits(ExecutionLimits {
+        max_steps,
+        max_depth,
+        max_allocations,
+    })
+}
+
+// source wrapped in a try which would catch any error it raised.
+fn in_try(source: &str) -> String {
+    format!("try {{ {} }} catch (e) {{ \"caught\"; }};", source)
+}
+
+#[test]
+fn step_limit() {
+    let interpreter = limited(Some(1000), None, None);
+    let source = "let loop = fn(n) { loop(n + 1); }; loop(0);";
+    assert_eq!(
+        eval(&interpreter, source),
+        "StepLimitError: Step limit of 1000 exceeded."
+    );
+    assert_eq!(
+        eval(&interpreter, &in_try(source)),
+        "StepLimitError: Step limit of 1000 exceeded."
+    );
+    assert_eq!(eval(&interpreter, "1 + 2;"), "3");
+}
+
+#[test]
+fn depth_limit() {
+    let interpreter = limited(None, Some(50), None);
+    let source = "let down = fn(n) { down(n + 1); }; down(0);";
+    assert_eq!(
+        eval(&interpreter, source),
+        "DepthLimitError: Call depth limit of 50 exceeded."
+    );
+    assert_eq!(
+        eval(&interpreter, &in_try(source)),
+        "DepthLimitError: Call depth limit of 50 exceeded."
+    );
+    assert_eq!(
+        eval(
+            &interpreter,
+            "let f = fn(n) { if (n == 0) { 0; } else { f(n - 1); }; }; f(40);"
+        ),
+        "0"
+    );
+}
+
+#[test]
+fn depth_limit_traceback() {
+    let interpreter = limited(None, Some(5), None);
+    let source = "let down = fn(n) {\n  down(n + 1);\n};\ndown(0);";
+    let traceback = [
+        "Traceback (most recent call last):",
+        "  line 4, column 5, in down(0)",
+        "  line 2, column 7, in down(1)",
+        "  [Previous call repeated 3 more times]",
+        "DepthLimitError: Call depth limit of 5 exceeded.",
+    ];
+    assert_eq!(interpreter.eval(source).to_string(), traceback.join("\n"));
+}
+
+#[test]
+fn allocation_limit() {
+    let interpreter = limited(None, None, Some(100));
+    let cases = [
+        "let grow = fn(arr) { grow(push(arr, 1)); }; grow([]);",
+        "range(0, 1000);",
+        "range(0, 9223372036854775807);",
+        "let s = \"aaaaaaaaaa\"; s + s + s + s + s + s + s + s + s + s + s;",
+        "map(range(0, 60), fn(x) { x; });",
+        "zip(range(0, 40), range(0, 40));",
+        "rest(range(0, 90));",
+    ];
+    for source in cases.iter() {
+        assert_eq!(
+            eval(&interpreter, source),
+            "AllocationLimitError: Allocation limit of 100 exceeded.",
+            "{}",
+            source
+        );
+        assert_eq!(
+            eval(&interpreter, &in_try(source)),
+            "AllocationLimitError: Allocation limit of 100 exceeded.",
+            "{}",
+            source
+        );
+    }
+    assert_eq!(eval(&interpreter, "len(range(0, 20));"), "20");
+
+    interpreter.set_args(vec!["a".to_string(); 200]);
+    assert_eq!(
+        eval(&interpreter, "args();"),
+        "AllocationLimitError: Allocation limit of 100 exceeded."
+    );
+    interpreter.set_args(vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(eval(&interpreter, "args();"), "[a, b]");
+}
+
+#[test]
+fn interrupt_from_another_thread() {
+    let interpreter = Interpreter::with_limits(ExecutionLimits::unlimited());
+    let handle = interpreter.interrupt_handle();
+    let interrupter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+    let source = "let spin = fn() { any(range(0, 1000000000), fn(x) { false; }); }; spin();";
+    assert_eq!(
+        eval(&interpreter, &in_try(source)),
+        "Interrupted: Evaluation interrupted."
+    );
+    interrupter.join().unwrap();
+    assert_eq!(eval(&interpreter, "1 + 2;"), "3");
+}
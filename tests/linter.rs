@@ -0,0 +1,149 @@
+/*
+Author: Jedda Boyle
+Contains: Linter tests.
+Each rule is checked against source which breaks it and similar source which
+does not, and the problems are printed as text and as JSON.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::linter;
+use rust_monkey::linter::Config;
+use rust_monkey::linter::Severity;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::process::Command;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// The problems found in source with the default configuration, as they are
+// printed.
+fn lint(source: &str) -> Vec<String> {
+    let diagnostics = linter::lint_source(source, &Config::new()).unwrap();
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect()
+}
+
+#[test]
+fn unused_variable() {
+    assert_eq!(
+        lint("let a = 1;"),
+        vec!["1:5: warning[unused-variable]: The variable a is never read."]
+    );
+    assert!(lint("let a = 1; puts(a);").is_empty());
+    assert!(lint("let _a = 1;").is_empty());
+    assert!(lint("export let a = 1;").is_empty());
+}
+
+#[test]
+fn shadowed_parameter() {
+    assert_eq!(
+        lint("let x = 1; let f = fn(x) { x; }; f(x);"),
+        vec!["1:23: warning[shadowed-parameter]: The parameter x shadows a binding in an outer scope."]
+    );
+    assert!(lint("let y = 1; let f = fn(x) { x; }; f(y);").is_empty());
+}
+
+#[test]
+fn unreachable_code() {
+    assert_eq!(
+        lint("let f = fn() {\n  return 1;\n  puts(2);\n}; f();"),
+        vec!["3:3: warning[unreachable-code]: This statement is never reached."]
+    );
+    assert_eq!(
+        lint("let f = fn() {\n  throw \"x\";\n  puts(2);\n}; f();"),
+        vec!["3:3: warning[unreachable-code]: This statement is never reached."]
+    );
+    assert!(lint("let f = fn() {\n  puts(2);\n  return 1;\n}; f();").is_empty());
+}
+
+#[test]
+fn constant_condition() {
+    assert_eq!(
+        lint("if (true) { puts(1); };"),
+        vec!["1:5: warning[constant-condition]: The condition is always true."]
+    );
+    assert!(lint("let a = 1; if (a == 1) { puts(1); };").is_empty());
+}
+
+#[test]
+fn argument_count() {
+    assert_eq!(
+        lint("let f = fn(x) { x; }; f(1, 2);"),
+        vec!["1:23: warning[argument-count]: f takes 1 arguments but is given 2."]
+    );
+    assert!(lint("let f = fn(x) { x; }; f(1);").is_empty());
+}
+
+#[test]
+fn configured_severities() {
+    let config =
+        Config::parse("# Comments are ignored.\nunused-variable = off\nargument-count = error\n")
+            .unwrap();
+    assert_eq!(config.severity("unused-variable"), Severity::Off);
+    assert_eq!(config.severity("argument-count"), Severity::Error);
+    assert_eq!(config.severity("unreachable-code"), Severity::Warning);
+    let diagnostics =
+        linter::lint_source("let a = 1; let f = fn() { 1; }; f(a);", &config).unwrap();
+    let printed: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    assert_eq!(
+        printed,
+        vec!["1:33: error[argument-count]: f takes 0 arguments but is given 1."]
+    );
+
+    assert_eq!(
+        Config::parse("unused = off").err().unwrap(),
+        "line 1: unknown rule unused."
+    );
+    assert_eq!(
+        Config::parse("\nunused-variable = loud").err().unwrap(),
+        "line 2: unknown severity loud, expected off, warning or error."
+    );
+    assert_eq!(
+        Config::parse("unused-variable").err().unwrap(),
+        "line 1: expected <rule> = <severity>."
+    );
+}
+
+#[test]
+fn json_output() {
+    let diagnostics = linter::lint_source("let a = 1;", &Config::new()).unwrap();
+    assert_eq!(
+        diagnostics[0].to_json("a.rm").to_string(),
+        "{\"file\":\"a.rm\",\"line\":1,\"column\":5,\"rule\":\"unused-variable\",\
+         \"severity\":\"warning\",\"message\":\"The variable a is never read.\"}"
+    );
+
+    let directory = env::temp_dir().join(format!("rust-monkey-lint-{}", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let file = directory.join("a.rm");
+    fs::write(&file, "let f = fn(x) { x; };\nf();\n").unwrap();
+    let config = directory.join("lint.config");
+    fs::write(&config, "argument-count = error\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+        .arg("lint")
+        .arg("--json")
+        .arg("--config")
+        .arg(&config)
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "[{{\"file\":\"{}\",\"line\":2,\"column\":1,\"rule\":\"argument-count\",\
+             \"severity\":\"error\",\"message\":\"f takes 1 arguments but is given 0.\"}}]\n",
+            file.display()
+        )
+    );
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_dir_all(&directory).unwrap();
+}
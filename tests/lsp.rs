@@ -0,0 +1,293 @@
+/*
+Author: Jedda Boyle
+Contains: Language server tests.
+A scripted client starts rust-monkey lsp, sends it a session of requests and
+notifications and checks the messages it sends back.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::json::Json;
+
+use std::io::prelude::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+// ================================================================================
+// Client.
+// ================================================================================
+
+const SOURCE: &str = "let add = fn(a, b) { a + b; };
+let total = add(1, 2);
+puts(total);
+";
+
+const URI: &str = "file:///tmp/rust-monkey-lsp-test/main.rm";
+
+// A session of messages to send to the server.
+struct Script {
+    messages: Vec<Json>,
+    next_id: i64,
+}
+
+impl Script {
+    fn new() -> Self {
+        Script {
+            messages: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    // Add a request and return its id.
+    fn request(&mut self, method: &str, params: Json) -> i64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.messages.push(Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("id", Json::number(id)),
+            ("method", Json::string(method)),
+            ("params", params),
+        ]));
+        id
+    }
+
+    fn notify(&mut self, method: &str, params: Json) {
+        self.messages.push(Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("method", Json::string(method)),
+            ("params", params),
+        ]));
+    }
+
+    // Run the server on the script and return its messages and exit status.
+    fn run(&self) -> (Vec<Json>, i32) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut input = String::new();
+        for message in self.messages.iter() {
+            let body = message.to_string();
+            input.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+        }
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        let mut text = String::from_utf8(output.stdout).unwrap();
+        let mut messages = Vec::new();
+        while !text.is_empty() {
+            let header_end = text.find("\r\n\r\n").unwrap();
+            let length: usize = text[..header_end]
+                .strip_prefix("Content-Length: ")
+                .unwrap()
+                .parse()
+                .unwrap();
+            let body = &text[header_end + 4..header_end + 4 + length];
+            messages.push(Json::parse(body).unwrap());
+            text = text[header_end + 4 + length..].to_string();
+        }
+        (messages, output.status.code().unwrap())
+    }
+}
+
+fn document(uri: &str) -> Json {
+    Json::object(vec![("uri", Json::string(uri))])
+}
+
+fn at(line: i64, character: i64) -> Json {
+    Json::object(vec![
+        ("textDocument", document(URI)),
+        (
+            "position",
+            Json::object(vec![
+                ("line", Json::number(line)),
+                ("character", Json::number(character)),
+            ]),
+        ),
+    ])
+}
+
+fn open(text: &str) -> Json {
+    Json::object(vec![(
+        "textDocument",
+        Json::object(vec![
+            ("uri", Json::string(URI)),
+            ("languageId", Json::string("monkey")),
+            ("version", Json::number(1)),
+            ("text", Json::string(text)),
+        ]),
+    )])
+}
+
+fn change(text: &str) -> Json {
+    Json::object(vec![
+        ("textDocument", document(URI)),
+        (
+            "contentChanges",
+            Json::Array(vec![Json::object(vec![("text", Json::string(text))])]),
+        ),
+    ])
+}
+
+fn response(messages: &[Json], id: i64) -> &Json {
+    messages
+        .iter()
+        .find(|message| message.get("id") == Some(&Json::number(id)))
+        .unwrap()
+}
+
+fn result(messages: &[Json], id: i64) -> &Json {
+    response(messages, id).get("result").unwrap()
+}
+
+// The published diagnostics in the order they were sent.
+fn diagnostics(messages: &[Json]) -> Vec<&Vec<Json>> {
+    messages
+        .iter()
+        .filter(|message| {
+            message.get("method").and_then(Json::as_str) == Some("textDocument/publishDiagnostics")
+        })
+        .map(|message| {
+            message
+                .get("params")
+                .and_then(|params| params.get("diagnostics"))
+                .and_then(Json::as_array)
+                .unwrap()
+        })
+        .collect()
+}
+
+// The start line and character of a location or diagnostic.
+fn start(value: &Json) -> (i64, i64) {
+    let start = value
+        .get("range")
+        .and_then(|range| range.get("start"))
+        .unwrap();
+    (
+        start.get("line").and_then(Json::as_i64).unwrap(),
+        start.get("character").and_then(Json::as_i64).unwrap(),
+    )
+}
+
+fn labels(items: &Json) -> Vec<&str> {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item.get("label").and_then(Json::as_str).unwrap())
+        .collect()
+}
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+#[test]
+fn scripted_session() {
+    let mut script = Script::new();
+    let initialize = script.request("initialize", Json::object(vec![]));
+    script.notify("initialized", Json::object(vec![]));
+    script.notify("textDocument/didOpen", open(SOURCE));
+    let hover = script.request("textDocument/hover", at(1, 13));
+    let definition = script.request("textDocument/definition", at(0, 21));
+    let mut references = at(0, 5);
+    if let Json::Object(members) = &mut references {
+        members.push((
+            "context".to_string(),
+            Json::object(vec![("includeDeclaration", Json::Bool(true))]),
+        ));
+    }
+    let references = script.request("textDocument/references", references);
+    let parameter = script.request("textDocument/hover", at(0, 25));
+    let symbols = script.request(
+        "textDocument/documentSymbol",
+        Json::object(vec![("textDocument", document(URI))]),
+    );
+    let top_completion = script.request("textDocument/completion", at(2, 0));
+    let body_completion = script.request("textDocument/completion", at(0, 22));
+    let unknown = script.request("textDocument/rename", at(0, 5));
+    script.notify("textDocument/didChange", change("let x = ;\n"));
+    script.notify("textDocument/didChange", change("let unused = 1;\n"));
+    let shutdown = script.request("shutdown", Json::Null);
+    script.notify("exit", Json::Null);
+    let (messages, status) = script.run();
+
+    let capabilities = result(&messages, initialize).get("capabilities").unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(capabilities.get("textDocumentSync"), Some(&Json::number(1)));
+
+    // Hover on the call of add shows its definition.
+    let contents = result(&messages, hover).get("contents").unwrap();
+    let value = contents.get("value").and_then(Json::as_str).unwrap();
+    assert!(value.contains("let add = fn(a, b)"), "{}", value);
+
+    let hover = result(&messages, parameter).get("contents").unwrap();
+    let value = hover.get("value").and_then(Json::as_str).unwrap();
+    assert!(value.contains("b: parameter of fn(a, b)"), "{}", value);
+
+    // a in the body is the first parameter.
+    let location = result(&messages, definition);
+    assert_eq!(location.get("uri").and_then(Json::as_str), Some(URI));
+    assert_eq!(start(location), (0, 13));
+
+    let locations = result(&messages, references).as_array().unwrap();
+    let starts: Vec<(i64, i64)> = locations.iter().map(start).collect();
+    assert_eq!(starts, vec![(0, 4), (1, 12)]);
+
+    let symbols = result(&messages, symbols).as_array().unwrap();
+    let names: Vec<&str> = symbols
+        .iter()
+        .map(|symbol| symbol.get("name").and_then(Json::as_str).unwrap())
+        .collect();
+    assert_eq!(names, vec!["add", "total"]);
+    assert_eq!(symbols[0].get("kind"), Some(&Json::number(12)));
+    assert_eq!(symbols[1].get("kind"), Some(&Json::number(13)));
+
+    // Parameters are only offered inside the function.
+    let top = labels(result(&messages, top_completion));
+    for label in ["add", "total", "map", "len", "let"].iter() {
+        assert!(top.contains(label), "{} not in {:?}", label, top);
+    }
+    assert!(!top.contains(&"a"));
+    let body = labels(result(&messages, body_completion));
+    assert!(body.contains(&"a") && body.contains(&"b") && body.contains(&"add"));
+
+    let error = response(&messages, unknown).get("error").unwrap();
+    assert_eq!(error.get("code"), Some(&Json::number(-32601)));
+
+    // The document parses when it is opened, then has a syntax error and then
+    // an unused variable.
+    let published = diagnostics(&messages);
+    assert_eq!(published.len(), 3);
+    assert_eq!(published[0].len(), 0);
+    assert!(!published[1].is_empty());
+    assert_eq!(published[1][0].get("severity"), Some(&Json::number(1)));
+    assert_eq!(start(&published[1][0]), (0, 8));
+    assert_eq!(published[2].len(), 1);
+    assert_eq!(
+        published[2][0].get("code").and_then(Json::as_str),
+        Some("unused-variable")
+    );
+    assert_eq!(published[2][0].get("severity"), Some(&Json::number(2)));
+
+    assert_eq!(result(&messages, shutdown), &Json::Null);
+    assert_eq!(status, 0);
+}
+
+#[test]
+fn exit_without_shutdown_fails() {
+    let mut script = Script::new();
+    script.request("initialize", Json::object(vec![]));
+    script.notify("exit", Json::Null);
+    let (messages, status) = script.run();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(status, 1);
+}
@@ -0,0 +1,105 @@
+/*
+Author: Jedda Boyle
+Contains: Module tests.
+The programs in tests/modules import each other by relative paths, which are
+resolved from the file that makes the import.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::object;
+use rust_monkey::output::Buffer;
+
+use std::fs;
+use std::path::PathBuf;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+fn module_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/modules")
+        .join(name)
+}
+
+// Evaluate source as if it were the file name in tests/modules and return the
+// value of its last statement, or the kind and message of the error it raised,
+// and what it wrote.
+fn eval_in(name: &str, source: &str) -> (String, String) {
+    let interpreter = Interpreter::new();
+    let buffer = Buffer::new();
+    interpreter.set_output(Box::new(buffer.clone()));
+    interpreter.set_path(module_path(name));
+    let result = interpreter.eval(source);
+    let value = match result.downcast_ref::<object::Error>() {
+        Some(error) => format!("{}: {}", error.kind, error.message),
+        None => result.to_string(),
+    };
+    (value, String::from_utf8(buffer.contents()).unwrap())
+}
+
+// Evaluate the file name in tests/modules.
+fn eval_file(name: &str) -> (String, String) {
+    let source = fs::read_to_string(module_path(name)).unwrap();
+    eval_in(name, &source)
+}
+
+#[test]
+fn relative_imports() {
+    // main.rm imports util/numbers.rm, and lib/geometry.rm imports it again
+    // as ../util/numbers.rm, which is the same module and is loaded once.
+    let (value, written) = eval_file("main.rm");
+    assert_eq!(value, "12");
+    assert_eq!(written, "loading numbers\n");
+}
+
+#[test]
+fn exports() {
+    let import = "import \"lib/geometry.rm\" as geometry;";
+    let (value, _) = eval_in(
+        "main.rm",
+        &format!("{} geometry.double_area([1, 4]);", import),
+    );
+    assert_eq!(value, "8");
+    let (value, _) = eval_in("main.rm", &format!("{} geometry.hidden;", import));
+    assert_eq!(value, "NameError: geometry has no member hidden.");
+    let (value, _) = eval_in("main.rm", &format!("{} numbers;", import));
+    assert_eq!(value, "NameError: Variable numbers is not in scope.");
+}
+
+#[test]
+fn missing_module() {
+    let (value, _) = eval_in("main.rm", "import \"lib/missing.rm\" as missing;");
+    assert!(
+        value.starts_with("RuntimeError: Could not import lib/missing.rm: "),
+        "{}",
+        value
+    );
+    // The path is relative to the importing file, not the working directory.
+    let (value, _) = eval_in("lib/geometry.rm", "import \"util/numbers.rm\" as numbers;");
+    assert!(
+        value.starts_with("RuntimeError: Could not import util/numbers.rm: "),
+        "{}",
+        value
+    );
+}
+
+#[test]
+fn import_cycle() {
+    let (value, _) = eval_in("main.rm", "import \"cycle/a.rm\" as a;");
+    let a = fs::canonicalize(module_path("cycle/a.rm")).unwrap();
+    let b = fs::canonicalize(module_path("cycle/b.rm")).unwrap();
+    assert_eq!(
+        value,
+        format!(
+            "RuntimeError: Import cycle detected: {} -> {} -> {}.",
+            a.display(),
+            b.display(),
+            a.display()
+        )
+    );
+}
@@ -0,0 +1,106 @@
+/*
+Author: Jedda Boyle
+Contains: Prelude tests.
+The library functions of the prelude are evaluated with the default limits,
+on arrays longer than the call depth limit, and are left out by an
+interpreter created without the prelude.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::object;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// The value of the last statement of source as it is printed, or the kind
+// and message of the error it raised.
+fn eval(interpreter: &Interpreter, source: &str) -> String {
+    let result = interpreter.eval(source);
+    match result.downcast_ref::<object::Error>() {
+        Some(error) => format!("{}: {}", error.kind, error.message),
+        None => result.to_string(),
+    }
+}
+
+#[test]
+fn library_loops_past_the_depth_limit() {
+    let interpreter = Interpreter::new();
+    let cases = [
+        ("len(range(0, 10000));", "10000"),
+        ("map(range(0, 10000), fn(x) { x * 2; })[9999];", "19998"),
+        (
+            "len(filter(range(0, 10000), fn(x) { x / 2 * 2 == x; }));",
+            "5000",
+        ),
+        (
+            "reduce(range(0, 10000), 0, fn(a, b) { a + b; });",
+            "49995000",
+        ),
+        ("len(zip(range(0, 10000), range(0, 20000)));", "10000"),
+        ("any(range(0, 10000), fn(x) { x == 9999; });", "true"),
+        ("all(range(0, 10000), fn(x) { x < 9999; });", "false"),
+        ("max(range(0, 10000));", "9999"),
+        ("min(map(range(0, 10000), fn(x) { 0 - x; }));", "-9999"),
+    ];
+    for (source, expected) in cases.iter() {
+        assert_eq!(eval(&interpreter, source), *expected, "{}", source);
+    }
+}
+
+#[test]
+fn library_functions_check_their_arguments() {
+    let interpreter = Interpreter::new();
+    let cases = [
+        (
+            "map([1], 5);",
+            "TypeError: map expects a function but was given Integer.",
+        ),
+        (
+            "range(\"a\", 1);",
+            "TypeError: range expects an integer but was given String.",
+        ),
+        (
+            "filter([1], fn(x) { 1; });",
+            "TypeError: Condition is not of boolean type.",
+        ),
+        (
+            "reduce([1], 0);",
+            "TypeError: reduce expects 3 arguments but was given 2.",
+        ),
+        ("map([[1], [2, 3]], len);", "[1, 2]"),
+        ("range(5, 2);", "[]"),
+    ];
+    for (source, expected) in cases.iter() {
+        assert_eq!(eval(&interpreter, source), *expected, "{}", source);
+    }
+}
+
+#[test]
+fn without_prelude_leaves_out_the_library() {
+    let interpreter = Interpreter::without_prelude();
+    let names = [
+        "identity", "compose", "range", "map", "filter", "reduce", "zip", "any", "all", "min",
+        "max",
+    ];
+    for name in names.iter() {
+        assert_eq!(
+            eval(&interpreter, &format!("{};", name)),
+            format!("NameError: Variable {} is not in scope.", name)
+        );
+    }
+    assert_eq!(eval(&interpreter, "len([1, 2]);"), "2");
+}
+
+#[test]
+fn embedders_add_prelude_files() {
+    let interpreter = Interpreter::new();
+    let source = "let sum = fn(arr) { reduce(arr, 0, fn(a, b) { a + b; }); };".to_string();
+    assert!(interpreter.add_prelude("sum.rm", &source).is_ok());
+    assert_eq!(eval(&interpreter, "sum(range(0, 5));"), "10");
+    assert_eq!(eval(&interpreter, "let sum = 1; sum;"), "1");
+}
@@ -0,0 +1,150 @@
+/*
+Author: Jedda Boyle
+Contains: Profiler tests.
+The times depend on the machine so the tests check the number of calls, how
+the times of callers and callees relate and the format of the folded stacks.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::profiler::Entry;
+use rust_monkey::profiler::Profiler;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+const PROGRAM: &str = "let fib = fn(n) {
+    if (n < 2) { n; } else { fib(n - 1) + fib(n - 2); };
+};
+let main = fn() {
+    fib(15);
+};
+main();
+main();
+";
+
+// Profile source and return the finished profiler.
+fn profile(source: &str) -> Profiler {
+    let interpreter = Interpreter::new();
+    let profiler = Rc::new(RefCell::new(Profiler::new()));
+    interpreter.add_hook(profiler.clone());
+    interpreter.eval(source);
+    let mut profiler = profiler.replace(Profiler::new());
+    profiler.finish();
+    profiler
+}
+
+fn entry<'a>(profiler: &'a Profiler, label: &str) -> &'a Entry {
+    profiler
+        .entries
+        .iter()
+        .find(|entry| entry.label() == label)
+        .unwrap()
+}
+
+#[test]
+fn totals() {
+    let profiler = profile(PROGRAM);
+    let labels: Vec<String> = profiler.entries.iter().map(|entry| entry.label()).collect();
+    assert_eq!(labels, vec!["<program>", "main (4:12)", "fib (1:11)"]);
+
+    let program = entry(&profiler, "<program>");
+    let main = entry(&profiler, "main (4:12)");
+    let fib = entry(&profiler, "fib (1:11)");
+    assert_eq!((program.calls, main.calls, fib.calls), (1, 2, 2 * 1973));
+
+    // The time of a function includes the functions it calls, and the time
+    // of a recursive function is only counted once for the outermost call.
+    for entry in profiler.entries.iter() {
+        assert!(entry.exclusive <= entry.inclusive, "{}", entry.label());
+    }
+    assert!(fib.inclusive <= main.inclusive);
+    assert!(main.inclusive <= program.inclusive);
+    assert!(fib.exclusive > Duration::ZERO);
+
+    // Every moment is spent in exactly one function.
+    let exclusive: Duration = profiler.entries.iter().map(|entry| entry.exclusive).sum();
+    assert_eq!(exclusive, program.inclusive);
+
+    let sorted = profiler.sorted();
+    for pair in sorted.windows(2) {
+        assert!(pair[0].exclusive >= pair[1].exclusive);
+    }
+    let table = profiler.table();
+    assert!(table.starts_with("     calls   inclusive ms   exclusive ms  function\n"));
+    assert!(table.contains("  fib (1:11)\n"), "{}", table);
+}
+
+#[test]
+fn functions_with_the_same_name() {
+    let profiler = profile("let f = fn() { 1; };\nf();\nlet f = fn() { 2; };\nf();\nf();\n");
+    let calls: Vec<(String, u64)> = profiler
+        .entries
+        .iter()
+        .map(|entry| (entry.label(), entry.calls))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            ("<program>".to_string(), 1),
+            ("f (1:9)".to_string(), 1),
+            ("f (3:9)".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn folded_stacks() {
+    let profiler = profile(PROGRAM);
+    let folded = profiler.folded();
+    assert!(folded.ends_with('\n'));
+    let lines: Vec<&str> = folded.lines().collect();
+    let mut sorted = lines.clone();
+    sorted.sort();
+    assert_eq!(lines, sorted);
+
+    let mut total = 0;
+    for line in lines.iter() {
+        // Each line is a stack of labels separated by ; and a time.
+        let (stack, time) = line.rsplit_once(' ').unwrap();
+        let time: u128 = time.parse().unwrap();
+        assert!(time > 0, "{}", line);
+        total += time;
+        let labels: Vec<&str> = stack.split(';').collect();
+        assert_eq!(labels[0], "<program>", "{}", line);
+        if labels.len() > 1 {
+            assert_eq!(labels[1], "main (4:12)", "{}", line);
+        }
+        assert!(
+            labels.iter().skip(2).all(|label| *label == "fib (1:11)"),
+            "{}",
+            line
+        );
+    }
+    // The deepest stack is the program, main and 15 calls of fib.
+    let deepest = lines.iter().map(|line| line.matches(';').count()).max();
+    assert!(deepest <= Some(16));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("<program>;main (4:12);fib (1:11)")));
+
+    // The times are rounded down to microseconds for each stack.
+    let program = entry(&profiler, "<program>").inclusive.as_micros();
+    assert!(total <= program, "{} {}", total, program);
+    assert!(
+        program - total <= lines.len() as u128,
+        "{} {}",
+        total,
+        program
+    );
+
+    assert_eq!(Profiler::new().folded(), "");
+}
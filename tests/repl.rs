@@ -0,0 +1,158 @@
+/*
+Author: Jedda Boyle
+Contains: REPL tests.
+Input is buffered until it is complete and the meta-commands inspect the
+session, without a terminal.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::interpreter::Interpreter;
+use rust_monkey::output::Buffer;
+use rust_monkey::repl;
+
+use std::env;
+use std::fs;
+use std::process;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+#[test]
+fn complete_input() {
+    let cases = [
+        "",
+        "   \n",
+        "let a = 1;",
+        "let a = 1;  \n",
+        "let f = fn(x) {\n  x + 1;\n};\n",
+        "[1, (2 + 3), {\"a\": 4}];",
+        "\"a { string\";",
+        "\"an \\\" escaped quote\";",
+        // Extra closing brackets are left for the parser to report.
+        "};",
+        ")",
+        "let a = [1]];",
+    ];
+    for input in cases.iter() {
+        assert!(repl::is_complete(input), "{:?}", input);
+    }
+}
+
+#[test]
+fn incomplete_input() {
+    let cases = [
+        "let a = 1",
+        "let f = fn(x) {",
+        "let f = fn(x) {\n  x + 1;\n",
+        "let f = fn(x) {\n  x + 1;\n}",
+        "[1, 2,",
+        "puts((1 + 2);",
+        "\"an unterminated string;",
+        "let a = \"a; b\"",
+        "if (true) { 1; } else {",
+    ];
+    for input in cases.iter() {
+        assert!(!repl::is_complete(input), "{:?}", input);
+    }
+}
+
+// An interpreter writing to a buffer and the buffer.
+fn session() -> (Interpreter, Buffer) {
+    let interpreter = Interpreter::new();
+    let buffer = Buffer::new();
+    interpreter.set_output(Box::new(buffer.clone()));
+    (interpreter, buffer)
+}
+
+// Run a meta-command and return its exit status and what it wrote.
+fn command(
+    line: &str,
+    interpreter: &Interpreter,
+    buffer: &Buffer,
+    history: &mut Vec<String>,
+) -> (Option<i32>, String) {
+    buffer.clear();
+    let code = repl::run_command(line, interpreter, history);
+    (code, String::from_utf8(buffer.contents()).unwrap())
+}
+
+#[test]
+fn meta_commands() {
+    let (interpreter, buffer) = session();
+    let mut history: Vec<String> = Vec::new();
+
+    let (code, written) = command(":help", &interpreter, &buffer, &mut history);
+    assert_eq!(code, None);
+    assert!(written.starts_with("Commands:\n"), "{}", written);
+    assert!(written.contains(":quit"), "{}", written);
+
+    let (_, written) = command(":time let a = 1 + 2;", &interpreter, &buffer, &mut history);
+    assert!(written.starts_with("Evaluated in "), "{}", written);
+    let (_, written) = command(":time a * 2;", &interpreter, &buffer, &mut history);
+    assert!(written.starts_with("6\nEvaluated in "), "{}", written);
+    assert_eq!(history, vec!["let a = 1 + 2;\n", "a * 2;\n"]);
+
+    let (_, written) = command(":env", &interpreter, &buffer, &mut history);
+    assert_eq!(written, "a: Integer\n");
+
+    let (_, written) = command(":reset", &interpreter, &buffer, &mut history);
+    assert_eq!(written, "");
+    assert!(history.is_empty());
+    let (_, written) = command(":env", &interpreter, &buffer, &mut history);
+    assert_eq!(written, "");
+
+    let (_, written) = command(":tokens a;", &interpreter, &buffer, &mut history);
+    assert_eq!(written, "1:1\tIdent\ta\n1:2\tSemiColon\t;\n");
+    let (_, written) = command(":ast -a + 1;", &interpreter, &buffer, &mut history);
+    assert_eq!(written, "[[[- a] + 1]]\n");
+
+    let (_, written) = command(":what", &interpreter, &buffer, &mut history);
+    assert_eq!(
+        written,
+        "Unknown command :what. Type :help for a list of commands.\n"
+    );
+
+    let (code, _) = command(":quit", &interpreter, &buffer, &mut history);
+    assert_eq!(code, Some(0));
+}
+
+#[test]
+fn load_and_save() {
+    let (interpreter, buffer) = session();
+    let mut history: Vec<String> = Vec::new();
+    let directory = env::temp_dir().join(format!("rust-monkey-repl-{}", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let loaded = directory.join("loaded.rm");
+    let saved = directory.join("saved.rm");
+    fs::write(&loaded, "let double = fn(x) { x * 2; };\ndouble(4);\n").unwrap();
+
+    let line = format!(":load {}", loaded.to_str().unwrap());
+    let (code, written) = command(&line, &interpreter, &buffer, &mut history);
+    assert_eq!((code, written.as_str()), (None, "8\n"));
+
+    command(":time double(5);", &interpreter, &buffer, &mut history);
+    let line = format!(":save {}", saved.to_str().unwrap());
+    let (_, written) = command(&line, &interpreter, &buffer, &mut history);
+    assert_eq!(written, "");
+    assert_eq!(
+        fs::read_to_string(&saved).unwrap(),
+        "let double = fn(x) { x * 2; };\ndouble(4);\ndouble(5);\n"
+    );
+
+    let missing = directory.join("missing.rm");
+    let line = format!(":load {}", missing.to_str().unwrap());
+    let (_, written) = command(&line, &interpreter, &buffer, &mut history);
+    assert!(written.starts_with("Could not read "), "{}", written);
+
+    let (code, _) = command(":load", &interpreter, &buffer, &mut history);
+    assert_eq!(code, None);
+    let line = format!(":load {}", loaded.to_str().unwrap());
+    fs::write(&loaded, "exit(4);\n").unwrap();
+    let (code, _) = command(&line, &interpreter, &buffer, &mut history);
+    assert_eq!(code, Some(4));
+    fs::remove_dir_all(&directory).unwrap();
+}
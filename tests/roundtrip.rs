@@ -0,0 +1,439 @@
+/*
+Author: Jedda Boyle
+Contains: Round trip property tests.
+Random trees are printed with to_source, parsed again and compared with the
+tree they came from. A difference means either the printer or the parser
+gets the precedence or associativity of an expression wrong. Every case is
+generated from its own seed so a failure can be reproduced from the seed in
+its message.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use rust_monkey::ast::escape_string;
+use rust_monkey::ast::*;
+use rust_monkey::formatter::format_source;
+use rust_monkey::lexer::token::Span;
+use rust_monkey::lexer::token::Token;
+use rust_monkey::lexer::Lexer;
+use rust_monkey::parser::Parser;
+
+use std::rc::Rc;
+
+const CASES: u64 = 2000;
+const MAX_DEPTH: u64 = 4;
+
+// ================================================================================
+// Random numbers.
+// ================================================================================
+
+// A xorshift generator so that the cases are the same on every run.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // A number from 0 up to but not including n.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+// ================================================================================
+// Generating trees.
+// ================================================================================
+
+const IDENTIFIERS: [&str; 6] = ["a", "b", "x", "foo", "bar_2", "_tmp"];
+const OPERATORS: [&str; 8] = ["+", "-", "*", "/", "<", ">", "==", "!="];
+const STRING_PARTS: [&str; 8] = ["a", "b c", "\\", "\"", "\n", "\t", "//", "{}"];
+
+fn identifier(rng: &mut Rng) -> IdentifierExpression {
+    IdentifierExpression {
+        token: Token::new(rng.pick(&IDENTIFIERS).to_string()),
+    }
+}
+
+fn literal(rng: &mut Rng) -> Box<dyn Node> {
+    match rng.below(4) {
+        0 => Box::new(identifier(rng)),
+        1 => {
+            let value = if rng.below(10) == 0 {
+                i64::MAX
+            } else {
+                rng.below(1000) as i64
+            };
+            Box::new(IntegralExpression {
+                token: Token::new(value.to_string()),
+                value,
+            })
+        }
+        2 => {
+            let mut value = String::new();
+            for _ in 0..rng.below(4) {
+                value.push_str(rng.pick(&STRING_PARTS));
+            }
+            Box::new(StringExpression {
+                token: Token::new(format!("\"{}\"", escape_string(&value))),
+                value,
+            })
+        }
+        _ => {
+            let value = rng.below(2) == 0;
+            Box::new(BoolExpression {
+                token: Token::new(value.to_string()),
+                value,
+            })
+        }
+    }
+}
+
+fn expressions(rng: &mut Rng, depth: u64) -> Vec<Box<dyn Node>> {
+    (0..rng.below(3)).map(|_| expression(rng, depth)).collect()
+}
+
+fn expression(rng: &mut Rng, depth: u64) -> Box<dyn Node> {
+    if depth == 0 || rng.below(4) == 0 {
+        return literal(rng);
+    }
+    let depth = depth - 1;
+    match rng.below(11) {
+        0 => Box::new(ArrayExpression {
+            token: Token::new("[".to_string()),
+            elements: expressions(rng, depth),
+        }),
+        1 => Box::new(PrefixExpression {
+            token: Token::new(rng.pick(&["-", "!"]).to_string()),
+            right: expression(rng, depth),
+        }),
+        2 | 3 => Box::new(InfixExpression {
+            token: Token::new(rng.pick(&OPERATORS).to_string()),
+            left: expression(rng, depth),
+            right: expression(rng, depth),
+        }),
+        4 => Box::new(IfElseExpression {
+            token: Token::new("if".to_string()),
+            condition: expression(rng, depth),
+            consequence: block(rng, depth),
+            alternative: if rng.below(2) == 0 {
+                Some(block(rng, depth))
+            } else {
+                None
+            },
+        }),
+        5 => {
+            let parameters: Vec<Box<dyn Node>> = (0..rng.below(3))
+                .map(|_| Box::new(identifier(rng)) as Box<dyn Node>)
+                .collect();
+            Box::new(FunctionExpression {
+                token: Token::new("fn".to_string()),
+                parameters: Rc::new(parameters),
+                body: Rc::new(block(rng, depth)),
+            })
+        }
+        6 => Box::new(CallExpression {
+            token: Token::new("(".to_string()),
+            func_identifier: expression(rng, depth),
+            arguments: expressions(rng, depth),
+        }),
+        7 => Box::new(IndexExpression {
+            token: Token::new("[".to_string()),
+            left: expression(rng, depth),
+            index: expression(rng, depth),
+        }),
+        8 => Box::new(MemberExpression {
+            token: Token::new(".".to_string()),
+            object: expression(rng, depth),
+            member: identifier(rng),
+        }),
+        9 => Box::new(PostfixExpression {
+            token: Token::new("?".to_string()),
+            left: expression(rng, depth),
+        }),
+        _ => {
+            // A try needs a catch or a finally block.
+            let kind = rng.below(3);
+            let mut catch_identifier = None;
+            let mut catch_body = None;
+            let mut finally_body = None;
+            if kind != 0 {
+                catch_identifier = Some(identifier(rng));
+                catch_body = Some(block(rng, depth));
+            }
+            if kind != 1 {
+                finally_body = Some(block(rng, depth));
+            }
+            Box::new(TryExpression {
+                token: Token::new("try".to_string()),
+                body: block(rng, depth),
+                catch_identifier,
+                catch_body,
+                finally_body,
+            })
+        }
+    }
+}
+
+fn block(rng: &mut Rng, depth: u64) -> Box<dyn Node> {
+    let statements = (0..rng.below(3))
+        .map(|_| statement(rng, depth, false))
+        .collect();
+    Box::new(BlockStatement {
+        token: Token::new("{".to_string()),
+        statements,
+        end: Span::default(),
+    })
+}
+
+fn let_statement(rng: &mut Rng, depth: u64) -> LetStatement {
+    LetStatement {
+        token: Token::new("let".to_string()),
+        identifier: identifier(rng),
+        value: expression(rng, depth),
+    }
+}
+
+// Imports and exports are only generated at the top level.
+fn statement(rng: &mut Rng, depth: u64, top_level: bool) -> Box<dyn Node> {
+    let kinds = if top_level { 7 } else { 5 };
+    match rng.below(kinds) {
+        0 => Box::new(let_statement(rng, depth)),
+        1 => Box::new(ReturnStatement {
+            token: Token::new("return".to_string()),
+            value: expression(rng, depth),
+        }),
+        2 => Box::new(ThrowStatement {
+            token: Token::new("throw".to_string()),
+            value: expression(rng, depth),
+        }),
+        5 => Box::new(ImportStatement {
+            token: Token::new("import".to_string()),
+            path: rng.pick(&["lib.rm", "../a/b.rm"]).to_string(),
+            alias: identifier(rng),
+        }),
+        6 => Box::new(ExportStatement {
+            token: Token::new("export".to_string()),
+            statement: Box::new(let_statement(rng, depth)),
+        }),
+        _ => Box::new(ExpressionStatement {
+            token: Token::new("(".to_string()),
+            value: expression(rng, depth),
+        }),
+    }
+}
+
+fn program(rng: &mut Rng) -> Vec<Box<dyn Node>> {
+    (0..1 + rng.below(3))
+        .map(|_| statement(rng, MAX_DEPTH, true))
+        .collect()
+}
+
+// ================================================================================
+// Comparing trees.
+// ================================================================================
+
+// Print a tree as an s-expression built from the fields of the nodes, so
+// that two trees are equal exactly when their s-expressions are.
+#[allow(clippy::borrowed_box)]
+fn tree(node: &Box<dyn Node>) -> String {
+    let nodes = |nodes: &Vec<Box<dyn Node>>| -> String {
+        let trees: Vec<String> = nodes.iter().map(tree).collect();
+        trees.join(" ")
+    };
+    let optional = |node: &Option<Box<dyn Node>>| -> String {
+        node.as_ref().map(tree).unwrap_or("-".to_string())
+    };
+    match node.get_type() {
+        NodeType::LetStatement => let_tree(node.downcast_ref::<LetStatement>().unwrap()),
+        NodeType::ReturnStatement => {
+            let n = node.downcast_ref::<ReturnStatement>().unwrap();
+            format!("(return {})", tree(&n.value))
+        }
+        NodeType::ExpressionStatement => {
+            let n = node.downcast_ref::<ExpressionStatement>().unwrap();
+            format!("(expression {})", tree(&n.value))
+        }
+        NodeType::BlockStatement => {
+            let n = node.downcast_ref::<BlockStatement>().unwrap();
+            format!("(block {})", nodes(&n.statements))
+        }
+        NodeType::ThrowStatement => {
+            let n = node.downcast_ref::<ThrowStatement>().unwrap();
+            format!("(throw {})", tree(&n.value))
+        }
+        NodeType::ImportStatement => {
+            let n = node.downcast_ref::<ImportStatement>().unwrap();
+            format!("(import {:?} {})", n.path, n.alias.token.literal)
+        }
+        NodeType::ExportStatement => {
+            let n = node.downcast_ref::<ExportStatement>().unwrap();
+            format!("(export {})", let_tree(&n.statement))
+        }
+        NodeType::IdentifierExpression => {
+            let n = node.downcast_ref::<IdentifierExpression>().unwrap();
+            n.token.literal.clone()
+        }
+        NodeType::IntegralExpression => {
+            let n = node.downcast_ref::<IntegralExpression>().unwrap();
+            n.value.to_string()
+        }
+        NodeType::BoolExpression => {
+            let n = node.downcast_ref::<BoolExpression>().unwrap();
+            n.value.to_string()
+        }
+        NodeType::StringExpression => {
+            let n = node.downcast_ref::<StringExpression>().unwrap();
+            format!("{:?}", n.value)
+        }
+        NodeType::ArrayExpression => {
+            let n = node.downcast_ref::<ArrayExpression>().unwrap();
+            format!("(array {})", nodes(&n.elements))
+        }
+        NodeType::PrefixExpression => {
+            let n = node.downcast_ref::<PrefixExpression>().unwrap();
+            format!("({} {})", n.token.literal, tree(&n.right))
+        }
+        NodeType::InfixExpression => {
+            let n = node.downcast_ref::<InfixExpression>().unwrap();
+            format!("({} {} {})", n.token.literal, tree(&n.left), tree(&n.right))
+        }
+        NodeType::IfElseExpression => {
+            let n = node.downcast_ref::<IfElseExpression>().unwrap();
+            format!(
+                "(if {} {} {})",
+                tree(&n.condition),
+                tree(&n.consequence),
+                optional(&n.alternative)
+            )
+        }
+        NodeType::FunctionExpression => {
+            let n = node.downcast_ref::<FunctionExpression>().unwrap();
+            format!("(fn ({}) {})", nodes(&n.parameters), tree(&n.body))
+        }
+        NodeType::CallExpression => {
+            let n = node.downcast_ref::<CallExpression>().unwrap();
+            format!(
+                "(call {} {})",
+                tree(&n.func_identifier),
+                nodes(&n.arguments)
+            )
+        }
+        NodeType::IndexExpression => {
+            let n = node.downcast_ref::<IndexExpression>().unwrap();
+            format!("(index {} {})", tree(&n.left), tree(&n.index))
+        }
+        NodeType::MemberExpression => {
+            let n = node.downcast_ref::<MemberExpression>().unwrap();
+            format!("(member {} {})", tree(&n.object), n.member.token.literal)
+        }
+        NodeType::PostfixExpression => {
+            let n = node.downcast_ref::<PostfixExpression>().unwrap();
+            format!("(? {})", tree(&n.left))
+        }
+        NodeType::TryExpression => {
+            let n = node.downcast_ref::<TryExpression>().unwrap();
+            let identifier = n.catch_identifier.as_ref().map(|x| x.token.literal.clone());
+            format!(
+                "(try {} {} {} {})",
+                tree(&n.body),
+                identifier.unwrap_or("-".to_string()),
+                optional(&n.catch_body),
+                optional(&n.finally_body)
+            )
+        }
+    }
+}
+
+fn let_tree(node: &LetStatement) -> String {
+    format!(
+        "(let {} {})",
+        node.identifier.token.literal,
+        tree(&node.value)
+    )
+}
+
+fn program_tree(statements: &Vec<Box<dyn Node>>) -> Vec<String> {
+    statements.iter().map(tree).collect()
+}
+
+// Parse source which is expected to be valid.
+fn parse(source: &String, seed: u64) -> Vec<Box<dyn Node>> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    assert!(
+        parser.errors().is_empty(),
+        "seed {}: could not parse\n{}\n{:?}",
+        seed,
+        source,
+        parser.errors()
+    );
+    program.statements
+}
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+#[test]
+fn parse_print_parse_gives_the_same_tree() {
+    for seed in 0..CASES {
+        let statements = program(&mut Rng::new(seed));
+        let source: Vec<String> = statements.iter().map(|x| x.to_source()).collect();
+        let source = source.join("\n");
+
+        let reparsed = parse(&source, seed);
+        assert_eq!(
+            program_tree(&statements),
+            program_tree(&reparsed),
+            "seed {}: printed as\n{}",
+            seed,
+            source
+        );
+
+        // Printing the parsed tree gives the same source back.
+        let printed: Vec<String> = reparsed.iter().map(|x| x.to_source()).collect();
+        assert_eq!(source, printed.join("\n"), "seed {}", seed);
+    }
+}
+
+#[test]
+fn formatting_keeps_the_tree_and_is_idempotent() {
+    for seed in 0..CASES {
+        let statements = program(&mut Rng::new(seed));
+        let source: Vec<String> = statements.iter().map(|x| x.to_source()).collect();
+        let source = source.join("\n");
+
+        let formatted = format_source(&source).unwrap();
+        assert_eq!(
+            program_tree(&statements),
+            program_tree(&parse(&formatted, seed)),
+            "seed {}: formatted as\n{}",
+            seed,
+            formatted
+        );
+        assert_eq!(
+            formatted,
+            format_source(&formatted).unwrap(),
+            "seed {}",
+            seed
+        );
+    }
+}
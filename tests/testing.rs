@@ -0,0 +1,77 @@
+/*
+Author: Jedda Boyle
+Contains: Test runner tests.
+Runs rust-monkey test on the files in tests/testing, which have passing and
+failing tests, and checks the report and the exit status.
+*/
+
+// ================================================================================
+// Imports
+// ================================================================================
+
+use std::process::Command;
+
+// ================================================================================
+// Tests.
+// ================================================================================
+
+// Run rust-monkey test with args from the package root and return its
+// output and exit status.
+fn run(args: &[&str]) -> (String, i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-monkey"))
+        .arg("test")
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.code().unwrap(),
+    )
+}
+
+#[test]
+fn reports_passes_and_failures() {
+    let (output, status) = run(&["tests/testing"]);
+    let expected = "tests/testing/math_test.rm
+  ok    test_square
+  ok    test_divide
+  FAIL  test_wrong_square
+        line 16, column 14: AssertionError: square of 2: expected 5 but was 4.
+  FAIL  test_missing_error
+        line 20, column 17: AssertionError: expected an error but the function returned 1.
+tests/testing/nested/scope_test.rm
+  ok    test_bindings_are_local
+  ok    test_globals_are_unchanged
+  FAIL  test_type_error
+        line 13, column 11: TypeError: assert expects a boolean but was given Integer.
+
+4 passed, 3 failed.
+";
+    assert_eq!(output, expected);
+    assert_eq!(status, 1);
+}
+
+#[test]
+fn filter_selects_tests_by_name() {
+    let (output, status) = run(&["--filter", "bindings", "tests/testing"]);
+    assert!(
+        output.contains("  ok    test_bindings_are_local\n"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("test_square"), "{}", output);
+    assert!(
+        output.ends_with("1 passed, 0 failed, 6 filtered out.\n"),
+        "{}",
+        output
+    );
+    assert_eq!(status, 0);
+}
+
+#[test]
+fn no_tests_fails() {
+    let (output, status) = run(&["--filter", "nothing", "tests/testing/math_test.rm"]);
+    assert!(output.ends_with("No tests were run.\n"), "{}", output);
+    assert_eq!(status, 1);
+}